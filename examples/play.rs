@@ -0,0 +1,93 @@
+//! 最小嵌入示例：只用库的公共 API 播放一个文件
+//!
+//! ```bash
+//! cargo run --release --example play -- music.flac
+//! ```
+
+use std::path::Path;
+use std::time::Duration;
+
+use roger_player::{Engine, EngineConfig, EngineError, PlaybackState};
+
+/// 播放一首曲目直到结束（new → play → wait → stop）
+fn play(path: &Path) -> Result<(), EngineError> {
+    let mut engine = Engine::new(EngineConfig::default())?;
+    engine.play(path)?;
+
+    if let Some(info) = engine.current_info() {
+        println!(
+            "{}: {} Hz, {} ch, {}",
+            path.display(),
+            info.sample_rate,
+            info.channels,
+            info.format
+        );
+    }
+
+    // 每秒打印一次进度，直到播放完毕（EOF 且缓冲区已排空）
+    while !engine.wait_finished(Some(Duration::from_secs(1))) {
+        if engine.state() == PlaybackState::Stopped {
+            break;
+        }
+        let stats = engine.stats();
+        println!(
+            "{:>7.1}s  buffer {:>3.0}%  underruns {}",
+            stats.position_secs,
+            stats.buffer_fill_ratio * 100.0,
+            stats.underrun_count
+        );
+    }
+    if let Some(error) = engine.last_error() {
+        eprintln!("Decode error: {}", error);
+    }
+
+    let reason = engine.stop()?;
+    println!("Stopped: {:?}", reason);
+    Ok(())
+}
+
+fn main() {
+    let Some(path) = std::env::args_os().nth(1) else {
+        eprintln!("Usage: play <file>");
+        std::process::exit(2);
+    };
+    if let Err(e) = play(Path::new(&path)) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 写一段 0.2 秒的 16-bit 立体声静音 WAV
+    fn make_wav(path: &Path) {
+        let data_len: u32 = 44100 / 5 * 4;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&44100u32.to_le_bytes());
+        wav.extend_from_slice(&(44100u32 * 4).to_le_bytes());
+        wav.extend_from_slice(&4u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.resize(wav.len() + data_len as usize, 0);
+        std::fs::write(path, wav).unwrap();
+    }
+
+    #[test]
+    #[ignore] // 需要音频设备
+    fn test_play_smoke() {
+        let path = std::env::temp_dir().join(format!("roger_example_{}.wav", std::process::id()));
+        make_wav(&path);
+        let result = play(&path);
+        std::fs::remove_file(&path).ok();
+        result.unwrap();
+    }
+}
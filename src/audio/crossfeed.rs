@@ -0,0 +1,142 @@
+//! 耳机交叉馈送（Bauer 风格）
+//!
+//! 耳机的左右声道完全隔离，硬声像的录音听起来“在头内”且偏激。
+//! 交叉馈送把每个声道经低通（模拟头部遮挡）和短延迟（模拟耳间时间差）后
+//! 混入另一声道，缩小极端的立体声分离度。
+//!
+//! 仅处理立体声交织样本；level 为 0 时由调用方旁路，保持 bit-perfect
+
+use std::f64::consts::PI;
+
+/// 交叉通路低通截止频率（Hz），Bauer 推荐约 700Hz
+const CUTOFF_HZ: f64 = 700.0;
+
+/// 交叉通路延迟（秒），约为耳间时间差
+const DELAY_SECS: f64 = 0.0003;
+
+/// level = 1.0 时的交叉通路增益（-4.5 dB）
+const MAX_FEED_GAIN: f64 = 0.595_662_143_529_011;
+
+/// 一阶低通系数：`y = (1 - a) * x + a * y[-1]`
+pub fn lowpass_coefficient(cutoff_hz: f64, sample_rate: f64) -> f64 {
+    (-2.0 * PI * cutoff_hz / sample_rate).exp()
+}
+
+/// 交叉馈送状态（每曲目创建一次，处理时不分配）
+pub struct Crossfeed {
+    /// 低通系数
+    a: f64,
+    /// 低通状态（L, R）
+    lowpass: [f64; 2],
+    /// 低通输出的延迟线（交织 L/R）
+    delay_line: Vec<f64>,
+    /// 延迟线当前位置（帧）
+    pos: usize,
+}
+
+impl Crossfeed {
+    /// 按采样率创建（延迟线长度随采样率变化）
+    pub fn new(sample_rate: u32) -> Self {
+        let delay_frames = ((DELAY_SECS * sample_rate as f64).round() as usize).max(1);
+        Self {
+            a: lowpass_coefficient(CUTOFF_HZ, sample_rate as f64),
+            lowpass: [0.0; 2],
+            delay_line: vec![0.0; delay_frames * 2],
+            pos: 0,
+        }
+    }
+
+    /// 清空滤波器与延迟线状态（seek 后调用）
+    pub fn reset(&mut self) {
+        self.lowpass = [0.0; 2];
+        self.delay_line.fill(0.0);
+        self.pos = 0;
+    }
+
+    /// 对立体声交织样本施加交叉馈送
+    ///
+    /// `level` 取 0.0-1.0；输出按 `1 / (1 + 交叉增益)` 缩放，避免叠加后削波
+    pub fn process(&mut self, samples: &mut [i32], level: f64) {
+        if level <= 0.0 {
+            return;
+        }
+        let cross = level.min(1.0) * MAX_FEED_GAIN;
+        let norm = 1.0 / (1.0 + cross);
+        let frames = self.delay_line.len() / 2;
+
+        for frame in samples.chunks_exact_mut(2) {
+            let left = frame[0] as f64;
+            let right = frame[1] as f64;
+
+            self.lowpass[0] = (1.0 - self.a) * left + self.a * self.lowpass[0];
+            self.lowpass[1] = (1.0 - self.a) * right + self.a * self.lowpass[1];
+
+            let slot = self.pos * 2;
+            let delayed_left = self.delay_line[slot];
+            let delayed_right = self.delay_line[slot + 1];
+            self.delay_line[slot] = self.lowpass[0];
+            self.delay_line[slot + 1] = self.lowpass[1];
+            self.pos = (self.pos + 1) % frames;
+
+            // f64 → i32 的 `as` 转换本身即饱和
+            frame[0] = ((left + cross * delayed_right) * norm) as i32;
+            frame[1] = ((right + cross * delayed_left) * norm) as i32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lowpass_coefficient() {
+        let a = lowpass_coefficient(700.0, 44100.0);
+        assert!((a - 0.905_08).abs() < 1e-5, "a = {}", a);
+
+        // 采样率越高，同一截止频率的系数越接近 1
+        assert!(lowpass_coefficient(700.0, 192000.0) > a);
+        assert_eq!(lowpass_coefficient(0.0, 48000.0), 1.0);
+
+        // 直流增益为 1
+        let mut y = 0.0;
+        for _ in 0..10_000 {
+            y = (1.0 - a) * 1.0 + a * y;
+        }
+        assert!((y - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_level_zero_is_passthrough() {
+        let mut crossfeed = Crossfeed::new(44100);
+        let input = [i32::MAX, i32::MIN, 12345, -6789, 0, 1];
+        let mut samples = input;
+        crossfeed.process(&mut samples, 0.0);
+        assert_eq!(samples, input);
+    }
+
+    #[test]
+    fn test_crossfeed_bleeds_after_delay() {
+        // 只有左声道有信号：右声道在延迟（0.3ms @ 48kHz ≈ 14 帧）之后才出现
+        let mut crossfeed = Crossfeed::new(48000);
+        let mut samples: Vec<i32> = (0..64).flat_map(|_| [1 << 30, 0]).collect();
+        crossfeed.process(&mut samples, 1.0);
+
+        for frame in 0..14 {
+            assert_eq!(samples[frame * 2 + 1], 0, "frame {}", frame);
+        }
+        assert!(samples[14 * 2 + 1] > 0);
+        // 直达声按归一化缩小
+        assert!(samples[0] < 1 << 30);
+    }
+
+    #[test]
+    fn test_full_scale_negative() {
+        // 满幅负值：叠加交叉信号后饱和，不回绕成正值
+        let mut crossfeed = Crossfeed::new(48000);
+        let mut samples = vec![i32::MIN; 256];
+        crossfeed.process(&mut samples, 1.0);
+        assert!(samples.iter().all(|&s| s < 0));
+        assert_eq!(samples[0], (i32::MIN as f64 / (1.0 + MAX_FEED_GAIN)) as i32);
+    }
+}
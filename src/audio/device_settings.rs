@@ -0,0 +1,214 @@
+//! 按设备记住的输出设置
+//!
+//! 不同 DAC 适合的 IO 缓冲大小、输出模式各不相同。这里按设备 UID
+//! （跨重启稳定）保存上次可用的设置，选中该设备时由 `AudioOutput::new` 自动套用。
+//! 缓存文件：`<平台配置目录>/roger-player/devices.toml`。
+//!
+//! 缓存的缓冲帧数在套用时按设备当前报告的范围重新限制，设备固件更新或
+//! 换了接口后不会请求一个设备不接受的值。
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::output::{clamp_buffer_frames, OutputConfig};
+
+/// 配置目录名（与用户配置同目录）
+const CONFIG_DIR_NAME: &str = "roger-player";
+
+/// 设备设置缓存文件名
+const DEVICES_FILE_NAME: &str = "devices.toml";
+
+/// 单个设备的设置（未记录的项沿用当前配置）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+    /// IO 缓冲帧数
+    pub buffer_frames: Option<u32>,
+    /// 是否使用 HALOutput（false 为系统混音器）
+    pub use_hal: Option<bool>,
+    /// 是否尝试独占模式
+    pub exclusive_mode: Option<bool>,
+    /// 是否尝试整数输出格式（false 为 Float32）
+    pub integer_mode: Option<bool>,
+}
+
+impl DeviceSettings {
+    /// 记录输出配置中的设备相关项
+    pub fn from_config(config: &OutputConfig) -> Self {
+        Self {
+            buffer_frames: Some(config.buffer_frames),
+            use_hal: Some(config.use_hal),
+            exclusive_mode: Some(config.exclusive_mode),
+            integer_mode: Some(config.integer_mode),
+        }
+    }
+
+    /// 套用到输出配置，缓冲帧数限制到设备范围 `buffer_range`
+    pub fn apply(&self, config: &mut OutputConfig, buffer_range: Option<(u32, u32)>) {
+        if let Some(frames) = self.buffer_frames.filter(|&f| f > 0) {
+            let (frames, clamped) = clamp_buffer_frames(frames, buffer_range);
+            if clamped {
+                log::warn!(
+                    "Cached buffer size out of device range, using {} frames",
+                    frames
+                );
+            }
+            config.buffer_frames = frames;
+        }
+        if let Some(use_hal) = self.use_hal {
+            config.use_hal = use_hal;
+        }
+        if let Some(exclusive) = self.exclusive_mode {
+            config.exclusive_mode = exclusive;
+        }
+        if let Some(integer) = self.integer_mode {
+            config.integer_mode = integer;
+        }
+    }
+}
+
+/// 设备设置缓存（设备 UID → 设置）
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettingsCache {
+    devices: BTreeMap<String, DeviceSettings>,
+}
+
+impl DeviceSettingsCache {
+    /// 缓存文件路径（无法确定平台配置目录时返回 None）
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(DEVICES_FILE_NAME))
+    }
+
+    /// 加载缓存，任何错误都回退到空表
+    pub fn load() -> Self {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Self::default(),
+        }
+    }
+
+    /// 从指定路径加载
+    pub fn load_from(path: &Path) -> Self {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    log::warn!("Failed to read device settings {}: {}", path.display(), e);
+                }
+                return Self::default();
+            }
+        };
+
+        toml::from_str(&text).unwrap_or_else(|e| {
+            log::warn!("Invalid device settings {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// 保存到默认路径
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "No config directory available")
+        })?;
+        self.save_to(&path)
+    }
+
+    /// 保存到指定路径（自动创建父目录）
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, text)
+    }
+
+    /// 查询设备的设置
+    pub fn get(&self, uid: &str) -> Option<DeviceSettings> {
+        self.devices.get(uid).copied()
+    }
+
+    /// 记录设备的设置
+    pub fn insert(&mut self, uid: &str, settings: DeviceSettings) {
+        self.devices.insert(uid.to_string(), settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let mut cache = DeviceSettingsCache::default();
+        let config = OutputConfig {
+            buffer_frames: 256,
+            use_hal: false,
+            ..OutputConfig::default()
+        };
+        cache.insert(
+            "AppleUSBAudioEngine:Topping:D10s:1234:1",
+            DeviceSettings::from_config(&config),
+        );
+        cache.insert(
+            "BuiltInSpeakerDevice",
+            DeviceSettings {
+                exclusive_mode: Some(false),
+                ..DeviceSettings::default()
+            },
+        );
+
+        let path = std::env::temp_dir()
+            .join(format!("roger_devices_{}", std::process::id()))
+            .join("devices.toml");
+        cache.save_to(&path).unwrap();
+        let loaded = DeviceSettingsCache::load_from(&path);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(loaded, cache);
+        let topping = loaded
+            .get("AppleUSBAudioEngine:Topping:D10s:1234:1")
+            .unwrap();
+        assert_eq!(topping.buffer_frames, Some(256));
+        assert_eq!(topping.use_hal, Some(false));
+        assert_eq!(loaded.get("unknown"), None);
+    }
+
+    #[test]
+    fn test_apply_clamps_to_device_range() {
+        let settings = DeviceSettings {
+            buffer_frames: Some(16),
+            use_hal: Some(true),
+            exclusive_mode: Some(false),
+            integer_mode: None,
+        };
+        let mut config = OutputConfig {
+            integer_mode: false,
+            ..OutputConfig::default()
+        };
+        settings.apply(&mut config, Some((64, 4096)));
+        assert_eq!(config.buffer_frames, 64);
+        assert!(config.use_hal);
+        assert!(!config.exclusive_mode);
+        // 未记录的项保持原值
+        assert!(!config.integer_mode);
+
+        // 范围内原样使用；范围未知时不限制
+        let settings = DeviceSettings {
+            buffer_frames: Some(1024),
+            ..DeviceSettings::default()
+        };
+        settings.apply(&mut config, Some((64, 4096)));
+        assert_eq!(config.buffer_frames, 1024);
+        let settings = DeviceSettings {
+            buffer_frames: Some(8192),
+            ..DeviceSettings::default()
+        };
+        settings.apply(&mut config, None);
+        assert_eq!(config.buffer_frames, 8192);
+    }
+}
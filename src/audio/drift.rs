@@ -0,0 +1,211 @@
+//! IO 回调内的时钟漂移校正
+//!
+//! 在解码线程里校正漂移要等整个 ring buffer 播完才生效；这里在 IO 回调中
+//! 对即将输出的样本做线性插值，只施加极小的 ppm 级比例（±`MAX_DRIFT_PPM`），
+//! 校正立即作用于下一次回调。大比例的采样率转换不在此处理。
+//!
+//! 创建时按回调最大帧数预分配，处理时不分配、不加锁。
+//! 插值引入一帧延迟（在两帧已读数据之间插值，不需要向前看）
+
+/// 可施加的最大校正量（ppm）
+pub const MAX_DRIFT_PPM: f64 = 1000.0;
+
+/// 限制校正量（非有限值视为 0）
+pub fn clamp_ppm(ppm: f64) -> f64 {
+    if ppm.is_finite() {
+        ppm.clamp(-MAX_DRIFT_PPM, MAX_DRIFT_PPM)
+    } else {
+        0.0
+    }
+}
+
+/// 线性插值漂移校正器（交织样本）
+pub struct DriftCorrector {
+    channels: usize,
+    /// 每输出一帧前进的输入帧数（1 + ppm × 1e-6）
+    step: f64,
+    /// 当前输出位置在 prev 与 cur 之间的比例（0.0-1.0）
+    frac: f64,
+    /// 插值区间的两帧
+    prev: Vec<i32>,
+    cur: Vec<i32>,
+    /// 是否已读入第一帧（reset 后需要先读一帧）
+    primed: bool,
+    /// 从 ring buffer 读取输入的预分配缓冲
+    input: Vec<i32>,
+}
+
+impl DriftCorrector {
+    /// 按声道数和单次最多输出帧数预分配
+    pub fn new(channels: usize, max_output_frames: usize) -> Self {
+        let channels = channels.max(1);
+        // 最大校正量下需要的输入帧数，加上首帧与取整的余量
+        let max_input_frames =
+            (max_output_frames as f64 * (1.0 + MAX_DRIFT_PPM * 1e-6)).ceil() as usize + 2;
+        Self {
+            channels,
+            step: 1.0,
+            frac: 0.0,
+            prev: vec![0; channels],
+            cur: vec![0; channels],
+            primed: false,
+            input: vec![0; max_input_frames * channels],
+        }
+    }
+
+    /// 设置校正量（ppm）：正值多消耗输入（缓冲区持续增长时），负值少消耗
+    pub fn set_ppm(&mut self, ppm: f64) {
+        self.step = 1.0 + clamp_ppm(ppm) * 1e-6;
+    }
+
+    /// 清空插值状态（校正关闭或 seek 后），下次处理重新读入首帧
+    pub fn reset(&mut self) {
+        self.frac = 0.0;
+        self.primed = false;
+    }
+
+    /// 输出 `output_frames` 帧需要读取的输入帧数
+    ///
+    /// 与 `process` 使用相同的相位累加，结果精确一致
+    pub fn input_frames_needed(&self, output_frames: usize) -> usize {
+        let mut frac = self.frac;
+        let mut needed = usize::from(!self.primed);
+        for _ in 0..output_frames {
+            frac += self.step;
+            while frac >= 1.0 {
+                frac -= 1.0;
+                needed += 1;
+            }
+        }
+        needed
+    }
+
+    /// 整个预分配的输入缓冲（供 mlock）
+    pub fn input_buffer(&self) -> &[i32] {
+        &self.input
+    }
+
+    /// 输入缓冲的前 `samples` 个样本（超出预分配容量时截断），供调用方从 ring buffer 读入
+    pub fn input_mut(&mut self, samples: usize) -> &mut [i32] {
+        let len = samples.min(self.input.len());
+        &mut self.input[..len]
+    }
+
+    /// 由输入缓冲的前 `input_samples` 个样本插值生成 `output`（整帧）
+    ///
+    /// 输入应为 `input_frames_needed` 帧；不足时保持最后一帧
+    pub fn process(&mut self, input_samples: usize, output: &mut [i32]) {
+        let channels = self.channels;
+        let input_frames = input_samples.min(self.input.len()) / channels;
+        let mut next = 0;
+
+        if !self.primed {
+            if input_frames > 0 {
+                self.cur.copy_from_slice(&self.input[..channels]);
+                next = 1;
+            }
+            self.prev.copy_from_slice(&self.cur);
+            self.primed = true;
+        }
+
+        for frame in output.chunks_exact_mut(channels) {
+            for ((out, &a), &b) in frame.iter_mut().zip(&self.prev).zip(&self.cur) {
+                *out = (a as f64 + self.frac * (b as f64 - a as f64)).round() as i32;
+            }
+            self.frac += self.step;
+            while self.frac >= 1.0 {
+                self.frac -= 1.0;
+                self.prev.copy_from_slice(&self.cur);
+                if next < input_frames {
+                    let start = next * channels;
+                    self.cur.copy_from_slice(&self.input[start..start + channels]);
+                    next += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 用递增的帧号填充输入并处理，返回输出
+    fn run(corrector: &mut DriftCorrector, output_frames: usize, first: &mut i32) -> Vec<i32> {
+        let needed = corrector.input_frames_needed(output_frames);
+        let input = corrector.input_mut(needed * 2);
+        for frame in input.chunks_exact_mut(2) {
+            frame[0] = *first;
+            frame[1] = -*first;
+            *first += 1;
+        }
+        let mut output = vec![0; output_frames * 2];
+        corrector.process(needed * 2, &mut output);
+        output
+    }
+
+    #[test]
+    fn test_drift_corrector_counts() {
+        let mut corrector = DriftCorrector::new(2, 512);
+        let input_ptr = corrector.input.as_ptr();
+        let input_len = corrector.input.len();
+
+        // 0 ppm：首次多读一帧（插值延迟），之后输入输出帧数相等，输出即输入
+        let mut next = 0;
+        assert_eq!(corrector.input_frames_needed(512), 513);
+        let output = run(&mut corrector, 512, &mut next);
+        assert_eq!(&output[..6], &[0, 0, 0, 0, 1, -1]);
+        assert_eq!(&output[1022..], &[510, -510]);
+        assert_eq!(corrector.input_frames_needed(512), 512);
+
+        // +1000 ppm：长期每输出 1000 帧多消耗 1 帧
+        corrector.set_ppm(1000.0);
+        let mut consumed = 0;
+        for _ in 0..100 {
+            consumed += corrector.input_frames_needed(500);
+            run(&mut corrector, 500, &mut next);
+        }
+        assert!((consumed as i64 - 50_050).abs() <= 1, "consumed {}", consumed);
+
+        // -500 ppm：每 2000 帧少消耗 1 帧
+        corrector.set_ppm(-500.0);
+        let mut consumed = 0;
+        for _ in 0..100 {
+            consumed += corrector.input_frames_needed(400);
+            run(&mut corrector, 400, &mut next);
+        }
+        assert!((consumed as i64 - 39_980).abs() <= 1, "consumed {}", consumed);
+
+        // 超出范围的校正量被限制；所需输入不超过预分配容量
+        corrector.set_ppm(1e9);
+        assert!(corrector.input_frames_needed(512) * 2 <= input_len);
+        assert_eq!(clamp_ppm(f64::NAN), 0.0);
+        assert_eq!(clamp_ppm(-5000.0), -MAX_DRIFT_PPM);
+
+        // 处理过程中没有重新分配输入缓冲
+        assert_eq!(corrector.input.as_ptr(), input_ptr);
+        assert_eq!(corrector.input.len(), input_len);
+    }
+
+    #[test]
+    fn test_drift_corrector_interpolates() {
+        // 输出位于相邻输入帧之间，输出单调、不越界（递增斜坡）
+        let mut corrector = DriftCorrector::new(2, 256);
+        corrector.set_ppm(MAX_DRIFT_PPM);
+        let mut next = 0;
+        let mut last = i32::MIN;
+        for _ in 0..10 {
+            let output = run(&mut corrector, 256, &mut next);
+            for frame in output.chunks_exact(2) {
+                assert!(frame[0] >= last);
+                assert_eq!(frame[1], -frame[0]);
+                last = frame[0];
+            }
+        }
+        assert!(last < next);
+
+        // reset 后重新读入首帧
+        corrector.reset();
+        assert_eq!(corrector.input_frames_needed(0), 1);
+    }
+}
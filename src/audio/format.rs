@@ -15,6 +15,81 @@ pub enum OutputLayout {
     NonInterleaved,
 }
 
+/// 样本字节序
+///
+/// WAV / 原始 PCM 为 little-endian，AIFF 为 big-endian
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// 解码 16-bit 有符号样本
+    #[inline]
+    fn read_i16(self, b: [u8; 2]) -> i16 {
+        match self {
+            Self::Little => i16::from_le_bytes(b),
+            Self::Big => i16::from_be_bytes(b),
+        }
+    }
+
+    /// 编码 16-bit 有符号样本
+    #[inline]
+    fn write_i16(self, v: i16) -> [u8; 2] {
+        match self {
+            Self::Little => v.to_le_bytes(),
+            Self::Big => v.to_be_bytes(),
+        }
+    }
+
+    /// 解码 24-bit 有符号样本（符号扩展到 i32，未左对齐）
+    #[inline]
+    fn read_i24(self, b: [u8; 3]) -> i32 {
+        // 按字节序取出 LSB/中间字节/MSB（MSB 含符号位）
+        let (lo, mid, hi) = match self {
+            Self::Little => (b[0], b[1], b[2]),
+            Self::Big => (b[2], b[1], b[0]),
+        };
+        let raw = (lo as i32) | ((mid as i32) << 8) | ((hi as i32) << 16);
+
+        // 符号扩展 24-bit → 32-bit
+        // 先左移把符号位移到 bit31，再算术右移恢复
+        (raw << 8) >> 8
+    }
+
+    /// 编码 24-bit 有符号样本（输入为未左对齐的 24-bit 值）
+    #[inline]
+    fn write_i24(self, v: i32) -> [u8; 3] {
+        let lo = (v & 0xFF) as u8;
+        let mid = ((v >> 8) & 0xFF) as u8;
+        let hi = ((v >> 16) & 0xFF) as u8;
+        match self {
+            Self::Little => [lo, mid, hi],
+            Self::Big => [hi, mid, lo],
+        }
+    }
+
+    /// 解码 32-bit 有符号样本
+    #[inline]
+    fn read_i32(self, b: [u8; 4]) -> i32 {
+        match self {
+            Self::Little => i32::from_le_bytes(b),
+            Self::Big => i32::from_be_bytes(b),
+        }
+    }
+
+    /// 编码 32-bit 有符号样本
+    #[inline]
+    fn write_i32(self, v: i32) -> [u8; 4] {
+        match self {
+            Self::Little => v.to_le_bytes(),
+            Self::Big => v.to_be_bytes(),
+        }
+    }
+}
+
 /// 音频格式
 #[derive(Clone, Copy, Debug)]
 pub struct AudioFormat {
@@ -22,19 +97,28 @@ pub struct AudioFormat {
     pub channels: u16,
     pub bits_per_sample: u16,
     pub layout: OutputLayout,
+    /// 字节编解码使用的字节序（默认 little-endian）
+    pub endian: Endian,
 }
 
 impl AudioFormat {
-    /// 创建新的音频格式
+    /// 创建新的音频格式（little-endian）
     pub fn new(sample_rate: u32, channels: u16, bits_per_sample: u16) -> Self {
         Self {
             sample_rate,
             channels,
             bits_per_sample,
             layout: OutputLayout::default(),
+            endian: Endian::default(),
         }
     }
 
+    /// 指定字节序
+    pub fn with_endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
     /// 每帧的样本数（= 声道数）
     #[inline]
     pub fn samples_per_frame(&self) -> usize {
@@ -55,19 +139,19 @@ impl AudioFormat {
 
     /// 将原始字节解码为 i32 样本（左对齐到 32-bit）
     ///
-    /// 内部表示：所有位深统一左对齐到 i32 的高位
+    /// 按 `endian` 解释字节序。内部表示：所有位深统一左对齐到 i32 的高位
     /// - 16-bit: 占据 bit[31:16]，bit[15:0] = 0
     /// - 24-bit: 占据 bit[31:8]，bit[7:0] = 0
     /// - 32-bit: 占据 bit[31:0]
     pub fn bytes_to_samples(&self, bytes: &[u8], output: &mut [i32]) -> usize {
+        let endian = self.endian;
         match self.bits_per_sample {
             16 => {
                 for (i, chunk) in bytes.chunks_exact(2).enumerate() {
                     if i >= output.len() {
                         break;
                     }
-                    // little-endian 16-bit signed
-                    let sample = i16::from_le_bytes([chunk[0], chunk[1]]);
+                    let sample = endian.read_i16([chunk[0], chunk[1]]);
                     // 左对齐：16-bit → 占据 i32 高 16 位
                     output[i] = (sample as i32) << 16;
                 }
@@ -78,17 +162,7 @@ impl AudioFormat {
                     if i >= output.len() {
                         break;
                     }
-
-                    // little-endian 24-bit 解码
-                    // chunk[0] = LSB, chunk[2] = MSB (含符号位)
-                    let raw = (chunk[0] as i32)
-                        | ((chunk[1] as i32) << 8)
-                        | ((chunk[2] as i32) << 16);
-
-                    // 符号扩展 24-bit → 32-bit
-                    // 先左移把符号位移到 bit31，再算术右移恢复
-                    let signed = (raw << 8) >> 8;
-
+                    let signed = endian.read_i24([chunk[0], chunk[1], chunk[2]]);
                     // 左对齐：24-bit → 占据 i32 高 24 位
                     output[i] = signed << 8;
                 }
@@ -99,8 +173,8 @@ impl AudioFormat {
                     if i >= output.len() {
                         break;
                     }
-                    // little-endian 32-bit signed，已经是完整 i32
-                    output[i] = i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                    // 32-bit signed，已经是完整 i32
+                    output[i] = endian.read_i32([chunk[0], chunk[1], chunk[2], chunk[3]]);
                 }
                 (bytes.len() / 4).min(output.len())
             }
@@ -108,8 +182,9 @@ impl AudioFormat {
         }
     }
 
-    /// 将 i32 样本（左对齐）打包为输出字节
+    /// 将 i32 样本（左对齐）按 `endian` 打包为输出字节
     pub fn samples_to_bytes(&self, samples: &[i32], output: &mut [u8]) {
+        let endian = self.endian;
         match self.bits_per_sample {
             16 => {
                 for (i, &sample) in samples.iter().enumerate() {
@@ -117,10 +192,8 @@ impl AudioFormat {
                         break;
                     }
                     // 右移 16 位取回 16-bit
-                    let val = (sample >> 16) as i16;
-                    let bytes = val.to_le_bytes();
-                    output[i * 2] = bytes[0];
-                    output[i * 2 + 1] = bytes[1];
+                    let bytes = endian.write_i16((sample >> 16) as i16);
+                    output[i * 2..i * 2 + 2].copy_from_slice(&bytes);
                 }
             }
             24 => {
@@ -129,11 +202,8 @@ impl AudioFormat {
                         break;
                     }
                     // 右移 8 位取回 24-bit（带符号）
-                    let v = sample >> 8;
-                    // little-endian 输出
-                    output[i * 3] = (v & 0xFF) as u8;
-                    output[i * 3 + 1] = ((v >> 8) & 0xFF) as u8;
-                    output[i * 3 + 2] = ((v >> 16) & 0xFF) as u8;
+                    let bytes = endian.write_i24(sample >> 8);
+                    output[i * 3..i * 3 + 3].copy_from_slice(&bytes);
                 }
             }
             32 => {
@@ -141,7 +211,7 @@ impl AudioFormat {
                     if i * 4 + 3 >= output.len() {
                         break;
                     }
-                    let bytes = sample.to_le_bytes();
+                    let bytes = endian.write_i32(sample);
                     output[i * 4..i * 4 + 4].copy_from_slice(&bytes);
                 }
             }
@@ -160,6 +230,7 @@ impl AudioFormat {
         output: &mut [u8],
     ) {
         let bytes_per_sample = self.bytes_per_sample();
+        let endian = self.endian;
         let mut frame_idx = 0;
 
         for sample_idx in (channel..samples.len()).step_by(channels) {
@@ -172,19 +243,15 @@ impl AudioFormat {
 
             match self.bits_per_sample {
                 16 => {
-                    let val = (sample >> 16) as i16;
-                    let bytes = val.to_le_bytes();
-                    output[offset] = bytes[0];
-                    output[offset + 1] = bytes[1];
+                    let bytes = endian.write_i16((sample >> 16) as i16);
+                    output[offset..offset + 2].copy_from_slice(&bytes);
                 }
                 24 => {
-                    let v = sample >> 8;
-                    output[offset] = (v & 0xFF) as u8;
-                    output[offset + 1] = ((v >> 8) & 0xFF) as u8;
-                    output[offset + 2] = ((v >> 16) & 0xFF) as u8;
+                    let bytes = endian.write_i24(sample >> 8);
+                    output[offset..offset + 3].copy_from_slice(&bytes);
                 }
                 32 => {
-                    let bytes = sample.to_le_bytes();
+                    let bytes = endian.write_i32(sample);
                     output[offset..offset + 4].copy_from_slice(&bytes);
                 }
                 _ => {}
@@ -295,4 +362,121 @@ mod tests {
         format.samples_to_bytes(&samples, &mut output_bytes);
         assert_eq!(input_bytes, output_bytes);
     }
+
+    #[test]
+    fn test_16bit_roundtrip_big_endian() {
+        let format = AudioFormat::new(48000, 1, 16).with_endian(Endian::Big);
+
+        // 正数
+        let input_bytes = [0x40, 0x00]; // +16384
+        let mut samples = [0i32; 1];
+        format.bytes_to_samples(&input_bytes, &mut samples);
+        assert_eq!(samples[0], 16384 << 16);
+
+        let mut output_bytes = [0u8; 2];
+        format.samples_to_bytes(&samples, &mut output_bytes);
+        assert_eq!(input_bytes, output_bytes);
+
+        // 负数
+        let input_bytes = [0xC0, 0x00]; // -16384
+        format.bytes_to_samples(&input_bytes, &mut samples);
+        assert!(samples[0] < 0, "negative sample should be negative after decode");
+
+        format.samples_to_bytes(&samples, &mut output_bytes);
+        assert_eq!(input_bytes, output_bytes);
+    }
+
+    #[test]
+    fn test_24bit_roundtrip_big_endian() {
+        let format = AudioFormat::new(96000, 1, 24).with_endian(Endian::Big);
+
+        // 测试正数
+        let input_bytes = [0x40, 0x00, 0x00]; // +0x400000
+        let mut samples = [0i32; 1];
+        format.bytes_to_samples(&input_bytes, &mut samples);
+        assert_eq!(samples[0], 0x400000 << 8);
+
+        let mut output_bytes = [0u8; 3];
+        format.samples_to_bytes(&samples, &mut output_bytes);
+        assert_eq!(input_bytes, output_bytes);
+
+        // 测试负数（MSB 在首字节）
+        let input_bytes = [0xC0, 0x00, 0x00]; // -0x400000
+        format.bytes_to_samples(&input_bytes, &mut samples);
+        assert!(
+            samples[0] < 0,
+            "negative sample should be negative after decode"
+        );
+
+        format.samples_to_bytes(&samples, &mut output_bytes);
+        assert_eq!(
+            input_bytes, output_bytes,
+            "24-bit big-endian roundtrip failed for negative"
+        );
+    }
+
+    #[test]
+    fn test_24bit_sign_extend_big_endian() {
+        let format = AudioFormat::new(96000, 1, 24).with_endian(Endian::Big);
+
+        // 最大正值: 0x7FFFFF
+        let max_pos = [0x7F, 0xFF, 0xFF];
+        let mut samples = [0i32; 1];
+        format.bytes_to_samples(&max_pos, &mut samples);
+        assert!(samples[0] > 0);
+        assert_eq!(samples[0], 0x7FFFFF << 8);
+
+        // 最小负值: 0x800000 = -8388608
+        let min_neg = [0x80, 0x00, 0x00];
+        format.bytes_to_samples(&min_neg, &mut samples);
+        assert!(samples[0] < 0);
+        assert_eq!(samples[0], (-8388608i32) << 8);
+
+        // -1: 0xFFFFFF
+        let neg_one = [0xFF, 0xFF, 0xFF];
+        format.bytes_to_samples(&neg_one, &mut samples);
+        assert_eq!(samples[0], (-1i32) << 8);
+
+        // 低字节为 0x80 不应被误当作符号位
+        let low_bit = [0x00, 0x00, 0x80];
+        format.bytes_to_samples(&low_bit, &mut samples);
+        assert_eq!(samples[0], 0x80 << 8);
+    }
+
+    #[test]
+    fn test_32bit_roundtrip_big_endian() {
+        let format = AudioFormat::new(192000, 1, 32).with_endian(Endian::Big);
+
+        // 正数
+        let input_bytes = [0x40, 0x00, 0x00, 0x00];
+        let mut samples = [0i32; 1];
+        format.bytes_to_samples(&input_bytes, &mut samples);
+        assert_eq!(samples[0], 0x40000000);
+
+        let mut output_bytes = [0u8; 4];
+        format.samples_to_bytes(&samples, &mut output_bytes);
+        assert_eq!(input_bytes, output_bytes);
+
+        // 负数
+        let input_bytes = [0xC0, 0x00, 0x00, 0x00];
+        format.bytes_to_samples(&input_bytes, &mut samples);
+        assert!(samples[0] < 0);
+
+        format.samples_to_bytes(&samples, &mut output_bytes);
+        assert_eq!(input_bytes, output_bytes);
+    }
+
+    #[test]
+    fn test_extract_channel_big_endian() {
+        let format = AudioFormat::new(48000, 2, 24).with_endian(Endian::Big);
+        let samples = [0x123456 << 8, -(0x010203 << 8), 0x7FFFFF << 8, 0];
+
+        let mut left = [0u8; 6];
+        format.extract_channel_to_bytes(&samples, 0, 2, &mut left);
+        assert_eq!(left, [0x12, 0x34, 0x56, 0x7F, 0xFF, 0xFF]);
+
+        let mut right = [0u8; 6];
+        format.extract_channel_to_bytes(&samples, 1, 2, &mut right);
+        assert_eq!(right, [0xFE, 0xFD, 0xFD, 0x00, 0x00, 0x00]);
+    }
 }
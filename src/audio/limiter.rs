@@ -0,0 +1,184 @@
+//! 软拐点峰值限幅器
+//!
+//! 软件增益 > 1（响度归一化提升）会把样本推过满幅，i32 转换只能硬削波。
+//! 限幅器在 f32 域施加增益，再按每帧峰值压到阈值以下：
+//! 无前瞻，瞬时起控（输出不会超过阈值），指数释放，拐点内平滑过渡。
+//! 关闭时由调用方旁路，保持 bit-perfect；不需要衰减的帧原样保留
+
+/// 默认阈值（dBFS）
+pub const DEFAULT_THRESHOLD_DBFS: f32 = -1.0;
+
+/// 阈值下限（dBFS）
+pub const MIN_THRESHOLD_DBFS: f32 = -24.0;
+
+/// 软拐点宽度（dB），以阈值为中心
+const KNEE_DB: f32 = 2.0;
+
+/// 释放时间常数（秒）
+const RELEASE_SECS: f32 = 0.1;
+
+/// 低于该衰减量（dB）视为已完全释放
+const MIN_REDUCTION_DB: f32 = 1e-4;
+
+/// i32 满幅
+const FULL_SCALE: f32 = 2_147_483_648.0;
+
+/// 阈值限制到 MIN_THRESHOLD_DBFS-0 dBFS（非法值取默认）
+pub fn threshold_dbfs(threshold: f32) -> f32 {
+    if threshold.is_finite() {
+        threshold.clamp(MIN_THRESHOLD_DBFS, 0.0)
+    } else {
+        DEFAULT_THRESHOLD_DBFS
+    }
+}
+
+/// 静态曲线：峰值电平（dBFS）→ 所需衰减（dB，>= 0）
+///
+/// 拐点以下不衰减，拐点以上输出恰为阈值，拐点内按二次曲线过渡
+pub fn gain_reduction_db(level_db: f32, threshold_db: f32) -> f32 {
+    let knee_start = threshold_db - KNEE_DB / 2.0;
+    if level_db <= knee_start {
+        0.0
+    } else if level_db >= threshold_db + KNEE_DB / 2.0 {
+        level_db - threshold_db
+    } else {
+        let over = level_db - knee_start;
+        over * over / (2.0 * KNEE_DB)
+    }
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// 限幅器状态（每曲目创建一次，处理时不分配）
+pub struct Limiter {
+    channels: usize,
+    /// 每帧的释放系数（dB 域）
+    release: f32,
+    /// 当前衰减量（dB）
+    envelope_db: f32,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            channels: channels.max(1),
+            release: (-1.0 / (RELEASE_SECS * sample_rate as f32)).exp(),
+            envelope_db: 0.0,
+        }
+    }
+
+    /// 清空释放状态（seek 后调用）
+    pub fn reset(&mut self) {
+        self.envelope_db = 0.0;
+    }
+
+    /// 施加线性增益 `gain` 后限幅，返回本块的最大衰减量（dB）
+    ///
+    /// 所有声道共用同一衰减（按帧峰值），不改变声像
+    pub fn process(&mut self, samples: &mut [i32], gain: f32, threshold_db: f32) -> f32 {
+        let knee_start = db_to_linear(threshold_db - KNEE_DB / 2.0);
+        let mut max_reduction = 0.0f32;
+
+        for frame in samples.chunks_exact_mut(self.channels) {
+            let peak = frame.iter().fold(0.0f32, |m, &s| m.max((s as f32).abs()));
+            let level = peak * gain / FULL_SCALE;
+
+            let target = if level > knee_start {
+                gain_reduction_db(20.0 * level.log10(), threshold_db)
+            } else {
+                0.0
+            };
+            // 瞬时起控，指数释放
+            self.envelope_db = target.max(self.envelope_db * self.release);
+            if self.envelope_db < MIN_REDUCTION_DB {
+                self.envelope_db = 0.0;
+            }
+            max_reduction = max_reduction.max(self.envelope_db);
+
+            let frame_gain = gain * db_to_linear(-self.envelope_db);
+            if frame_gain != 1.0 {
+                for sample in frame.iter_mut() {
+                    // f32 → i32 的 `as` 转换本身即饱和
+                    *sample = (*sample as f32 * frame_gain) as i32;
+                }
+            }
+        }
+        max_reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_below_threshold_is_unchanged() {
+        // -12 dBFS 的信号，阈值 -1 dBFS：逐位不变
+        let mut limiter = Limiter::new(48000, 2);
+        let input: Vec<i32> = (0..512)
+            .map(|i| ((i as f32 * 0.05).sin() * 0.25 * FULL_SCALE) as i32 | 1)
+            .collect();
+        let mut samples = input.clone();
+        assert_eq!(limiter.process(&mut samples, 1.0, -1.0), 0.0);
+        assert_eq!(samples, input);
+    }
+
+    #[test]
+    fn test_over_threshold_is_limited_without_overshoot() {
+        // 满幅方波 + 6 dB 增益，阈值 -1 dBFS
+        let mut limiter = Limiter::new(48000, 2);
+        let gain = db_to_linear(6.0);
+        let mut samples: Vec<i32> = (0..4800)
+            .map(|i| {
+                if (i / 96) % 2 == 0 {
+                    i32::MAX
+                } else {
+                    -i32::MAX
+                }
+            })
+            .collect();
+        let reduction = limiter.process(&mut samples, gain, -1.0);
+
+        let ceiling = db_to_linear(-1.0) * FULL_SCALE;
+        let peak = samples
+            .iter()
+            .map(|&s| (s as f32).abs())
+            .fold(0.0, f32::max);
+        assert!(
+            peak <= ceiling * 1.000_01,
+            "peak {} ceiling {}",
+            peak,
+            ceiling
+        );
+        assert!(peak >= ceiling * 0.999, "peak {} ceiling {}", peak, ceiling);
+        assert!((reduction - 7.0).abs() < 0.01, "reduction {}", reduction);
+    }
+
+    #[test]
+    fn test_full_scale_negative() {
+        // 满幅负值：提升后限幅到阈值，不回绕成正值
+        let mut limiter = Limiter::new(48000, 2);
+        let mut samples = vec![i32::MIN; 2048];
+        limiter.process(&mut samples, db_to_linear(6.0), -1.0);
+        assert!(samples.iter().all(|&s| s < 0));
+        let ceiling = db_to_linear(-1.0) * FULL_SCALE;
+        let last = (samples[samples.len() - 1] as f32).abs();
+        assert!(last <= ceiling * 1.000_01, "{} vs {}", last, ceiling);
+    }
+
+    #[test]
+    fn test_gain_curve() {
+        assert_eq!(gain_reduction_db(-10.0, -1.0), 0.0);
+        assert_eq!(gain_reduction_db(-2.0, -1.0), 0.0);
+        // 拐点中心：阈值处衰减 knee/8
+        assert!((gain_reduction_db(-1.0, -1.0) - KNEE_DB / 8.0).abs() < 1e-6);
+        assert_eq!(gain_reduction_db(3.0, -1.0), 4.0);
+
+        assert_eq!(threshold_dbfs(-3.0), -3.0);
+        assert_eq!(threshold_dbfs(2.0), 0.0);
+        assert_eq!(threshold_dbfs(-100.0), MIN_THRESHOLD_DBFS);
+        assert_eq!(threshold_dbfs(f32::NAN), DEFAULT_THRESHOLD_DBFS);
+    }
+}
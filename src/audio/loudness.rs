@@ -0,0 +1,459 @@
+//! 响度测量（EBU R128 / ITU-R BS.1770）
+//!
+//! 离线分析用（`roger-player scan`），不在播放路径上运行：
+//! - K 计权：高架滤波 + 高通滤波两级 biquad，系数按采样率计算
+//! - 积分响度：400ms 块、75% 重叠，-70 LUFS 绝对门限 + -10 LU 相对门限
+//! - 真峰值：4 倍过采样（`PolyphaseResampler`，Blackman 窗 sinc）
+//!
+//! 例外：`TruePeakMeter` 可选地在解码线程上运行（`--true-peak`），创建后不分配
+//!
+//! 输入为左对齐的 i32 交织样本（与解码器输出一致）
+
+use std::f64::consts::PI;
+
+use super::resample::PolyphaseResampler;
+
+/// i32 满幅 → 1.0
+const I32_SCALE: f64 = 1.0 / 2_147_483_648.0;
+
+/// 绝对门限（LUFS）
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+
+/// 相对门限（相对于绝对门限后的响度，LU）
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// 真峰值过采样倍数
+const OVERSAMPLE: usize = 4;
+
+/// 过采样前右移的位数：留出 12 dB 余量，采样点之间超过满幅的峰值不被 i32 截断
+const HEADROOM_SHIFT: u32 = 2;
+
+/// 每次送入过采样器的帧数（缓冲区按此预留，处理时不分配）
+const OVERSAMPLE_CHUNK_FRAMES: usize = 1024;
+
+/// 二阶 IIR 滤波器（Direct Form I）
+#[derive(Clone, Copy, Debug)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+impl Biquad {
+    fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Self {
+        Self {
+            b0,
+            b1,
+            b2,
+            a1,
+            a2,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+
+    /// 系数 (b0, b1, b2, a1, a2)
+    pub fn coefficients(&self) -> (f64, f64, f64, f64, f64) {
+        (self.b0, self.b1, self.b2, self.a1, self.a2)
+    }
+}
+
+/// K 计权滤波器（BS.1770 两级，任意采样率）
+#[derive(Clone, Copy, Debug)]
+pub struct KWeighting {
+    shelf: Biquad,
+    highpass: Biquad,
+}
+
+impl KWeighting {
+    pub fn new(sample_rate: u32) -> Self {
+        let fs = sample_rate as f64;
+
+        // 第一级：高架滤波（模拟头部声学效应）
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (PI * f0 / fs).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+            2.0 * (k * k - 1.0) / a0,
+            (1.0 - k / q + k * k) / a0,
+        );
+
+        // 第二级：RLB 高通
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (PI * f0 / fs).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let highpass = Biquad::new(
+            1.0,
+            -2.0,
+            1.0,
+            2.0 * (k * k - 1.0) / a0,
+            (1.0 - k / q + k * k) / a0,
+        );
+
+        Self { shelf, highpass }
+    }
+
+    #[inline]
+    pub fn process(&mut self, x: f64) -> f64 {
+        self.highpass.process(self.shelf.process(x))
+    }
+
+    /// 两级滤波器（高架、高通）
+    pub fn stages(&self) -> (&Biquad, &Biquad) {
+        (&self.shelf, &self.highpass)
+    }
+}
+
+/// 声道权重（5.1 布局 L R C LFE Ls Rs：LFE 不计入，环绕声道 +1.5dB）
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    if channels == 6 {
+        match channel {
+            3 => 0.0,
+            4 | 5 => 1.41,
+            _ => 1.0,
+        }
+    } else {
+        1.0
+    }
+}
+
+/// 均方功率 → LUFS
+#[inline]
+fn power_to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// 按 BS.1770 门限计算积分响度
+///
+/// `block_powers` 为各 400ms 块的声道加权均方功率；
+/// 全部被门限排除时返回 None
+pub fn gated_loudness(block_powers: &[f64]) -> Option<f64> {
+    let mean_above = |threshold: f64| {
+        let (sum, count) = block_powers
+            .iter()
+            .filter(|&&p| p > 0.0 && power_to_lufs(p) > threshold)
+            .fold((0.0, 0usize), |(s, c), &p| (s + p, c + 1));
+        (count > 0).then(|| sum / count as f64)
+    };
+
+    let absolute = mean_above(ABSOLUTE_GATE_LUFS)?;
+    let relative_threshold = power_to_lufs(absolute) + RELATIVE_GATE_LU;
+    mean_above(relative_threshold).map(power_to_lufs)
+}
+
+/// 响度计（积分响度 + 真峰值）
+pub struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<KWeighting>,
+    true_peak: TruePeakMeter,
+    weights: Vec<f64>,
+    /// 100ms 子块帧数
+    step_frames: usize,
+    /// 当前子块已累计的帧数和加权平方和
+    step_pos: usize,
+    step_sum: f64,
+    /// 最近 4 个子块的平方和（组成一个 400ms 块）
+    recent_steps: [f64; 4],
+    steps_seen: usize,
+    /// 各 400ms 块的均方功率
+    block_powers: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        Self {
+            channels,
+            filters: vec![KWeighting::new(sample_rate); channels],
+            true_peak: TruePeakMeter::new(channels),
+            weights: (0..channels).map(|ch| channel_weight(ch, channels)).collect(),
+            step_frames: (sample_rate as usize / 10).max(1),
+            step_pos: 0,
+            step_sum: 0.0,
+            recent_steps: [0.0; 4],
+            steps_seen: 0,
+            block_powers: Vec::new(),
+        }
+    }
+
+    /// 输入交织样本（不足一帧的尾部被忽略）
+    pub fn add_samples(&mut self, samples: &[i32]) {
+        self.true_peak.process(samples);
+        for frame in samples.chunks_exact(self.channels) {
+            let mut sum = 0.0;
+            for (ch, &sample) in frame.iter().enumerate() {
+                let x = sample as f64 * I32_SCALE;
+                let y = self.filters[ch].process(x);
+                sum += self.weights[ch] * y * y;
+            }
+            self.step_sum += sum;
+            self.step_pos += 1;
+
+            if self.step_pos == self.step_frames {
+                self.recent_steps[self.steps_seen % 4] = self.step_sum;
+                self.steps_seen += 1;
+                self.step_pos = 0;
+                self.step_sum = 0.0;
+                if self.steps_seen >= 4 {
+                    let block_sum: f64 = self.recent_steps.iter().sum();
+                    self.block_powers.push(block_sum / (4 * self.step_frames) as f64);
+                }
+            }
+        }
+    }
+
+    /// 积分响度（LUFS）；不足 400ms 或全部低于门限时返回 None
+    pub fn integrated_lufs(&self) -> Option<f64> {
+        gated_loudness(&self.block_powers)
+    }
+
+    /// 真峰值（dBTP）；全静音时返回 None
+    pub fn true_peak_dbtp(&self) -> Option<f64> {
+        peak_to_db(self.true_peak.true_peak())
+    }
+}
+
+/// 播放中的真峰值表（交织样本，逐声道 4 倍过采样）
+///
+/// `LoudnessMeter` 也用它测真峰值；同时记录样本峰值，二者之差即采样点之间的过冲
+pub struct TruePeakMeter {
+    channels: usize,
+    oversampler: PolyphaseResampler,
+    /// 右移后的输入块与过采样输出（创建时预留）
+    scaled: Vec<i32>,
+    oversampled: Vec<i32>,
+    true_peak: f64,
+    sample_peak: f64,
+}
+
+impl TruePeakMeter {
+    pub fn new(channels: usize) -> Self {
+        let channels = channels.max(1);
+        let oversampler = PolyphaseResampler::new(channels, OVERSAMPLE, 1);
+        let oversampled_len = oversampler.max_output_frames(OVERSAMPLE_CHUNK_FRAMES) * channels;
+        Self {
+            channels,
+            oversampler,
+            scaled: Vec::with_capacity(OVERSAMPLE_CHUNK_FRAMES * channels),
+            oversampled: Vec::with_capacity(oversampled_len),
+            true_peak: 0.0,
+            sample_peak: 0.0,
+        }
+    }
+
+    /// 输入交织样本（不足一帧的尾部被忽略）
+    pub fn process(&mut self, samples: &[i32]) {
+        let whole = samples.len() / self.channels * self.channels;
+        for chunk in samples[..whole].chunks(OVERSAMPLE_CHUNK_FRAMES * self.channels) {
+            let peak = chunk.iter().map(|&s| s.unsigned_abs()).max().unwrap_or(0);
+            self.sample_peak = self.sample_peak.max(peak as f64 * I32_SCALE);
+
+            self.scaled.clear();
+            self.scaled.extend(chunk.iter().map(|&s| s >> HEADROOM_SHIFT));
+            self.oversampler.process(&self.scaled, &mut self.oversampled);
+            let peak = self.oversampled.iter().map(|&s| s.unsigned_abs()).max().unwrap_or(0);
+            let peak = (peak as u64) << HEADROOM_SHIFT;
+            self.true_peak = self.true_peak.max(peak as f64 * I32_SCALE);
+        }
+    }
+
+    /// seek 后调用：丢弃插值历史，峰值继续累计
+    pub fn clear_history(&mut self) {
+        self.oversampler.reset();
+    }
+
+    /// 真峰值（线性，1.0 = 0 dBTP）
+    pub fn true_peak(&self) -> f64 {
+        self.true_peak
+    }
+
+    /// 样本峰值（线性，1.0 = 0 dBFS）
+    pub fn sample_peak(&self) -> f64 {
+        self.sample_peak
+    }
+}
+
+/// 线性峰值转 dB（0 返回 None）
+pub fn peak_to_db(peak: f64) -> Option<f64> {
+    (peak > 0.0).then(|| 20.0 * peak.log10())
+}
+
+/// 计算归一化增益（dB）
+///
+/// 增益 = 目标响度 - 积分响度，并限制在真峰值不超过 -1 dBTP
+pub fn normalization_gain_db(integrated_lufs: f64, true_peak_dbtp: f64, target_lufs: f64) -> f64 {
+    let gain = target_lufs - integrated_lufs;
+    let headroom = -1.0 - true_peak_dbtp;
+    gain.min(headroom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 生成交织正弦（峰值 amplitude，满幅 1.0）
+    fn sine(sample_rate: u32, channels: usize, freq: f64, amplitude: f64, secs: f64) -> Vec<i32> {
+        let frames = (sample_rate as f64 * secs) as usize;
+        let mut samples = Vec::with_capacity(frames * channels);
+        for n in 0..frames {
+            let x = amplitude * (2.0 * PI * freq * n as f64 / sample_rate as f64).sin();
+            let s = (x * 2_147_483_647.0) as i32;
+            samples.extend(std::iter::repeat_n(s, channels));
+        }
+        samples
+    }
+
+    #[test]
+    fn test_k_weighting_coefficients_48k() {
+        // BS.1770-4 表 1/表 2 给出的 48kHz 系数
+        let k = KWeighting::new(48000);
+        let (shelf, highpass) = k.stages();
+        let expected_shelf = (
+            1.53512485958697,
+            -2.69169618940638,
+            1.19839281085285,
+            -1.69065929318241,
+            0.73248077421585,
+        );
+        let (b0, b1, b2, a1, a2) = shelf.coefficients();
+        for (got, want) in [b0, b1, b2, a1, a2].iter().zip([
+            expected_shelf.0,
+            expected_shelf.1,
+            expected_shelf.2,
+            expected_shelf.3,
+            expected_shelf.4,
+        ]) {
+            assert!((got - want).abs() < 1e-9, "{} != {}", got, want);
+        }
+
+        let (b0, b1, b2, a1, a2) = highpass.coefficients();
+        assert_eq!((b0, b1, b2), (1.0, -2.0, 1.0));
+        assert!((a1 - -1.99004745483398).abs() < 1e-9);
+        assert!((a2 - 0.99007225036621).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stereo_sine_loudness() {
+        // EBU Tech 3341 用例：1kHz 立体声正弦 -23 dBFS → -23 LUFS
+        let amplitude = 10f64.powf(-23.0 / 20.0);
+        let mut meter = LoudnessMeter::new(48000, 2);
+        meter.add_samples(&sine(48000, 2, 1000.0, amplitude, 5.0));
+        let lufs = meter.integrated_lufs().unwrap();
+        assert!((lufs - -23.0).abs() < 0.1, "{}", lufs);
+    }
+
+    #[test]
+    fn test_gating() {
+        let amplitude = 10f64.powf(-20.0 / 20.0);
+        let quiet = 10f64.powf(-40.0 / 20.0);
+
+        // 静音段被绝对门限排除（跨越边界的 3 个块只含部分信号，略拉低结果）
+        let mut meter = LoudnessMeter::new(48000, 2);
+        meter.add_samples(&sine(48000, 2, 1000.0, amplitude, 5.0));
+        meter.add_samples(&vec![0; 48000 * 2 * 5]);
+        let lufs = meter.integrated_lufs().unwrap();
+        assert!((lufs - -20.0).abs() < 0.2, "{}", lufs);
+
+        // -40 dB 段低于相对门限（约 -33 LUFS），同样被排除
+        let mut meter = LoudnessMeter::new(48000, 2);
+        meter.add_samples(&sine(48000, 2, 1000.0, amplitude, 5.0));
+        meter.add_samples(&sine(48000, 2, 1000.0, quiet, 5.0));
+        let lufs = meter.integrated_lufs().unwrap();
+        assert!((lufs - -20.0).abs() < 0.2, "{}", lufs);
+
+        // 直接验证门限函数：-80 LUFS 块被绝对门限排除
+        let p = |lufs: f64| 10f64.powf((lufs + 0.691) / 10.0);
+        assert!((gated_loudness(&[p(-20.0), p(-80.0)]).unwrap() - -20.0).abs() < 1e-9);
+        assert_eq!(gated_loudness(&[p(-80.0), 0.0]), None);
+        assert_eq!(gated_loudness(&[]), None);
+
+        // 全静音
+        let mut meter = LoudnessMeter::new(48000, 2);
+        meter.add_samples(&vec![0; 48000 * 2]);
+        assert_eq!(meter.integrated_lufs(), None);
+        assert_eq!(meter.true_peak_dbtp(), None);
+    }
+
+    #[test]
+    fn test_true_peak_inter_sample() {
+        // fs/4 正弦、相位 45°：采样点只有峰值的 0.707（-3dB），真峰值应接近 0 dBTP
+        let sample_rate = 48000;
+        let amplitude = 0.5;
+        let samples: Vec<i32> = (0..4800)
+            .map(|n| {
+                let x = amplitude * (PI / 2.0 * n as f64 + PI / 4.0).sin();
+                (x * 2_147_483_647.0) as i32
+            })
+            .collect();
+        let mut meter = LoudnessMeter::new(sample_rate, 1);
+        meter.add_samples(&samples);
+        let expected = 20.0 * amplitude.log10();
+        let tp = meter.true_peak_dbtp().unwrap();
+        assert!((tp - expected).abs() < 0.2, "{} vs {}", tp, expected);
+    }
+
+    #[test]
+    fn test_true_peak_meter_overs() {
+        // fs/4 正弦、相位 45°、峰值 +1.5 dB：采样点只有 -1.5 dBFS，采样点之间过冲到 0 dBTP 以上
+        let amplitude = 10f64.powf(1.5 / 20.0);
+        let samples: Vec<i32> = (0..4800)
+            .flat_map(|n| {
+                let x = amplitude * (PI / 2.0 * n as f64 + PI / 4.0).sin();
+                let s = (x * 2_147_483_647.0) as i32;
+                [s, s / 2]
+            })
+            .collect();
+        let mut meter = TruePeakMeter::new(2);
+        // 分块送入，结果与整块一致
+        for chunk in samples.chunks(1000) {
+            meter.process(chunk);
+        }
+        let sample_peak_db = peak_to_db(meter.sample_peak()).unwrap();
+        let true_peak_db = peak_to_db(meter.true_peak()).unwrap();
+        assert!(sample_peak_db <= 0.0, "sample peak {}", sample_peak_db);
+        assert!(true_peak_db > 0.0, "true peak {}", true_peak_db);
+        assert!((true_peak_db - 1.5).abs() < 0.2, "true peak {}", true_peak_db);
+
+        // 清空历史不丢失峰值；静音输入不产生峰值
+        meter.clear_history();
+        assert_eq!(peak_to_db(meter.true_peak()), Some(true_peak_db));
+        let silent = TruePeakMeter::new(2);
+        assert_eq!(peak_to_db(silent.true_peak()), None);
+    }
+
+    #[test]
+    fn test_normalization_gain() {
+        // 响亮曲目衰减到目标
+        assert_eq!(normalization_gain_db(-8.0, -0.1, -14.0), -6.0);
+        // 安静曲目提升受真峰值限制（峰值 -3 dBTP 最多提升 2dB）
+        assert_eq!(normalization_gain_db(-20.0, -3.0, -14.0), 2.0);
+    }
+}
@@ -6,14 +6,30 @@
 //! - Timing: Mach 时间相关函数
 //! - Stats: 播放统计
 //! - Output: Core Audio AUHAL 输出
+//! - DeviceSettings: 按设备 UID 记住的输出设置
+//! - Loudness: EBU R128 响度/真峰值测量（离线扫描）
+//! - Crossfeed: 耳机交叉馈送
+//! - Width: 立体声宽度（M/S 侧信号缩放）
+//! - Limiter: 软拐点峰值限幅（防止增益 > 1 削波）
+//! - Drift: IO 回调内的 ppm 级时钟漂移校正（可选）
+//! - Resample: 整数比多相 FIR 重采样（软件 SRC，播放路径仍由 CoreAudio 转换）
+//! - Tone: 测试信号发生器（`test-tone` 自检）
 
+pub mod crossfeed;
+pub mod device_settings;
+pub mod drift;
 pub mod format;
+pub mod limiter;
+pub mod loudness;
 pub mod output;
+pub mod resample;
 pub mod ring_buffer;
 pub mod stats;
 pub mod timing;
+pub mod tone;
+pub mod width;
 
-pub use format::AudioFormat;
-pub use output::{AudioOutput, OutputConfig, OutputError};
+pub use format::{AudioFormat, Endian};
+pub use output::{parse_device_uid, AudioOutput, OutputConfig, OutputError};
 pub use ring_buffer::RingBuffer;
 pub use stats::PlaybackStats;
@@ -9,12 +9,14 @@
 
 use std::ffi::c_void;
 use std::ptr;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 
 
+use super::device_settings::DeviceSettingsCache;
+use super::drift::{self, DriftCorrector};
 use super::format::{AudioFormat, OutputLayout};
-use super::ring_buffer::{CacheLine, RingBuffer};
+use super::ring_buffer::{exceeds_memlock_limit, memlock_limit, CacheLine, RingBuffer};
 use super::stats::PlaybackStats;
 
 /// Core Audio 类型定义
@@ -41,6 +43,7 @@ const K_AUDIO_DEVICE_PROPERTY_STREAM_CONFIGURATION: AudioObjectPropertySelector
 const K_AUDIO_STREAM_PROPERTY_PHYSICAL_FORMAT: AudioObjectPropertySelector = 0x70667420; // 'pft '
 const K_AUDIO_DEVICE_PROPERTY_TRANSPORT_TYPE: AudioObjectPropertySelector = 0x7472616E; // 'tran'
 const K_AUDIO_OBJECT_PROPERTY_NAME: AudioObjectPropertySelector = 0x6E616D65; // 'name'
+const K_AUDIO_DEVICE_PROPERTY_DEVICE_UID: AudioObjectPropertySelector = 0x75696420; // 'uid '
 
 // 设备能力查询属性
 const K_AUDIO_DEVICE_PROPERTY_BUFFER_FRAME_SIZE_RANGE: AudioObjectPropertySelector = 0x66737223; // 'fsr#'
@@ -114,6 +117,14 @@ struct AudioValueRange {
     maximum: f64,
 }
 
+/// 物理格式及其支持的采样率范围（`kAudioStreamPropertyAvailablePhysicalFormats` 的元素）
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct AudioStreamRangedDescription {
+    format: AudioStreamBasicDescription,
+    sample_rate_range: AudioValueRange,
+}
+
 #[repr(C)]
 struct AudioComponentDescription {
     component_type: u32,
@@ -144,6 +155,11 @@ type RenderCallback = extern "C" fn(
 const K_AUDIO_TIME_STAMP_SAMPLE_TIME_VALID: u32 = 1;
 const K_AUDIO_TIME_STAMP_HOST_TIME_VALID: u32 = 2;
 
+/// 当前持有独占模式的设备 ID（0 = 无）
+///
+/// 供退出兜底清理读取（`AudioOutput::release_hog_mode_on_exit`）
+static HOG_DEVICE: AtomicU32 = AtomicU32::new(0);
+
 #[repr(C)]
 struct AudioTimeStamp {
     sample_time: f64,
@@ -181,10 +197,22 @@ struct SMPTETime {
     frames: i16,
 }
 
+/// 变长结构：CoreAudio 声明为 `mBuffers[1]`，实际个数为 `number_buffers`
+/// （非交织多声道时每声道一个），只能通过 [`audio_buffers`] 访问
 #[repr(C)]
 struct AudioBufferList {
     number_buffers: u32,
-    buffers: [AudioBuffer; 2], // 支持最多 2 个 buffer（立体声非交织）
+    buffers: [AudioBuffer; 1],
+}
+
+/// 按 `number_buffers` 取出完整的 buffer 数组
+///
+/// # Safety
+/// `list` 必须指向 CoreAudio 提供的、至少含 `number_buffers` 个 buffer 的有效列表
+unsafe fn audio_buffers<'a>(list: *mut AudioBufferList) -> &'a mut [AudioBuffer] {
+    let count = (*list).number_buffers as usize;
+    let first = ptr::addr_of_mut!((*list).buffers) as *mut AudioBuffer;
+    std::slice::from_raw_parts_mut(first, count)
 }
 
 #[repr(C)]
@@ -339,6 +367,41 @@ mod power_management {
     }
 }
 
+/// 按需持有的电源断言
+///
+/// 渲染时持有；用户暂停时释放（暂停一小时也不阻止系统睡眠），恢复时重新创建。
+/// seek、切歌等短暂的输出暂停不释放。断言由 `create` 创建、drop 释放（测试中替换为假断言）
+struct PowerHold<A> {
+    create: fn() -> Option<A>,
+    held: Option<A>,
+}
+
+impl<A> PowerHold<A> {
+    fn new(create: fn() -> Option<A>) -> Self {
+        Self { create, held: None }
+    }
+
+    /// 未持有时创建断言（创建失败下次再试）
+    fn acquire(&mut self) {
+        if self.held.is_none() {
+            self.held = (self.create)();
+        }
+    }
+
+    fn release(&mut self) {
+        self.held = None;
+    }
+
+    fn is_held(&self) -> bool {
+        self.held.is_some()
+    }
+}
+
+/// 播放时的电源断言
+fn create_playback_assertion() -> Option<power_management::PowerAssertion> {
+    power_management::PowerAssertion::new("Roger Player Audio Playback")
+}
+
 #[link(name = "AudioToolbox", kind = "framework")]
 extern "C" {
     fn AudioComponentFindNext(
@@ -381,8 +444,11 @@ extern "C" {
 /// 音频输出设备信息
 #[derive(Debug, Clone)]
 pub struct DeviceInfo {
+    /// 设备 ID（重启/重新连接后可能变化，不宜持久化）
     pub id: AudioDeviceID,
     pub name: String,
+    /// 设备 UID（跨重启稳定，用于保存设备偏好）
+    pub uid: Option<String>,
     pub supported_sample_rates: Vec<f64>,
     pub current_sample_rate: f64,
     pub is_bluetooth: bool,
@@ -405,6 +471,20 @@ pub struct OutputConfig {
     pub use_hal: bool,
     /// 指定输出设备 ID（None 表示使用系统默认设备）
     pub device_id: Option<u32>,
+    /// 恢复/seek 后淡入、暂停/停止前淡出的时长（毫秒，0 表示不淡入淡出；曲目开头不淡入）
+    pub fade_ms: u32,
+    /// dither 随机数种子（None 表示按当前时间生成；固定种子用于可复现的输出）
+    pub dither_seed: Option<u32>,
+    /// 套用目标设备缓存的设置（见 `device_settings`，覆盖上面的缓冲/模式项）
+    pub device_settings: bool,
+    /// 锁定设备采样率：从不切换，保持设备当前采样率，源采样率不同时由 CoreAudio SRC
+    ///
+    /// 适用于每次切换采样率都会爆音或重新锁定很慢的 DAC，代价是失去 bit-perfect
+    pub lock_device_rate: bool,
+    /// 在 IO 回调内预分配漂移校正器（见 `audio::drift`），校正量由 `set_drift_ppm` 设置
+    ///
+    /// 校正量为 0 时完全旁路；非 0 时插值输出，不再 bit-perfect
+    pub callback_drift_correction: bool,
 }
 
 impl Default for OutputConfig {
@@ -416,6 +496,11 @@ impl Default for OutputConfig {
             integer_mode: true,
             use_hal: true, // 默认使用 HALOutput（有线设备最佳）
             device_id: None, // 默认使用系统默认设备
+            fade_ms: 20,     // 足以消除爆音，又不会明显拖慢响应
+            dither_seed: None,
+            device_settings: false,
+            lock_device_rate: false,
+            callback_drift_correction: false,
         }
     }
 }
@@ -430,6 +515,10 @@ pub enum OutputError {
     SampleRateNotSupported(u32),
     InvalidState(&'static str),
     NoAudioComponent,
+    /// 独占模式已被其他进程持有（PID）
+    HogModeHeld(i32),
+    /// 输出已启动但 IO 回调从未触发
+    NoCallback,
 }
 
 impl std::fmt::Display for OutputError {
@@ -442,12 +531,134 @@ impl std::fmt::Display for OutputError {
             Self::SampleRateNotSupported(r) => write!(f, "Sample rate {} not supported", r),
             Self::InvalidState(s) => write!(f, "Invalid state: {}", s),
             Self::NoAudioComponent => write!(f, "No audio component found"),
+            Self::HogModeHeld(pid) => {
+                write!(f, "Exclusive mode is held by another process (PID {})", pid)
+            }
+            Self::NoCallback => write!(f, "Output started but the IO callback never ran"),
         }
     }
 }
 
 impl std::error::Error for OutputError {}
 
+/// 判断独占模式是否被其他进程持有
+///
+/// `owner` 为 kAudioDevicePropertyHogMode 的值（-1 表示空闲），
+/// 返回持有者 PID；本进程持有或空闲时返回 None
+fn hog_mode_held_by_other(owner: i32, own_pid: i32) -> Option<i32> {
+    if owner >= 0 && owner != own_pid {
+        Some(owner)
+    } else {
+        None
+    }
+}
+
+/// packed 有符号整数 LPCM 格式描述
+fn integer_asbd(sample_rate: f64, channels: u32, bits: u32) -> AudioStreamBasicDescription {
+    let bytes_per_frame = bits / 8 * channels;
+    AudioStreamBasicDescription {
+        sample_rate,
+        format_id: K_AUDIO_FORMAT_LINEAR_PCM,
+        format_flags: K_AUDIO_FORMAT_FLAG_IS_SIGNED_INTEGER | K_AUDIO_FORMAT_FLAG_IS_PACKED,
+        bytes_per_packet: bytes_per_frame,
+        frames_per_packet: 1,
+        bytes_per_frame,
+        channels_per_frame: channels,
+        bits_per_channel: bits,
+        reserved: 0,
+    }
+}
+
+/// 从设备声明的物理格式中选出可用的整数位深，按优先级（从高到低）排列
+///
+/// 只考虑声道数、采样率（0 表示任意）匹配的 packed 有符号整数 16/24/32-bit 格式；
+/// 位深越高越好（≥ 源位深时无需 dither）。列表为空（查询失败）时返回 32/24/16 全部尝试
+fn physical_depth_candidates(
+    available: &[AudioStreamBasicDescription],
+    channels: u32,
+    sample_rate: f64,
+) -> Vec<u32> {
+    if available.is_empty() {
+        return vec![32, 24, 16];
+    }
+    let mut depths: Vec<u32> = available
+        .iter()
+        .filter(|f| {
+            f.format_id == K_AUDIO_FORMAT_LINEAR_PCM
+                && (f.format_flags & K_AUDIO_FORMAT_FLAG_IS_SIGNED_INTEGER) != 0
+                && (f.format_flags & K_AUDIO_FORMAT_FLAG_IS_FLOAT) == 0
+                && f.channels_per_frame == channels
+                && (f.sample_rate == sample_rate || f.sample_rate == 0.0)
+                && matches!(f.bits_per_channel, 16 | 24 | 32)
+                && f.bytes_per_frame == f.bits_per_channel / 8 * channels
+        })
+        .map(|f| f.bits_per_channel)
+        .collect();
+    depths.sort_unstable_by(|a, b| b.cmp(a));
+    depths.dedup();
+    depths
+}
+
+/// 解析 `-d uid:<UID>` 形式的设备参数，返回 UID；其他形式（ID、名称）返回 None
+pub fn parse_device_uid(spec: &str) -> Option<&str> {
+    let (prefix, uid) = spec.split_once(':')?;
+    let uid = uid.trim();
+    (prefix.trim().eq_ignore_ascii_case("uid") && !uid.is_empty()).then_some(uid)
+}
+
+/// 将请求的 IO 缓冲帧数限制到设备支持的范围
+///
+/// 返回 (实际使用的帧数, 是否被调整)；范围未知时原样返回
+pub fn clamp_buffer_frames(requested: u32, range: Option<(u32, u32)>) -> (u32, bool) {
+    match range {
+        Some((min, max)) if min <= max => {
+            let frames = requested.clamp(min, max);
+            (frames, frames != requested)
+        }
+        _ => (requested, false),
+    }
+}
+
+/// 锁定设备采样率时使用的采样率
+///
+/// 未锁定或读不到设备当前采样率时为 None（按源采样率切换设备）
+fn locked_device_rate(config: &OutputConfig, current_rate: Option<f64>) -> Option<f64> {
+    current_rate.filter(|&rate| config.lock_device_rate && rate > 0.0)
+}
+
+/// 采样率所属系列：44100（11025 的整数倍）或 48000（8000 的整数倍）
+///
+/// 两个系列的公倍数远超实际采样率，不会同时匹配；其他采样率返回 None
+pub fn rate_family(rate: u32) -> Option<u32> {
+    if rate == 0 {
+        None
+    } else if rate.is_multiple_of(11025) {
+        Some(44100)
+    } else if rate.is_multiple_of(8000) {
+        Some(48000)
+    } else {
+        None
+    }
+}
+
+/// 源与设备采样率是否跨系列（如 44.1k → 48k）
+///
+/// 跨系列 SRC 不是整数比，音质损失最明显；同系列或系列未知时为 false
+pub fn is_cross_family(source_rate: u32, device_rate: u32) -> bool {
+    match (rate_family(source_rate), rate_family(device_rate)) {
+        (Some(source), Some(device)) => source != device,
+        _ => false,
+    }
+}
+
+/// 按当前时间生成 dither 种子（未指定固定种子时使用）
+pub fn clock_seed() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u32)
+        .unwrap_or(0xCAFEBABE)
+}
+
 /// TPDF Dither 批量缓冲区大小
 /// 选择 64 以匹配常见的 SIMD 向量批处理大小
 const DITHER_BATCH_SIZE: usize = 64;
@@ -552,6 +763,73 @@ impl DitherState {
     }
 }
 
+/// 等功率淡入淡出状态机
+///
+/// 以帧为单位推进，增益 = sin(进度 · π/2)，在 `length` 帧内从静音到全音量
+/// （或反向）。整数帧计数保证在确定的样本数内到达目标，realtime-safe
+pub struct FadeRamp {
+    /// 当前进度（0 = 静音，length = 全音量）
+    frame: u32,
+    /// 渐变总帧数
+    length: u32,
+}
+
+impl FadeRamp {
+    /// 创建静音起点的渐变（`length` 为 0 时立即到达目标）
+    pub fn new(length: u32) -> Self {
+        Self {
+            frame: 0,
+            length: length.max(1),
+        }
+    }
+
+    /// 是否已完全静音
+    #[inline(always)]
+    pub fn is_silent(&self) -> bool {
+        self.frame == 0
+    }
+
+    /// 是否已到达全音量
+    #[inline(always)]
+    pub fn is_full(&self) -> bool {
+        self.frame == self.length
+    }
+
+    /// 直接到达全音量（曲目开头不淡入）
+    #[inline(always)]
+    pub fn skip_to_full(&mut self) {
+        self.frame = self.length;
+    }
+
+    /// 向目标推进一帧，返回该帧的增益
+    #[inline(always)]
+    pub fn next_gain(&mut self, fade_in: bool) -> f64 {
+        if fade_in {
+            self.frame = (self.frame + 1).min(self.length);
+        } else {
+            self.frame = self.frame.saturating_sub(1);
+        }
+        (self.frame as f64 / self.length as f64 * std::f64::consts::FRAC_PI_2).sin()
+    }
+
+    /// 对交织样本逐帧施加增益
+    ///
+    /// 全音量且目标为淡入时直接返回（常规播放零开销，保持 bit-perfect）
+    #[inline(always)]
+    pub fn apply(&mut self, fade_in: bool, samples: &mut [i32], channels: usize) {
+        if fade_in && self.is_full() {
+            return;
+        }
+        for frame in samples.chunks_mut(channels) {
+            let gain = self.next_gain(fade_in);
+            for sample in frame.iter_mut() {
+                // f64 → i32 的 `as` 转换本身即饱和
+                *sample = (*sample as f64 * gain) as i32;
+            }
+        }
+    }
+}
+
 /// 输出格式模式
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormatMode {
@@ -561,6 +839,101 @@ pub enum OutputFormatMode {
     Int32,
     /// Int24 格式（24-bit packed）
     Int24,
+    /// Int16 格式（老旧或 USB 带宽受限的 DAC，高位深源需 dither）
+    Int16,
+}
+
+/// 协商结果中的一项：请求值与实际值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Negotiated<T> {
+    pub requested: T,
+    pub actual: T,
+}
+
+impl<T: PartialEq> Negotiated<T> {
+    /// 实际值是否与请求一致
+    pub fn as_requested(&self) -> bool {
+        self.requested == self.actual
+    }
+}
+
+/// 启动后请求的输出配置与实际结果的对照
+///
+/// 采样率回退、整数格式被拒、独占被占用等妥协集中在这里，不必翻日志
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegotiationReport {
+    /// 设备采样率（请求值为源采样率）
+    pub sample_rate: Negotiated<u32>,
+    /// 整数格式（实际为 Float32 时未满足）
+    pub integer: Negotiated<bool>,
+    /// 独占（hog）模式
+    pub exclusive: Negotiated<bool>,
+    /// HAL 直接输出（否则经系统混音器）
+    pub hal: Negotiated<bool>,
+    /// 实际输出格式
+    pub format: OutputFormatMode,
+}
+
+impl NegotiationReport {
+    /// 由启动时的请求配置和实际结果构造
+    ///
+    /// 选择系统混音器（`use_hal = false`）时整数格式和独占无从谈起，视为未请求
+    pub fn new(
+        requested: &OutputConfig,
+        actual_rate: u32,
+        format: OutputFormatMode,
+        exclusive: bool,
+        hal: bool,
+    ) -> Self {
+        Self {
+            sample_rate: Negotiated {
+                requested: requested.sample_rate,
+                actual: actual_rate,
+            },
+            integer: Negotiated {
+                requested: requested.use_hal && requested.integer_mode,
+                actual: format != OutputFormatMode::Float32,
+            },
+            exclusive: Negotiated {
+                requested: requested.use_hal && requested.exclusive_mode,
+                actual: exclusive,
+            },
+            hal: Negotiated {
+                requested: requested.use_hal,
+                actual: hal,
+            },
+            format,
+        }
+    }
+
+    /// 所有项是否都如请求
+    pub fn all_as_requested(&self) -> bool {
+        self.sample_rate.as_requested()
+            && self.integer.as_requested()
+            && self.exclusive.as_requested()
+            && self.hal.as_requested()
+    }
+}
+
+impl std::fmt::Display for NegotiationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 如 "Requested 96kHz/Integer/Exclusive → Got 96kHz/Int24/Shared"
+        let mode = |hal: bool, exclusive: bool| match (hal, exclusive) {
+            (false, _) => "Mixer",
+            (true, true) => "Exclusive",
+            (true, false) => "Shared",
+        };
+        write!(
+            f,
+            "Requested {}kHz/{}/{} → Got {}kHz/{:?}/{}",
+            self.sample_rate.requested as f64 / 1000.0,
+            if self.integer.requested { "Integer" } else { "Float32" },
+            mode(self.hal.requested, self.exclusive.requested),
+            self.sample_rate.actual as f64 / 1000.0,
+            self.format,
+            mode(self.hal.actual, self.exclusive.actual),
+        )
+    }
 }
 
 /// Render 回调上下文
@@ -581,8 +954,22 @@ pub struct CallbackContext {
     pub format: AudioFormat,
     pub output_layout: OutputLayout,
     pub source_bits: u16,
+    pub fade: FadeRamp,
+    /// 本次 callback 的 host time（mach ticks，无效时为 0），用于记录 underrun 时间
+    pub callback_host_time: u64,
+    /// 漂移校正器（未开启 `callback_drift_correction` 时为 None）
+    pub drift: Option<DriftCorrector>,
 
     // === 冷字段：一次性或外部访问 ===
+    /// 淡入淡出目标（true = 淡入到全音量，false = 淡出到静音），由控制线程写入
+    pub fade_in: AtomicBool,
+
+    /// 淡出是否已完成（IO 回调写入，控制线程等待后再暂停/停止）
+    pub fade_silent: AtomicBool,
+
+    /// 下一次回调直接到达全音量（曲目开头不淡入，保持 bit-perfect），由控制线程写入
+    pub fade_skip: AtomicBool,
+
     pub buffer_frames: u32,
 
     /// 是否正在运行
@@ -593,6 +980,22 @@ pub struct CallbackContext {
     /// IO 线程是否已设置时间约束策略
     /// 使用 CacheLine 包装确保独占缓存行
     pub thread_policy_set: CacheLine<AtomicBool>,
+
+    /// 时间约束策略是否设置成功（失败时 IO 线程没有实时保证）
+    /// 仅首次回调写入一次，UI 线程低频读取
+    pub realtime_active: AtomicBool,
+
+    /// 超过预分配容量的最大请求帧数（0 = 未发生），IO 回调写入
+    pub oversized_frames: AtomicU32,
+
+    /// 超额请求是否已记录日志（只记一次）
+    pub oversized_reported: AtomicBool,
+
+    /// sample_buffer/dither_buffer 是否已被 mlock
+    pub memory_locked: AtomicBool,
+
+    /// 漂移校正量（ppm 的 f64 位表示，0 为旁路），由控制线程写入
+    pub drift_ppm_bits: AtomicU64,
 }
 
 /// Mach 线程策略相关类型和常量
@@ -718,6 +1121,10 @@ impl CallbackContext {
     /// 在实时音频回调中，page fault 会导致严重的时序问题。
     /// 此函数锁定 CallbackContext 结构体、sample_buffer 和 dither_buffer 的内存。
     pub fn lock_memory(&self) -> bool {
+        if self.memory_locked.load(Ordering::Acquire) {
+            return true; // 已经锁定
+        }
+
         let mut success = true;
 
         // 锁定 sample_buffer
@@ -750,11 +1157,26 @@ impl CallbackContext {
             success = false;
         }
 
+        // 锁定漂移校正的输入缓冲（开启时）
+        if let Some(input) = self.drift.as_ref().map(DriftCorrector::input_buffer) {
+            let result = unsafe { libc::mlock(input.as_ptr() as *const libc::c_void, input.len() * 4) };
+            if result != 0 {
+                log::warn!("Failed to lock drift corrector buffer");
+                success = false;
+            }
+        }
+
+        // 部分失败也按已锁定记账：munlock 未锁定的页无副作用
+        self.memory_locked.store(true, Ordering::Release);
         success
     }
 
-    /// 解锁上下文内存
+    /// 解锁上下文内存（可重复调用）
     pub fn unlock_memory(&self) {
+        if !self.memory_locked.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
         let sample_ptr = self.sample_buffer.as_ptr() as *const libc::c_void;
         let sample_len = self.sample_buffer.len() * std::mem::size_of::<i32>();
         unsafe {
@@ -766,6 +1188,31 @@ impl CallbackContext {
         unsafe {
             libc::munlock(dither_ptr, dither_len);
         }
+        if let Some(input) = self.drift.as_ref().map(DriftCorrector::input_buffer) {
+            unsafe {
+                libc::munlock(input.as_ptr() as *const libc::c_void, input.len() * 4);
+            }
+        }
+        log::debug!("CallbackContext memory unlocked");
+    }
+
+    /// 检查内存是否已锁定
+    pub fn is_memory_locked(&self) -> bool {
+        self.memory_locked.load(Ordering::Acquire)
+    }
+
+    /// sample_buffer + dither_buffer（+ 漂移校正输入缓冲）占用的字节数（mlock 的大小）
+    pub fn memory_bytes(&self) -> usize {
+        self.sample_buffer.len() * std::mem::size_of::<i32>()
+            + self.dither_buffer.len() * std::mem::size_of::<f32>()
+            + self.drift.as_ref().map_or(0, |d| std::mem::size_of_val(d.input_buffer()))
+    }
+}
+
+impl Drop for CallbackContext {
+    /// 兜底解锁：频繁切歌时未解锁的页会累积占用 RLIMIT_MEMLOCK
+    fn drop(&mut self) {
+        self.unlock_memory();
     }
 }
 
@@ -785,6 +1232,8 @@ pub struct AudioOutput {
     context: Option<Box<CallbackContext>>,
     original_sample_rate: f64,
     hog_mode_acquired: bool,
+    /// 最近一次独占模式获取失败的原因
+    hog_mode_failure: Option<OutputError>,
     actual_format: AudioFormat,
     /// 设备支持的采样率列表
     supported_sample_rates: Vec<f64>,
@@ -792,14 +1241,20 @@ pub struct AudioOutput {
     is_hal_output: bool,
     /// 是否已暂停
     paused: bool,
-    /// 电源管理断言（防止 CPU 降频）
-    power_assertion: Option<power_management::PowerAssertion>,
+    /// 电源管理断言（防止 CPU 降频），用户暂停时释放
+    power_assertion: PowerHold<power_management::PowerAssertion>,
     /// 设备最小缓冲帧数
     min_buffer_frames: u32,
+    /// 设备支持的 IO 缓冲帧数范围（最小/最大，查询失败或 DefaultOutput 时为 None）
+    buffer_size_range: Option<(u32, u32)>,
     /// 设备延迟（帧数）
     device_latency_frames: u32,
     /// 安全偏移（帧数）
     safety_offset_frames: u32,
+    /// 启动时确定的 IO 缓冲帧数（未启动时为 0）
+    io_buffer_frames: u32,
+    /// `start()` 时请求的配置（协商前，未启动时为 None）
+    requested: Option<OutputConfig>,
 }
 
 impl AudioOutput {
@@ -846,6 +1301,7 @@ impl AudioOutput {
         Ok(DeviceInfo {
             id: device_id,
             name: device_name,
+            uid: Self::get_device_uid(device_id),
             supported_sample_rates: sample_rates,
             current_sample_rate: current_rate,
             is_bluetooth,
@@ -921,6 +1377,14 @@ impl AudioOutput {
         Ok(output_devices)
     }
 
+    /// 输出目标设备：指定 ID 的设备，未指定时为系统默认设备
+    pub fn target_device(device_id: Option<AudioDeviceID>) -> Result<DeviceInfo, OutputError> {
+        match device_id {
+            Some(device_id) => Self::get_device_info(device_id),
+            None => Self::get_default_device(),
+        }
+    }
+
     /// 根据设备 ID 获取设备信息
     pub fn get_device_info(device_id: AudioDeviceID) -> Result<DeviceInfo, OutputError> {
         let device_name = Self::get_device_name(device_id);
@@ -935,6 +1399,7 @@ impl AudioOutput {
         Ok(DeviceInfo {
             id: device_id,
             name: device_name,
+            uid: Self::get_device_uid(device_id),
             supported_sample_rates: sample_rates,
             current_sample_rate: current_rate,
             is_bluetooth,
@@ -963,6 +1428,14 @@ impl AudioOutput {
         None
     }
 
+    /// 按 UID 查找设备（精确匹配）
+    pub fn find_device_by_uid(uid: &str) -> Option<DeviceInfo> {
+        Self::get_all_output_devices()
+            .ok()?
+            .into_iter()
+            .find(|device| device.uid.as_deref() == Some(uid))
+    }
+
     /// 检查设备是否有输出通道
     fn has_output_channels(device_id: AudioDeviceID) -> bool {
         // 使用 kAudioDevicePropertyStreams 检查是否有输出流
@@ -1059,10 +1532,44 @@ impl AudioOutput {
         cf_string.to_string()
     }
 
+    /// 获取设备 UID（`kAudioDevicePropertyDeviceUID`）
+    fn get_device_uid(device_id: AudioDeviceID) -> Option<String> {
+        use core_foundation::base::TCFType;
+        use core_foundation::string::CFString;
+
+        let address = AudioObjectPropertyAddress {
+            selector: K_AUDIO_DEVICE_PROPERTY_DEVICE_UID,
+            scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+            element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+        };
+
+        let mut size: u32 = std::mem::size_of::<*const c_void>() as u32;
+        let mut cf_string_ref: *const c_void = ptr::null();
+
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                device_id,
+                &address,
+                0,
+                ptr::null(),
+                &mut size,
+                &mut cf_string_ref as *mut _ as *mut c_void,
+            )
+        };
+
+        if status != NO_ERR || cf_string_ref.is_null() {
+            return None;
+        }
+
+        // 属性返回的 CFString 由调用方释放
+        let cf_string = unsafe { CFString::wrap_under_create_rule(cf_string_ref as *const _) };
+        Some(cf_string.to_string())
+    }
+
     /// 查询缓冲区帧数范围 (最小/最大)
     ///
-    /// 用于 IOProc 模式下选择最优 buffer size
-    fn get_buffer_size_range(device_id: AudioDeviceID) -> Option<(u32, u32)> {
+    /// 用于 IOProc 模式下选择最优 buffer size，以及 `--io-buffer-frames` 校验
+    pub fn get_buffer_size_range(device_id: AudioDeviceID) -> Option<(u32, u32)> {
         let address = AudioObjectPropertyAddress {
             selector: K_AUDIO_DEVICE_PROPERTY_BUFFER_FRAME_SIZE_RANGE,
             scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT,
@@ -1288,41 +1795,23 @@ impl AudioOutput {
             }
         }
 
-        // 2. 整数倍关系 - 优先下采样（96→48）
-        // 44100 系列：44100, 88200, 176400
-        // 48000 系列：48000, 96000, 192000
-        let rate_families: [(f64, &[f64]); 2] = [
-            (44100.0, &[44100.0, 88200.0, 176400.0]),
-            (48000.0, &[48000.0, 96000.0, 192000.0]),
-        ];
-
-        // 确定请求的采样率属于哪个系列
-        let requested_family = if (requested / 44100.0).fract().abs() < 0.01 {
-            Some(44100.0)
-        } else if (requested / 48000.0).fract().abs() < 0.01 {
-            Some(48000.0)
-        } else {
-            None
-        };
-
-        if let Some(base) = requested_family {
-            // 找同系列中设备支持的整数分频采样率
-            let family = rate_families.iter().find(|(b, _)| (*b - base).abs() < 1.0);
-            if let Some((_, rates)) = family {
-                // 从请求的采样率开始向下找
-                for &rate in rates.iter().rev() {
-                    if rate <= requested + 1.0 {
-                        for &supported_rate in supported {
-                            if (supported_rate - rate).abs() < 1.0 {
-                                log::info!(
-                                    "Sample rate fallback: {} → {} Hz (integer division)",
-                                    requested, supported_rate
-                                );
-                                return supported_rate;
-                            }
-                        }
-                    }
-                }
+        // 2. 同系列（见 `rate_family`）：优先不高于请求的最高采样率（96→48），
+        //    否则取高于请求的最低采样率（22.05→44.1），避免跨系列 SRC
+        if let Some(family) = rate_family(requested.round() as u32) {
+            let same_family = || {
+                supported
+                    .iter()
+                    .copied()
+                    .filter(move |&rate| rate_family(rate.round() as u32) == Some(family))
+            };
+            let below = same_family().filter(|&rate| rate <= requested).reduce(f64::max);
+            let above = || same_family().filter(|&rate| rate > requested).reduce(f64::min);
+            if let Some(rate) = below.or_else(above) {
+                log::info!(
+                    "Sample rate fallback: {} → {} Hz (same family)",
+                    requested, rate
+                );
+                return rate;
             }
         }
 
@@ -1522,8 +2011,40 @@ impl AudioOutput {
         Ok(frames)
     }
 
+    /// 查询当前持有独占模式的进程 PID（-1 表示空闲）
+    fn get_hog_mode_owner(device_id: AudioDeviceID) -> Option<i32> {
+        let address = AudioObjectPropertyAddress {
+            selector: K_AUDIO_DEVICE_PROPERTY_HOG_MODE,
+            scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT,
+            element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+        };
+
+        let mut owner: i32 = -1;
+        let mut size = std::mem::size_of::<i32>() as u32;
+
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                device_id,
+                &address,
+                0,
+                ptr::null(),
+                &mut size,
+                &mut owner as *mut _ as *mut c_void,
+            )
+        };
+
+        if status == NO_ERR {
+            Some(owner)
+        } else {
+            None
+        }
+    }
+
     /// 尝试获取独占模式
-    fn acquire_hog_mode(device_id: AudioDeviceID) -> Result<bool, OutputError> {
+    ///
+    /// 先查询当前持有者：被其他进程占用时返回 `HogModeHeld(pid)`，
+    /// 设置失败时返回 `SetPropertyFailed`（常见于蓝牙/聚合设备）
+    fn acquire_hog_mode(device_id: AudioDeviceID) -> Result<(), OutputError> {
         let address = AudioObjectPropertyAddress {
             selector: K_AUDIO_DEVICE_PROPERTY_HOG_MODE,
             scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT,
@@ -1532,6 +2053,12 @@ impl AudioOutput {
 
         let pid = unsafe { libc::getpid() };
 
+        if let Some(owner) = Self::get_hog_mode_owner(device_id)
+            .and_then(|owner| hog_mode_held_by_other(owner, pid))
+        {
+            return Err(OutputError::HogModeHeld(owner));
+        }
+
         let status = unsafe {
             AudioObjectSetPropertyData(
                 device_id,
@@ -1543,7 +2070,11 @@ impl AudioOutput {
             )
         };
 
-        Ok(status == NO_ERR)
+        if status == NO_ERR {
+            Ok(())
+        } else {
+            Err(OutputError::SetPropertyFailed(status))
+        }
     }
 
     /// 释放独占模式
@@ -1568,22 +2099,44 @@ impl AudioOutput {
         };
     }
 
+    /// 兜底释放当前持有的独占模式（进程即将异常退出时调用）
+    ///
+    /// 正常退出由 `stop()`/Drop 释放；被 SIGTERM/SIGHUP 终止且来不及走 Drop 时，
+    /// 按 `HOG_DEVICE` 中记录的设备 ID 直接释放，否则设备会一直被独占到注销。
+    ///
+    /// CoreAudio 调用不是 async-signal-safe（内部会加锁、分配），
+    /// 只能在普通线程（如 ctrlc 的处理线程）中调用，不能在原始信号处理函数里调用
+    pub fn release_hog_mode_on_exit() {
+        let device_id = HOG_DEVICE.swap(0, Ordering::AcqRel);
+        if device_id != 0 {
+            Self::release_hog_mode(device_id);
+        }
+    }
+
     /// 创建音频输出
     ///
     /// 优先级：
     /// 1. IOProc（直接 HAL，最低延迟）
     /// 2. HALOutput AudioUnit（绕过系统混音器）
     /// 3. DefaultOutput（通过系统混音器，蓝牙设备）
-    pub fn new(config: OutputConfig) -> Result<Self, OutputError> {
+    pub fn new(mut config: OutputConfig) -> Result<Self, OutputError> {
         // 获取目标设备（指定的或默认的）
-        let target_device = if let Some(device_id) = config.device_id {
-            Self::get_device_info(device_id)?
-        } else {
-            Self::get_default_device()?
-        };
+        let target_device = Self::target_device(config.device_id)?;
 
         log::info!("Target device: {} (ID: {})", target_device.name, target_device.id);
 
+        // 套用该设备上次可用的设置（缓冲帧数按设备当前范围重新限制）
+        if config.device_settings {
+            let cached = target_device
+                .uid
+                .as_deref()
+                .and_then(|uid| DeviceSettingsCache::load().get(uid));
+            if let Some(settings) = cached {
+                log::info!("Applying cached settings for {}: {:?}", target_device.name, settings);
+                settings.apply(&mut config, Self::get_buffer_size_range(target_device.id));
+            }
+        }
+
         // 检测目标设备是否是蓝牙
         let is_bluetooth = target_device.is_bluetooth;
         if is_bluetooth {
@@ -1660,8 +2213,8 @@ impl AudioOutput {
         }
 
         // 查询设备能力
-        let (min_buffer, _max_buffer) = Self::get_buffer_size_range(device.id)
-            .unwrap_or((64, 4096));
+        let buffer_size_range = Self::get_buffer_size_range(device.id);
+        let (min_buffer, _max_buffer) = buffer_size_range.unwrap_or((64, 4096));
         let device_latency = Self::get_device_latency(device.id);
         let safety_offset = Self::get_safety_offset(device.id);
 
@@ -1672,14 +2225,18 @@ impl AudioOutput {
             context: None,
             original_sample_rate: device.current_sample_rate,
             hog_mode_acquired: false,
+            hog_mode_failure: None,
             actual_format: AudioFormat::new(48000, 2, 32),
             supported_sample_rates: device.supported_sample_rates.clone(),
             is_hal_output: true,
             paused: false,
-            power_assertion: None,
+            power_assertion: PowerHold::new(create_playback_assertion),
             min_buffer_frames: min_buffer,
+            buffer_size_range,
             device_latency_frames: device_latency,
             safety_offset_frames: safety_offset,
+            io_buffer_frames: 0,
+            requested: None,
         })
     }
 
@@ -1699,14 +2256,18 @@ impl AudioOutput {
             context: None,
             original_sample_rate: 48000.0,
             hog_mode_acquired: false,
+            hog_mode_failure: None,
             actual_format: AudioFormat::new(48000, 2, 32),
             supported_sample_rates: vec![44100.0, 48000.0],  // DefaultOutput 常见支持率
             is_hal_output: false,
             paused: false,
-            power_assertion: None,
+            power_assertion: PowerHold::new(create_playback_assertion),
             min_buffer_frames: 512,
+            buffer_size_range: None,
             device_latency_frames: 0,
             safety_offset_frames: 0,
+            io_buffer_frames: 0,
+            requested: None,
         })
     }
 
@@ -1858,49 +2419,43 @@ impl AudioOutput {
             );
         }
 
-        // 尝试设置 32-bit 整数物理格式（使用设备实际采样率）
-        let asbd_int32 = AudioStreamBasicDescription {
-            sample_rate: device_sample_rate as f64,
-            format_id: K_AUDIO_FORMAT_LINEAR_PCM,
-            format_flags: K_AUDIO_FORMAT_FLAG_IS_SIGNED_INTEGER | K_AUDIO_FORMAT_FLAG_IS_PACKED,
-            bytes_per_packet: 4 * format.channels as u32,
-            frames_per_packet: 1,
-            bytes_per_frame: 4 * format.channels as u32,
-            channels_per_frame: format.channels as u32,
-            bits_per_channel: 32,
-            reserved: 0,
-        };
+        // 只尝试设备声明支持的整数位深（从高到低）；查询失败时按 32 → 24 → 16 逐个尝试
+        let channels = format.channels as u32;
+        let available = Self::get_available_physical_formats(stream_id);
+        let depths = physical_depth_candidates(&available, channels, device_sample_rate as f64);
+        match depths.first() {
+            Some(&best) if !available.is_empty() => log::info!(
+                "Device advertises integer depths {:?}, preferred {}bit for {}bit source",
+                depths,
+                best,
+                format.bits_per_sample
+            ),
+            _ if !available.is_empty() => log::info!(
+                "No integer physical format for {}ch @ {}Hz among {} advertised formats",
+                channels,
+                device_sample_rate,
+                available.len()
+            ),
+            _ => {}
+        }
 
-        if Self::set_physical_format(stream_id, &asbd_int32) {
+        for bits in depths {
+            let mode = match bits {
+                32 => OutputFormatMode::Int32,
+                24 => OutputFormatMode::Int24,
+                _ => OutputFormatMode::Int16,
+            };
+            let asbd = integer_asbd(device_sample_rate as f64, channels, bits);
+            if !Self::set_physical_format(stream_id, &asbd) {
+                continue;
+            }
             // 验证设置成功
             if let Some(actual) = Self::get_physical_format(stream_id) {
-                if actual.bits_per_channel == 32
+                if actual.bits_per_channel == bits
                     && (actual.format_flags & K_AUDIO_FORMAT_FLAG_IS_SIGNED_INTEGER) != 0
                 {
-                    log::info!("Physical format set to Int32 (direct hardware path)");
-                    return Some((actual, OutputFormatMode::Int32));
-                }
-            }
-        }
-
-        // 尝试 24-bit 整数（使用设备实际采样率）
-        let asbd_int24 = AudioStreamBasicDescription {
-            sample_rate: device_sample_rate as f64,
-            format_id: K_AUDIO_FORMAT_LINEAR_PCM,
-            format_flags: K_AUDIO_FORMAT_FLAG_IS_SIGNED_INTEGER | K_AUDIO_FORMAT_FLAG_IS_PACKED,
-            bytes_per_packet: 3 * format.channels as u32,
-            frames_per_packet: 1,
-            bytes_per_frame: 3 * format.channels as u32,
-            channels_per_frame: format.channels as u32,
-            bits_per_channel: 24,
-            reserved: 0,
-        };
-
-        if Self::set_physical_format(stream_id, &asbd_int24) {
-            if let Some(actual) = Self::get_physical_format(stream_id) {
-                if actual.bits_per_channel == 24 {
-                    log::info!("Physical format set to Int24 (direct hardware path)");
-                    return Some((actual, OutputFormatMode::Int24));
+                    log::info!("Physical format set to {:?} (direct hardware path)", mode);
+                    return Some((actual, mode));
                 }
             }
         }
@@ -1909,10 +2464,59 @@ impl AudioOutput {
         None
     }
 
-    /// 尝试设置整数输出格式
+    /// 查询流支持的物理格式（`kAudioStreamPropertyAvailablePhysicalFormats`）
     ///
-    /// 整数格式避免了 i32 → f32 的转换，信号路径更直接。
-    /// 返回 (成功与否, 输出格式模式)
+    /// 查询失败时返回空列表
+    fn get_available_physical_formats(stream_id: u32) -> Vec<AudioStreamBasicDescription> {
+        let address = AudioObjectPropertyAddress {
+            selector: K_AUDIO_STREAM_PROPERTY_AVAILABLE_PHYSICAL_FORMATS,
+            scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT,
+            element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+        };
+
+        let mut size: u32 = 0;
+        let status = unsafe {
+            AudioObjectGetPropertyDataSize(stream_id, &address, 0, ptr::null(), &mut size)
+        };
+        if status != NO_ERR || size == 0 {
+            return Vec::new();
+        }
+
+        let count = size as usize / std::mem::size_of::<AudioStreamRangedDescription>();
+        let mut ranged = vec![AudioStreamRangedDescription::default(); count];
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                stream_id,
+                &address,
+                0,
+                ptr::null(),
+                &mut size,
+                ranged.as_mut_ptr() as *mut c_void,
+            )
+        };
+        if status != NO_ERR {
+            return Vec::new();
+        }
+
+        ranged.truncate(size as usize / std::mem::size_of::<AudioStreamRangedDescription>());
+        ranged
+            .into_iter()
+            .map(|r| {
+                // 采样率为 0 的条目由范围描述；范围为单一值时还原为该采样率，否则保留 0 表示任意
+                let mut format = r.format;
+                let range = r.sample_rate_range;
+                if format.sample_rate == 0.0 && range.minimum == range.maximum {
+                    format.sample_rate = range.minimum;
+                }
+                format
+            })
+            .collect()
+    }
+
+    /// 尝试设置整数输出格式
+    ///
+    /// 整数格式避免了 i32 → f32 的转换，信号路径更直接。
+    /// 返回 (成功与否, 输出格式模式)
     ///
     /// # Arguments
     /// * `format` - 音频格式（包含源文件采样率）
@@ -1979,6 +2583,35 @@ impl AudioOutput {
             return (true, OutputFormatMode::Int24);
         }
 
+        // 尝试 16-bit Integer（使用源文件采样率）
+        let asbd_int16 = AudioStreamBasicDescription {
+            sample_rate: format.sample_rate as f64,
+            format_id: K_AUDIO_FORMAT_LINEAR_PCM,
+            format_flags: K_AUDIO_FORMAT_FLAG_IS_SIGNED_INTEGER | K_AUDIO_FORMAT_FLAG_IS_PACKED,
+            bytes_per_packet: 2 * format.channels as u32,
+            frames_per_packet: 1,
+            bytes_per_frame: 2 * format.channels as u32,
+            channels_per_frame: format.channels as u32,
+            bits_per_channel: 16,
+            reserved: 0,
+        };
+
+        let status = unsafe {
+            AudioUnitSetProperty(
+                audio_unit,
+                K_AUDIO_UNIT_PROPERTY_STREAM_FORMAT,
+                K_AUDIO_UNIT_SCOPE_INPUT,
+                0,
+                &asbd_int16 as *const _ as *const c_void,
+                std::mem::size_of::<AudioStreamBasicDescription>() as u32,
+            )
+        };
+
+        if status == NO_ERR {
+            log::info!("Integer 16-bit output mode enabled");
+            return (true, OutputFormatMode::Int16);
+        }
+
         log::info!("Integer formats not supported, using Float32");
         (false, OutputFormatMode::Float32)
     }
@@ -1990,6 +2623,9 @@ impl AudioOutput {
         ring_buffer: Arc<RingBuffer<i32>>,
         stats: Arc<PlaybackStats>,
     ) -> Result<(), OutputError> {
+        // 记录协商前的请求（下面会把实际值写回 config）
+        self.requested = Some(self.config.clone());
+
         // 显示输出模式
         if self.is_hal_output {
             log::info!("Output mode: HALOutput (direct hardware access, bit-perfect)");
@@ -2001,25 +2637,52 @@ impl AudioOutput {
         if self.device_id != 0 {
             // 尝试独占模式
             if self.config.exclusive_mode {
-                self.hog_mode_acquired = Self::acquire_hog_mode(self.device_id)?;
-                if self.hog_mode_acquired {
-                    log::info!("Acquired exclusive (hog) mode");
-                } else {
-                    log::warn!("Failed to acquire exclusive mode, continuing in shared mode");
+                match Self::acquire_hog_mode(self.device_id) {
+                    Ok(()) => {
+                        self.hog_mode_acquired = true;
+                        self.hog_mode_failure = None;
+                        HOG_DEVICE.store(self.device_id, Ordering::Release);
+                        log::info!("Acquired exclusive (hog) mode");
+                    }
+                    Err(e) => {
+                        log::warn!(
+                            "Failed to acquire exclusive mode ({}), continuing in shared mode",
+                            e
+                        );
+                        self.hog_mode_acquired = false;
+                        self.hog_mode_failure = Some(e);
+                    }
                 }
             }
 
-            // 智能选择并设置采样率
-            let actual_rate = Self::set_sample_rate_smart(
-                self.device_id,
-                self.config.sample_rate as f64,
-                &self.supported_sample_rates,
-            )?;
+            // 锁定时保持设备当前采样率，否则智能选择并设置采样率
+            let current_rate = Self::get_current_sample_rate(self.device_id).ok();
+            let actual_rate = match locked_device_rate(&self.config, current_rate) {
+                Some(rate) => {
+                    log::info!("Device rate locked at {} Hz, not switching", rate);
+                    rate
+                }
+                None => Self::set_sample_rate_smart(
+                    self.device_id,
+                    self.config.sample_rate as f64,
+                    &self.supported_sample_rates,
+                )?,
+            };
             // 更新 config 中的采样率为实际使用的值
             self.config.sample_rate = actual_rate as u32;
 
-            // 设置缓冲区大小
-            Self::set_buffer_size(self.device_id, self.config.buffer_frames)?;
+            // 设置缓冲区大小（超出设备范围时 clamp）
+            let (buffer_frames, clamped) =
+                clamp_buffer_frames(self.config.buffer_frames, self.buffer_size_range);
+            if clamped {
+                let (min, max) = self.buffer_size_range.unwrap_or_default();
+                log::warn!(
+                    "IO buffer of {} frames is outside the device range {}-{}, using {}",
+                    self.config.buffer_frames, min, max, buffer_frames
+                );
+                self.config.buffer_frames = buffer_frames;
+            }
+            Self::set_buffer_size(self.device_id, buffer_frames)?;
 
             // 设置输出设备
             let audio_unit = self.get_audio_unit();
@@ -2087,10 +2750,14 @@ impl AudioOutput {
         };
 
         // 确定输出模式
+        let mut physical_bits = None;
         let output_mode = if self.config.integer_mode && self.device_id != 0 {
             // HALOutput 模式：物理格式 > Integer > Float32
             let physical_mode = if !needs_src {
-                self.try_set_physical_format(&format, device_sample_rate).map(|(_, mode)| mode)
+                self.try_set_physical_format(&format, device_sample_rate).map(|(asbd, mode)| {
+                    physical_bits = Some(asbd.bits_per_channel as u16);
+                    mode
+                })
             } else {
                 log::info!("SRC required ({}Hz → {}Hz), skipping physical format", format.sample_rate, device_sample_rate);
                 None
@@ -2120,9 +2787,17 @@ impl AudioOutput {
         } else {
             4096  // DefaultOutput 使用较大缓冲区
         };
+        // DefaultOutput 的 4096 只是预分配用的估计值，不计入输出延迟
+        self.io_buffer_frames = if self.device_id != 0 { buffer_frames } else { 0 };
         // 预分配 2 倍 buffer_frames 作为安全余量（应对偶发的大 callback）
         let max_samples_per_callback = (buffer_frames * 2) as usize * format.channels as usize;
         log::info!("Buffer frames: {}, max samples: {}", buffer_frames, max_samples_per_callback);
+        log::info!(
+            "IO callback period: {:.2}ms ({} frames @ {}Hz)",
+            buffer_frames as f64 * 1000.0 / device_sample_rate as f64,
+            buffer_frames,
+            device_sample_rate
+        );
 
         // 查询输出布局
         let output_layout = self.query_output_layout()?;
@@ -2132,19 +2807,18 @@ impl AudioOutput {
         // 预分配 dither_buffer（用于 SIMD 批量 dither）
         let dither_buffer = vec![0.0f32; max_samples_per_callback];
 
-        // 保存实际格式（使用设备实际采样率，而非源文件采样率）
+        // 保存实际格式（使用设备实际采样率，而非源文件采样率；
+        // 设置了物理格式时位深为设备的原生位深）
         self.actual_format = AudioFormat {
             sample_rate: device_sample_rate,
             channels: format.channels,
-            bits_per_sample: format.bits_per_sample,
+            bits_per_sample: physical_bits.unwrap_or(format.bits_per_sample),
             layout: output_layout,
+            endian: format.endian,
         };
 
-        // 创建上下文（使用当前时间戳作为 dither 种子）
-        let dither_seed = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_nanos() as u32)
-            .unwrap_or(0xCAFEBABE);
+        // 创建上下文（未指定种子时使用当前时间戳作为 dither 种子）
+        let dither_seed = self.config.dither_seed.unwrap_or_else(clock_seed);
 
         let context = Box::new(CallbackContext {
             ring_buffer: Arc::clone(&ring_buffer),
@@ -2156,15 +2830,52 @@ impl AudioOutput {
             dither: DitherState::new(dither_seed),
             output_mode,
             source_bits: format.bits_per_sample,
+            fade: FadeRamp::new(
+                (self.config.fade_ms as u64 * device_sample_rate as u64 / 1000) as u32,
+            ),
+            fade_in: AtomicBool::new(true),
+            fade_silent: AtomicBool::new(false),
+            fade_skip: AtomicBool::new(true),
+            callback_host_time: 0,
+            drift: self
+                .config
+                .callback_drift_correction
+                .then(|| DriftCorrector::new(format.channels as usize, buffer_frames as usize * 2)),
             buffer_frames,
             running: CacheLine::new(AtomicBool::new(true)),
             thread_policy_set: CacheLine::new(AtomicBool::new(false)),
+            realtime_active: AtomicBool::new(false),
+            oversized_frames: AtomicU32::new(0),
+            oversized_reported: AtomicBool::new(false),
+            memory_locked: AtomicBool::new(false),
+            drift_ppm_bits: AtomicU64::new(0),
         });
 
         // 锁定关键内存，防止 page fault
-        ring_buffer.lock_memory();
-        context.lock_memory();
-        log::info!("Memory locked for realtime safety");
+        // 超出 RLIMIT_MEMLOCK 时 mlock 必然失败：优先锁定每次回调都访问的上下文缓冲，
+        // 环形缓冲区放弃锁定并给出具体数值
+        let context_bytes = context.memory_bytes();
+        let total_bytes = ring_buffer.memory_bytes() + context_bytes;
+        let limit = memlock_limit();
+        let context_locked = context.lock_memory();
+        let ring_locked = if exceeds_memlock_limit(total_bytes, limit) {
+            let needed_kb = total_bytes.div_ceil(1024);
+            log::warn!(
+                "Realtime buffers need {} KB locked but RLIMIT_MEMLOCK is {} KB; \
+                 ring buffer left unlocked (raise with `ulimit -l {}` or lower --buffer-ms)",
+                needed_kb,
+                limit.unwrap_or(0) / 1024,
+                needed_kb
+            );
+            false
+        } else {
+            ring_buffer.lock_memory()
+        };
+        if context_locked && ring_locked {
+            log::info!("Memory locked for realtime safety");
+        } else {
+            log::warn!("Memory only partially locked, page faults may cause dropouts");
+        }
 
         let context_ptr = Box::into_raw(context);
 
@@ -2215,7 +2926,7 @@ impl AudioOutput {
 
         // 创建电源管理断言，防止 CPU 降频
         // 这对于保持音频处理的时序稳定性非常重要
-        self.power_assertion = power_management::PowerAssertion::new("Roger Player Audio Playback");
+        self.power_assertion.acquire();
 
         log::info!(
             "Audio output started: {}Hz (device), {} channels, {}bit, {:?}, mode={:?}",
@@ -2229,7 +2940,30 @@ impl AudioOutput {
         Ok(())
     }
 
-    /// 暂停输出
+    /// 淡出并等待 IO 回调到达静音
+    ///
+    /// 超时（回调未运行等情况）后直接返回，不阻塞暂停/停止
+    fn fade_out_and_wait(&self) {
+        let Some(ref context) = self.context else {
+            return;
+        };
+        if self.config.fade_ms == 0 || !context.running.0.load(Ordering::Acquire) {
+            return;
+        }
+
+        context.fade_in.store(false, Ordering::Release);
+        let timeout = std::time::Duration::from_millis(self.config.fade_ms as u64 * 2 + 50);
+        let deadline = std::time::Instant::now() + timeout;
+        while !context.fade_silent.load(Ordering::Acquire) {
+            if std::time::Instant::now() >= deadline {
+                log::debug!("Fade-out timed out");
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    /// 暂停输出（先淡出）
     pub fn pause(&mut self) -> Result<(), OutputError> {
         if self.paused {
             return Ok(());
@@ -2240,6 +2974,8 @@ impl AudioOutput {
             return Ok(());
         }
 
+        self.fade_out_and_wait();
+
         let status = unsafe { AudioOutputUnitStop(audio_unit) };
         if status != NO_ERR {
             return Err(OutputError::AudioUnitFailed(status));
@@ -2250,8 +2986,17 @@ impl AudioOutput {
         Ok(())
     }
 
-    /// 恢复输出
+    /// 恢复输出（从静音淡入）
     pub fn resume(&mut self) -> Result<(), OutputError> {
+        self.resume_with_fade(true)
+    }
+
+    /// 从曲目开头恢复输出：不淡入，首个样本即全音量
+    pub fn resume_track_start(&mut self) -> Result<(), OutputError> {
+        self.resume_with_fade(false)
+    }
+
+    fn resume_with_fade(&mut self, fade: bool) -> Result<(), OutputError> {
         if !self.paused {
             return Ok(());
         }
@@ -2261,6 +3006,15 @@ impl AudioOutput {
             return Ok(());
         }
 
+        if let Some(ref context) = self.context {
+            context.fade_silent.store(false, Ordering::Release);
+            context.fade_skip.store(!fade, Ordering::Release);
+            context.fade_in.store(true, Ordering::Release);
+        }
+
+        // 用户暂停时释放过的电源断言在恢复渲染前重新获取
+        self.power_assertion.acquire();
+
         let status = unsafe { AudioOutputUnitStart(audio_unit) };
         if status != NO_ERR {
             return Err(OutputError::AudioUnitFailed(status));
@@ -2276,8 +3030,43 @@ impl AudioOutput {
         self.paused
     }
 
-    /// 停止输出
+    /// 设置 IO 回调内的漂移校正量（ppm，限制到 ±`drift::MAX_DRIFT_PPM`，0 为旁路）
+    ///
+    /// 正值多消耗输入（缓冲区持续增长），负值少消耗。下一次回调生效。
+    /// 未开启 `callback_drift_correction` 或未启动时返回 false
+    pub fn set_drift_ppm(&self, ppm: f64) -> bool {
+        match self.context.as_ref() {
+            Some(ctx) if ctx.drift.is_some() => {
+                ctx.drift_ppm_bits
+                    .store(drift::clamp_ppm(ppm).to_bits(), Ordering::Relaxed);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 当前漂移校正量（ppm，未开启时为 None）
+    pub fn drift_ppm(&self) -> Option<f64> {
+        let ctx = self.context.as_ref().filter(|c| c.drift.is_some())?;
+        Some(f64::from_bits(ctx.drift_ppm_bits.load(Ordering::Relaxed)))
+    }
+
+    /// 暂停期间释放电源断言，允许系统空闲睡眠（用户暂停时调用；`resume` 重新获取）
+    ///
+    /// 未暂停时忽略：渲染中必须保持时序稳定
+    pub fn release_power_assertion(&mut self) {
+        if self.paused && self.power_assertion.is_held() {
+            self.power_assertion.release();
+            log::info!("Power assertion released while paused");
+        }
+    }
+
+    /// 停止输出（播放中先淡出）
     pub fn stop(&mut self) -> Result<(), OutputError> {
+        if !self.paused {
+            self.fade_out_and_wait();
+        }
+
         if let Some(ref context) = self.context {
             context.running.0.store(false, Ordering::Release);
         }
@@ -2292,6 +3081,12 @@ impl AudioOutput {
         if self.hog_mode_acquired {
             Self::release_hog_mode(self.device_id);
             self.hog_mode_acquired = false;
+            let _ = HOG_DEVICE.compare_exchange(
+                self.device_id,
+                0,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
         }
 
         // 恢复原始采样率（仅 HALOutput 需要，DefaultOutput 的 device_id 为 0）
@@ -2300,9 +3095,12 @@ impl AudioOutput {
         }
 
         // 释放电源管理断言（允许系统恢复节能模式）
-        self.power_assertion = None;
+        self.power_assertion.release();
 
-        self.context = None;
+        // 回调已停止，解锁后再释放上下文
+        if let Some(context) = self.context.take() {
+            context.unlock_memory();
+        }
 
         log::info!("Audio output stopped");
         Ok(())
@@ -2331,37 +3129,78 @@ impl AudioOutput {
         self.hog_mode_acquired
     }
 
-    /// 获取输出格式模式
-    pub fn output_format_mode(&self) -> Option<OutputFormatMode> {
-        self.context.as_ref().map(|c| c.output_mode)
+    /// 独占模式获取失败的原因（请求了独占但未获取时）
+    pub fn hog_mode_failure(&self) -> Option<&OutputError> {
+        self.hog_mode_failure.as_ref()
+    }
+
+    /// 设备支持的 IO 缓冲帧数范围（最小/最大）
+    pub fn buffer_size_range(&self) -> Option<(u32, u32)> {
+        self.buffer_size_range
     }
 
-    /// 检查是否为 bit-perfect 输出
+    /// 设备 IO 缓冲帧数（每次回调的周期）；系统混音器或未启动时为 0
+    pub fn io_buffer_frames(&self) -> u32 {
+        self.io_buffer_frames
+    }
+
+    /// 样本被回调取走后到实际发声的延迟（帧）
     ///
-    /// Bit-perfect 条件：
-    /// 1. HAL 直接输出
-    /// 2. 独占模式
-    /// 3. 整数格式（Int32 或 Int24）
-    /// 4. 源采样率与设备采样率相同（无 SRC）
-    pub fn is_bit_perfect(&self, source_sample_rate: u32) -> bool {
-        // 必须是 HAL 输出
-        if !self.is_hal_output {
-            return false;
+    /// IO 缓冲 + 安全偏移 + 设备延迟；系统混音器或未启动时为 0
+    pub fn output_latency_frames(&self) -> u32 {
+        if self.io_buffer_frames == 0 {
+            return 0;
         }
+        self.io_buffer_frames + self.safety_offset_frames + self.device_latency_frames
+    }
 
-        // 必须是整数格式
-        let output_mode = match self.output_format_mode() {
-            Some(mode) => mode,
-            None => return false,
-        };
+    /// 样本被回调取走后到实际发声的延迟（秒）
+    pub fn output_latency_secs(&self) -> f64 {
+        if self.actual_format.sample_rate == 0 {
+            return 0.0;
+        }
+        self.output_latency_frames() as f64 / self.actual_format.sample_rate as f64
+    }
 
-        if !matches!(output_mode, OutputFormatMode::Int32 | OutputFormatMode::Int24) {
-            return false;
+    /// IO 线程是否以实时调度策略运行
+    pub fn io_thread_realtime(&self) -> bool {
+        self.context
+            .as_ref()
+            .map(|c| c.realtime_active.load(Ordering::Acquire))
+            .unwrap_or(false)
+    }
+
+    /// 检查 IO 回调是否收到过超过预分配容量的请求
+    ///
+    /// 回调内不能记日志，由控制线程定期调用；只记录一次
+    pub fn report_oversized_requests(&self) {
+        let Some(ctx) = self.context.as_ref() else {
+            return;
+        };
+        let frames = ctx.oversized_frames.load(Ordering::Relaxed);
+        if frames > 0 && !ctx.oversized_reported.swap(true, Ordering::Relaxed) {
+            log::warn!(
+                "Render callback requested {} frames, more than the {} preallocated; processing in chunks",
+                frames,
+                ctx.sample_buffer.len() / ctx.format.channels.max(1) as usize
+            );
         }
+    }
+
+    /// 获取输出格式模式
+    pub fn output_format_mode(&self) -> Option<OutputFormatMode> {
+        self.context.as_ref().map(|c| c.output_mode)
+    }
 
-        // 必须是无 SRC（采样率匹配）
-        let device_rate = self.actual_format.sample_rate;
-        source_sample_rate == device_rate
+    /// 请求的配置与实际结果的对照（未启动时为 None）
+    pub fn negotiation_report(&self) -> Option<NegotiationReport> {
+        Some(NegotiationReport::new(
+            self.requested.as_ref()?,
+            self.actual_format.sample_rate,
+            self.output_format_mode()?,
+            self.hog_mode_acquired,
+            self.is_hal_output,
+        ))
     }
 
     /// 获取设备 ID
@@ -2394,10 +3233,102 @@ impl Drop for AudioOutput {
     }
 }
 
+/// 左对齐 i32 样本量化到 16-bit（TPDF dither）
+///
+/// dither 为 ±1 LSB 三角分布，加 0.5 LSB 偏置使算术右移（向下截断）
+/// 在统计上等效于舍入，量化误差均值为零
+#[inline(always)]
+fn dither_to_i16(sample: i32, dither: &mut DitherState) -> i16 {
+    let r1 = (dither.next_u32() & 0xFFFF) as i32;
+    let r2 = (dither.next_u32() & 0xFFFF) as i32;
+    let offset = r1 + r2 - 0x8000;
+    (sample.saturating_add(offset) >> 16) as i16
+}
+
+/// 左对齐 i32 样本原地加 24-bit TPDF dither（±1 LSB，饱和）
+///
+/// 随机数发生器是串行的，单独一遍生成；之后按 24-bit 打包即为量化结果
+#[inline(always)]
+fn dither_to_i24(samples: &mut [i32], dither: &mut DitherState) {
+    for sample in samples.iter_mut() {
+        let r1 = (dither.next_u32() & 0xFF) as i32;
+        let r2 = (dither.next_u32() & 0xFF) as i32;
+        *sample = sample.saturating_add((r1 + r2 - 256) << 8);
+    }
+}
+
+/// 左对齐 i32 样本打包为 24-bit packed（小端，取高 3 字节）
+///
+/// 使用 SIMD 加速（ARM NEON），结果与标量路径逐字节一致
+#[inline(always)]
+fn pack_i24(samples: &[i32], out: &mut [u8]) {
+    #[cfg(target_arch = "aarch64")]
+    {
+        pack_i24_neon(samples, out);
+    }
+
+    #[cfg(not(target_arch = "aarch64"))]
+    {
+        pack_i24_scalar(samples, out);
+    }
+}
+
+/// 标量 24-bit 打包（参考实现，也处理 NEON 路径的剩余样本）
+#[inline(always)]
+fn pack_i24_scalar(samples: &[i32], out: &mut [u8]) {
+    for (packed, sample) in out.chunks_exact_mut(3).zip(samples) {
+        let bytes = sample.to_le_bytes();
+        packed.copy_from_slice(&bytes[1..]);
+    }
+}
+
+/// NEON 优化的 24-bit 打包
+///
+/// 每次处理 16 个样本：64 字节输入经查表重排为 48 字节输出
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn pack_i24_neon(samples: &[i32], out: &mut [u8]) {
+    use std::arch::aarch64::*;
+
+    // 输出第 k 字节取自样本 k/3 的第 k%3+1 字节（跳过最低字节）
+    const fn shuffle_indices(part: usize) -> [u8; 16] {
+        let mut indices = [0u8; 16];
+        let mut i = 0;
+        while i < 16 {
+            let k = part * 16 + i;
+            indices[i] = ((k / 3) * 4 + k % 3 + 1) as u8;
+            i += 1;
+        }
+        indices
+    }
+    const INDICES: [[u8; 16]; 3] = [shuffle_indices(0), shuffle_indices(1), shuffle_indices(2)];
+
+    let count = samples.len().min(out.len() / 3);
+    let chunks = count / 16;
+    unsafe {
+        let idx0 = vld1q_u8(INDICES[0].as_ptr());
+        let idx1 = vld1q_u8(INDICES[1].as_ptr());
+        let idx2 = vld1q_u8(INDICES[2].as_ptr());
+        for chunk in 0..chunks {
+            let src = samples.as_ptr().add(chunk * 16) as *const u8;
+            let dst = out.as_mut_ptr().add(chunk * 48);
+            // 加载 16 个 i32（4 个 128-bit 寄存器作为查找表）
+            let table = vld1q_u8_x4(src);
+            vst1q_u8(dst, vqtbl4q_u8(table, idx0));
+            vst1q_u8(dst.add(16), vqtbl4q_u8(table, idx1));
+            vst1q_u8(dst.add(32), vqtbl4q_u8(table, idx2));
+        }
+    }
+
+    // 处理剩余样本
+    let done = chunks * 16;
+    pack_i24_scalar(&samples[done..count], &mut out[done * 3..]);
+}
+
 /// 音频输出处理逻辑
 ///
 /// 供 render_callback 调用。
-/// 处理 Int32/Int24/Float32 三种输出格式。
+/// 处理 Int32/Int24/Int16/Float32 四种输出格式。
 ///
 /// **绝对禁止：**
 /// - 锁
@@ -2406,29 +3337,69 @@ impl Drop for AudioOutput {
 #[inline(always)]
 unsafe fn process_audio_output(
     ctx: &mut CallbackContext,
-    buffer_list: &mut AudioBufferList,
+    buffers: &mut [AudioBuffer],
     samples_needed: usize,
 ) {
-    if buffer_list.number_buffers == 0 {
+    if buffers.is_empty() {
+        return;
+    }
+
+    if ctx.fade_skip.swap(false, Ordering::AcqRel) {
+        ctx.fade.skip_to_full();
+    }
+    let fade_in = ctx.fade_in.load(Ordering::Acquire);
+    if !fade_in && ctx.fade.is_silent() {
+        // 已淡出：输出静音且不消费 ring buffer，等待控制线程暂停/停止
+        for buf in buffers.iter() {
+            ptr::write_bytes(buf.data as *mut u8, 0, buf.data_byte_size as usize);
+        }
+        ctx.fade_silent.store(true, Ordering::Release);
         return;
     }
 
     // NonInterleaved 设备：每声道独立 buffer（极罕见，防御性处理）
     if ctx.output_layout == OutputLayout::NonInterleaved {
-        process_non_interleaved(ctx, buffer_list, samples_needed);
+        process_non_interleaved(ctx, buffers, samples_needed, fade_in);
         return;
     }
 
+    let channels = ctx.format.channels as usize;
+
     match ctx.output_mode {
+        OutputFormatMode::Int32 if drift_active(ctx) => {
+            // 漂移校正中：经 sample_buffer 插值后复制到输出
+            let output_ptr = buffers[0].data as *mut i32;
+            let output_samples = buffers[0].data_byte_size as usize / 4;
+            let output_slice = std::slice::from_raw_parts_mut(output_ptr, output_samples);
+
+            let total = samples_needed.min(output_slice.len());
+            let chunk_samples = sample_chunk_capacity(ctx, total, channels);
+            let mut missing = 0;
+            let mut offset = 0;
+
+            while offset < total {
+                let n = (total - offset).min(chunk_samples);
+                missing += read_sample_chunk(ctx, n, fade_in, channels);
+                output_slice[offset..offset + n].copy_from_slice(&ctx.sample_buffer[..n]);
+                offset += n;
+            }
+
+            if missing > 0 {
+                ctx.stats.record_underrun(ctx.callback_host_time, missing as u64);
+            }
+
+            output_slice[total..].fill(0);
+        }
         OutputFormatMode::Int32 => {
             // 零拷贝路径：直接从 ring buffer 读取到输出缓冲区
-            let output_ptr = buffer_list.buffers[0].data as *mut i32;
-            let output_samples = buffer_list.buffers[0].data_byte_size as usize / 4;
+            let output_ptr = buffers[0].data as *mut i32;
+            let output_samples = buffers[0].data_byte_size as usize / 4;
             let output_slice = std::slice::from_raw_parts_mut(output_ptr, output_samples);
 
             let count = samples_needed.min(output_slice.len());
             let samples_read = ctx.ring_buffer.read(&mut output_slice[..count]);
             ctx.stats.add_samples_played(samples_read as u64);
+            ctx.fade.apply(fade_in, &mut output_slice[..samples_read], channels);
 
             // 填零
             for i in samples_read..output_slice.len() {
@@ -2436,221 +3407,327 @@ unsafe fn process_audio_output(
             }
 
             if samples_read < count {
-                ctx.stats.record_underrun();
+                ctx.stats
+                    .record_underrun(ctx.callback_host_time, (count - samples_read) as u64);
             }
         }
         OutputFormatMode::Int24 => {
-            let actual_samples = samples_needed.min(ctx.sample_buffer.len());
-            let sample_buffer = &mut ctx.sample_buffer[..actual_samples];
-            let samples_read = ctx.ring_buffer.read(sample_buffer);
-            ctx.stats.add_samples_played(samples_read as u64);
+            let output_ptr = buffers[0].data as *mut u8;
+            let output_bytes = buffers[0].data_byte_size as usize;
+            let output_slice = std::slice::from_raw_parts_mut(output_ptr, output_bytes);
+
+            let total = samples_needed.min(output_bytes / 3);
+            let chunk_samples = sample_chunk_capacity(ctx, total, channels);
+            let mut missing = 0;
+            let mut offset = 0;
+
+            while offset < total {
+                let n = (total - offset).min(chunk_samples);
+                missing += read_sample_chunk(ctx, n, fade_in, channels);
+                let out = &mut output_slice[offset * 3..(offset + n) * 3];
 
-            if samples_read < actual_samples {
-                ctx.stats.record_underrun();
-                for i in samples_read..actual_samples {
-                    sample_buffer[i] = 0;
+                // ≤24-bit 源直接取高 3 字节（bit-perfect），更高位深先加 dither
+                if ctx.source_bits > 24 {
+                    dither_to_i24(&mut ctx.sample_buffer[..n], &mut ctx.dither);
                 }
+                pack_i24(&ctx.sample_buffer[..n], out);
+                offset += n;
             }
 
-            let output_ptr = buffer_list.buffers[0].data as *mut u8;
-            let output_bytes = buffer_list.buffers[0].data_byte_size as usize;
-            let output_slice = std::slice::from_raw_parts_mut(output_ptr, output_bytes);
+            if missing > 0 {
+                ctx.stats.record_underrun(ctx.callback_host_time, missing as u64);
+            }
 
-            let count = actual_samples.min(output_bytes / 3);
+            for i in (total * 3)..output_bytes {
+                output_slice[i] = 0;
+            }
+        }
+        OutputFormatMode::Int16 => {
+            let output_ptr = buffers[0].data as *mut i16;
+            let output_samples = buffers[0].data_byte_size as usize / 2;
+            let output_slice = std::slice::from_raw_parts_mut(output_ptr, output_samples);
 
-            if ctx.source_bits <= 24 {
-                for i in 0..count {
-                    let bytes = sample_buffer[i].to_le_bytes();
-                    output_slice[i * 3] = bytes[1];
-                    output_slice[i * 3 + 1] = bytes[2];
-                    output_slice[i * 3 + 2] = bytes[3];
-                }
-            } else {
-                for i in 0..count {
-                    let sample = sample_buffer[i];
-                    let r1 = (ctx.dither.next_u32() & 0xFF) as i32;
-                    let r2 = (ctx.dither.next_u32() & 0xFF) as i32;
-                    let dither = (r1 + r2 - 256) << 8;
-                    let dithered = sample.saturating_add(dither);
-
-                    let bytes = dithered.to_le_bytes();
-                    output_slice[i * 3] = bytes[1];
-                    output_slice[i * 3 + 1] = bytes[2];
-                    output_slice[i * 3 + 2] = bytes[3];
+            let total = samples_needed.min(output_slice.len());
+            let chunk_samples = sample_chunk_capacity(ctx, total, channels);
+            let mut missing = 0;
+            let mut offset = 0;
+
+            while offset < total {
+                let n = (total - offset).min(chunk_samples);
+                missing += read_sample_chunk(ctx, n, fade_in, channels);
+                let sample_buffer = &ctx.sample_buffer[..n];
+                let out = &mut output_slice[offset..offset + n];
+
+                if ctx.source_bits <= 16 {
+                    // 16-bit 源：直接取高 16 位，bit-perfect
+                    for i in 0..n {
+                        out[i] = (sample_buffer[i] >> 16) as i16;
+                    }
+                } else {
+                    for i in 0..n {
+                        out[i] = dither_to_i16(sample_buffer[i], &mut ctx.dither);
+                    }
                 }
+                offset += n;
+            }
+
+            if missing > 0 {
+                ctx.stats.record_underrun(ctx.callback_host_time, missing as u64);
             }
 
-            for i in (count * 3)..output_bytes {
+            for i in total..output_slice.len() {
                 output_slice[i] = 0;
             }
         }
         OutputFormatMode::Float32 => {
             // Float32 需要通过 sample_buffer 进行格式转换
-            let actual_samples = samples_needed.min(ctx.sample_buffer.len());
-            let sample_buffer = &mut ctx.sample_buffer[..actual_samples];
-            let samples_read = ctx.ring_buffer.read(sample_buffer);
-            ctx.stats.add_samples_played(samples_read as u64);
-
-            // 数据不够则填零 + 记录 underrun
-            if samples_read < actual_samples {
-                ctx.stats.record_underrun();
-                for i in samples_read..actual_samples {
-                    sample_buffer[i] = 0;
-                }
-            }
-
-            // Float32 输出 + TPDF dither
-            let output_ptr = buffer_list.buffers[0].data as *mut f32;
-            let output_samples = buffer_list.buffers[0].data_byte_size as usize / 4;
+            let output_ptr = buffers[0].data as *mut f32;
+            let output_samples = buffers[0].data_byte_size as usize / 4;
             let output_slice = std::slice::from_raw_parts_mut(output_ptr, output_samples);
 
             const DITHER_SCALE: f32 = 1.0 / 8388608.0; // 2^-23
             const I32_TO_FLOAT: f32 = 1.0 / 2147483648.0; // 1 / 2^31
 
-            let count = actual_samples.min(output_slice.len());
-
-            // 预生成所有 dither 值到缓冲区，避免 SIMD 循环中断
-            let dither_buffer = &mut ctx.dither_buffer[..count];
-            ctx.dither.fill_tpdf_batch(dither_buffer);
-
-            // SIMD 优化路径（4 样本一批）
-            // 现在是纯 SIMD 循环，dither 值已预生成
-            #[cfg(target_arch = "aarch64")]
-            {
-                use std::arch::aarch64::*;
-                let scale_vec = vdupq_n_f32(I32_TO_FLOAT);
-                let dither_scale_vec = vdupq_n_f32(DITHER_SCALE);
-
-                let chunks = count / 4;
-                for chunk_idx in 0..chunks {
-                    let i = chunk_idx * 4;
-                    // 加载 4 个 i32 样本
-                    let i32x4 = vld1q_s32(sample_buffer.as_ptr().add(i));
-                    // 转换为 f32
-                    let f32x4 = vcvtq_f32_s32(i32x4);
-                    // 乘以缩放因子
-                    let scaled = vmulq_f32(f32x4, scale_vec);
-                    // 加载预生成的 dither 值
-                    let dither_vec = vld1q_f32(dither_buffer.as_ptr().add(i));
-                    // 缩放 dither 值
-                    let scaled_dither = vmulq_f32(dither_vec, dither_scale_vec);
-                    // 添加 dither
-                    let result = vaddq_f32(scaled, scaled_dither);
-                    // 存储结果
-                    vst1q_f32(output_slice.as_mut_ptr().add(i), result);
+            let total = samples_needed.min(output_slice.len());
+            let chunk_samples = sample_chunk_capacity(ctx, total, channels);
+            let mut missing = 0;
+            let mut offset = 0;
+
+            while offset < total {
+                let count = (total - offset).min(chunk_samples);
+                // 数据不够则填零（underrun 每次 callback 只记录一次）
+                missing += read_sample_chunk(ctx, count, fade_in, channels);
+                let sample_buffer = &ctx.sample_buffer[..count];
+                let out = &mut output_slice[offset..offset + count];
+
+                // ≤24-bit 源转 f32 无损（24-bit 尾数），无需 dither
+                if ctx.source_bits <= 24 {
+                    for (o, &sample) in out.iter_mut().zip(sample_buffer) {
+                        *o = sample as f32 * I32_TO_FLOAT;
+                    }
+                    offset += count;
+                    continue;
                 }
 
-                // 处理剩余样本（标量）
-                for i in (chunks * 4)..count {
-                    let sample = sample_buffer[i] as f32 * I32_TO_FLOAT;
-                    let dither = dither_buffer[i] * DITHER_SCALE;
-                    output_slice[i] = sample + dither;
+                // 预生成所有 dither 值到缓冲区，避免 SIMD 循环中断
+                let dither_buffer = &mut ctx.dither_buffer[..count];
+                ctx.dither.fill_tpdf_batch(dither_buffer);
+
+                // SIMD 优化路径（4 样本一批）
+                // 现在是纯 SIMD 循环，dither 值已预生成
+                #[cfg(target_arch = "aarch64")]
+                {
+                    use std::arch::aarch64::*;
+                    let scale_vec = vdupq_n_f32(I32_TO_FLOAT);
+                    let dither_scale_vec = vdupq_n_f32(DITHER_SCALE);
+
+                    let chunks = count / 4;
+                    for chunk_idx in 0..chunks {
+                        let i = chunk_idx * 4;
+                        // 加载 4 个 i32 样本
+                        let i32x4 = vld1q_s32(sample_buffer.as_ptr().add(i));
+                        // 转换为 f32
+                        let f32x4 = vcvtq_f32_s32(i32x4);
+                        // 乘以缩放因子
+                        let scaled = vmulq_f32(f32x4, scale_vec);
+                        // 加载预生成的 dither 值
+                        let dither_vec = vld1q_f32(dither_buffer.as_ptr().add(i));
+                        // 缩放 dither 值
+                        let scaled_dither = vmulq_f32(dither_vec, dither_scale_vec);
+                        // 添加 dither
+                        let result = vaddq_f32(scaled, scaled_dither);
+                        // 存储结果
+                        vst1q_f32(out.as_mut_ptr().add(i), result);
+                    }
+
+                    // 处理剩余样本（标量）
+                    for i in (chunks * 4)..count {
+                        let sample = sample_buffer[i] as f32 * I32_TO_FLOAT;
+                        let dither = dither_buffer[i] * DITHER_SCALE;
+                        out[i] = sample + dither;
+                    }
                 }
-            }
 
-            // 非 ARM64 的标量路径
-            #[cfg(not(target_arch = "aarch64"))]
-            {
-                for i in 0..count {
-                    let sample = sample_buffer[i] as f32 * I32_TO_FLOAT;
-                    let dither = dither_buffer[i] * DITHER_SCALE;
-                    output_slice[i] = sample + dither;
+                // 非 ARM64 的标量路径
+                #[cfg(not(target_arch = "aarch64"))]
+                {
+                    for i in 0..count {
+                        let sample = sample_buffer[i] as f32 * I32_TO_FLOAT;
+                        let dither = dither_buffer[i] * DITHER_SCALE;
+                        out[i] = sample + dither;
+                    }
                 }
+                offset += count;
             }
 
-            for i in count..output_slice.len() {
+            if missing > 0 {
+                ctx.stats.record_underrun(ctx.callback_host_time, missing as u64);
+            }
+
+            for i in total..output_slice.len() {
                 output_slice[i] = 0.0;
             }
         }
     }
 }
 
-/// NonInterleaved 输出处理
+/// 本次 callback 每块可处理的样本数（整帧）
 ///
-/// 从 ring buffer 读取交织数据，按声道拆分写入各独立 buffer。
-/// 标量实现（NonInterleaved 极罕见，不做 SIMD 优化）。
+/// AudioUnit 在设备重新配置后可能请求超过预分配容量的帧数，
+/// 此时按块分批转换而不是截断；记录最大请求帧数，由控制线程读取后记日志
 #[inline(always)]
-unsafe fn process_non_interleaved(
+fn sample_chunk_capacity(ctx: &CallbackContext, samples_needed: usize, channels: usize) -> usize {
+    let capacity = ctx.sample_buffer.len().min(ctx.dither_buffer.len()) / channels * channels;
+    if samples_needed > capacity {
+        ctx.oversized_frames
+            .fetch_max((samples_needed / channels) as u32, Ordering::Relaxed);
+    }
+    capacity.max(channels)
+}
+
+/// 漂移校正是否生效（开启了校正器且校正量非 0）
+#[inline(always)]
+fn drift_active(ctx: &CallbackContext) -> bool {
+    ctx.drift.is_some() && f64::from_bits(ctx.drift_ppm_bits.load(Ordering::Relaxed)) != 0.0
+}
+
+/// 从 ring buffer 读取 `count` 个样本到 `sample_buffer[..count]`
+///
+/// 应用淡入淡出，不足部分填零。返回未能读到的样本数（0 表示没有 underrun）。
+/// 漂移校正生效时按校正比例多读或少读输入，插值出 `count` 个样本
+#[inline(always)]
+fn read_sample_chunk(
     ctx: &mut CallbackContext,
-    buffer_list: &mut AudioBufferList,
-    samples_needed: usize,
-) {
-    let channels = ctx.format.channels as usize;
-    let frames = samples_needed / channels;
+    count: usize,
+    fade_in: bool,
+    channels: usize,
+) -> usize {
+    if let Some(corrector) = ctx.drift.as_mut() {
+        let ppm = f64::from_bits(ctx.drift_ppm_bits.load(Ordering::Relaxed));
+        if ppm != 0.0 {
+            corrector.set_ppm(ppm);
+            let needed = corrector.input_frames_needed(count / channels) * channels;
+            let input = corrector.input_mut(needed);
+            let samples_read = ctx.ring_buffer.read(input);
+            ctx.stats.add_samples_played(samples_read as u64);
+            input[samples_read..].fill(0);
+            let sample_buffer = &mut ctx.sample_buffer[..count];
+            corrector.process(needed, sample_buffer);
+            ctx.fade.apply(fade_in, sample_buffer, channels);
+            return needed - samples_read;
+        }
+        // 校正关闭：丢弃插值状态（保留的一帧随之丢弃），恢复直通
+        corrector.reset();
+    }
 
-    // 读取交织数据到 sample_buffer
-    let actual_samples = samples_needed.min(ctx.sample_buffer.len());
-    let sample_buffer = &mut ctx.sample_buffer[..actual_samples];
+    let sample_buffer = &mut ctx.sample_buffer[..count];
     let samples_read = ctx.ring_buffer.read(sample_buffer);
     ctx.stats.add_samples_played(samples_read as u64);
+    ctx.fade.apply(fade_in, &mut sample_buffer[..samples_read], channels);
 
-    if samples_read < actual_samples {
-        ctx.stats.record_underrun();
-        for i in samples_read..actual_samples {
+    if samples_read < count {
+        for i in samples_read..count {
             sample_buffer[i] = 0;
         }
     }
+    count - samples_read
+}
 
-    let frames_read = samples_read / channels;
-
-    // 按声道拆分到各独立 buffer
-    for ch in 0..channels.min(buffer_list.number_buffers as usize) {
-        let buf = &buffer_list.buffers[ch];
-
-        match ctx.output_mode {
-            OutputFormatMode::Int32 => {
-                let out_ptr = buf.data as *mut i32;
-                let out_frames = buf.data_byte_size as usize / 4;
-                let out_slice = std::slice::from_raw_parts_mut(out_ptr, out_frames);
-                for f in 0..frames.min(out_frames) {
-                    out_slice[f] = if f < frames_read {
-                        sample_buffer[f * channels + ch]
-                    } else {
-                        0
-                    };
-                }
-                for f in frames.min(out_frames)..out_frames {
-                    out_slice[f] = 0;
-                }
-            }
-            OutputFormatMode::Int24 => {
-                let out_ptr = buf.data as *mut u8;
-                let out_bytes = buf.data_byte_size as usize;
-                let out_slice = std::slice::from_raw_parts_mut(out_ptr, out_bytes);
-                let out_frames = out_bytes / 3;
-                for f in 0..frames.min(out_frames) {
-                    let sample = if f < frames_read {
-                        sample_buffer[f * channels + ch]
-                    } else {
-                        0
-                    };
-                    let bytes = sample.to_le_bytes();
-                    out_slice[f * 3] = bytes[1];
-                    out_slice[f * 3 + 1] = bytes[2];
-                    out_slice[f * 3 + 2] = bytes[3];
+/// NonInterleaved 输出处理
+///
+/// 从 ring buffer 读取交织数据，按声道拆分写入各独立 buffer。
+/// 标量实现（NonInterleaved 极罕见，不做 SIMD 优化）。
+#[inline(always)]
+unsafe fn process_non_interleaved(
+    ctx: &mut CallbackContext,
+    buffers: &mut [AudioBuffer],
+    samples_needed: usize,
+    fade_in: bool,
+) {
+    let channels = ctx.format.channels as usize;
+    let frames = samples_needed / channels;
+    // buffer 少于声道数时多出的声道丢弃，多于声道数时多出的 buffer 整体填零
+    let used = channels.min(buffers.len());
+
+    let chunk_frames = sample_chunk_capacity(ctx, samples_needed, channels) / channels;
+    let mut missing = 0;
+    let mut frame_offset = 0;
+
+    // 按块读取交织数据到 sample_buffer，再按声道拆分到各独立 buffer
+    while frame_offset < frames {
+        let chunk = (frames - frame_offset).min(chunk_frames);
+        missing += read_sample_chunk(ctx, chunk * channels, fade_in, channels);
+        let sample_buffer = &ctx.sample_buffer[..chunk * channels];
+
+        for (ch, buf) in buffers[..used].iter().enumerate() {
+            match ctx.output_mode {
+                OutputFormatMode::Int32 => {
+                    let out_ptr = buf.data as *mut i32;
+                    let out_frames = buf.data_byte_size as usize / 4;
+                    let out_slice = std::slice::from_raw_parts_mut(out_ptr, out_frames);
+                    for f in 0..chunk.min(out_frames.saturating_sub(frame_offset)) {
+                        out_slice[frame_offset + f] = sample_buffer[f * channels + ch];
+                    }
                 }
-                for i in (frames.min(out_frames) * 3)..out_bytes {
-                    out_slice[i] = 0;
+                OutputFormatMode::Int24 => {
+                    let out_ptr = buf.data as *mut u8;
+                    let out_bytes = buf.data_byte_size as usize;
+                    let out_slice = std::slice::from_raw_parts_mut(out_ptr, out_bytes);
+                    let out_frames = out_bytes / 3;
+                    for f in 0..chunk.min(out_frames.saturating_sub(frame_offset)) {
+                        let bytes = sample_buffer[f * channels + ch].to_le_bytes();
+                        let o = (frame_offset + f) * 3;
+                        out_slice[o] = bytes[1];
+                        out_slice[o + 1] = bytes[2];
+                        out_slice[o + 2] = bytes[3];
+                    }
                 }
-            }
-            OutputFormatMode::Float32 => {
-                let out_ptr = buf.data as *mut f32;
-                let out_frames = buf.data_byte_size as usize / 4;
-                let out_slice = std::slice::from_raw_parts_mut(out_ptr, out_frames);
-                const I32_TO_FLOAT: f32 = 1.0 / 2147483648.0;
-                for f in 0..frames.min(out_frames) {
-                    let sample = if f < frames_read {
-                        sample_buffer[f * channels + ch]
-                    } else {
-                        0
-                    };
-                    out_slice[f] = sample as f32 * I32_TO_FLOAT;
+                OutputFormatMode::Int16 => {
+                    let out_ptr = buf.data as *mut i16;
+                    let out_frames = buf.data_byte_size as usize / 2;
+                    let out_slice = std::slice::from_raw_parts_mut(out_ptr, out_frames);
+                    for f in 0..chunk.min(out_frames.saturating_sub(frame_offset)) {
+                        let sample = sample_buffer[f * channels + ch];
+                        out_slice[frame_offset + f] = if ctx.source_bits <= 16 {
+                            (sample >> 16) as i16
+                        } else {
+                            dither_to_i16(sample, &mut ctx.dither)
+                        };
+                    }
                 }
-                for f in frames.min(out_frames)..out_frames {
-                    out_slice[f] = 0.0;
+                OutputFormatMode::Float32 => {
+                    let out_ptr = buf.data as *mut f32;
+                    let out_frames = buf.data_byte_size as usize / 4;
+                    let out_slice = std::slice::from_raw_parts_mut(out_ptr, out_frames);
+                    const I32_TO_FLOAT: f32 = 1.0 / 2147483648.0;
+                    for f in 0..chunk.min(out_frames.saturating_sub(frame_offset)) {
+                        out_slice[frame_offset + f] =
+                            sample_buffer[f * channels + ch] as f32 * I32_TO_FLOAT;
+                    }
                 }
             }
         }
+        frame_offset += chunk;
+    }
+
+    if missing > 0 {
+        ctx.stats.record_underrun(ctx.callback_host_time, missing as u64);
+    }
+
+    // 各 buffer 超出请求帧数的部分填零
+    let bytes_per_frame = match ctx.output_mode {
+        OutputFormatMode::Int32 | OutputFormatMode::Float32 => 4,
+        OutputFormatMode::Int24 => 3,
+        OutputFormatMode::Int16 => 2,
+    };
+    for (ch, buf) in buffers.iter().enumerate() {
+        let out_bytes = buf.data_byte_size as usize;
+        let written = if ch < used {
+            (frames * bytes_per_frame).min(out_bytes)
+        } else {
+            0
+        };
+        ptr::write_bytes((buf.data as *mut u8).add(written), 0, out_bytes - written);
     }
 }
 
@@ -2664,23 +3741,28 @@ unsafe fn process_non_interleaved(
 extern "C" fn render_callback(
     in_ref_con: *mut c_void,
     _io_action_flags: *mut u32,
-    _in_time_stamp: *const AudioTimeStamp,
+    in_time_stamp: *const AudioTimeStamp,
     _in_bus_number: u32,
     in_number_frames: u32,
     io_data: *mut AudioBufferList,
 ) -> OSStatus {
     let ctx = unsafe { &mut *(in_ref_con as *mut CallbackContext) };
+    ctx.callback_host_time = unsafe { in_time_stamp.as_ref() }
+        .map(|t| t.valid_host_time())
+        .unwrap_or(0);
 
     if !ctx.running.0.load(Ordering::Acquire) {
         return NO_ERR;
     }
+    ctx.stats.record_callback();
 
     // 首次调用时设置 IO 线程的实时调度策略
     if ctx.thread_policy_set.0
         .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
         .is_ok()
     {
-        ctx.set_realtime_thread_policy();
+        let active = ctx.set_realtime_thread_policy();
+        ctx.realtime_active.store(active, Ordering::Release);
     }
 
     let frames = in_number_frames as usize;
@@ -2688,8 +3770,11 @@ extern "C" fn render_callback(
     let samples_needed = frames * channels;
 
     // 调用共享的音频处理逻辑
-    let buffer_list = unsafe { &mut *io_data };
-    unsafe { process_audio_output(ctx, buffer_list, samples_needed); }
+    if io_data.is_null() {
+        return NO_ERR;
+    }
+    let buffers = unsafe { audio_buffers(io_data) };
+    unsafe { process_audio_output(ctx, buffers, samples_needed); }
 
     NO_ERR
 }
@@ -2705,4 +3790,582 @@ mod tests {
         println!("Device: {:?}", device);
         assert!(!device.supported_sample_rates.is_empty());
     }
+
+    #[test]
+    fn test_hog_mode_owner() {
+        assert_eq!(hog_mode_held_by_other(-1, 100), None);
+        assert_eq!(hog_mode_held_by_other(100, 100), None);
+        assert_eq!(hog_mode_held_by_other(4242, 100), Some(4242));
+        assert_eq!(
+            OutputError::HogModeHeld(4242).to_string(),
+            "Exclusive mode is held by another process (PID 4242)"
+        );
+    }
+
+    #[test]
+    fn test_clamp_buffer_frames() {
+        assert_eq!(clamp_buffer_frames(512, Some((64, 4096))), (512, false));
+        assert_eq!(clamp_buffer_frames(16, Some((64, 4096))), (64, true));
+        assert_eq!(clamp_buffer_frames(8192, Some((64, 4096))), (4096, true));
+        // 范围未知或无效时不调整
+        assert_eq!(clamp_buffer_frames(16, None), (16, false));
+        assert_eq!(clamp_buffer_frames(16, Some((4096, 64))), (16, false));
+    }
+
+    #[test]
+    fn test_locked_device_rate() {
+        // 锁定时无论源采样率如何，都保持设备当前采样率
+        let locked = OutputConfig {
+            lock_device_rate: true,
+            ..OutputConfig::default()
+        };
+        for source in [44100, 48000, 96000, 192000] {
+            let config = OutputConfig {
+                sample_rate: source,
+                ..locked.clone()
+            };
+            assert_eq!(locked_device_rate(&config, Some(48000.0)), Some(48000.0));
+        }
+        // 读不到当前采样率时退回按源切换
+        assert_eq!(locked_device_rate(&locked, None), None);
+
+        // 未锁定：按源采样率切换
+        let config = OutputConfig {
+            sample_rate: 96000,
+            ..OutputConfig::default()
+        };
+        assert_eq!(locked_device_rate(&config, Some(48000.0)), None);
+    }
+
+    #[test]
+    fn test_power_hold() {
+        use std::sync::atomic::AtomicUsize;
+
+        // 假断言：记录创建与释放次数
+        static CREATED: AtomicUsize = AtomicUsize::new(0);
+        static RELEASED: AtomicUsize = AtomicUsize::new(0);
+        struct FakeAssertion;
+        impl Drop for FakeAssertion {
+            fn drop(&mut self) {
+                RELEASED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        fn create() -> Option<FakeAssertion> {
+            CREATED.fetch_add(1, Ordering::Relaxed);
+            Some(FakeAssertion)
+        }
+
+        // start：获取；重复获取（seek 后 resume）不重建
+        let mut hold = PowerHold::new(create);
+        assert!(!hold.is_held());
+        hold.acquire();
+        hold.acquire();
+        assert!(hold.is_held());
+        assert_eq!(CREATED.load(Ordering::Relaxed), 1);
+
+        // 用户暂停：释放
+        hold.release();
+        assert!(!hold.is_held());
+        assert_eq!(RELEASED.load(Ordering::Relaxed), 1);
+
+        // 恢复：重新创建
+        hold.acquire();
+        assert!(hold.is_held());
+        assert_eq!(CREATED.load(Ordering::Relaxed), 2);
+
+        // 输出释放时一并释放
+        drop(hold);
+        assert_eq!(RELEASED.load(Ordering::Relaxed), 2);
+
+        // 创建失败：不持有，下次再试
+        let mut failing: PowerHold<FakeAssertion> = PowerHold::new(|| None);
+        failing.acquire();
+        assert!(!failing.is_held());
+    }
+
+    #[test]
+    fn test_negotiation_report() {
+        let requested = OutputConfig {
+            sample_rate: 96000,
+            ..OutputConfig::default()
+        };
+
+        // 全部满足
+        let report = NegotiationReport::new(&requested, 96000, OutputFormatMode::Int32, true, true);
+        assert!(report.all_as_requested());
+        assert_eq!(
+            report.to_string(),
+            "Requested 96kHz/Integer/Exclusive → Got 96kHz/Int32/Exclusive"
+        );
+
+        // 独占被占用：只有该项不满足；Int24 仍算整数格式
+        let report = NegotiationReport::new(&requested, 96000, OutputFormatMode::Int24, false, true);
+        assert!(!report.all_as_requested());
+        assert!(!report.exclusive.as_requested());
+        assert!(report.integer.as_requested());
+        assert!(report.sample_rate.as_requested());
+        assert_eq!(
+            report.to_string(),
+            "Requested 96kHz/Integer/Exclusive → Got 96kHz/Int24/Shared"
+        );
+
+        // 采样率回退 + 整数格式被拒
+        let report =
+            NegotiationReport::new(&requested, 48000, OutputFormatMode::Float32, true, true);
+        assert_eq!(report.sample_rate, Negotiated { requested: 96000, actual: 48000 });
+        assert!(!report.sample_rate.as_requested());
+        assert!(!report.integer.as_requested());
+        assert!(report.exclusive.as_requested());
+
+        // 主动选择系统混音器：Float32/非独占均如请求（即使配置里仍开着整数/独占）
+        let mixer = OutputConfig {
+            sample_rate: 44100,
+            use_hal: false,
+            ..OutputConfig::default()
+        };
+        let report = NegotiationReport::new(&mixer, 44100, OutputFormatMode::Float32, false, false);
+        assert!(report.all_as_requested());
+        assert_eq!(
+            report.to_string(),
+            "Requested 44.1kHz/Float32/Mixer → Got 44.1kHz/Float32/Mixer"
+        );
+    }
+
+    #[test]
+    fn test_rate_family() {
+        for rate in [11025, 22050, 44100, 88200, 176400, 352800] {
+            assert_eq!(rate_family(rate), Some(44100), "{}", rate);
+        }
+        for rate in [8000, 16000, 32000, 48000, 96000, 192000, 384000] {
+            assert_eq!(rate_family(rate), Some(48000), "{}", rate);
+        }
+        assert_eq!(rate_family(0), None);
+        assert_eq!(rate_family(37800), None);
+
+        assert!(is_cross_family(44100, 48000));
+        assert!(is_cross_family(96000, 88200));
+        assert!(is_cross_family(22050, 192000));
+        // 同系列整数比 SRC
+        assert!(!is_cross_family(44100, 88200));
+        assert!(!is_cross_family(192000, 48000));
+        assert!(!is_cross_family(48000, 48000));
+        // 非标准采样率不判定
+        assert!(!is_cross_family(37800, 48000));
+    }
+
+    #[test]
+    fn test_select_optimal_sample_rate() {
+        let select = AudioOutput::select_optimal_sample_rate;
+        let supported = [44100.0, 48000.0, 88200.0, 96000.0];
+        assert_eq!(select(96000.0, &supported), 96000.0);
+        // 同系列向下取整数比：176.4k → 88.2k，192k → 96k
+        assert_eq!(select(176400.0, &supported), 88200.0);
+        assert_eq!(select(192000.0, &supported), 96000.0);
+        // 22.05k 与 rate_family 一致归入 44.1k 系列，而不是选最接近的 48k 以外的值
+        assert_eq!(select(22050.0, &[48000.0, 88200.0]), 88200.0);
+        assert_eq!(select(32000.0, &supported), 48000.0);
+        assert!(!is_cross_family(22050, select(22050.0, &supported) as u32));
+        // 设备没有同系列采样率：取最接近的
+        assert_eq!(select(44100.0, &[48000.0, 96000.0]), 48000.0);
+        // 非标准采样率
+        assert_eq!(select(37800.0, &supported), 44100.0);
+        assert_eq!(select(44100.0, &[]), 44100.0);
+    }
+
+    #[test]
+    fn test_physical_depth_candidates() {
+        let float32 = AudioStreamBasicDescription {
+            format_flags: K_AUDIO_FORMAT_FLAG_IS_FLOAT | K_AUDIO_FORMAT_FLAG_IS_PACKED,
+            ..integer_asbd(48000.0, 2, 32)
+        };
+        // 24-bit 放在 4 字节容器中（非 packed 的 Int24 模式）
+        let int24_in_32 = AudioStreamBasicDescription {
+            bytes_per_frame: 8,
+            bytes_per_packet: 8,
+            ..integer_asbd(48000.0, 2, 24)
+        };
+
+        // 只支持 24-bit 的 DAC：不再尝试 Int32
+        let available = [float32, integer_asbd(48000.0, 2, 16), integer_asbd(48000.0, 2, 24)];
+        assert_eq!(physical_depth_candidates(&available, 2, 48000.0), vec![24, 16]);
+
+        // 采样率/声道数不匹配的条目被忽略
+        let available = [
+            integer_asbd(44100.0, 2, 32),
+            integer_asbd(48000.0, 8, 32),
+            integer_asbd(48000.0, 2, 16),
+            integer_asbd(48000.0, 2, 16),
+            int24_in_32,
+        ];
+        assert_eq!(physical_depth_candidates(&available, 2, 48000.0), vec![16]);
+        assert_eq!(physical_depth_candidates(&available, 2, 44100.0), vec![32]);
+        let any_rate = [integer_asbd(0.0, 2, 24)];
+        assert_eq!(physical_depth_candidates(&any_rate, 2, 96000.0), vec![24]);
+
+        // 只有浮点格式：没有可用的整数位深
+        assert!(physical_depth_candidates(&[float32], 2, 48000.0).is_empty());
+        // 查询失败：全部尝试
+        assert_eq!(physical_depth_candidates(&[], 2, 48000.0), vec![32, 24, 16]);
+    }
+
+    #[test]
+    fn test_parse_device_uid() {
+        assert_eq!(
+            parse_device_uid("uid:AppleUSBAudioEngine:Topping:D10s:1234:1"),
+            Some("AppleUSBAudioEngine:Topping:D10s:1234:1")
+        );
+        assert_eq!(parse_device_uid("UID: BuiltInSpeakerDevice"), Some("BuiltInSpeakerDevice"));
+        assert_eq!(parse_device_uid("uid:"), None);
+        assert_eq!(parse_device_uid("73"), None);
+        assert_eq!(parse_device_uid("MacBook Pro Speakers"), None);
+        assert_eq!(parse_device_uid("Topping: D10s"), None);
+    }
+
+    #[test]
+    fn test_dither_seed_reproducible() {
+        let mut a = DitherState::new(12345);
+        let mut b = DitherState::new(12345);
+        // 跨越多个批次（DITHER_BATCH_SIZE）验证整个序列一致
+        for _ in 0..DITHER_BATCH_SIZE * 4 {
+            assert_eq!(a.next_tpdf().to_bits(), b.next_tpdf().to_bits());
+        }
+
+        let mut c = DitherState::new(54321);
+        let same = (0..DITHER_BATCH_SIZE).all(|_| a.next_tpdf() == c.next_tpdf());
+        assert!(!same);
+    }
+
+    #[test]
+    fn test_fade_ramp_reaches_target() {
+        // 20ms @ 48kHz = 960 帧
+        let length = 20 * 48000 / 1000;
+        let mut ramp = FadeRamp::new(length);
+        assert!(ramp.is_silent());
+
+        let mut samples = vec![1 << 30; length as usize * 2];
+        ramp.apply(true, &mut samples, 2);
+        assert!(ramp.is_full());
+        // 首帧已有增益，末帧达到全音量，同帧两声道一致
+        assert!(samples[0] > 0 && samples[0] < 1 << 21);
+        assert_eq!(samples[0], samples[1]);
+        assert_eq!(samples[samples.len() - 1], 1 << 30);
+
+        // 全音量后不再改动样本
+        let mut passthrough = vec![12345; 8];
+        ramp.apply(true, &mut passthrough, 2);
+        assert!(passthrough.iter().all(|&s| s == 12345));
+
+        // 淡出：差一帧未到静音，恰好 length 帧到达
+        let mut samples = vec![1 << 30; (length as usize - 1) * 2];
+        ramp.apply(false, &mut samples, 2);
+        assert!(!ramp.is_silent());
+        let mut last = vec![1 << 30; 2];
+        ramp.apply(false, &mut last, 2);
+        assert!(ramp.is_silent());
+        assert_eq!(last, [0, 0]);
+
+        // 等功率：中点增益为 √2/2
+        let mut ramp = FadeRamp::new(4);
+        ramp.next_gain(true);
+        let mid = ramp.next_gain(true);
+        assert!((mid - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-12);
+
+        // 满幅负值：渐变过程中不溢出，全音量时保持原值
+        let mut ramp = FadeRamp::new(4);
+        let mut samples = [i32::MIN; 8];
+        ramp.apply(true, &mut samples, 2);
+        assert!(samples.iter().all(|&s| s < 0));
+        assert_eq!(samples[6..], [i32::MIN; 2]);
+    }
+
+    #[test]
+    fn test_pack_i24_matches_scalar() {
+        let mut rng = DitherState::new(7);
+        let mut samples: Vec<i32> = (0..1003).map(|_| rng.next_u32() as i32).collect();
+        samples[..4].copy_from_slice(&[i32::MIN, i32::MAX, 0, -1]);
+
+        // ≤24-bit 源：逐字节等于取高 3 字节（含 NEON 整块之后的剩余样本）
+        for len in [0, 1, 15, 16, 17, 33, 1003] {
+            let expected: Vec<u8> =
+                samples[..len].iter().flat_map(|s| s.to_le_bytes()[1..].to_vec()).collect();
+            let mut scalar = vec![0u8; len * 3];
+            pack_i24_scalar(&samples[..len], &mut scalar);
+            assert_eq!(scalar, expected, "len {}", len);
+            let mut packed = vec![0u8; len * 3];
+            pack_i24(&samples[..len], &mut packed);
+            assert_eq!(packed, expected, "len {}", len);
+        }
+
+        // >24-bit 源：与逐样本 dither + 打包的结果一致（同一 dither 种子）
+        let mut dither = DitherState::new(99);
+        let expected: Vec<u8> = samples
+            .iter()
+            .flat_map(|&sample| {
+                let r1 = (dither.next_u32() & 0xFF) as i32;
+                let r2 = (dither.next_u32() & 0xFF) as i32;
+                let dithered = sample.saturating_add((r1 + r2 - 256) << 8);
+                dithered.to_le_bytes()[1..].to_vec()
+            })
+            .collect();
+        let mut dithered = samples.clone();
+        dither_to_i24(&mut dithered, &mut DitherState::new(99));
+        let mut packed = vec![0u8; samples.len() * 3];
+        pack_i24(&dithered, &mut packed);
+        assert_eq!(packed, expected);
+    }
+
+    #[test]
+    fn test_dither_to_i16_full_scale() {
+        // 满幅值加 dither 后饱和，不回绕到另一端
+        let mut dither = DitherState::new(12345);
+        for _ in 0..1000 {
+            assert!(dither_to_i16(i32::MIN, &mut dither) <= i16::MIN + 1);
+            assert!(dither_to_i16(i32::MAX, &mut dither) >= i16::MAX - 1);
+        }
+    }
+
+    #[test]
+    fn test_dither_to_i16_unbiased() {
+        let mut dither = DitherState::new(12345);
+        const N: i64 = 20_000;
+
+        // 24-bit 源值（左对齐），覆盖 16-bit LSB 内的不同小数位置
+        for &source in &[0x123456i32, -0x123456, 0x12AB40, -0x000040, 0x000080] {
+            let sample = source << 8;
+            let exact = sample as f64 / 65536.0;
+            let rounded = exact.round();
+
+            let mut sum = 0i64;
+            for _ in 0..N {
+                let out = dither_to_i16(sample, &mut dither) as f64;
+                // 单个样本误差不超过 ±1 LSB（相对于舍入值）
+                assert!((out - rounded).abs() <= 1.0, "{} vs {}", out, rounded);
+                sum += out as i64;
+            }
+
+            // 均值逼近精确值（dither 无偏）
+            let mean = sum as f64 / N as f64;
+            assert!((mean - exact).abs() < 0.05, "mean {} vs exact {}", mean, exact);
+        }
+    }
+
+    /// 测试用回调上下文：预分配容量只有 `capacity` 个样本，淡入已完成
+    fn test_context(output_mode: OutputFormatMode, capacity: usize) -> CallbackContext {
+        CallbackContext {
+            ring_buffer: Arc::new(RingBuffer::new(1024)),
+            stats: Arc::new(PlaybackStats::new()),
+            sample_buffer: vec![0; capacity],
+            dither_buffer: vec![0.0; capacity],
+            dither: DitherState::new(1),
+            output_mode,
+            format: AudioFormat::new(48000, 2, 16),
+            output_layout: OutputLayout::Interleaved,
+            source_bits: 16,
+            fade: FadeRamp { frame: 1, length: 1 },
+            callback_host_time: 0,
+            drift: None,
+            fade_in: AtomicBool::new(true),
+            fade_silent: AtomicBool::new(false),
+            fade_skip: AtomicBool::new(false),
+            buffer_frames: (capacity / 2) as u32,
+            running: CacheLine::new(AtomicBool::new(true)),
+            thread_policy_set: CacheLine::new(AtomicBool::new(true)),
+            realtime_active: AtomicBool::new(false),
+            oversized_frames: AtomicU32::new(0),
+            oversized_reported: AtomicBool::new(false),
+            memory_locked: AtomicBool::new(false),
+            drift_ppm_bits: AtomicU64::new(0),
+        }
+    }
+
+    #[test]
+    fn test_context_unlock_memory_idempotent() {
+        let ctx = test_context(OutputFormatMode::Int32, 4096);
+        assert!(!ctx.is_memory_locked());
+
+        // 未锁定时解锁无操作
+        ctx.unlock_memory();
+        assert!(!ctx.is_memory_locked());
+
+        ctx.lock_memory();
+        assert!(ctx.is_memory_locked());
+        // 重复锁定不重复计数
+        assert!(ctx.lock_memory());
+
+        ctx.unlock_memory();
+        assert!(!ctx.is_memory_locked());
+        ctx.unlock_memory();
+        assert!(!ctx.is_memory_locked());
+
+        // 锁定状态下直接释放，由 Drop 解锁
+        ctx.lock_memory();
+        drop(ctx);
+    }
+
+    #[test]
+    fn test_track_start_skips_fade_in() {
+        let input: Vec<i32> = (1..=16).map(|i| i << 20).collect();
+        let run = |skip: bool| {
+            let mut ctx = test_context(OutputFormatMode::Int32, 16);
+            ctx.fade = FadeRamp::new(8);
+            ctx.fade_skip.store(skip, Ordering::Relaxed);
+            ctx.ring_buffer.write(&input);
+            let mut output = vec![0i32; 16];
+            let mut buffers = [AudioBuffer {
+                number_channels: 2,
+                data_byte_size: 64,
+                data: output.as_mut_ptr() as *mut c_void,
+            }];
+            unsafe { process_audio_output(&mut ctx, &mut buffers, 16) };
+            assert!(!ctx.fade_skip.load(Ordering::Relaxed));
+            output
+        };
+
+        // 曲目开头：首个样本即全音量，逐位不变
+        assert_eq!(run(true), input);
+        // 恢复：从静音淡入
+        let faded = run(false);
+        assert!(faded[0] < input[0] && faded[14..] == input[14..]);
+    }
+
+    #[test]
+    fn test_oversized_request_processed_in_chunks() {
+        // 请求 32 帧，预分配只有 4 帧：应分块转换全部样本，不截断
+        let mut ctx = test_context(OutputFormatMode::Int16, 8);
+        let input: Vec<i32> = (1..=64).map(|i| i << 16).collect();
+        assert_eq!(ctx.ring_buffer.write(&input), 64);
+
+        let mut output = vec![-1i16; 64];
+        let mut buffers = [AudioBuffer {
+            number_channels: 2,
+            data_byte_size: 128,
+            data: output.as_mut_ptr() as *mut c_void,
+        }];
+        unsafe { process_audio_output(&mut ctx, &mut buffers, 64) };
+
+        let expected: Vec<i16> = (1..=64).collect();
+        assert_eq!(output, expected);
+        assert_eq!(ctx.stats.underrun_count(), 0);
+        assert_eq!(ctx.oversized_frames.load(Ordering::Relaxed), 32);
+
+        // Float32 路径同样分块，数据不足时填零且只记一次 underrun
+        let mut ctx = test_context(OutputFormatMode::Float32, 8);
+        ctx.ring_buffer.write(&input[..20]);
+        let mut output = vec![1.0f32; 64];
+        buffers[0].data_byte_size = 256;
+        buffers[0].data = output.as_mut_ptr() as *mut c_void;
+        unsafe { process_audio_output(&mut ctx, &mut buffers, 64) };
+
+        for (i, &v) in output.iter().enumerate() {
+            let expected = if i < 20 { (i + 1) as f32 / 32768.0 } else { 0.0 };
+            assert!((v - expected).abs() < 1e-6, "sample {}: {} vs {}", i, v, expected);
+        }
+        assert_eq!(ctx.stats.underrun_count(), 1);
+        assert_eq!(ctx.stats.underrun_samples_total(), 44);
+    }
+
+    #[test]
+    fn test_underrun_missing_samples() {
+        // 零拷贝路径：每次部分读取按缺失样本数累计，次数每次 callback 只记一次
+        let mut ctx = test_context(OutputFormatMode::Int32, 8);
+        let mut output = vec![0i32; 64];
+        let mut buffers = [AudioBuffer {
+            number_channels: 2,
+            data_byte_size: 256,
+            data: output.as_mut_ptr() as *mut c_void,
+        }];
+
+        ctx.ring_buffer.write(&[1; 60]);
+        unsafe { process_audio_output(&mut ctx, &mut buffers, 64) };
+        assert_eq!(ctx.stats.underrun_count(), 1);
+        assert_eq!(ctx.stats.underrun_samples_total(), 4);
+
+        // 完全没有数据：整块缺失
+        unsafe { process_audio_output(&mut ctx, &mut buffers, 64) };
+        assert_eq!(ctx.stats.underrun_count(), 2);
+        assert_eq!(ctx.stats.underrun_samples_total(), 68);
+
+        // 数据充足：不累计
+        ctx.ring_buffer.write(&[1; 64]);
+        unsafe { process_audio_output(&mut ctx, &mut buffers, 64) };
+        assert_eq!(ctx.stats.underrun_count(), 2);
+        assert_eq!(ctx.stats.underrun_samples_total(), 68);
+
+        // 分块路径：各块缺失之和
+        let mut ctx = test_context(OutputFormatMode::Int16, 8);
+        ctx.ring_buffer.write(&[1; 30]);
+        let mut output = vec![0i16; 64];
+        buffers[0].data_byte_size = 128;
+        buffers[0].data = output.as_mut_ptr() as *mut c_void;
+        unsafe { process_audio_output(&mut ctx, &mut buffers, 64) };
+        assert_eq!(ctx.stats.underrun_count(), 1);
+        assert_eq!(ctx.stats.underrun_samples_total(), 34);
+    }
+
+    #[test]
+    fn test_float32_skips_dither_for_16bit_source() {
+        let input: Vec<i32> = (1..=16).map(|i| (i * 1000) << 16).collect();
+        let run = |source_bits: u16| {
+            let mut ctx = test_context(OutputFormatMode::Float32, 16);
+            ctx.source_bits = source_bits;
+            ctx.ring_buffer.write(&input);
+            let mut output = vec![0.0f32; 16];
+            let mut buffers = [AudioBuffer {
+                number_channels: 2,
+                data_byte_size: 64,
+                data: output.as_mut_ptr() as *mut c_void,
+            }];
+            unsafe { process_audio_output(&mut ctx, &mut buffers, 16) };
+            output
+        };
+
+        // 16-bit 源：逐样本精确，不加 dither
+        let exact: Vec<f32> = input.iter().map(|&s| s as f32 / 2147483648.0).collect();
+        assert_eq!(run(16), exact);
+
+        // 32-bit 源仍加 dither
+        assert_ne!(run(32), exact);
+    }
+
+    #[test]
+    fn test_non_interleaved_multichannel_buffer_list() {
+        // 与 CoreAudio 内存布局相同的 8-buffer 列表（头部 + 连续的 AudioBuffer）
+        #[repr(C)]
+        struct BufferList8 {
+            number_buffers: u32,
+            buffers: [AudioBuffer; 8],
+        }
+
+        // 6 声道源，设备给出 8 个非交织 buffer：前 6 个按声道拆分，后 2 个填零
+        let mut ctx = test_context(OutputFormatMode::Int32, 64);
+        ctx.format = AudioFormat::new(48000, 6, 24);
+        ctx.output_layout = OutputLayout::NonInterleaved;
+        let frames = 4;
+        let input: Vec<i32> = (0..frames * 6).map(|i| i as i32 + 1).collect();
+        assert_eq!(ctx.ring_buffer.write(&input), input.len());
+
+        let mut outputs = vec![vec![-1i32; frames]; 8];
+        let mut list = BufferList8 {
+            number_buffers: 8,
+            buffers: std::array::from_fn(|ch| AudioBuffer {
+                number_channels: 1,
+                data_byte_size: (frames * 4) as u32,
+                data: outputs[ch].as_mut_ptr() as *mut c_void,
+            }),
+        };
+        let list_ptr = &mut list as *mut BufferList8 as *mut AudioBufferList;
+        let buffers = unsafe { audio_buffers(list_ptr) };
+        assert_eq!(buffers.len(), 8);
+        unsafe { process_audio_output(&mut ctx, buffers, frames * 6) };
+
+        for (ch, output) in outputs.iter().enumerate() {
+            let expected: Vec<i32> = if ch < 6 {
+                (0..frames).map(|f| (f * 6 + ch) as i32 + 1).collect()
+            } else {
+                vec![0; frames]
+            };
+            assert_eq!(output, &expected, "channel {}", ch);
+        }
+        assert_eq!(ctx.stats.underrun_count(), 0);
+    }
 }
@@ -0,0 +1,283 @@
+//! 整数比多相 FIR 重采样
+//!
+//! 播放路径上的采样率转换交给 CoreAudio（保持解码端 bit-perfect）；
+//! 本模块供需要软件 SRC 的场合使用（过采样分析、离线转换），
+//! 只处理整数倍关系（2×/4× 过采样、96k↔48k、88.2k↔44.1k）。
+//! 原型滤波器为 Blackman 窗 sinc，按 L/M 拆成 L 个子滤波器，每个输出只计算一个相位。
+//!
+//! 输入输出为左对齐的 i32 交织样本，内部按 f64 计算。创建时分配，处理时复用输出容量
+
+use std::f64::consts::PI;
+
+/// 支持的最大倍数（升/降采样）
+pub const MAX_FACTOR: usize = 8;
+
+/// 原型滤波器长度 = TAPS_PER_FACTOR × max(L, M)
+const TAPS_PER_FACTOR: usize = 48;
+
+/// 截止频率相对于较低一侧奈奎斯特频率的比例
+const ROLLOFF: f64 = 0.9;
+
+/// 最大公约数
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// 两个采样率是否为整数倍关系，返回化简后的 (L, M)（升 L 倍、降 M 倍）
+///
+/// 非整数比（如 44.1k↔48k）或倍数超过 `MAX_FACTOR` 时返回 None
+pub fn integer_ratio(from: u32, to: u32) -> Option<(usize, usize)> {
+    if from == 0 || to == 0 {
+        return None;
+    }
+    let g = gcd(from, to);
+    let (up, down) = ((to / g) as usize, (from / g) as usize);
+    (up.min(down) == 1 && up.max(down) <= MAX_FACTOR).then_some((up, down))
+}
+
+/// 多相 FIR 重采样器（交织样本）
+pub struct PolyphaseResampler {
+    channels: usize,
+    up: usize,
+    down: usize,
+    /// phases[p][k] = h[p + k·L]，与第 k 新的输入样本相乘
+    phases: Vec<Vec<f64>>,
+    /// 每声道最近 taps 个输入样本（环形，交织存放；存两份，窗口总是连续的，内层循环不取模）
+    history: Vec<f64>,
+    /// 抽头数（每相）
+    taps: usize,
+    /// 下一个写入位置（帧）
+    pos: usize,
+    /// 下一个输出在当前输入之后的相位（0..L）
+    phase: usize,
+}
+
+impl PolyphaseResampler {
+    /// 按 L/M 创建（自动约分；0 视为 1）
+    pub fn new(channels: usize, up: usize, down: usize) -> Self {
+        let channels = channels.max(1);
+        let (up, down) = (up.max(1), down.max(1));
+        let g = gcd(up as u32, down as u32) as usize;
+        let (up, down) = (up / g, down / g);
+
+        // 升采样域中的归一化截止频率（周期/样本），增益 L 补偿插零
+        let factor = up.max(down);
+        let len = TAPS_PER_FACTOR * factor;
+        let cutoff = ROLLOFF * 0.5 / factor as f64;
+        let center = (len - 1) as f64 / 2.0;
+        let prototype: Vec<f64> = (0..len)
+            .map(|n| {
+                let x = n as f64 - center;
+                let sinc = if x.abs() < 1e-12 {
+                    1.0
+                } else {
+                    (2.0 * PI * cutoff * x).sin() / (PI * x)
+                };
+                let w = 2.0 * PI * n as f64 / (len - 1) as f64;
+                let window = 0.42 - 0.5 * w.cos() + 0.08 * (2.0 * w).cos();
+                up as f64 * sinc * window
+            })
+            .collect();
+
+        let taps = len.div_ceil(up);
+        let phases = (0..up)
+            .map(|p| {
+                (0..taps)
+                    .map(|k| prototype.get(p + k * up).copied().unwrap_or(0.0))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            channels,
+            up,
+            down,
+            phases,
+            history: vec![0.0; 2 * taps * channels],
+            taps,
+            pos: 0,
+            phase: 0,
+        }
+    }
+
+    /// 按源/目标采样率创建，非整数倍关系时返回 None
+    pub fn for_rates(channels: usize, from: u32, to: u32) -> Option<Self> {
+        integer_ratio(from, to).map(|(up, down)| Self::new(channels, up, down))
+    }
+
+    /// 约分后的 (L, M)
+    pub fn ratio(&self) -> (usize, usize) {
+        (self.up, self.down)
+    }
+
+    /// 滤波器群延迟（输出帧）
+    pub fn latency(&self) -> usize {
+        let len = TAPS_PER_FACTOR * self.up.max(self.down);
+        ((len - 1) as f64 / 2.0 / self.down as f64).round() as usize
+    }
+
+    /// 输入 `input_frames` 帧最多产生的输出帧数（用于预留容量）
+    pub fn max_output_frames(&self, input_frames: usize) -> usize {
+        (input_frames * self.up).div_ceil(self.down) + 1
+    }
+
+    /// 清空滤波历史与相位（seek 后不跨越不连续点滤波）
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.pos = 0;
+        self.phase = 0;
+    }
+
+    /// 处理一块交织样本（整帧），结果写入 `output`（先清空，容量足够时不分配）
+    ///
+    /// 分块处理与整块处理结果一致
+    pub fn process(&mut self, input: &[i32], output: &mut Vec<i32>) {
+        output.clear();
+        let channels = self.channels;
+        let taps = self.taps;
+        output.reserve(self.max_output_frames(input.len() / channels) * channels);
+
+        for frame in input.chunks_exact(channels) {
+            for copy in [self.pos, self.pos + taps] {
+                let base = copy * channels;
+                for (h, &x) in self.history[base..base + channels].iter_mut().zip(frame) {
+                    *h = x as f64;
+                }
+            }
+            self.pos = (self.pos + 1) % taps;
+
+            // 从旧到新的 taps 帧：[pos, pos + taps)
+            let window = &self.history[self.pos * channels..(self.pos + taps) * channels];
+            while self.phase < self.up {
+                let coeffs = &self.phases[self.phase];
+                for ch in 0..channels {
+                    let mut y = 0.0;
+                    // 第 k 新的样本在窗口末尾往前 k 帧
+                    for (&c, frame) in coeffs.iter().zip(window.chunks_exact(channels).rev()) {
+                        y += frame[ch] * c;
+                    }
+                    output.push(y.round().clamp(i32::MIN as f64, i32::MAX as f64) as i32);
+                }
+                self.phase += self.down;
+            }
+            self.phase -= self.up;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 半满幅正弦（单声道）
+    fn tone(freq: f64, rate: f64, frames: usize) -> Vec<f64> {
+        (0..frames)
+            .map(|n| 0.5 * (2.0 * PI * freq * n as f64 / rate).sin())
+            .collect()
+    }
+
+    fn to_i32(samples: &[f64]) -> Vec<i32> {
+        samples.iter().map(|&x| (x * 2_147_483_648.0) as i32).collect()
+    }
+
+    /// Goertzel：单频幅度（相对满幅）
+    fn magnitude(samples: &[i32], freq: f64, rate: f64) -> f64 {
+        let coeff = 2.0 * (2.0 * PI * freq / rate).cos();
+        let (mut s1, mut s2) = (0.0, 0.0);
+        for &x in samples {
+            let s = x as f64 / 2_147_483_648.0 + coeff * s1 - s2;
+            s2 = s1;
+            s1 = s;
+        }
+        let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+        2.0 * power.max(0.0).sqrt() / samples.len() as f64
+    }
+
+    fn db(ratio: f64) -> f64 {
+        20.0 * ratio.log10()
+    }
+
+    #[test]
+    fn test_integer_ratio() {
+        assert_eq!(integer_ratio(96000, 48000), Some((1, 2)));
+        assert_eq!(integer_ratio(44100, 88200), Some((2, 1)));
+        assert_eq!(integer_ratio(44100, 176400), Some((4, 1)));
+        assert_eq!(integer_ratio(48000, 48000), Some((1, 1)));
+        assert_eq!(integer_ratio(44100, 48000), None);
+        assert_eq!(integer_ratio(8000, 192000), None);
+        assert_eq!(integer_ratio(0, 48000), None);
+        assert!(PolyphaseResampler::for_rates(2, 44100, 48000).is_none());
+        assert_eq!(PolyphaseResampler::new(2, 4, 2).ratio(), (2, 1));
+    }
+
+    #[test]
+    fn test_interpolation_attenuates_images() {
+        // 48k → 96k：1kHz 保持，47kHz 镜像被抑制
+        let mut resampler = PolyphaseResampler::for_rates(1, 48000, 96000).unwrap();
+        let input = to_i32(&tone(1000.0, 48000.0, 9600));
+        let mut output = Vec::new();
+        resampler.process(&input, &mut output);
+        assert_eq!(output.len(), 19200);
+
+        let settled = &output[resampler.latency() * 2..][..9600];
+        let wanted = magnitude(settled, 1000.0, 96000.0);
+        assert!(db(wanted / 0.5).abs() < 0.1, "tone {:.2}dB", db(wanted / 0.5));
+        let image = magnitude(settled, 47000.0, 96000.0);
+        assert!(db(image / wanted) < -60.0, "image {:.1}dB", db(image / wanted));
+    }
+
+    #[test]
+    fn test_decimation_rejects_aliases() {
+        // 96k → 48k：1kHz 保持，40kHz（超出新奈奎斯特）不折叠到 8kHz
+        let mut resampler = PolyphaseResampler::for_rates(2, 96000, 48000).unwrap();
+        let low = tone(1000.0, 96000.0, 19200);
+        let high = tone(40000.0, 96000.0, 19200);
+        let mixed: Vec<f64> = low.iter().zip(&high).map(|(a, b)| (a + b) / 2.0).collect();
+        // 立体声：左声道混合信号，右声道静音
+        let input: Vec<i32> = to_i32(&mixed).into_iter().flat_map(|x| [x, 0]).collect();
+
+        // 分块处理与整块一致
+        let mut whole = Vec::new();
+        resampler.process(&input, &mut whole);
+        resampler.reset();
+        let mut split = Vec::new();
+        let mut chunk = Vec::new();
+        for block in input.chunks(2 * 777) {
+            resampler.process(block, &mut chunk);
+            split.extend_from_slice(&chunk);
+        }
+        assert_eq!(whole, split);
+        assert_eq!(whole.len(), 19200);
+
+        let left: Vec<i32> = whole.iter().step_by(2).copied().collect();
+        let settled = &left[resampler.latency()..][..9000];
+        let wanted = magnitude(settled, 1000.0, 48000.0);
+        assert!(db(wanted / 0.25).abs() < 0.1, "tone {:.2}dB", db(wanted / 0.25));
+        let alias = magnitude(settled, 8000.0, 48000.0);
+        assert!(db(alias / wanted) < -60.0, "alias {:.1}dB", db(alias / wanted));
+        assert!(whole.iter().skip(1).step_by(2).all(|&x| x == 0));
+    }
+
+    #[test]
+    fn test_latency_and_reset() {
+        // 冲激响应的峰值位于 latency 处
+        for (up, down) in [(2, 1), (4, 1), (1, 2)] {
+            let mut resampler = PolyphaseResampler::new(1, up, down);
+            let mut input = vec![0; 512];
+            input[0] = 1 << 30;
+            let mut output = Vec::new();
+            resampler.process(&input, &mut output);
+            let peak = (0..output.len()).max_by_key(|&i| output[i].unsigned_abs()).unwrap();
+            assert!(peak.abs_diff(resampler.latency()) <= 1, "{}/{}: {}", up, down, peak);
+
+            // reset 后历史清零：静音输入只产生静音
+            resampler.reset();
+            resampler.process(&[0; 64], &mut output);
+            assert!(output.iter().all(|&x| x == 0));
+        }
+    }
+}
@@ -38,6 +38,43 @@ impl<T: Default> Default for CacheLine<T> {
     }
 }
 
+/// 当前进程的 RLIMIT_MEMLOCK 软限制（字节），无限制或查询失败时返回 None
+pub fn memlock_limit() -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    let result = unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) };
+    if result != 0 || limit.rlim_cur == libc::RLIM_INFINITY {
+        None
+    } else {
+        Some(limit.rlim_cur)
+    }
+}
+
+/// 系统内存页大小（字节），查询失败时按 Apple Silicon 的 16 KB 计
+fn page_size() -> u64 {
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as u64
+    } else {
+        16 * 1024
+    }
+}
+
+/// 锁定 `bytes` 字节是否会超过 memlock 限制（None 表示无限制）
+///
+/// mlock 按页计费，按页向上取整后比较
+pub fn exceeds_memlock_limit(bytes: usize, limit: Option<u64>) -> bool {
+    match limit {
+        Some(limit) => {
+            let page = page_size();
+            (bytes as u64).div_ceil(page) * page > limit
+        }
+        None => false,
+    }
+}
+
 /// SPSC 无锁环形缓冲区
 ///
 /// 内存布局保证：
@@ -131,6 +168,11 @@ impl<T: Copy + Default> RingBuffer<T> {
         self.memory_locked.load(Ordering::Acquire)
     }
 
+    /// 缓冲区占用的字节数（mlock 的大小）
+    pub fn memory_bytes(&self) -> usize {
+        self.capacity * std::mem::size_of::<UnsafeCell<T>>()
+    }
+
     /// 创建指定最小容量的 Ring Buffer（自动向上取整到 2 的幂）
     pub fn with_min_capacity(min_capacity: usize) -> Self {
         Self::new(min_capacity.next_power_of_two())
@@ -234,6 +276,24 @@ impl<T: Copy + Default> RingBuffer<T> {
         to_read
     }
 
+    /// 丢弃最多 n 个样本（消费者调用）
+    ///
+    /// 返回实际丢弃的样本数（不超过 available）
+    /// 与 read 相同的 Ordering，但不拷贝数据
+    #[inline]
+    pub fn skip(&self, n: usize) -> usize {
+        let read = self.read_pos.0.load(Ordering::Relaxed);
+        let write = self.write_pos.0.load(Ordering::Acquire);
+
+        let available = write.wrapping_sub(read);
+        let to_skip = n.min(available);
+
+        if to_skip > 0 {
+            self.read_pos.0.store(read.wrapping_add(to_skip), Ordering::Release);
+        }
+        to_skip
+    }
+
     /// 获取当前可读样本数
     #[inline]
     pub fn available(&self) -> usize {
@@ -281,6 +341,22 @@ impl<T: Copy + Default> Drop for RingBuffer<T> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_exceeds_memlock_limit() {
+        // 2 秒 @ 384kHz 立体声 i32 ≈ 6 MB
+        let rb = RingBuffer::<i32>::with_min_capacity(384_000 * 2 * 2);
+        assert_eq!(rb.memory_bytes(), (1 << 21) * 4);
+        assert!(exceeds_memlock_limit(rb.memory_bytes(), Some(64 * 1024)));
+        assert!(!exceeds_memlock_limit(rb.memory_bytes(), None));
+        assert!(!exceeds_memlock_limit(rb.memory_bytes(), Some(8 << 20)));
+
+        // 按页取整：不足一页也占一页
+        let page = page_size();
+        assert!(!exceeds_memlock_limit(page as usize, Some(page)));
+        assert!(exceeds_memlock_limit(page as usize + 1, Some(page + 1)));
+        assert!(exceeds_memlock_limit(1, Some(0)));
+    }
+
     #[test]
     fn test_ring_buffer_basic() {
         let rb = RingBuffer::<i32>::new(16);
@@ -317,6 +393,27 @@ mod tests {
         assert_eq!(all, [3, 4, 5, 6]);
     }
 
+    #[test]
+    fn test_ring_buffer_skip() {
+        let rb = RingBuffer::<i32>::new(4);
+
+        // 跳过超过可用数量
+        assert_eq!(rb.write(&[1, 2, 3]), 3);
+        assert_eq!(rb.skip(10), 3);
+        assert_eq!(rb.available(), 0);
+        assert_eq!(rb.skip(1), 0);
+
+        // 环绕：read_pos=3，写入 4 个样本跨越缓冲区末尾
+        assert_eq!(rb.write(&[4, 5, 6, 7]), 4);
+        assert_eq!(rb.skip(2), 2);
+        assert_eq!(rb.free_space(), 2);
+
+        // 之后的 read 返回剩余数据
+        let mut output = [0i32; 4];
+        assert_eq!(rb.read(&mut output), 2);
+        assert_eq!(&output[..2], &[6, 7]);
+    }
+
     #[test]
     fn test_ring_buffer_full() {
         let rb = RingBuffer::<i32>::new(4);
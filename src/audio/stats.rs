@@ -3,34 +3,92 @@
 //! IO callback 内仅记录 samples_played 和 underrun_count，
 //! 不做任何诊断性采样（interval timing、water level 等），
 //! 确保信号路径上只有必要的计算。
+//! 生产端（解码线程）对称地记录 write_overflow_count。
+//! 最近几次 underrun 的时间写入预分配的定长数组，供事后排查；
+//! 缺失的样本数累计成总量，区分轻微的毛刺与严重的断音。
+//! 每首曲目从 `reset()` 到预缓冲完成（`arm_underruns()`）之间缓冲区本就为空，不计 underrun；
+//! 解码到 EOF 后（`disarm_underruns()`）排空缓冲区同样不计。
 
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 use super::ring_buffer::CacheLine;
+use super::timing::mach_ticks_to_ns;
+
+/// 保留的 underrun 时间记录数
+pub const UNDERRUN_HISTORY: usize = 16;
 
 /// 播放统计收集器
 ///
-/// IO callback 内仅调用 `add_samples_played()` 和 `record_underrun()`，
-/// 各自只需一次 `fetch_add(Relaxed)` 原子操作。
+/// IO callback 内仅调用 `record_callback()`、`add_samples_played()` 和 `record_underrun()`，
+/// 前两者只需一次 `fetch_add(Relaxed)`；后者仅在 underrun 时额外写入两条时间记录和缺失样本数。
 ///
 /// 内存布局：两个字段独占缓存行，避免 false sharing。
 pub struct PlaybackStats {
+    /// IO callback 调用次数（用于检测输出启动后回调从不触发）
+    callback_count: CacheLine<AtomicU64>,
     samples_played: CacheLine<AtomicU64>,
     underrun_count: CacheLine<AtomicU64>,
+    /// 解码线程写入时缓冲区已满的次数（生产端，与 IO callback 的字段分开缓存行）
+    write_overflow_count: CacheLine<AtomicU64>,
+    /// 最近 underrun 的 host time（mach ticks），按 `count % UNDERRUN_HISTORY` 循环写入
+    underrun_host_times: [AtomicU64; UNDERRUN_HISTORY],
+    /// 最近 underrun 发生时的 samples_played，与 `underrun_host_times` 一一对应
+    underrun_positions: [AtomicU64; UNDERRUN_HISTORY],
+    /// 所有 underrun 累计缺失的样本数（交织样本，含全部声道）
+    underrun_samples_total: AtomicU64,
+    /// 是否统计 underrun（`reset()` 后关闭，预缓冲完成时由解码线程打开）
+    underruns_armed: AtomicBool,
 }
 
 impl PlaybackStats {
     pub fn new() -> Self {
         Self {
+            callback_count: CacheLine::new(AtomicU64::new(0)),
             samples_played: CacheLine::new(AtomicU64::new(0)),
             underrun_count: CacheLine::new(AtomicU64::new(0)),
+            write_overflow_count: CacheLine::new(AtomicU64::new(0)),
+            underrun_host_times: std::array::from_fn(|_| AtomicU64::new(0)),
+            underrun_positions: std::array::from_fn(|_| AtomicU64::new(0)),
+            underrun_samples_total: AtomicU64::new(0),
+            underruns_armed: AtomicBool::new(true),
         }
     }
 
+    /// 记录一次 IO callback（IO callback 内调用）
+    #[inline]
+    pub fn record_callback(&self) {
+        self.callback_count.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 获取 IO callback 调用次数
+    #[inline]
+    pub fn callback_count(&self) -> u64 {
+        self.callback_count.0.load(Ordering::Relaxed)
+    }
+
     /// 记录 underrun（IO callback 内调用）
+    ///
+    /// `host_time` 为本次 callback 的 host time（mach ticks），`missing_samples` 为本次
+    /// callback 未能读到而填零的样本数；时间和当前播放位置写入定长数组，
+    /// 缺失样本累加到总量，只有原子操作，无锁无分配。预缓冲完成前忽略
+    #[inline]
+    pub fn record_underrun(&self, host_time: u64, missing_samples: u64) {
+        if !self.underruns_armed.load(Ordering::Acquire) {
+            return;
+        }
+        self.underrun_samples_total
+            .fetch_add(missing_samples, Ordering::Relaxed);
+        let index = self.underrun_count.0.load(Ordering::Relaxed) as usize % UNDERRUN_HISTORY;
+        self.underrun_host_times[index].store(host_time, Ordering::Relaxed);
+        self.underrun_positions[index].store(self.samples_played(), Ordering::Relaxed);
+        // 计数最后更新（Release），读取端看到计数时对应记录已写入
+        self.underrun_count.0.fetch_add(1, Ordering::Release);
+    }
+
+    /// 记录写入溢出：`write()` 未能写入全部样本（解码线程调用）
     #[inline]
-    pub fn record_underrun(&self) {
-        self.underrun_count.0.fetch_add(1, Ordering::Relaxed);
+    pub fn record_write_overflow(&self) {
+        self.write_overflow_count.0.fetch_add(1, Ordering::Relaxed);
     }
 
     /// 更新已播放样本数（IO callback 内调用）
@@ -45,15 +103,68 @@ impl PlaybackStats {
         self.underrun_count.0.load(Ordering::Relaxed)
     }
 
+    /// 所有 underrun 累计缺失的样本数（交织样本，换算时长需除以声道数和采样率）
+    #[inline]
+    pub fn underrun_samples_total(&self) -> u64 {
+        self.underrun_samples_total.load(Ordering::Relaxed)
+    }
+
+    /// 最近 underrun 的 host time（纳秒），按发生顺序排列，最多 `UNDERRUN_HISTORY` 条
+    pub fn underrun_times_ns(&self) -> Vec<u64> {
+        self.underrun_history(&self.underrun_host_times)
+            .into_iter()
+            .map(mach_ticks_to_ns)
+            .collect()
+    }
+
+    /// 最近 underrun 发生时的已播放样本数，顺序与 `underrun_times_ns()` 相同
+    pub fn underrun_positions(&self) -> Vec<u64> {
+        self.underrun_history(&self.underrun_positions)
+    }
+
+    /// 按发生顺序读出循环数组中的有效记录
+    fn underrun_history(&self, slots: &[AtomicU64; UNDERRUN_HISTORY]) -> Vec<u64> {
+        let count = self.underrun_count.0.load(Ordering::Acquire) as usize;
+        let first = count.saturating_sub(UNDERRUN_HISTORY);
+        (first..count)
+            .map(|i| slots[i % UNDERRUN_HISTORY].load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// 获取写入溢出计数
+    #[inline]
+    pub fn write_overflow_count(&self) -> u64 {
+        self.write_overflow_count.0.load(Ordering::Relaxed)
+    }
+
     /// 获取已播放样本数
     #[inline]
     pub fn samples_played(&self) -> u64 {
         self.samples_played.0.load(Ordering::Relaxed)
     }
 
-    /// 重置统计
+    /// 设置已播放样本数（seek 时调用，此时输出已暂停）
+    pub fn set_samples_played(&self, samples: u64) {
+        self.samples_played.0.store(samples, Ordering::Relaxed);
+    }
+
+    /// 开始统计 underrun（解码线程在预缓冲完成时调用）
+    pub fn arm_underruns(&self) {
+        self.underruns_armed.store(true, Ordering::Release);
+    }
+
+    /// 停止统计 underrun（解码线程到达 EOF 时调用，之后读空缓冲区是正常的收尾）
+    pub fn disarm_underruns(&self) {
+        self.underruns_armed.store(false, Ordering::Release);
+    }
+
+    /// 重置统计（新曲目开始，预缓冲完成前不计 underrun）
     pub fn reset(&self) {
+        self.underruns_armed.store(false, Ordering::Release);
+        self.callback_count.0.store(0, Ordering::Relaxed);
         self.underrun_count.0.store(0, Ordering::Relaxed);
+        self.underrun_samples_total.store(0, Ordering::Relaxed);
+        self.write_overflow_count.0.store(0, Ordering::Relaxed);
         self.samples_played.0.store(0, Ordering::Relaxed);
     }
 }
@@ -63,3 +174,55 @@ impl Default for PlaybackStats {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_underrun_history_in_order() {
+        let stats = PlaybackStats::new();
+        assert!(stats.underrun_times_ns().is_empty());
+
+        stats.add_samples_played(100);
+        stats.record_underrun(1_000, 4);
+        stats.add_samples_played(100);
+        stats.record_underrun(2_000, 4096);
+        assert_eq!(stats.underrun_count(), 2);
+        assert_eq!(stats.underrun_samples_total(), 4100);
+        assert_eq!(stats.underrun_positions(), vec![100, 200]);
+        assert_eq!(
+            stats.underrun_times_ns(),
+            vec![mach_ticks_to_ns(1_000), mach_ticks_to_ns(2_000)]
+        );
+
+        // 超过容量后只保留最近的记录，仍按发生顺序
+        for i in 3..=UNDERRUN_HISTORY as u64 + 5 {
+            stats.record_underrun(i * 1_000, 1);
+        }
+        let times = stats.underrun_times_ns();
+        assert_eq!(times.len(), UNDERRUN_HISTORY);
+        assert_eq!(times[0], mach_ticks_to_ns(6_000));
+        assert_eq!(
+            times[UNDERRUN_HISTORY - 1],
+            mach_ticks_to_ns((UNDERRUN_HISTORY as u64 + 5) * 1_000)
+        );
+
+        stats.reset();
+        assert!(stats.underrun_positions().is_empty());
+        assert_eq!(stats.underrun_samples_total(), 0);
+
+        // 重置后到预缓冲完成前缓冲区本就为空，不计 underrun
+        stats.record_underrun(1_000, 512);
+        assert_eq!(stats.underrun_count(), 0);
+        stats.arm_underruns();
+        stats.record_underrun(2_000, 512);
+        assert_eq!(stats.underrun_count(), 1);
+
+        // EOF 后排空缓冲区不计
+        stats.disarm_underruns();
+        stats.record_underrun(3_000, 512);
+        assert_eq!(stats.underrun_count(), 1);
+        assert_eq!(stats.underrun_samples_total(), 512);
+    }
+}
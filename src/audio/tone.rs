@@ -0,0 +1,204 @@
+//! 测试信号发生器（bit-perfect 自检）
+//!
+//! 不经解码器，直接按指定位深生成左对齐的 i32 样本写入 ring buffer，
+//! 用于确认声道映射、极性，以及输出路径上没有意外的音量/SRC。
+//! 所有信号都精确落在目标位深的整数码值上（黑电平 dither 也是整数 LSB）。
+
+use super::output::DitherState;
+
+/// 正弦 / 方波频率
+pub const TONE_FREQ_HZ: u32 = 1000;
+
+/// 正弦幅度（-6.02 dBFS，留出余量避免采样间过载）
+pub const SINE_AMPLITUDE: f64 = 0.5;
+
+/// 位深阶梯每一级的时长（秒）
+pub const RAMP_STEP_SECS: f64 = 0.25;
+
+/// 测试信号类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneKind {
+    /// 1kHz 正弦，-6 dBFS
+    Sine,
+    /// 1kHz 满幅方波（正半周在前，用于检查极性）
+    Square,
+    /// 带 TPDF dither（±1 LSB）的数字静音
+    Black,
+    /// 位深阶梯：1kHz 方波幅度从 1 LSB 起每级翻倍，直到满幅
+    Ramp,
+}
+
+impl std::str::FromStr for ToneKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sine" => Ok(Self::Sine),
+            "square" => Ok(Self::Square),
+            "black" => Ok(Self::Black),
+            "ramp" => Ok(Self::Ramp),
+            _ => Err(format!(
+                "unknown test tone '{}' (sine, square, black, ramp)",
+                s
+            )),
+        }
+    }
+}
+
+/// 测试信号发生器，输出交错的 i32 左对齐样本
+pub struct ToneGenerator {
+    kind: ToneKind,
+    sample_rate: u32,
+    channels: usize,
+    bits: u32,
+    /// 只在此声道输出（其余声道静音）；None 表示所有声道
+    only_channel: Option<usize>,
+    /// 已生成的帧数
+    frame: u64,
+    dither: DitherState,
+}
+
+impl ToneGenerator {
+    /// `bits` 为 16、24 或 32
+    pub fn new(kind: ToneKind, sample_rate: u32, channels: usize, bits: u32) -> Self {
+        debug_assert!(matches!(bits, 16 | 24 | 32));
+        Self {
+            kind,
+            sample_rate,
+            channels,
+            bits,
+            only_channel: None,
+            frame: 0,
+            dither: DitherState::new(0x5EED),
+        }
+    }
+
+    /// 只在指定声道（从 0 开始）输出，用于确认声道映射
+    pub fn with_channel(mut self, channel: Option<usize>) -> Self {
+        self.only_channel = channel;
+        self
+    }
+
+    /// 最大正码值
+    fn max_code(&self) -> i64 {
+        (1i64 << (self.bits - 1)) - 1
+    }
+
+    /// 码值左对齐到 i32
+    fn align(&self, code: i64) -> i32 {
+        (code << (32 - self.bits)) as i32
+    }
+
+    /// 1kHz 方波当前处于正半周
+    fn square_positive(&self) -> bool {
+        (self.frame * 2 * TONE_FREQ_HZ as u64 / self.sample_rate as u64).is_multiple_of(2)
+    }
+
+    /// 当前帧的码值
+    fn next_code(&mut self) -> i64 {
+        let max = self.max_code();
+        match self.kind {
+            ToneKind::Sine => {
+                let t = self.frame as f64 / self.sample_rate as f64;
+                let x =
+                    SINE_AMPLITUDE * (2.0 * std::f64::consts::PI * TONE_FREQ_HZ as f64 * t).sin();
+                (x * max as f64).round() as i64
+            }
+            ToneKind::Square => {
+                if self.square_positive() {
+                    max
+                } else {
+                    -max
+                }
+            }
+            // TPDF：两个独立的 0/1 相减，得到 -1/0/+1 LSB（概率 1/4、1/2、1/4）
+            ToneKind::Black => {
+                let r = self.dither.next_u32();
+                (r & 1) as i64 - ((r >> 1) & 1) as i64
+            }
+            ToneKind::Ramp => {
+                let step_frames = ((self.sample_rate as f64 * RAMP_STEP_SECS) as u64).max(1);
+                let bit = (self.frame / step_frames) % self.bits as u64;
+                let level = (1i64 << bit).min(max);
+                if self.square_positive() {
+                    level
+                } else {
+                    -level
+                }
+            }
+        }
+    }
+
+    /// 填充交错样本（长度应为声道数的整数倍）
+    pub fn fill(&mut self, out: &mut [i32]) {
+        for frame in out.chunks_exact_mut(self.channels) {
+            let code = self.next_code();
+            let value = self.align(code);
+            for (ch, sample) in frame.iter_mut().enumerate() {
+                *sample = match self.only_channel {
+                    Some(only) if only != ch => 0,
+                    _ => value,
+                };
+            }
+            self.frame += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(kind: ToneKind, rate: u32, bits: u32, frames: usize) -> Vec<i32> {
+        let mut out = vec![0; frames];
+        ToneGenerator::new(kind, rate, 1, bits).fill(&mut out);
+        out
+    }
+
+    #[test]
+    fn test_sine_and_square_values() {
+        // 48kHz 下 1kHz 正弦每周期 48 帧：第 12 帧为峰值
+        let sine = generate(ToneKind::Sine, 48000, 24, 48);
+        assert_eq!(sine[0], 0);
+        assert_eq!(sine[12], 4194304 << 8); // round(0.5 * 8388607)
+        assert_eq!(sine[36], -4194304 << 8);
+
+        // 满幅方波：正半周在前，16-bit 码值 ±32767
+        let square = generate(ToneKind::Square, 48000, 16, 48);
+        assert!(square[..24].iter().all(|&s| s == 32767 << 16));
+        assert!(square[24..].iter().all(|&s| s == -32767 << 16));
+    }
+
+    #[test]
+    fn test_black_is_dithered_lsb() {
+        let black = generate(ToneKind::Black, 44100, 24, 4096);
+        let lsb = 1 << 8;
+        assert!(black.iter().all(|&s| [-lsb, 0, lsb].contains(&s)));
+        assert!(black.contains(&lsb) && black.contains(&-lsb));
+        // TPDF：均值接近 0
+        let mean = black.iter().map(|&s| (s / lsb) as f64).sum::<f64>() / black.len() as f64;
+        assert!(mean.abs() < 0.1, "mean {}", mean);
+    }
+
+    #[test]
+    fn test_ramp_steps_through_bits() {
+        // 4000Hz：每级 1000 帧，方波半周期 2 帧
+        let ramp = generate(ToneKind::Ramp, 4000, 16, 16 * 1000);
+        for bit in 0..16 {
+            let level = (1i32 << bit).min(32767) << 16;
+            assert_eq!(ramp[bit * 1000], level, "bit {}", bit);
+            assert_eq!(ramp[bit * 1000 + 2], -level, "bit {}", bit);
+        }
+    }
+
+    #[test]
+    fn test_single_channel_output() {
+        let mut out = vec![1; 8];
+        ToneGenerator::new(ToneKind::Square, 48000, 2, 24)
+            .with_channel(Some(1))
+            .fill(&mut out);
+        for frame in out.chunks_exact(2) {
+            assert_eq!(frame, [0, 8388607 << 8]);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! 立体声宽度（M/S）
+//!
+//! 把 L/R 转为 中/侧（M = (L+R)/2，S = (L-R)/2），按宽度缩放侧信号后转回：
+//! 1.0 不变，0.0 折叠为单声道，> 1.0 展宽。
+//! 内部按 f32 计算，M、S 分别加 TPDF dither 量化到 24-bit 后重建 L/R，
+//! 宽度为 0 时侧信号恒为 0，L/R 逐位相等。
+//!
+//! 仅处理立体声交织样本；宽度为 1.0 时由调用方旁路，保持 bit-perfect
+
+use super::output::DitherState;
+
+/// 宽度上限（侧信号最多放大 2 倍，约 +6 dB）
+pub const MAX_WIDTH: f64 = 2.0;
+
+/// i32 满幅
+const FULL_SCALE: f32 = 2_147_483_648.0;
+
+/// 24-bit 量化步长对应的 i32 位移（f32 尾数恰好 24 位）
+const QUANT_SHIFT: u32 = 8;
+
+/// 24-bit 满幅
+const FULL_SCALE_24: f32 = (1 << 23) as f32;
+
+/// 宽度限制到 0.0-MAX_WIDTH（非法值视为 1.0，即直通）
+pub fn width_factor(width: f64) -> f64 {
+    if width.is_finite() {
+        width.clamp(0.0, MAX_WIDTH)
+    } else {
+        1.0
+    }
+}
+
+/// L/R → M/S
+#[inline]
+pub fn to_mid_side(left: f32, right: f32) -> (f32, f32) {
+    ((left + right) * 0.5, (left - right) * 0.5)
+}
+
+/// M/S → L/R
+#[inline]
+pub fn from_mid_side(mid: f32, side: f32) -> (f32, f32) {
+    (mid + side, mid - side)
+}
+
+/// 宽度处理状态（每曲目创建一次，处理时不分配）
+pub struct StereoWidth {
+    dither: DitherState,
+}
+
+impl StereoWidth {
+    /// 按 dither 种子创建
+    pub fn new(seed: u32) -> Self {
+        Self {
+            dither: DitherState::new(seed),
+        }
+    }
+
+    /// 对立体声交织样本施加宽度
+    ///
+    /// `width` 取 0.0-MAX_WIDTH；展宽后超出满幅的样本饱和
+    pub fn process(&mut self, samples: &mut [i32], width: f64) {
+        if width == 1.0 {
+            return;
+        }
+        let width = width_factor(width) as f32;
+
+        for frame in samples.chunks_exact_mut(2) {
+            let (mid, side) =
+                to_mid_side(frame[0] as f32 / FULL_SCALE, frame[1] as f32 / FULL_SCALE);
+            // M/S 分别量化到 24-bit 整数，L/R 由整数和差得到（宽度 0 时 L == R）
+            let mid = self.quantize(mid);
+            let side = if width > 0.0 {
+                self.quantize(side * width)
+            } else {
+                0
+            };
+            frame[0] = to_i32(mid + side);
+            frame[1] = to_i32(mid - side);
+        }
+    }
+
+    /// f32（满幅 ±1）→ 24-bit 整数（TPDF dither 后取整）
+    #[inline]
+    fn quantize(&mut self, x: f32) -> i64 {
+        (x * FULL_SCALE_24 + self.dither.next_tpdf()).round() as i64
+    }
+}
+
+/// 24-bit 整数 → 左对齐 i32（饱和）
+#[inline]
+fn to_i32(x: i64) -> i32 {
+    let max = (i32::MAX >> QUANT_SHIFT) as i64;
+    let min = (i32::MIN >> QUANT_SHIFT) as i64;
+    (x.clamp(min, max) as i32) << QUANT_SHIFT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mid_side_roundtrip() {
+        for (left, right) in [
+            (0.5, -0.25),
+            (1.0, 1.0),
+            (-1.0, 0.0),
+            (0.123_456, 0.654_321),
+        ] {
+            let (mid, side) = to_mid_side(left, right);
+            let (l, r) = from_mid_side(mid, side);
+            assert!(
+                (l - left).abs() < 1e-6 && (r - right).abs() < 1e-6,
+                "{} {}",
+                left,
+                right
+            );
+        }
+        assert_eq!(width_factor(f64::NAN), 1.0);
+        assert_eq!(width_factor(5.0), MAX_WIDTH);
+        assert_eq!(width_factor(-1.0), 0.0);
+
+        // 宽度 ≠ 1 时整数路径误差在 dither 量级（几个 24-bit LSB）
+        let input = [
+            1 << 30,
+            -(1 << 29),
+            123_456_789,
+            -987_654_321,
+            i32::MAX,
+            i32::MIN,
+        ];
+        let mut samples = input;
+        StereoWidth::new(1).process(&mut samples, 1.0 + 1e-9);
+        for (&out, &inp) in samples.iter().zip(&input) {
+            assert!(
+                (out as i64 - inp as i64).abs() <= 4 << QUANT_SHIFT,
+                "{} vs {}",
+                out,
+                inp
+            );
+        }
+    }
+
+    #[test]
+    fn test_unity_width_is_passthrough() {
+        let mut width = StereoWidth::new(1);
+        let input = [i32::MAX, i32::MIN, 12345, -6789, 0, 1];
+        let mut samples = input;
+        width.process(&mut samples, 1.0);
+        assert_eq!(samples, input);
+    }
+
+    #[test]
+    fn test_zero_width_is_mono() {
+        let mut width = StereoWidth::new(7);
+        let mut samples: Vec<i32> = (0..256)
+            .flat_map(|i| [i * 1_000_003, -(i * 777_777)])
+            .chain([i32::MAX, i32::MIN, i32::MIN, i32::MIN])
+            .collect();
+        width.process(&mut samples, 0.0);
+        for frame in samples.chunks_exact(2) {
+            assert_eq!(frame[0], frame[1]);
+        }
+        // 满幅负值不回绕
+        assert!(samples[samples.len() - 1] < 0);
+        assert_eq!(samples[samples.len() - 2], i32::MIN);
+    }
+
+    #[test]
+    fn test_widen_scales_side() {
+        // 只有左声道：M = S = x/2；宽度 2 时 L = 1.5x，R = -0.5x
+        let mut width = StereoWidth::new(3);
+        let mut samples = [1 << 28, 0];
+        width.process(&mut samples, 2.0);
+        let x = (1 << 28) as f64;
+        assert!(
+            (samples[0] as f64 - 1.5 * x).abs() <= 512.0,
+            "{}",
+            samples[0]
+        );
+        assert!(
+            (samples[1] as f64 + 0.5 * x).abs() <= 512.0,
+            "{}",
+            samples[1]
+        );
+
+        // 展宽超出满幅时饱和
+        let mut samples = [i32::MAX, i32::MIN];
+        width.process(&mut samples, 2.0);
+        assert!(samples[0] > i32::MAX - 1024 && samples[1] == i32::MIN);
+    }
+}
@@ -0,0 +1,456 @@
+//! 用户配置持久化
+//!
+//! 配置文件：`<平台配置目录>/roger-player/config.toml`
+//! （macOS 上为 `~/Library/Application Support/roger-player/config.toml`）
+//!
+//! 优先级：CLI 参数 > 配置文件 > 内置默认值。
+//! 文件缺失、字段缺失或解析失败时回退到默认值，不影响启动。
+//!
+//! 续播位置单独保存在同目录的 `resume.toml`，避免频繁改写用户配置；
+//! 响度扫描结果（`roger-player scan`）保存在 `loudness.toml`。
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tui::theme::ThemeName;
+
+/// 默认缓冲时长（毫秒，未指定 `--buffer-ms` 时只用于首次创建 ring buffer）
+pub const DEFAULT_BUFFER_MS: u32 = 2000;
+
+/// 配置目录名
+const CONFIG_DIR_NAME: &str = "roger-player";
+
+/// 配置文件名
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// 续播位置文件名
+const RESUME_FILE_NAME: &str = "resume.toml";
+
+/// 响度缓存文件名
+const LOUDNESS_FILE_NAME: &str = "loudness.toml";
+
+/// 停止时播放超过该时长（秒）才记住位置
+pub const RESUME_MIN_SECS: f64 = 60.0;
+
+/// 循环播放模式
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepeatMode {
+    #[default]
+    Off,   // 播放完列表后停止
+    All,   // 列表循环
+    Track, // 单曲循环
+}
+
+/// 用户配置
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserConfig {
+    /// 缓冲时长（毫秒，None 按格式/来源自动确定）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buffer_ms: Option<u32>,
+    /// 是否使用 HAL 直接输出
+    pub use_hal: bool,
+    /// 循环播放模式
+    pub repeat: RepeatMode,
+    /// 是否随机播放
+    pub shuffle: bool,
+    /// 目录模式是否包含子目录
+    pub recursive: bool,
+    /// 音量（0.0-1.0）
+    pub volume: f32,
+    /// 日志文件路径（未设置时 TUI 模式不记录日志）
+    pub log_file: Option<PathBuf>,
+    /// 响度归一化目标（LUFS，需先 `scan`；None 表示不归一化）
+    pub normalize_lufs: Option<f64>,
+    /// 输出设备（同 `-d`；建议用 `uid:<UID>`，设备 ID 重启后会变）
+    pub device: Option<String>,
+    /// TUI 配色主题（dark / light / high-contrast）
+    pub theme: ThemeName,
+    /// TUI 启动时加载第一首但保持暂停（按空格开始）
+    pub start_paused: bool,
+    /// TUI 自定义按键（`[keys]` 表，按键 → 动作名，覆盖默认映射）
+    ///
+    /// 动作名在 `KeyMap::with_overrides` 中解析：写错时回退到默认映射，不影响其余配置
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, String>,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            buffer_ms: None,
+            use_hal: true,
+            repeat: RepeatMode::Off,
+            shuffle: false,
+            recursive: false,
+            volume: 1.0,
+            log_file: None,
+            normalize_lufs: None,
+            device: None,
+            theme: ThemeName::Dark,
+            start_paused: false,
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl UserConfig {
+    /// 配置文件路径（无法确定平台配置目录时返回 None）
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+    }
+
+    /// 加载配置，任何错误都回退到默认值
+    pub fn load() -> Self {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Self::default(),
+        }
+    }
+
+    /// 从指定路径加载配置
+    pub fn load_from(path: &Path) -> Self {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    log::warn!("Failed to read config {}: {}", path.display(), e);
+                }
+                return Self::default();
+            }
+        };
+
+        Self::from_toml(&text).unwrap_or_else(|e| {
+            log::warn!("Invalid config {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// 保存配置到默认路径
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "No config directory available")
+        })?;
+        self.save_to(&path)
+    }
+
+    /// 保存配置到指定路径（自动创建父目录）
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = self
+            .to_toml()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, text)
+    }
+
+    /// 从 TOML 文本解析（缺失字段使用默认值）
+    pub fn from_toml(text: &str) -> Result<Self, toml::de::Error> {
+        let mut config: Self = toml::from_str(text)?;
+        config.volume = config.volume.clamp(0.0, 1.0);
+        Ok(config)
+    }
+
+    /// 序列化为 TOML 文本
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string_pretty(self)
+    }
+}
+
+/// 续播位置（文件路径 → 上次停止的位置，秒）
+///
+/// 用于有声书、长混音等：停止时超过 `RESUME_MIN_SECS` 则记住位置，
+/// 下次播放同一文件时从该位置继续；完整播放结束后清除
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResumePositions {
+    positions: BTreeMap<String, f64>,
+}
+
+impl ResumePositions {
+    /// 续播位置文件路径（无法确定平台配置目录时返回 None）
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(RESUME_FILE_NAME))
+    }
+
+    /// 加载续播位置，任何错误都回退到空表
+    pub fn load() -> Self {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Self::default(),
+        }
+    }
+
+    /// 从指定路径加载
+    pub fn load_from(path: &Path) -> Self {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    log::warn!("Failed to read resume positions {}: {}", path.display(), e);
+                }
+                return Self::default();
+            }
+        };
+
+        toml::from_str(&text).unwrap_or_else(|e| {
+            log::warn!("Invalid resume positions {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// 保存到默认路径
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "No config directory available")
+        })?;
+        self.save_to(&path)
+    }
+
+    /// 保存到指定路径（自动创建父目录）
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, text)
+    }
+
+    /// 获取文件的续播位置
+    pub fn get(&self, file: &Path) -> Option<f64> {
+        self.positions.get(&*file.to_string_lossy()).copied()
+    }
+
+    /// 停止播放时记录位置（不足 `RESUME_MIN_SECS` 时清除旧记录）
+    pub fn record_stop(&mut self, file: &Path, position_secs: f64) {
+        let key = file.to_string_lossy().into_owned();
+        if position_secs >= RESUME_MIN_SECS {
+            self.positions.insert(key, position_secs);
+        } else {
+            self.positions.remove(&key);
+        }
+    }
+
+    /// 完整播放结束：清除记录，返回是否存在过记录
+    pub fn record_finished(&mut self, file: &Path) -> bool {
+        self.positions.remove(&*file.to_string_lossy()).is_some()
+    }
+}
+
+/// 单个文件的响度扫描结果
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessEntry {
+    /// 扫描时文件的修改时间（UNIX 秒），不一致时视为过期
+    pub mtime: u64,
+    /// 积分响度（LUFS）
+    pub integrated_lufs: f64,
+    /// 真峰值（dBTP）
+    pub true_peak_dbtp: f64,
+}
+
+impl LoudnessEntry {
+    /// 归一化到目标响度所需的增益（dB，受真峰值限制）
+    pub fn gain_db(&self, target_lufs: f64) -> f64 {
+        crate::audio::loudness::normalization_gain_db(
+            self.integrated_lufs,
+            self.true_peak_dbtp,
+            target_lufs,
+        )
+    }
+}
+
+/// 响度缓存（文件路径 → 扫描结果）
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoudnessCache {
+    entries: BTreeMap<String, LoudnessEntry>,
+}
+
+impl LoudnessCache {
+    /// 缓存文件路径（无法确定平台配置目录时返回 None）
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(LOUDNESS_FILE_NAME))
+    }
+
+    /// 加载缓存，任何错误都回退到空表
+    pub fn load() -> Self {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Self::default(),
+        }
+    }
+
+    /// 从指定路径加载
+    pub fn load_from(path: &Path) -> Self {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    log::warn!("Failed to read loudness cache {}: {}", path.display(), e);
+                }
+                return Self::default();
+            }
+        };
+
+        toml::from_str(&text).unwrap_or_else(|e| {
+            log::warn!("Invalid loudness cache {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// 保存到默认路径
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "No config directory available")
+        })?;
+        self.save_to(&path)
+    }
+
+    /// 保存到指定路径（自动创建父目录）
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, text)
+    }
+
+    /// 查询扫描结果（`mtime` 与记录不一致时视为过期）
+    pub fn get(&self, file: &Path, mtime: u64) -> Option<LoudnessEntry> {
+        self.entries
+            .get(&*file.to_string_lossy())
+            .filter(|e| e.mtime == mtime)
+            .copied()
+    }
+
+    /// 查询扫描结果（按文件当前修改时间校验）
+    pub fn lookup(&self, file: &Path) -> Option<LoudnessEntry> {
+        self.get(file, file_mtime(file)?)
+    }
+
+    /// 记录扫描结果
+    pub fn insert(&mut self, file: &Path, entry: LoudnessEntry) {
+        self.entries.insert(file.to_string_lossy().into_owned(), entry);
+    }
+}
+
+/// 文件修改时间（UNIX 秒）
+pub fn file_mtime(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_roundtrip() {
+        let config = UserConfig {
+            buffer_ms: Some(500),
+            use_hal: false,
+            repeat: RepeatMode::Track,
+            shuffle: true,
+            recursive: true,
+            volume: 0.25,
+            log_file: Some(PathBuf::from("/tmp/roger-player.log")),
+            normalize_lufs: Some(-14.0),
+            device: Some("uid:AppleUSBAudioEngine:Topping:D10s:1234:1".to_string()),
+            theme: ThemeName::HighContrast,
+            start_paused: true,
+            keys: BTreeMap::from([
+                ("?".to_string(), "help".to_string()),
+                ("h".to_string(), "seek-back".to_string()),
+            ]),
+        };
+        let text = config.to_toml().unwrap();
+        assert!(text.contains("repeat = \"track\""));
+        assert!(text.contains("[keys]"));
+        assert!(text.contains("theme = \"high-contrast\""));
+        assert_eq!(UserConfig::from_toml(&text).unwrap(), config);
+    }
+
+    #[test]
+    fn test_partial_toml_uses_defaults() {
+        let config = UserConfig::from_toml("shuffle = true\nvolume = 3.0\n").unwrap();
+        assert!(config.shuffle);
+        assert_eq!(config.volume, 1.0);
+        assert_eq!(config.buffer_ms, None);
+        assert_eq!(config.repeat, RepeatMode::Off);
+
+        assert_eq!(UserConfig::from_toml("").unwrap(), UserConfig::default());
+        assert!(UserConfig::from_toml("repeat = \"sometimes\"").is_err());
+
+        // 动作名写错不影响其余配置（由按键映射回退到默认）
+        let config = UserConfig::from_toml("shuffle = true\n[keys]\nh = \"seekback\"\n").unwrap();
+        assert!(config.shuffle);
+        assert_eq!(config.keys["h"], "seekback");
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let path = std::env::temp_dir().join("roger_missing_config_dir/config.toml");
+        assert_eq!(UserConfig::load_from(&path), UserConfig::default());
+    }
+
+    #[test]
+    fn test_resume_positions() {
+        let book = Path::new("/music/book.m4a");
+        let song = Path::new("/music/song.flac");
+
+        let mut resume = ResumePositions::default();
+        resume.record_stop(book, 1234.5);
+        // 不足 60 秒不记录
+        resume.record_stop(song, 30.0);
+        assert_eq!(resume.get(book), Some(1234.5));
+        assert_eq!(resume.get(song), None);
+
+        // TOML 往返
+        let path = std::env::temp_dir()
+            .join(format!("roger_resume_{}", std::process::id()))
+            .join(RESUME_FILE_NAME);
+        resume.save_to(&path).unwrap();
+        let loaded = ResumePositions::load_from(&path);
+        assert_eq!(loaded, resume);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        // 播放到结尾后清除
+        assert!(resume.record_finished(book));
+        assert_eq!(resume.get(book), None);
+        assert!(!resume.record_finished(book));
+
+        // 提前停止也会清除旧记录
+        resume.record_stop(song, 90.0);
+        resume.record_stop(song, 10.0);
+        assert_eq!(resume.get(song), None);
+    }
+
+    #[test]
+    fn test_loudness_cache() {
+        let file = Path::new("/music/track.flac");
+        let entry = LoudnessEntry {
+            mtime: 1_700_000_000,
+            integrated_lufs: -9.5,
+            true_peak_dbtp: -0.3,
+        };
+
+        let mut cache = LoudnessCache::default();
+        cache.insert(file, entry);
+        assert_eq!(cache.get(file, 1_700_000_000), Some(entry));
+        // 文件被修改后结果过期
+        assert_eq!(cache.get(file, 1_700_000_001), None);
+
+        let text = toml::to_string_pretty(&cache).unwrap();
+        assert_eq!(toml::from_str::<LoudnessCache>(&text).unwrap(), cache);
+    }
+}
@@ -0,0 +1,133 @@
+//! CUE 表单解析（章节标记）
+//!
+//! 只处理“单文件 + 多 TRACK”的整轨 CUE（常见于整张专辑抓轨），
+//! 提取每条 TRACK 的 `INDEX 01` 起点与 TITLE 作为章节标记。
+//! 引用多个 FILE 的 CUE 无法映射到单一音频文件的时间轴，直接忽略。
+
+use std::path::Path;
+
+/// CUE 时间码每秒帧数（CD 扇区）
+const CUE_FRAMES_PER_SEC: f64 = 75.0;
+
+/// 解析 `mm:ss:ff` 时间码为秒
+fn parse_timestamp(s: &str) -> Option<f64> {
+    let mut parts = s.split(':');
+    let minutes: u32 = parts.next()?.parse().ok()?;
+    let seconds: u32 = parts.next()?.parse().ok()?;
+    let frames: u32 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || seconds >= 60 || frames >= 75 {
+        return None;
+    }
+    Some(minutes as f64 * 60.0 + seconds as f64 + frames as f64 / CUE_FRAMES_PER_SEC)
+}
+
+/// 去掉首尾引号
+fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s)
+}
+
+/// 解析 CUE 文本为按时间排序的章节 `(起始秒, 标题)`
+///
+/// 缺少 TITLE 的轨道使用 `Track NN`；不符合要求的 CUE 返回空列表
+pub fn parse_cue(text: &str) -> Vec<(f64, String)> {
+    let mut files = 0;
+    let mut chapters = Vec::new();
+    // 当前 TRACK：(编号, 标题, INDEX 01 起点)
+    let mut current: Option<(u32, Option<String>, Option<f64>)> = None;
+
+    let mut finish = |track: Option<(u32, Option<String>, Option<f64>)>| {
+        if let Some((number, title, Some(start))) = track {
+            let title = title.unwrap_or_else(|| format!("Track {:02}", number));
+            chapters.push((start, title));
+        }
+    };
+
+    for line in text.lines() {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => files += 1,
+            "TRACK" => {
+                finish(current.take());
+                let number = rest.split_whitespace().next().and_then(|n| n.parse().ok());
+                current = number.map(|n| (n, None, None));
+            }
+            "TITLE" => {
+                if let Some((_, title, _)) = current.as_mut() {
+                    *title = Some(unquote(rest).to_string());
+                }
+            }
+            "INDEX" => {
+                let mut fields = rest.split_whitespace();
+                let is_01 = fields.next().and_then(|n| n.parse::<u32>().ok()) == Some(1);
+                if let (true, Some((_, _, start))) = (is_01, current.as_mut()) {
+                    *start = fields.next().and_then(parse_timestamp);
+                }
+            }
+            _ => {}
+        }
+    }
+    finish(current.take());
+
+    if files != 1 {
+        return Vec::new();
+    }
+    chapters.sort_by(|a, b| a.0.total_cmp(&b.0));
+    chapters
+}
+
+/// 读取音频文件同名的 `.cue` 旁挂文件（不存在或无法解析时返回空列表）
+pub fn load_sidecar(audio_path: &Path) -> Vec<(f64, String)> {
+    std::fs::read(audio_path.with_extension("cue"))
+        .map(|bytes| parse_cue(&String::from_utf8_lossy(&bytes)))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("00:00:00"), Some(0.0));
+        assert_eq!(parse_timestamp("03:25:30"), Some(205.4));
+        assert_eq!(parse_timestamp("72:00:74"), Some(4320.0 + 74.0 / 75.0));
+        assert_eq!(parse_timestamp("00:60:00"), None);
+        assert_eq!(parse_timestamp("00:00:75"), None);
+        assert_eq!(parse_timestamp("1:2"), None);
+    }
+
+    #[test]
+    fn test_parse_cue() {
+        let cue = r#"PERFORMER "Artist"
+TITLE "Album"
+FILE "album.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "Intro"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 00 03:10:00
+    INDEX 01 03:12:15
+  TRACK 03 AUDIO
+    TITLE "Outro"
+    INDEX 01 07:00:00
+"#;
+        let chapters = parse_cue(cue);
+        assert_eq!(
+            chapters,
+            vec![
+                (0.0, "Intro".to_string()),
+                (192.2, "Track 02".to_string()),
+                (420.0, "Outro".to_string()),
+            ]
+        );
+
+        // 多文件 CUE 不适用于单一时间轴
+        let multi = "FILE \"a.wav\" WAVE\nTRACK 01 AUDIO\nINDEX 01 00:00:00\n\
+                     FILE \"b.wav\" WAVE\nTRACK 02 AUDIO\nINDEX 01 00:00:00\n";
+        assert!(parse_cue(multi).is_empty());
+    }
+}
@@ -1,23 +1,45 @@
 //! 音频文件解码器
 //!
 //! 使用 symphonia 库解码无损音频格式
-//! 支持：FLAC, WAV, AIFF, MP3
+//! 支持：FLAC, WAV, AIFF, MP3, ALAC (M4A)，以及无文件头的原始 PCM（`.pcm`）
+//! 路径为 `http://`/`https://` URL 时经 `http::HttpSource` 渐进式读取
 //!
 //! 设计目标：
 //! - 整数直通：PCM 整数格式直接转换到 i32，避免 f64 中间表示
 //! - 精度保持：16/24/32-bit 源文件无精度损失
 
 use std::fs::File;
+use std::io::Read;
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
 use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Signal, SignalSpec};
-use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::codecs::{
+    CodecType, Decoder, DecoderOptions, CODEC_TYPE_AAC, CODEC_TYPE_ALAC, CODEC_TYPE_MP1,
+    CODEC_TYPE_MP2, CODEC_TYPE_MP3, CODEC_TYPE_NULL, CODEC_TYPE_OPUS, CODEC_TYPE_VORBIS,
+};
 use symphonia::core::errors::Error as SymphoniaError;
-use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::formats::{Cue, FormatOptions, FormatReader, SeekMode, SeekTo, Track};
+use symphonia::core::io::{MediaSource, MediaSourceStream, ReadOnlySource};
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag};
 use symphonia::core::probe::Hint;
-use symphonia::core::units::Time;
+use symphonia::core::sample::Sample;
+use symphonia::core::units::{Time, TimeBase};
+
+use super::cue;
+use super::follow::{FollowReader, FOLLOW_IDLE_TIMEOUT};
+use super::http::{self, HttpSource};
+use super::layout::{self, ChannelRole};
+use super::pcm::{RawPcmFormat, RawPcmReader};
+
+/// 表示从 stdin 读取的路径参数
+pub const STDIN_PATH: &str = "-";
+
+/// 检查路径是否表示 stdin（"-"）
+pub fn is_stdin_path(path: &Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
 
 /// 解码错误
 #[derive(Debug)]
@@ -26,14 +48,26 @@ pub enum DecodeError {
     FileOpen(std::io::Error),
     /// 格式不支持
     UnsupportedFormat,
+    /// 按给定的扩展名提示探测失败（提示来自文件扩展名或 `--force-format`）
+    ProbeFailed(String),
     /// 没有找到音频轨道
     NoAudioTrack,
+    /// 指定的音频轨道序号不存在
+    TrackNotFound(usize),
     /// 解码器创建失败
     DecoderCreation(String),
     /// 解码失败
     DecodeFailed(String),
     /// Seek 失败
     SeekFailed(String),
+    /// 流中途改变了采样率或声道数（如链式 Ogg 流）
+    ///
+    /// 输出按打开时的格式配置，继续写入会错位，需要按新格式重新打开输出
+    FormatChanged { sample_rate: u32, channels: u32 },
+    /// 网络数据源（HTTP）请求失败
+    Network(String),
+    /// 连续大量 packet 解码失败（重置解码器后仍未恢复），放弃当前曲目
+    CorruptStream(u32),
 }
 
 impl std::fmt::Display for DecodeError {
@@ -41,16 +75,44 @@ impl std::fmt::Display for DecodeError {
         match self {
             Self::FileOpen(e) => write!(f, "Failed to open file: {}", e),
             Self::UnsupportedFormat => write!(f, "Unsupported audio format"),
+            Self::ProbeFailed(hint) => {
+                write!(f, "Unsupported audio format (probed with hint '{}')", hint)
+            }
             Self::NoAudioTrack => write!(f, "No audio track found"),
+            Self::TrackNotFound(index) => write!(f, "Audio track {} not found", index),
             Self::DecoderCreation(s) => write!(f, "Failed to create decoder: {}", s),
             Self::DecodeFailed(s) => write!(f, "Decode failed: {}", s),
             Self::SeekFailed(s) => write!(f, "Seek failed: {}", s),
+            Self::FormatChanged { sample_rate, channels } => write!(
+                f,
+                "Stream format changed to {} Hz, {} channels",
+                sample_rate, channels
+            ),
+            Self::Network(s) => write!(f, "Network error: {}", s),
+            Self::CorruptStream(packets) => write!(
+                f,
+                "Stream corrupt: {} consecutive packets failed to decode",
+                packets
+            ),
         }
     }
 }
 
 impl std::error::Error for DecodeError {}
 
+/// 容器内的一条音频轨道
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackDescriptor {
+    /// 容器内的轨道 ID
+    pub id: u32,
+    /// 编解码器名称
+    pub codec: String,
+    /// 声道数（容器未声明时为 None）
+    pub channels: Option<u32>,
+    /// 语言标签（如 "eng"，未知时为 None）
+    pub language: Option<String>,
+}
+
 /// 音频文件信息
 #[derive(Debug, Clone)]
 pub struct AudioInfo {
@@ -58,6 +120,10 @@ pub struct AudioInfo {
     pub sample_rate: u32,
     /// 声道数
     pub channels: u32,
+    /// 各声道的扬声器角色（按交错顺序，长度等于 `channels`）
+    ///
+    /// 来自容器声明的布局（WAV channel mask 等），未声明时按声道数推定
+    pub channel_layout: Vec<ChannelRole>,
     /// 位深度（原始格式）
     pub bit_depth: Option<u32>,
     /// 总帧数（如果已知）
@@ -68,33 +134,279 @@ pub struct AudioInfo {
     pub format: String,
     /// 编解码器名称
     pub codec: String,
+    /// 是否支持 seek（stdin 等管道输入为 false）
+    pub seekable: bool,
+    /// 平均码率（bps，由文件大小 / 时长估算）
+    pub bitrate_bps: Option<u32>,
+    /// 是否为无损编码
+    pub lossless: bool,
+    /// 编码器延迟（帧，已从开头裁掉）
+    pub encoder_delay: u32,
+    /// 编码器填充（帧，已从结尾裁掉）
+    pub encoder_padding: u32,
+    /// 容器内所有音频轨道（多音轨 MP4/Matroska 等），按容器顺序排列
+    pub tracks: Vec<TrackDescriptor>,
+    /// 当前播放的轨道在 `tracks` 中的序号
+    pub track_index: usize,
+    /// 章节标记 `(起始秒, 标题)`，按时间排序；来自容器 cue 或同名 `.cue` 文件
+    pub chapters: Vec<(f64, String)>,
+}
+
+/// 解码数据源
+enum DecoderSource {
+    /// symphonia 探测的容器格式
+    Symphonia {
+        reader: Box<dyn FormatReader>,
+        decoder: Box<dyn Decoder>,
+        track_id: u32,
+        spec: SignalSpec,
+    },
+    /// 无文件头的原始 PCM
+    RawPcm(RawPcmReader),
 }
 
 /// 音频文件解码器
 pub struct AudioDecoder {
-    reader: Box<dyn FormatReader>,
-    decoder: Box<dyn Decoder>,
-    track_id: u32,
+    source: DecoderSource,
     info: AudioInfo,
     /// i32 样本缓冲区（整数直通路径）
     i32_buffer: Vec<i32>,
-    spec: SignalSpec,
+    /// seek 后需要丢弃的帧数（精确 seek：packet 起点到目标时间戳的差值）
+    skip_frames: u64,
+    /// 需自行裁剪的编码器延迟（帧）
+    ///
+    /// LAME 标签由 symphonia 裁剪（此处为 0）；iTunSMPB 标签由解码器自行裁剪
+    gapless_delay: u32,
+    /// 自行裁剪时的有效帧数，输出到此为止（丢弃结尾的 padding）
+    gapless_end: Option<u64>,
+    /// 已输出的帧数（不含裁掉的延迟）
+    frames_out: u64,
+    /// 连续损坏 packet 计数
+    corrupt_run: CorruptRun,
+}
+
+/// 连续损坏多少个 packet 后重置解码器（清除可能已损坏的内部状态，如 MP3 bit reservoir）
+const CORRUPT_PACKETS_BEFORE_RESET: u32 = 32;
+
+/// 连续损坏多少个 packet 后放弃当前曲目
+const CORRUPT_PACKETS_BEFORE_GIVE_UP: u32 = 128;
+
+/// 遇到损坏 packet 时的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CorruptAction {
+    /// 跳过该 packet
+    Skip,
+    /// 跳过并重置解码器
+    Reset,
+    /// 放弃（返回 `DecodeError::CorruptStream`）
+    GiveUp,
+}
+
+/// 连续损坏 packet 计数
+///
+/// 损坏区域内每个 packet 都会解码失败并被跳过，期间不产生任何样本：
+/// 无上限时一大段损坏数据会让播放看似卡住直到缓冲区耗尽
+#[derive(Debug, Default)]
+struct CorruptRun {
+    count: u32,
+}
+
+impl CorruptRun {
+    /// 记录一次解码失败，返回处理方式
+    fn on_error(&mut self) -> CorruptAction {
+        self.count += 1;
+        if self.count >= CORRUPT_PACKETS_BEFORE_GIVE_UP {
+            CorruptAction::GiveUp
+        } else if self.count.is_multiple_of(CORRUPT_PACKETS_BEFORE_RESET) {
+            CorruptAction::Reset
+        } else {
+            CorruptAction::Skip
+        }
+    }
+
+    /// 解码成功（或 seek、放弃）后计数清零
+    fn clear(&mut self) {
+        self.count = 0;
+    }
 }
 
 impl AudioDecoder {
     /// 打开音频文件
+    ///
+    /// `.pcm` 文件按默认格式（44100Hz/2ch/16bit）解释
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DecodeError> {
-        let path = path.as_ref();
+        Self::open_with_pcm_format(path, RawPcmFormat::default())
+    }
+
+    /// 打开音频文件，`.pcm` 文件使用指定的原始 PCM 格式
+    pub fn open_with_pcm_format<P: AsRef<Path>>(
+        path: P,
+        pcm: RawPcmFormat,
+    ) -> Result<Self, DecodeError> {
+        Self::open_with_options(path, pcm, None)
+    }
+
+    /// 忽略文件扩展名，按 `ext_hint`（如 "flac"）探测格式
+    ///
+    /// 用于扩展名与实际格式不符的文件；`"pcm"` 按默认原始 PCM 格式解释
+    pub fn open_with_hint<P: AsRef<Path>>(path: P, ext_hint: &str) -> Result<Self, DecodeError> {
+        Self::open_with_options(path, RawPcmFormat::default(), Some(ext_hint))
+    }
+
+    /// 打开音频文件：`.pcm`（或强制为 `"pcm"`）使用 `pcm` 格式，
+    /// `ext_hint` 非 None 时代替文件扩展名作为探测提示
+    pub fn open_with_options<P: AsRef<Path>>(
+        path: P,
+        pcm: RawPcmFormat,
+        ext_hint: Option<&str>,
+    ) -> Result<Self, DecodeError> {
+        Self::open_track(path, pcm, ext_hint, 0)
+    }
+
+    /// 打开音频文件的第 `index` 条音频轨道（从 0 开始，顺序同 `AudioInfo.tracks`）
+    ///
+    /// 多音轨容器（语言、替代混音）用于选择非默认轨道；`pcm` 与 `ext_hint` 同
+    /// `open_with_options`，后者等价于 `index = 0`
+    pub fn open_track<P: AsRef<Path>>(
+        path: P,
+        pcm: RawPcmFormat,
+        ext_hint: Option<&str>,
+        index: usize,
+    ) -> Result<Self, DecodeError> {
+        Self::open_file(path.as_ref(), pcm, index, ext_hint)
+    }
+
+    /// 打开文件（原始 PCM 只有一条轨道）
+    fn open_file(
+        path: &Path,
+        pcm: RawPcmFormat,
+        track_index: usize,
+        ext_hint: Option<&str>,
+    ) -> Result<Self, DecodeError> {
+        if let Some(url) = path.to_str().filter(|_| http::is_url(path)) {
+            return Self::open_url(url, track_index, ext_hint);
+        }
+
+        // 提示文件扩展名（显式提示优先）
+        let ext = probe_extension(path, ext_hint);
+
+        // 原始 PCM 无文件头，跳过 symphonia 探测
+        let is_raw_pcm = ext.is_some_and(|e| e.eq_ignore_ascii_case("pcm"));
+        if is_raw_pcm {
+            if track_index != 0 {
+                return Err(DecodeError::TrackNotFound(track_index));
+            }
+            return Self::open_raw_pcm(path, pcm);
+        }
 
         // 打开文件
         let file = File::open(path).map_err(DecodeError::FileOpen)?;
+        let byte_len = file.metadata().map(|m| m.len()).unwrap_or(0);
         let mss = MediaSourceStream::new(Box::new(file), Default::default());
 
-        // 提示文件扩展名
-        let mut hint = Hint::new();
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            hint.with_extension(ext);
+        let mut decoder = Self::open_stream(mss, ext, Some(byte_len), track_index)?;
+
+        // 容器内无章节时，尝试同名 .cue 文件
+        if decoder.info.chapters.is_empty() {
+            decoder.info.chapters = cue::load_sidecar(path);
         }
+        Ok(decoder)
+    }
+
+    /// 从任意字节流打开（例如 stdin 管道）
+    ///
+    /// 流不可 seek：`AudioInfo.duration_secs` 为 `None`，`seek()` 返回错误。
+    /// `ext_hint` 为可选的扩展名提示，帮助 symphonia 探测格式。
+    pub fn open_reader(
+        reader: Box<dyn Read + Send + Sync>,
+        ext_hint: Option<&str>,
+    ) -> Result<Self, DecodeError> {
+        let source = ReadOnlySource::new(reader);
+        let mss = MediaSourceStream::new(Box::new(source), Default::default());
+        Self::open_stream(mss, ext_hint, None, 0)
+    }
+
+    /// 打开仍在写入的文件（跟随模式）
+    ///
+    /// 读到末尾时等待文件增长，`FOLLOW_IDLE_TIMEOUT` 内无新数据或 `active` 置为 false
+    /// 才结束。与管道一样不可 seek，时长未知
+    /// `ext_hint` 同 `open_with_options`
+    pub fn open_follow(
+        path: &Path,
+        active: Arc<AtomicBool>,
+        ext_hint: Option<&str>,
+    ) -> Result<Self, DecodeError> {
+        let file = File::open(path).map_err(DecodeError::FileOpen)?;
+        let reader = FollowReader::new(file, FOLLOW_IDLE_TIMEOUT, active);
+        Self::open_reader(Box::new(reader), probe_extension(path, ext_hint))
+    }
+
+    /// 打开 HTTP URL（渐进式播放；服务器不支持 Range 时与管道一样不可 seek）
+    fn open_url(
+        url: &str,
+        track_index: usize,
+        ext_hint: Option<&str>,
+    ) -> Result<Self, DecodeError> {
+        let source = HttpSource::open(url)?;
+        let byte_len = source.byte_len().filter(|_| source.is_seekable());
+        let ext = ext_hint.map(str::to_string).or_else(|| http::url_extension(url));
+        let mss = MediaSourceStream::new(Box::new(source), Default::default());
+        Self::open_stream(mss, ext.as_deref(), byte_len, track_index)
+    }
+
+    /// 打开原始 PCM 文件
+    fn open_raw_pcm(path: &Path, pcm: RawPcmFormat) -> Result<Self, DecodeError> {
+        let raw = RawPcmReader::open(path, pcm)?;
+        let total_frames = raw.total_frames();
+
+        let info = AudioInfo {
+            sample_rate: pcm.sample_rate,
+            channels: pcm.channels as u32,
+            channel_layout: layout::positional_roles(pcm.channels as usize),
+            bit_depth: Some(pcm.bits_per_sample as u32),
+            total_frames: Some(total_frames),
+            duration_secs: Some(total_frames as f64 / pcm.sample_rate as f64),
+            format: "PCM".to_string(),
+            codec: pcm.codec_name(),
+            seekable: true,
+            bitrate_bps: pcm.bitrate_bps(),
+            lossless: true,
+            encoder_delay: 0,
+            encoder_padding: 0,
+            tracks: vec![TrackDescriptor {
+                id: 0,
+                codec: pcm.codec_name(),
+                channels: Some(pcm.channels as u32),
+                language: None,
+            }],
+            track_index: 0,
+            chapters: Vec::new(),
+        };
+
+        Ok(Self {
+            source: DecoderSource::RawPcm(raw),
+            info,
+            i32_buffer: Vec::with_capacity(65536),
+            skip_frames: 0,
+            gapless_delay: 0,
+            gapless_end: None,
+            frames_out: 0,
+            corrupt_run: CorruptRun::default(),
+        })
+    }
+
+    /// 探测格式并创建解码器（文件与字节流共用）
+    ///
+    /// `byte_len` 为文件大小；为 None 表示不可 seek 的流。
+    /// `track_index` 为音频轨道序号（跳过非音频轨道后计数）
+    fn open_stream(
+        mss: MediaSourceStream,
+        ext: Option<&str>,
+        byte_len: Option<u64>,
+        track_index: usize,
+    ) -> Result<Self, DecodeError> {
+        let seekable = byte_len.is_some();
+        let hint = probe_hint(ext);
 
         // 探测格式
         let format_opts = FormatOptions {
@@ -103,24 +415,30 @@ impl AudioDecoder {
         };
         let metadata_opts = MetadataOptions::default();
 
-        let probed = symphonia::default::get_probe()
+        let mut probed = symphonia::default::get_probe()
             .format(&hint, mss, &format_opts, &metadata_opts)
-            .map_err(|_| DecodeError::UnsupportedFormat)?;
+            .map_err(|_| match ext {
+                Some(ext) => DecodeError::ProbeFailed(ext.to_string()),
+                None => DecodeError::UnsupportedFormat,
+            })?;
 
         let mut reader = probed.format;
+
+        // iTunSMPB 可能在探测阶段的 ID3 标签中（MP3），也可能在容器内（MP4 ilst）
+        let itunes_gapless = probed
+            .metadata
+            .get()
+            .and_then(|m| m.current().and_then(|r| gapless_from_tags(r.tags())))
+            .or_else(|| reader.metadata().current().and_then(|r| gapless_from_tags(r.tags())));
         // 简单起见，直接使用文件扩展名作为格式名称
         // symphonia 的 metadata debug 输出对用户不友好
-        let format_name = path.extension()
-            .and_then(|e| e.to_str())
+        let format_name = ext
             .map(|s| s.to_uppercase())
             .unwrap_or_else(|| "Unknown".to_string());
 
-        // 查找第一个音频轨道
-        let track = reader
-            .tracks()
-            .iter()
-            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
-            .ok_or(DecodeError::NoAudioTrack)?;
+        // 查找指定的音频轨道（默认第一条）
+        let tracks = describe_tracks(reader.tracks());
+        let track = select_track(reader.tracks(), track_index)?;
 
         let track_id = track.id;
         let codec_params = &track.codec_params;
@@ -131,8 +449,46 @@ impl AudioDecoder {
             .channels
             .map(|c| c.count() as u32)
             .unwrap_or(2);
-        let bit_depth = codec_params.bits_per_sample;
-        let total_frames = codec_params.n_frames;
+        let channel_layout = match codec_params.channels {
+            Some(mask) => layout::roles_from_channels(mask),
+            None => layout::positional_roles(channels as usize),
+        };
+        // ALAC 的位深度只在 magic cookie 中，容器层不填 bits_per_sample
+        let bit_depth = codec_params.bits_per_sample.or_else(|| {
+            if codec_params.codec == CODEC_TYPE_ALAC {
+                codec_params.extra_data.as_deref().and_then(alac_bit_depth)
+            } else {
+                None
+            }
+        });
+        // 编码器延迟/填充：LAME 标签由 symphonia 解析并在解码时裁剪（n_frames 已扣除），
+        // 否则使用 iTunSMPB 标签自行裁剪
+        let gapless = if codec_params.delay.is_some() || codec_params.padding.is_some() {
+            None
+        } else {
+            itunes_gapless
+        };
+        let (encoder_delay, encoder_padding) = match gapless {
+            Some(g) => (g.delay, g.padding),
+            None => (
+                codec_params.delay.unwrap_or(0),
+                codec_params.padding.unwrap_or(0),
+            ),
+        };
+        let gapless_end = gapless.and_then(|g| {
+            if g.valid_frames > 0 {
+                Some(g.valid_frames)
+            } else {
+                codec_params
+                    .n_frames
+                    .map(|n| n.saturating_sub(g.delay as u64 + g.padding as u64))
+            }
+        });
+
+        // 不可 seek 的流：头部的帧数不可信（管道可能被截断），时长视为未知
+        let total_frames = gapless_end
+            .or(codec_params.n_frames)
+            .filter(|_| seekable);
         let duration_secs = total_frames.map(|f| f as f64 / sample_rate as f64);
 
         let codec_name = symphonia::default::get_codecs()
@@ -143,11 +499,20 @@ impl AudioDecoder {
         let info = AudioInfo {
             sample_rate,
             channels,
+            channel_layout,
             bit_depth,
             total_frames,
             duration_secs,
             format: format_name,
             codec: codec_name,
+            seekable,
+            bitrate_bps: byte_len.zip(duration_secs).and_then(|(b, d)| estimate_bitrate(b, d)),
+            lossless: !is_lossy_codec(codec_params.codec),
+            encoder_delay,
+            encoder_padding,
+            tracks,
+            track_index,
+            chapters: chapters_from_cues(reader.cues(), sample_rate),
         };
 
         // 创建解码器
@@ -163,12 +528,20 @@ impl AudioDecoder {
         let i32_buffer = Vec::with_capacity(65536);
 
         Ok(Self {
-            reader,
-            decoder,
-            track_id,
+            source: DecoderSource::Symphonia {
+                reader,
+                decoder,
+                track_id,
+                spec,
+            },
             info,
             i32_buffer,
-            spec,
+            // 开头的编码器延迟与精确 seek 一样在解码时丢弃
+            skip_frames: gapless.map_or(0, |g| g.delay as u64),
+            gapless_delay: gapless.map_or(0, |g| g.delay),
+            gapless_end,
+            frames_out: 0,
+            corrupt_run: CorruptRun::default(),
         })
     }
 
@@ -183,9 +556,29 @@ impl AudioDecoder {
     /// 对于整数源格式，避免 f64 中间转换，实现 bit-perfect 路径
     /// 返回空切片表示文件结束
     pub fn decode_next_i32(&mut self) -> Result<&[i32], DecodeError> {
+        let (reader, decoder, track_id, spec) = match self.source {
+            DecoderSource::Symphonia {
+                ref mut reader,
+                ref mut decoder,
+                track_id,
+                ref spec,
+            } => (reader, decoder, track_id, spec),
+            DecoderSource::RawPcm(ref mut raw) => {
+                // 原始 PCM：直接按字节解释，无需 skip（seek 按帧精确定位）
+                let samples = raw.read_samples(&mut self.i32_buffer)?;
+                return Ok(&self.i32_buffer[..samples]);
+            }
+        };
+
+        // 有效帧已全部输出：结尾的 padding 不再解码
+        if self.gapless_end.is_some_and(|end| self.frames_out >= end) {
+            self.i32_buffer.clear();
+            return Ok(&self.i32_buffer); // EOF
+        }
+
         loop {
             // 读取下一个 packet
-            let packet = match self.reader.next_packet() {
+            let packet = match reader.next_packet() {
                 Ok(p) => p,
                 Err(SymphoniaError::IoError(ref e))
                     if e.kind() == std::io::ErrorKind::UnexpectedEof =>
@@ -197,17 +590,42 @@ impl AudioDecoder {
             };
 
             // 跳过非目标轨道
-            if packet.track_id() != self.track_id {
+            if packet.track_id() != track_id {
                 continue;
             }
 
             // 解码
-            let decoded = match self.decoder.decode(&packet) {
-                Ok(d) => d,
-                Err(SymphoniaError::DecodeError(_)) => continue, // 跳过损坏的帧
+            let decoded = match decoder.decode(&packet) {
+                Ok(d) => {
+                    self.corrupt_run.clear();
+                    d
+                }
+                Err(SymphoniaError::DecodeError(e)) => {
+                    // 跳过损坏的帧，连续过多时先重置解码器，仍失败则放弃
+                    match self.corrupt_run.on_error() {
+                        CorruptAction::Skip => {}
+                        CorruptAction::Reset => {
+                            log::warn!(
+                                "{} consecutive corrupt packets ({}), resetting decoder",
+                                self.corrupt_run.count,
+                                e
+                            );
+                            decoder.reset();
+                        }
+                        CorruptAction::GiveUp => {
+                            let packets = self.corrupt_run.count;
+                            self.corrupt_run.clear();
+                            return Err(DecodeError::CorruptStream(packets));
+                        }
+                    }
+                    continue;
+                }
                 Err(e) => return Err(DecodeError::DecodeFailed(e.to_string())),
             };
 
+            // 链式流可能中途改变格式，不能按旧格式继续输出
+            check_spec(spec, decoded.spec())?;
+
             // 获取帧数和声道数
             let frames = decoded.frames();
             let channels = decoded.spec().channels.count();
@@ -240,6 +658,7 @@ impl AudioDecoder {
                 }
                 AudioBufferRef::S32(buf) => {
                     // 32-bit → i32: 直接复制
+                    // ALAC 解码器输出也走这里（已按位深度左对齐）
                     convert_s32_to_i32(&buf, i32_buffer);
                 }
                 AudioBufferRef::F32(buf) => {
@@ -272,23 +691,80 @@ impl AudioDecoder {
                 }
             }
 
-            return Ok(&self.i32_buffer[..total_samples]);
+            // 精确 seek / 编码器延迟：丢弃目标位置之前的帧
+            let mut start = 0;
+            if self.skip_frames > 0 {
+                let skip = (self.skip_frames as usize).min(frames);
+                self.skip_frames -= skip as u64;
+                if skip == frames {
+                    continue;
+                }
+                start = skip;
+            }
+
+            // 编码器填充：超过有效帧数的部分截掉
+            let mut end = frames;
+            if let Some(gapless_end) = self.gapless_end {
+                let remaining = gapless_end.saturating_sub(self.frames_out);
+                end = end.min(start + remaining.min(frames as u64) as usize);
+            }
+            self.frames_out += (end - start) as u64;
+
+            return Ok(&self.i32_buffer[start * channels..end * channels]);
         }
     }
 
     /// Seek 到指定时间（秒）
     pub fn seek(&mut self, time_secs: f64) -> Result<(), DecodeError> {
-        let seek_to = SeekTo::Time {
-            time: Time::new(time_secs as u64, time_secs.fract()),
-            track_id: Some(self.track_id),
+        if !self.info.seekable {
+            return Err(DecodeError::SeekFailed("source is not seekable".to_string()));
+        }
+
+        let (reader, decoder, track_id) = match self.source {
+            DecoderSource::Symphonia {
+                ref mut reader,
+                ref mut decoder,
+                track_id,
+                ..
+            } => (reader, decoder, track_id),
+            DecoderSource::RawPcm(ref mut raw) => {
+                let frame = (time_secs * self.info.sample_rate as f64) as u64;
+                return raw.seek_frame(frame);
+            }
+        };
+
+        // 裁剪后时间轴上的目标帧；自行裁剪延迟时，文件内的位置比播放位置多出延迟帧
+        let target_frame = (time_secs * self.info.sample_rate as f64).round() as u64;
+        let source_frame = target_frame + self.gapless_delay as u64;
+        // 按整数时间戳 seek：秒数加上延迟后再经浮点换算会截断，落在目标前一帧
+        let time_base = reader
+            .tracks()
+            .iter()
+            .find(|t| t.id == track_id)
+            .and_then(|t| t.codec_params.time_base);
+        let seek_to = match time_base {
+            Some(tb) => SeekTo::TimeStamp {
+                ts: frame_to_timestamp(source_frame, self.info.sample_rate, tb),
+                track_id,
+            },
+            None => {
+                let secs = source_frame as f64 / self.info.sample_rate as f64;
+                SeekTo::Time {
+                    time: Time::new(secs as u64, secs.fract()),
+                    track_id: Some(track_id),
+                }
+            }
         };
 
-        self.reader
+        let seeked = reader
             .seek(SeekMode::Accurate, seek_to)
             .map_err(|e| DecodeError::SeekFailed(e.to_string()))?;
 
         // 重置解码器状态
-        self.decoder.reset();
+        decoder.reset();
+        self.corrupt_run.clear();
+        self.skip_frames = seeked.required_ts.saturating_sub(seeked.actual_ts);
+        self.frames_out = target_frame;
 
         Ok(())
     }
@@ -301,6 +777,172 @@ impl AudioDecoder {
     }
 }
 
+/// 帧号换算为容器时间戳（向上取整，保证不早于该帧）
+fn frame_to_timestamp(frame: u64, sample_rate: u32, time_base: TimeBase) -> u64 {
+    let ts = frame as u128 * time_base.denom as u128;
+    let unit = sample_rate as u128 * time_base.numer as u128;
+    ts.div_ceil(unit.max(1)) as u64
+}
+
+/// 探测用的扩展名：显式提示优先，否则取文件扩展名
+fn probe_extension<'a>(path: &'a Path, ext_hint: Option<&'a str>) -> Option<&'a str> {
+    ext_hint.or_else(|| path.extension().and_then(|e| e.to_str()))
+}
+
+/// 构造传给 symphonia 探测器的提示
+fn probe_hint(ext: Option<&str>) -> Hint {
+    let mut hint = Hint::new();
+    if let Some(ext) = ext {
+        hint.with_extension(ext);
+    }
+    hint
+}
+
+/// 按序号选择音频轨道（跳过编解码器为空的非音频轨道）
+fn select_track(tracks: &[Track], index: usize) -> Result<&Track, DecodeError> {
+    let mut audio = tracks.iter().filter(|t| t.codec_params.codec != CODEC_TYPE_NULL);
+    match audio.nth(index) {
+        Some(track) => Ok(track),
+        None if index == 0 => Err(DecodeError::NoAudioTrack),
+        None => Err(DecodeError::TrackNotFound(index)),
+    }
+}
+
+/// 列出所有音频轨道（顺序与 `select_track` 的序号一致）
+fn describe_tracks(tracks: &[Track]) -> Vec<TrackDescriptor> {
+    tracks
+        .iter()
+        .filter(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .map(|t| TrackDescriptor {
+            id: t.id,
+            codec: symphonia::default::get_codecs()
+                .get_codec(t.codec_params.codec)
+                .map(|c| c.short_name.to_string())
+                .unwrap_or_else(|| "Unknown".to_string()),
+            channels: t.codec_params.channels.map(|c| c.count() as u32),
+            language: t.language.clone(),
+        })
+        .collect()
+}
+
+/// 容器内的 cue（如 FLAC CUESHEET）转为章节 `(起始秒, 标题)`
+///
+/// 标题取 cue 的 TITLE 标签，缺失时使用 `Track NN`；CD 的 lead-out（170 号）不是章节
+fn chapters_from_cues(cues: &[Cue], sample_rate: u32) -> Vec<(f64, String)> {
+    const CDDA_LEAD_OUT: u32 = 170;
+    let mut chapters: Vec<(f64, String)> = cues
+        .iter()
+        .filter(|cue| cue.index != CDDA_LEAD_OUT)
+        .map(|cue| {
+            let title = cue
+                .tags
+                .iter()
+                .find(|t| {
+                    t.std_key == Some(StandardTagKey::TrackTitle)
+                        || t.key.eq_ignore_ascii_case("TITLE")
+                })
+                .map(|t| t.value.to_string())
+                .unwrap_or_else(|| format!("Track {:02}", cue.index));
+            (cue.start_ts as f64 / sample_rate as f64, title)
+        })
+        .collect();
+    chapters.sort_by(|a, b| a.0.total_cmp(&b.0));
+    chapters
+}
+
+/// iTunes 无缝播放信息（iTunSMPB 标签）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GaplessInfo {
+    /// 编码器延迟（帧）
+    delay: u32,
+    /// 编码器填充（帧）
+    padding: u32,
+    /// 原始音频帧数（0 表示未知）
+    valid_frames: u64,
+}
+
+/// 解析 iTunSMPB 标签值
+///
+/// 格式为空格分隔的十六进制字段：`00000000 <delay> <padding> <valid frames> ...`
+fn parse_itunsmpb(value: &str) -> Option<GaplessInfo> {
+    let mut fields = value.split_whitespace();
+    let reserved = fields.next()?;
+    if reserved.len() != 8 || u32::from_str_radix(reserved, 16).is_err() {
+        return None;
+    }
+    let delay = u32::from_str_radix(fields.next()?, 16).ok()?;
+    let padding = u32::from_str_radix(fields.next()?, 16).ok()?;
+    let valid_frames = u64::from_str_radix(fields.next()?, 16).ok()?;
+    Some(GaplessInfo {
+        delay,
+        padding,
+        valid_frames,
+    })
+}
+
+/// 在标签中查找 iTunSMPB
+///
+/// MP4 为 `com.apple.iTunes:iTunSMPB` 自由标签；ID3 中存为描述为 iTunSMPB 的 COMM 帧
+/// （symphonia 丢弃了描述，按值格式识别）
+fn gapless_from_tags(tags: &[Tag]) -> Option<GaplessInfo> {
+    tags.iter()
+        .filter(|t| t.key.ends_with("iTunSMPB") || t.key.starts_with("COMM"))
+        .find_map(|t| parse_itunsmpb(&t.value.to_string()))
+}
+
+/// 检查解码出的 packet 格式是否与打开时一致
+///
+/// 容器未声明声道布局时（expected 为空）只比较采样率
+fn check_spec(expected: &SignalSpec, actual: &SignalSpec) -> Result<(), DecodeError> {
+    let channels = actual.channels.count();
+    let expected_channels = expected.channels.count();
+    if actual.rate != expected.rate || (expected_channels != 0 && channels != expected_channels) {
+        return Err(DecodeError::FormatChanged {
+            sample_rate: actual.rate,
+            channels: channels as u32,
+        });
+    }
+    Ok(())
+}
+
+/// 由文件大小和时长估算平均码率（bps）
+///
+/// 包含容器开销（元数据、封面图），对 CBR 有损文件足够准确
+fn estimate_bitrate(byte_len: u64, duration_secs: f64) -> Option<u32> {
+    if duration_secs <= 0.0 || byte_len == 0 {
+        return None;
+    }
+    let bps = byte_len as f64 * 8.0 / duration_secs;
+    Some(bps.round().min(u32::MAX as f64) as u32)
+}
+
+/// 是否为有损编解码器
+fn is_lossy_codec(codec: CodecType) -> bool {
+    matches!(
+        codec,
+        CODEC_TYPE_MP1
+            | CODEC_TYPE_MP2
+            | CODEC_TYPE_MP3
+            | CODEC_TYPE_AAC
+            | CODEC_TYPE_VORBIS
+            | CODEC_TYPE_OPUS
+    )
+}
+
+/// 从 ALAC magic cookie 读取位深度
+///
+/// cookie 布局：frameLength(u32 BE) + compatibleVersion(u8) + bitDepth(u8) + ...
+/// 长度为 24 或 48 字节（48 字节版本附带声道布局）
+fn alac_bit_depth(cookie: &[u8]) -> Option<u32> {
+    if cookie.len() != 24 && cookie.len() != 48 {
+        return None;
+    }
+    match cookie[5] {
+        bits @ 1..=32 => Some(bits as u32),
+        _ => None,
+    }
+}
+
 /// 最大单次解码样本数（覆盖所有常见格式）
 /// 8192 frames * 8 channels = 65536 samples
 const MAX_SAMPLES_PER_DECODE: usize = 65536;
@@ -413,6 +1055,8 @@ pub struct DecoderIterator {
     decoder: AudioDecoder,
     /// 双缓冲（i32 直通路径）
     double_buffer: DoubleBuffer,
+    /// 解码器已返回 EOF（之后只排空缓冲区，不再解码）
+    eof: bool,
 }
 
 impl DecoderIterator {
@@ -420,6 +1064,7 @@ impl DecoderIterator {
         Self {
             decoder,
             double_buffer: DoubleBuffer::new(),
+            eof: false,
         }
     }
 
@@ -430,32 +1075,26 @@ impl DecoderIterator {
 
     /// 读取指定数量的 i32 样本
     ///
-    /// 返回的样本已左对齐到 i32 高位
+    /// 返回的样本已左对齐到 i32 高位。到达 EOF 后先分多次返回缓冲区中的
+    /// 剩余样本（每次不超过 `count`），全部返回后才返回空切片
     pub fn read_i32(&mut self, count: usize) -> Result<&[i32], DecodeError> {
         // 如果当前缓冲区有足够数据，直接返回切片（零拷贝快速路径）
         if self.double_buffer.available() >= count {
             return Ok(self.double_buffer.read(count));
         }
 
-        // 需要解码更多数据
-        // 解码一批数据
-        let samples = self.decoder.decode_next_i32()?;
-        if samples.is_empty() {
-            // EOF - 返回剩余数据
-            let remaining = self.double_buffer.available();
-            if remaining > 0 {
-                return Ok(self.double_buffer.read(remaining));
+        // 需要解码更多数据（EOF 后不再调用解码器，部分格式 EOF 后再读会报错）
+        if !self.eof {
+            let samples = self.decoder.decode_next_i32()?;
+            if samples.is_empty() {
+                self.eof = true;
+            } else if self.double_buffer.available() == 0 {
+                // 缓冲区已空，直接使用新数据
+                self.double_buffer.append(samples);
+            } else {
+                // 还有剩余数据，交换缓冲区并合并
+                self.double_buffer.swap_and_append(samples);
             }
-            return Ok(&[]);
-        }
-
-        // 使用双缓冲策略
-        if self.double_buffer.available() == 0 {
-            // 缓冲区已空，直接使用新数据
-            self.double_buffer.append(samples);
-        } else {
-            // 还有剩余数据，交换缓冲区并合并
-            self.double_buffer.swap_and_append(samples);
         }
 
         // 返回请求的数据量（或全部可用数据）
@@ -463,9 +1102,18 @@ impl DecoderIterator {
         Ok(self.double_buffer.read(to_return))
     }
 
-    /// 检查是否到达文件末尾
+    /// Seek 到指定时间（秒），丢弃已缓冲的样本
+    pub fn seek(&mut self, time_secs: f64) -> Result<(), DecodeError> {
+        self.decoder.seek(time_secs)?;
+        self.double_buffer.len = 0;
+        self.double_buffer.position = 0;
+        self.eof = false;
+        Ok(())
+    }
+
+    /// 检查是否到达文件末尾（解码器已 EOF 且缓冲样本已全部读出）
     pub fn is_eof(&self) -> bool {
-        self.double_buffer.available() == 0
+        self.eof && self.double_buffer.available() == 0
     }
 }
 
@@ -473,6 +1121,27 @@ impl DecoderIterator {
 // 独立转换函数（避免借用冲突）
 // ============================================================================
 
+/// 通用多声道交织：逐声道取一次平面切片，按声道数步长写入输出
+///
+/// 逐样本调用 `chan(ch)[frame]` 每次都要重新取平面并做边界检查，
+/// 6/8 声道高采样率内容上开销明显；这里内层循环只有一次转换和一次写入
+#[inline]
+fn interleave_planes<S: Sample>(
+    buf: &AudioBuffer<S>,
+    output: &mut [i32],
+    convert: impl Fn(S) -> i32,
+) {
+    let channels = buf.spec().channels.count();
+    let frames = buf.frames();
+    let output = &mut output[..frames * channels];
+    for ch in 0..channels {
+        let plane = &buf.chan(ch)[..frames];
+        for (out, &sample) in output[ch..].iter_mut().step_by(channels).zip(plane) {
+            *out = convert(sample);
+        }
+    }
+}
+
 /// 转换 i8 样本到 i32 左对齐
 #[inline]
 fn convert_s8_to_i32(buf: &AudioBuffer<i8>, output: &mut [i32]) {
@@ -557,24 +1226,16 @@ fn convert_s16_to_i32_stereo_neon(buf: &AudioBuffer<i16>, output: &mut [i32], fr
 /// 使用 SIMD 加速（ARM NEON）实现向量化转换
 #[inline]
 fn convert_s24_to_i32(buf: &AudioBuffer<symphonia::core::sample::i24>, output: &mut [i32]) {
-    let channels = buf.spec().channels.count();
-    let frames = buf.frames();
-
     // 立体声 + ARM64 SIMD 优化路径
     #[cfg(target_arch = "aarch64")]
-    if channels == 2 {
-        convert_s24_to_i32_stereo_neon(buf, output, frames);
+    if buf.spec().channels.count() == 2 {
+        convert_s24_to_i32_stereo_neon(buf, output, buf.frames());
         return;
     }
 
-    // 标量回退路径
-    for frame in 0..frames {
-        for ch in 0..channels {
-            // i24 内部是 i32，需要左移 8 位对齐到高位
-            let sample = buf.chan(ch)[frame].inner();
-            output[frame * channels + ch] = sample << 8;
-        }
-    }
+    // 多声道（及非 ARM 平台）：按声道交织
+    // i24 内部是 i32，需要左移 8 位对齐到高位
+    interleave_planes(buf, output, |sample| sample.inner() << 8);
 }
 
 /// NEON 优化的立体声 i24→i32 转换
@@ -635,23 +1296,16 @@ fn convert_s24_to_i32_stereo_neon(
 
 /// 转换 i32 样本（直接复制）
 ///
-/// 立体声 NEON 优化：vld1q + vst2q 交织写入
+/// 立体声 NEON 优化：vld1q + vst2q 交织写入；多声道按声道交织
 #[inline]
 fn convert_s32_to_i32(buf: &AudioBuffer<i32>, output: &mut [i32]) {
-    let channels = buf.spec().channels.count();
-    let frames = buf.frames();
-
     #[cfg(target_arch = "aarch64")]
-    if channels == 2 {
-        convert_s32_to_i32_stereo_neon(buf, output, frames);
+    if buf.spec().channels.count() == 2 {
+        convert_s32_to_i32_stereo_neon(buf, output, buf.frames());
         return;
     }
 
-    for frame in 0..frames {
-        for ch in 0..channels {
-            output[frame * channels + ch] = buf.chan(ch)[frame];
-        }
-    }
+    interleave_planes(buf, output, |sample| sample);
 }
 
 /// NEON 优化的立体声 i32→i32 交织拷贝
@@ -683,25 +1337,16 @@ fn convert_s32_to_i32_stereo_neon(buf: &AudioBuffer<i32>, output: &mut [i32], fr
 
 /// 转换 f32 样本到 i32 左对齐
 ///
-/// 立体声 NEON 优化：向量化 clamp + f32→i32 转换
+/// 立体声 NEON 优化：向量化 clamp + f32→i32 转换；多声道按声道交织
 #[inline]
 fn convert_f32_to_i32(buf: &AudioBuffer<f32>, output: &mut [i32]) {
-    let channels = buf.spec().channels.count();
-    let frames = buf.frames();
-
     #[cfg(target_arch = "aarch64")]
-    if channels == 2 {
-        convert_f32_to_i32_stereo_neon(buf, output, frames);
+    if buf.spec().channels.count() == 2 {
+        convert_f32_to_i32_stereo_neon(buf, output, buf.frames());
         return;
     }
 
-    for frame in 0..frames {
-        for ch in 0..channels {
-            let sample = buf.chan(ch)[frame];
-            let clamped = sample.clamp(-1.0, 1.0);
-            output[frame * channels + ch] = (clamped * i32::MAX as f32) as i32;
-        }
-    }
+    interleave_planes(buf, output, |sample| (sample.clamp(-1.0, 1.0) * i32::MAX as f32) as i32);
 }
 
 /// NEON 优化的立体声 f32→i32 转换
@@ -825,6 +1470,9 @@ fn convert_u32_to_i32(buf: &AudioBuffer<u32>, output: &mut [i32]) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::decode::{make_flac_with_comments, make_wav};
+    use std::time::Duration;
+    use symphonia::core::meta::Value;
 
     #[test]
     #[ignore] // 需要实际音频文件
@@ -833,4 +1481,405 @@ mod tests {
         let info = decoder.info();
         println!("Info: {:?}", info);
     }
+
+    #[test]
+    fn test_estimate_bitrate() {
+        // 320kbps MP3：60 秒 = 2,400,000 字节
+        assert_eq!(estimate_bitrate(2_400_000, 60.0), Some(320_000));
+        // 44.1kHz/16bit 立体声 WAV：1411.2kbps
+        assert_eq!(estimate_bitrate(176_400 * 10, 10.0), Some(1_411_200));
+        assert_eq!(estimate_bitrate(1000, 0.0), None);
+        assert_eq!(estimate_bitrate(0, 10.0), None);
+        assert!(is_lossy_codec(CODEC_TYPE_MP3));
+        assert!(!is_lossy_codec(CODEC_TYPE_ALAC));
+    }
+
+    #[test]
+    fn test_check_spec_detects_format_change() {
+        use symphonia::core::audio::Channels;
+
+        let stereo = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+        let opened = SignalSpec::new(44100, stereo);
+        assert!(check_spec(&opened, &SignalSpec::new(44100, stereo)).is_ok());
+        let unknown = SignalSpec::new(44100, Channels::empty());
+        assert!(check_spec(&unknown, &SignalSpec::new(44100, stereo)).is_ok());
+
+        assert!(matches!(
+            check_spec(&opened, &SignalSpec::new(48000, stereo)),
+            Err(DecodeError::FormatChanged { sample_rate: 48000, channels: 2 })
+        ));
+        assert!(matches!(
+            check_spec(&opened, &SignalSpec::new(44100, Channels::FRONT_LEFT)),
+            Err(DecodeError::FormatChanged { sample_rate: 44100, channels: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_multichannel_conversion_matches_scalar() {
+        use rand::{Rng, SeedableRng};
+        use symphonia::core::audio::Channels;
+        use symphonia::core::sample::i24;
+
+        /// 逐样本的标量参考实现
+        fn reference<S: Sample>(buf: &AudioBuffer<S>, convert: impl Fn(S) -> i32) -> Vec<i32> {
+            let channels = buf.spec().channels.count();
+            let mut out = vec![0; buf.frames() * channels];
+            for frame in 0..buf.frames() {
+                for ch in 0..channels {
+                    out[frame * channels + ch] = convert(buf.chan(ch)[frame]);
+                }
+            }
+            out
+        }
+
+        fn random_buffer<S: Sample>(
+            channels: Channels,
+            frames: usize,
+            mut sample: impl FnMut() -> S,
+        ) -> AudioBuffer<S> {
+            let mut buf = AudioBuffer::new(frames as u64, SignalSpec::new(48000, channels));
+            buf.render_reserved(Some(frames));
+            for ch in 0..channels.count() {
+                buf.chan_mut(ch).iter_mut().for_each(|s| *s = sample());
+            }
+            buf
+        }
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let layouts = [
+            Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            Channels::FRONT_LEFT
+                | Channels::FRONT_RIGHT
+                | Channels::FRONT_CENTRE
+                | Channels::LFE1
+                | Channels::REAR_LEFT
+                | Channels::REAR_RIGHT,
+            Channels::FRONT_LEFT
+                | Channels::FRONT_RIGHT
+                | Channels::FRONT_CENTRE
+                | Channels::LFE1
+                | Channels::REAR_LEFT
+                | Channels::REAR_RIGHT
+                | Channels::SIDE_LEFT
+                | Channels::SIDE_RIGHT,
+        ];
+        // 帧数不是 4 的倍数，覆盖 SIMD 的尾部处理
+        let frames = 1001;
+
+        for channels in layouts {
+            let mut out = vec![0i32; frames * channels.count()];
+
+            let buf = random_buffer(channels, frames, || {
+                i24::from(rng.gen_range(-(1 << 23)..1 << 23))
+            });
+            convert_s24_to_i32(&buf, &mut out);
+            assert_eq!(out, reference(&buf, |s| s.inner() << 8));
+
+            let buf = random_buffer(channels, frames, || rng.gen::<i32>());
+            convert_s32_to_i32(&buf, &mut out);
+            assert_eq!(out, reference(&buf, |s| s));
+
+            // 含超出 [-1, 1] 的样本，验证 clamp 一致
+            let buf = random_buffer(channels, frames, || rng.gen_range(-1.5f32..1.5));
+            convert_f32_to_i32(&buf, &mut out);
+            assert_eq!(
+                out,
+                reference(&buf, |s| (s.clamp(-1.0, 1.0) * i32::MAX as f32) as i32)
+            );
+        }
+    }
+
+    #[test]
+    fn test_select_track() {
+        use symphonia::core::audio::Channels;
+        use symphonia::core::codecs::{CodecParameters, CODEC_TYPE_FLAC, CODEC_TYPE_PCM_S16LE};
+
+        // 模拟多音轨容器：一条非音频轨道 + 两条音频轨道（不同语言）
+        let mut stereo = CodecParameters::new();
+        stereo
+            .for_codec(CODEC_TYPE_FLAC)
+            .with_channels(Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+        let mut mono = CodecParameters::new();
+        mono.for_codec(CODEC_TYPE_PCM_S16LE)
+            .with_channels(Channels::FRONT_CENTRE);
+
+        let mut english = Track::new(2, stereo);
+        english.language = Some("eng".to_string());
+        let mut commentary = Track::new(5, mono);
+        commentary.language = Some("jpn".to_string());
+        let tracks = [Track::new(1, CodecParameters::new()), english, commentary];
+
+        assert_eq!(select_track(&tracks, 0).unwrap().id, 2);
+        assert_eq!(select_track(&tracks, 1).unwrap().id, 5);
+        assert!(matches!(select_track(&tracks, 2), Err(DecodeError::TrackNotFound(2))));
+        assert!(matches!(select_track(&tracks[..1], 0), Err(DecodeError::NoAudioTrack)));
+
+        let described = describe_tracks(&tracks);
+        assert_eq!(described.len(), 2);
+        assert_eq!(described[0].codec, "flac");
+        assert_eq!(described[0].channels, Some(2));
+        assert_eq!(described[1].language.as_deref(), Some("jpn"));
+        assert_eq!(described[1].channels, Some(1));
+    }
+
+    #[test]
+    fn test_parse_itunsmpb() {
+        let value = " 00000000 00000840 000001CA 00000000001A1E76 00000000 00000000 \
+                     00000000 00000000 00000000 00000000 00000000 00000000";
+        assert_eq!(
+            parse_itunsmpb(value),
+            Some(GaplessInfo {
+                delay: 2112,
+                padding: 458,
+                valid_frames: 0x1A1E76,
+            })
+        );
+
+        // 普通注释不是 iTunSMPB
+        assert_eq!(parse_itunsmpb("ripped with foo"), None);
+        assert_eq!(parse_itunsmpb("00000000 00000840"), None);
+
+        let tags = [
+            Tag::new(None, "COMM!eng", Value::from("great album")),
+            Tag::new(None, "com.apple.iTunes:iTunSMPB", Value::from(value)),
+        ];
+        assert_eq!(gapless_from_tags(&tags).map(|g| g.delay), Some(2112));
+    }
+
+    #[test]
+    fn test_alac_bit_depth() {
+        let mut cookie = [0u8; 24];
+        cookie[..4].copy_from_slice(&4096u32.to_be_bytes());
+        cookie[5] = 24;
+        assert_eq!(alac_bit_depth(&cookie), Some(24));
+
+        cookie[5] = 0;
+        assert_eq!(alac_bit_depth(&cookie), None);
+        assert_eq!(alac_bit_depth(&cookie[..20]), None);
+    }
+
+    #[test]
+    fn test_explicit_hint_overrides_extension() {
+        let path = Path::new("mislabeled.wav");
+        assert_eq!(probe_extension(path, None), Some("wav"));
+        assert_eq!(probe_extension(path, Some("flac")), Some("flac"));
+        assert_eq!(probe_extension(Path::new("noext"), None), None);
+
+        let hint = format!("{:?}", probe_hint(probe_extension(path, Some("flac"))));
+        assert!(hint.contains("Some(\"flac\")"), "{}", hint);
+
+        // WAV 数据以 .flac 命名：强制提示后格式名随提示
+        let dir = std::env::temp_dir().join(format!("roger-hint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("track.flac");
+        std::fs::write(&file, make_wav(44100, 2, 100)).unwrap();
+        let decoder = AudioDecoder::open_with_hint(&file, "wav").unwrap();
+        assert_eq!(decoder.info().format, "WAV");
+
+        // 探测失败时错误中带上所用的提示
+        std::fs::write(&file, [0u8; 64]).unwrap();
+        let err = AudioDecoder::open_with_hint(&file, "wav").err().unwrap();
+        assert!(err.to_string().contains("'wav'"), "{}", err);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_track_uses_pcm_format() {
+        let file = std::env::temp_dir().join(format!("roger-track-{}.pcm", std::process::id()));
+        std::fs::write(&file, [0u8; 48 * 3]).unwrap();
+        let pcm = RawPcmFormat {
+            sample_rate: 96000,
+            channels: 1,
+            bits_per_sample: 24,
+            endian: crate::audio::Endian::Big,
+        };
+        let decoder = AudioDecoder::open_track(&file, pcm, None, 0).unwrap();
+        assert_eq!(decoder.info().sample_rate, 96000);
+        assert_eq!(decoder.info().channels, 1);
+        assert_eq!(decoder.info().total_frames, Some(48));
+        assert_eq!(decoder.info().codec, "pcm_s24be");
+
+        // 原始 PCM 只有一条轨道
+        assert!(matches!(
+            AudioDecoder::open_track(&file, pcm, None, 1),
+            Err(DecodeError::TrackNotFound(1))
+        ));
+        std::fs::remove_file(&file).ok();
+    }
+
+    #[test]
+    fn test_read_i32_drains_tail() {
+        // 1001 帧立体声 = 2002 样本，不是读取块（300）的整数倍
+        let wav = make_wav(44100, 2, 1001);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        let mut iter = DecoderIterator::new(decoder);
+
+        let mut all = Vec::new();
+        loop {
+            let samples = iter.read_i32(300).unwrap();
+            assert!(samples.len() <= 300);
+            if samples.is_empty() {
+                break;
+            }
+            all.extend_from_slice(samples);
+        }
+        assert!(iter.is_eof());
+
+        let expected: Vec<i32> = (0..2002i32).map(|i| (i % 1000) << 16).collect();
+        assert_eq!(all, expected);
+        // EOF 之后保持返回空切片，不再调用解码器
+        assert!(iter.read_i32(300).unwrap().is_empty());
+    }
+
+    /// 模拟正在写入的文件：读到已写入部分的末尾时返回 EOF，随后再“写入”一段
+    struct GrowingReader {
+        data: Vec<u8>,
+        pos: usize,
+        written: usize,
+        chunk: usize,
+    }
+
+    impl Read for GrowingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pos == self.written {
+                self.written = (self.written + self.chunk).min(self.data.len());
+                return Ok(0);
+            }
+            let n = buf.len().min(self.written - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_follow_resumes_after_eof() {
+        let wav = make_wav(44100, 2, 4410);
+        let reader = GrowingReader {
+            written: 1000,
+            chunk: 3000,
+            data: wav,
+            pos: 0,
+        };
+        let active = Arc::new(AtomicBool::new(true));
+        let reader = FollowReader::new(reader, Duration::from_millis(200), active);
+        let mut decoder = AudioDecoder::open_reader(Box::new(reader), None).unwrap();
+        assert!(decoder.info().duration_secs.is_none());
+
+        // 多次遇到暂时的 EOF 后仍解码出全部帧
+        let mut total = 0;
+        loop {
+            let samples = decoder.decode_next_i32().unwrap();
+            if samples.is_empty() {
+                break;
+            }
+            total += samples.len();
+        }
+        assert_eq!(total, 4410 * 2);
+    }
+
+    #[test]
+    fn test_open_reader_not_seekable() {
+        let wav = make_wav(44100, 2, 4410);
+        let mut decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+
+        let info = decoder.info().clone();
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.channels, 2);
+        assert!(!info.seekable);
+        assert!(info.duration_secs.is_none());
+        assert!(matches!(decoder.seek(0.05), Err(DecodeError::SeekFailed(_))));
+
+        let mut total = 0;
+        loop {
+            let samples = decoder.decode_next_i32().unwrap();
+            if samples.is_empty() {
+                break;
+            }
+            total += samples.len();
+        }
+        assert_eq!(total, 4410 * 2);
+    }
+
+    #[test]
+    fn test_corrupt_run_escapes() {
+        // 注入一长串解码失败：中途重置解码器，最终放弃而不是无限跳过
+        let mut run = CorruptRun::default();
+        let mut resets = 0;
+        let mut errors = 0;
+        loop {
+            errors += 1;
+            assert!(errors <= 1000, "corrupt run never gave up");
+            match run.on_error() {
+                CorruptAction::Skip => {}
+                CorruptAction::Reset => resets += 1,
+                CorruptAction::GiveUp => break,
+            }
+        }
+        assert_eq!(errors, CORRUPT_PACKETS_BEFORE_GIVE_UP);
+        assert_eq!(resets, CORRUPT_PACKETS_BEFORE_GIVE_UP / CORRUPT_PACKETS_BEFORE_RESET - 1);
+
+        // 中间穿插成功解码的 packet：零星损坏永远不会放弃
+        let mut run = CorruptRun::default();
+        for _ in 0..1000 {
+            for _ in 0..CORRUPT_PACKETS_BEFORE_RESET - 1 {
+                assert_eq!(run.on_error(), CorruptAction::Skip);
+            }
+            run.clear();
+        }
+
+        assert_eq!(
+            DecodeError::CorruptStream(128).to_string(),
+            "Stream corrupt: 128 consecutive packets failed to decode"
+        );
+    }
+
+    #[test]
+    fn test_seek_after_gapless_trim() {
+        // 40 块 = 163840 帧：延迟 2112，有效 150000，其余为填充
+        let comment = "iTunSMPB= 00000000 00000840 00002DD0 00000000000249F0";
+        let dir = std::env::temp_dir().join(format!("roger-gapless-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("ramp.flac");
+        std::fs::write(&file, make_flac_with_comments(44100, 1, 40 * 4096, &[comment])).unwrap();
+
+        let mut decoder = AudioDecoder::open(&file).unwrap();
+        let info = decoder.info().clone();
+        assert_eq!(info.encoder_delay, 2112);
+        assert_eq!(info.encoder_padding, 0x2DD0);
+        assert_eq!(info.total_frames, Some(150_000));
+
+        // 读出剩余全部帧，返回 (首样本 = 源帧号 % 1000, 帧数)
+        let mut drain = |decoder: &mut AudioDecoder| {
+            let mut first = None;
+            let mut frames = 0u64;
+            loop {
+                let samples = decoder.decode_next_i32().unwrap();
+                if samples.is_empty() {
+                    break;
+                }
+                first.get_or_insert(samples[0] >> 16);
+                frames += samples.len() as u64;
+            }
+            (first.unwrap(), frames)
+        };
+
+        // 不 seek：从延迟之后（源帧 2112）开始，到有效帧数为止
+        assert_eq!(drain(&mut decoder), (112, 150_000));
+
+        // seek 到 T：首样本是裁剪后时间轴上的 T（源文件中的 T + 延迟）
+        for &(secs, frame) in &[(1.5, 66_150u64), (0.1, 4410), (3.0, 132_300), (0.0, 0)] {
+            decoder.seek(secs).unwrap();
+            let expected = ((frame + 2112) % 1000) as i32;
+            assert_eq!(
+                drain(&mut decoder),
+                (expected, 150_000 - frame),
+                "seek {}",
+                secs
+            );
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
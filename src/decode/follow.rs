@@ -0,0 +1,85 @@
+//! 跟随增长中的文件（边录边播、网络共享同步中）
+//!
+//! 读到文件末尾时不立即报告 EOF，而是短暂等待后重试：
+//! 有新数据就继续返回，超过空闲超时仍无增长才视为真正结束。
+//! 等待发生在字节层，symphonia 的格式读取器不会看到半截 packet 后的 EOF。
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 文件停止增长多久后视为结束
+pub const FOLLOW_IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 等待新数据的轮询间隔
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 读到末尾时等待数据增长的读取器
+pub struct FollowReader<R> {
+    inner: R,
+    idle_timeout: Duration,
+    poll_interval: Duration,
+    /// 置为 false 时立即结束等待（停止播放）
+    active: Arc<AtomicBool>,
+}
+
+impl<R: Read> FollowReader<R> {
+    pub fn new(inner: R, idle_timeout: Duration, active: Arc<AtomicBool>) -> Self {
+        Self {
+            inner,
+            idle_timeout,
+            poll_interval: FOLLOW_POLL_INTERVAL.min(idle_timeout),
+            active,
+        }
+    }
+}
+
+impl<R: Read> Read for FollowReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let idle_since = Instant::now();
+        loop {
+            let n = self.inner.read(buf)?;
+            if n > 0 {
+                return Ok(n);
+            }
+            if !self.active.load(Ordering::Acquire) || idle_since.elapsed() >= self.idle_timeout {
+                return Ok(0);
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_reader_ends_on_idle_or_stop() {
+        let active = Arc::new(AtomicBool::new(true));
+        let mut reader = FollowReader::new(
+            io::Cursor::new(vec![1u8, 2, 3]),
+            Duration::from_millis(100),
+            Arc::clone(&active),
+        );
+        let mut buf = [0u8; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 3);
+
+        // 没有增长：等满空闲超时后报告 EOF
+        let started = Instant::now();
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+
+        // 停止播放时不再等待
+        active.store(false, Ordering::Release);
+        reader.idle_timeout = Duration::from_secs(60);
+        let started = Instant::now();
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,454 @@
+//! HTTP(S) 渐进式播放数据源
+//!
+//! 把 `http://`/`https://` URL 包装为 symphonia 的 `MediaSource`：后台 `http-fetch` 线程顺序拉取
+//! 响应体到有界的内存预读缓冲区，解码线程只从缓冲区读取，短暂的网络停顿由缓冲吸收。
+//!
+//! 服务器返回 `Accept-Ranges: bytes` 且带 `Content-Length` 时可 seek：
+//! 缓冲区内的前向 seek 直接丢弃字节，其余情况以 `Range: bytes=N-` 重新请求。
+//! 否则视为不可 seek 的流（与 stdin 相同）。
+//!
+//! 请求由 `ureq` 发出（HTTP/1.1、分块传输、重定向；TLS 用 rustls 与内置根证书）。
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use symphonia::core::io::MediaSource;
+
+use super::decoder::DecodeError;
+
+/// 预读缓冲区上限（字节）：约 6 秒 CD 音质 FLAC
+const READ_AHEAD_BYTES: usize = 1024 * 1024;
+
+/// 后台线程每次从响应体读取的字节数
+const FETCH_CHUNK_BYTES: usize = 16 * 1024;
+
+/// 最多跟随的重定向次数
+const MAX_REDIRECTS: u32 = 5;
+
+/// 连接与读写超时
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 路径参数是否为 HTTP(S) URL
+pub fn is_url(path: &Path) -> bool {
+    path.to_str().is_some_and(|s| {
+        s.get(..7).is_some_and(|p| p.eq_ignore_ascii_case("http://"))
+            || s.get(..8).is_some_and(|p| p.eq_ignore_ascii_case("https://"))
+    })
+}
+
+/// URL 路径部分的扩展名（去掉查询串与片段），用作格式探测提示
+pub fn url_extension(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let path = &rest[rest.find('/')?..];
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_string())
+}
+
+/// 共享的请求配置（超时、重定向、User-Agent）
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(NETWORK_TIMEOUT)
+        .timeout_read(NETWORK_TIMEOUT)
+        .timeout_write(NETWORK_TIMEOUT)
+        .redirects(MAX_REDIRECTS)
+        .user_agent("roger-player")
+        .build()
+}
+
+/// 发送 GET 请求（`offset > 0` 时带 Range 头，跟随重定向），非 2xx 状态返回错误
+fn send_request(agent: &ureq::Agent, url: &str, offset: u64) -> Result<ureq::Response, String> {
+    let mut request = agent.get(url);
+    if offset > 0 {
+        request = request.set("Range", &format!("bytes={}-", offset));
+    }
+    match request.call() {
+        Ok(response) if (200..300).contains(&response.status()) => Ok(response),
+        Ok(response) => Err(format!("{}: HTTP {}", response.get_url(), response.status())),
+        Err(ureq::Error::Status(status, response)) => {
+            Err(format!("{}: HTTP {}", response.get_url(), status))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 预读缓冲区状态
+struct FetchState {
+    buffer: VecDeque<u8>,
+    eof: bool,
+    error: Option<String>,
+    cancelled: bool,
+}
+
+struct FetchShared {
+    state: Mutex<FetchState>,
+    /// 缓冲区有新数据 / 有新空间 / 已取消
+    changed: Condvar,
+}
+
+/// 一次响应体的后台拉取
+///
+/// drop 时通知线程退出：等待空间的线程立即退出，阻塞在读取上的线程在读取返回后退出
+struct Fetcher {
+    shared: Arc<FetchShared>,
+}
+
+impl Fetcher {
+    fn spawn(body: impl Read + Send + 'static) -> io::Result<Self> {
+        let fetcher = Self::new(false);
+        let shared = fetcher.shared.clone();
+        thread::Builder::new()
+            .name("http-fetch".to_string())
+            .spawn(move || fetch_loop(body, &shared))?;
+        Ok(fetcher)
+    }
+
+    /// 已到结尾的空数据源（seek 到文件末尾之后）
+    fn finished() -> Self {
+        Self::new(true)
+    }
+
+    fn new(eof: bool) -> Self {
+        Self {
+            shared: Arc::new(FetchShared {
+                state: Mutex::new(FetchState {
+                    buffer: VecDeque::with_capacity(READ_AHEAD_BYTES),
+                    eof,
+                    error: None,
+                    cancelled: false,
+                }),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// 从缓冲区读取，缓冲区空时等待后台线程
+    fn read(&self, out: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if !state.buffer.is_empty() {
+                let n = out.len().min(state.buffer.len());
+                for (dst, src) in out.iter_mut().zip(state.buffer.drain(..n)) {
+                    *dst = src;
+                }
+                self.shared.changed.notify_all();
+                return Ok(n);
+            }
+            if let Some(ref error) = state.error {
+                return Err(io::Error::other(error.clone()));
+            }
+            if state.eof || out.is_empty() {
+                return Ok(0);
+            }
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// 丢弃缓冲区开头的 `n` 字节；缓冲区不足时不做任何事并返回 false
+    fn skip_buffered(&self, n: u64) -> bool {
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        if n > state.buffer.len() as u64 {
+            return false;
+        }
+        state.buffer.drain(..n as usize);
+        self.shared.changed.notify_all();
+        true
+    }
+}
+
+impl Drop for Fetcher {
+    fn drop(&mut self) {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .cancelled = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+/// 后台拉取循环：缓冲区满时等待消费，取消或出错时退出
+fn fetch_loop(mut body: impl Read, shared: &FetchShared) {
+    let mut chunk = vec![0u8; FETCH_CHUNK_BYTES];
+    loop {
+        {
+            let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            while state.buffer.len() >= READ_AHEAD_BYTES && !state.cancelled {
+                state = shared.changed.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+            if state.cancelled {
+                return;
+            }
+        }
+
+        let result = body.read(&mut chunk);
+        let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(0) => {
+                state.eof = true;
+                shared.changed.notify_all();
+                return;
+            }
+            Ok(n) => state.buffer.extend(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                if !state.cancelled {
+                    state.error = Some(e.to_string());
+                }
+                shared.changed.notify_all();
+                return;
+            }
+        }
+        shared.changed.notify_all();
+    }
+}
+
+/// HTTP 数据源（实现 `MediaSource`）
+pub struct HttpSource {
+    agent: ureq::Agent,
+    /// 跟随重定向后的最终 URL（seek 时重新请求）
+    url: String,
+    /// Content-Length
+    len: Option<u64>,
+    /// 服务器支持 Range 请求
+    seekable: bool,
+    /// 已交给调用方的字节位置
+    pos: u64,
+    fetcher: Fetcher,
+}
+
+impl HttpSource {
+    /// 请求 URL 并开始后台预读
+    pub fn open(url: &str) -> Result<Self, DecodeError> {
+        let agent = agent();
+        let response = send_request(&agent, url, 0).map_err(DecodeError::Network)?;
+        let url = response.get_url().to_string();
+
+        let len: Option<u64> = response
+            .header("content-length")
+            .and_then(|v| v.parse().ok());
+        let seekable = len.is_some()
+            && response
+                .header("accept-ranges")
+                .is_some_and(|v| v.eq_ignore_ascii_case("bytes"));
+        log::info!(
+            "HTTP source: {} ({} bytes, {})",
+            url,
+            len.map(|l| l.to_string()).unwrap_or_else(|| "?".to_string()),
+            if seekable { "seekable" } else { "not seekable" }
+        );
+
+        let fetcher = Fetcher::spawn(response.into_reader())
+            .map_err(|e| DecodeError::Network(e.to_string()))?;
+        Ok(Self {
+            agent,
+            url,
+            len,
+            seekable,
+            pos: 0,
+            fetcher,
+        })
+    }
+
+    /// 从 `offset` 重新请求（Range）
+    fn reconnect(&mut self, offset: u64) -> io::Result<()> {
+        if self.len.is_some_and(|len| offset >= len) {
+            self.fetcher = Fetcher::finished();
+            return Ok(());
+        }
+        let response = send_request(&self.agent, &self.url, offset).map_err(io::Error::other)?;
+        if response.status() != 206 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{}: server ignored Range request", self.url),
+            ));
+        }
+        self.fetcher = Fetcher::spawn(response.into_reader())?;
+        Ok(())
+    }
+}
+
+impl Read for HttpSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.fetcher.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for HttpSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+            SeekFrom::End(delta) => self.len.and_then(|len| len.checked_add_signed(delta)),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek position"))?;
+
+        if target == self.pos {
+            return Ok(target);
+        }
+        // 已预读的范围内：直接丢弃字节，无需重新请求
+        let buffered = target > self.pos && self.fetcher.skip_buffered(target - self.pos);
+        if !buffered {
+            if !self.seekable {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "HTTP source does not support range requests",
+                ));
+            }
+            self.reconnect(target)?;
+        }
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+impl MediaSource for HttpSource {
+    fn is_seekable(&self) -> bool {
+        self.seekable
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::make_flac;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// 本地 HTTP 测试服务器，返回 URL；`ranges` 为 false 时忽略 Range 头
+    fn serve(body: Vec<u8>, ranges: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let body = Arc::new(body);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let body = body.clone();
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut offset = 0usize;
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                        if let Some(v) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                            offset = v.trim().trim_end_matches('-').parse().unwrap();
+                        }
+                        line.clear();
+                    }
+                    let head = if ranges && offset > 0 {
+                        format!(
+                            "HTTP/1.0 206 Partial Content\r\nContent-Length: {}\r\n\
+                             Content-Range: bytes {}-{}/{}\r\nAccept-Ranges: bytes\r\n\r\n",
+                            body.len() - offset,
+                            offset,
+                            body.len() - 1,
+                            body.len()
+                        )
+                    } else {
+                        offset = 0;
+                        let accept = if ranges { "Accept-Ranges: bytes\r\n" } else { "" };
+                        format!("HTTP/1.0 200 OK\r\nContent-Length: {}\r\n{}\r\n", body.len(), accept)
+                    };
+                    let _ = stream.write_all(head.as_bytes());
+                    let _ = stream.write_all(&body[offset..]);
+                });
+            }
+        });
+        format!("http://{}/music/test.flac?token=1", addr)
+    }
+
+    #[test]
+    fn test_url_helpers() {
+        assert!(is_url(Path::new("http://host/a.flac")));
+        assert!(is_url(Path::new("HTTPS://host/a.flac")));
+        assert!(!is_url(Path::new("/music/http.flac")));
+        assert_eq!(url_extension("http://h/dir/a.FLAC?v=2").as_deref(), Some("FLAC"));
+        assert_eq!(url_extension("https://h:8443/a.m4a#t=10").as_deref(), Some("m4a"));
+        assert_eq!(url_extension("http://h/stream"), None);
+        assert_eq!(url_extension("http://h"), None);
+
+        // https 走 TLS：本地端口已关闭，失败在连接阶段而不是协议不支持
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let error = agent().get(&format!("https://{}/a.flac", addr)).call().unwrap_err();
+        assert_eq!(error.kind(), ureq::ErrorKind::ConnectionFailed);
+    }
+
+    #[test]
+    fn test_http_source_read_and_seek() {
+        let body: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let url = serve(body.clone(), true);
+
+        let mut source = HttpSource::open(&url).unwrap();
+        assert!(source.is_seekable());
+        assert_eq!(source.byte_len(), Some(body.len() as u64));
+
+        let mut head = [0u8; 1000];
+        source.read_exact(&mut head).unwrap();
+        assert_eq!(head[..], body[..1000]);
+
+        // 向后 seek：重新发起 Range 请求
+        source.seek(SeekFrom::Start(10)).unwrap();
+        source.read_exact(&mut head[..10]).unwrap();
+        assert_eq!(head[..10], body[10..20]);
+
+        // 向前 seek 与从末尾 seek
+        source.seek(SeekFrom::Current(150_000)).unwrap();
+        source.read_exact(&mut head[..10]).unwrap();
+        assert_eq!(head[..10], body[150_020..150_030]);
+        source.seek(SeekFrom::End(-5)).unwrap();
+        let mut tail = Vec::new();
+        source.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, body[body.len() - 5..]);
+
+        source.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(source.read(&mut head).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_decode_over_http() {
+        let flac = make_flac(44100, 2, 44100);
+        let url = serve(flac.clone(), true);
+
+        let mut decoder = super::super::AudioDecoder::open(&url).unwrap();
+        let info = decoder.info().clone();
+        assert_eq!(info.format, "FLAC");
+        assert!(info.seekable);
+        assert_eq!(info.total_frames, Some(44100));
+        let head = decoder.decode_next_i32().unwrap();
+        let expected: Vec<i32> = (0..head.len() as i32).map(|i| (i % 1000) << 16).collect();
+        assert_eq!(head, expected);
+
+        decoder.seek(0.5).unwrap();
+        let mut total = 0;
+        loop {
+            let samples = decoder.decode_next_i32().unwrap();
+            if samples.is_empty() {
+                break;
+            }
+            total += samples.len();
+        }
+        assert_eq!(total, 22050 * 2);
+
+        // 服务器不支持 Range：按流播放，不可 seek
+        let url = serve(flac, false);
+        let mut decoder = super::super::AudioDecoder::open(&url).unwrap();
+        assert!(!decoder.info().seekable);
+        assert!(decoder.seek(0.5).is_err());
+        assert!(!decoder.decode_next_i32().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+//! 声道布局（各声道的扬声器角色）
+//!
+//! WAV（WAVE_FORMAT_EXTENSIBLE 的 channel mask）、FLAC 等容器会声明声道布局，
+//! 同样是 4 声道，四声道环绕（4.0）与 3.1 的声道含义完全不同。
+//! 这里把 symphonia 的 `Channels` 位掩码映射为内部角色，按交错顺序排列；
+//! 容器未声明时按声道数套用 WAV 默认的位置顺序。
+
+use symphonia::core::audio::Channels;
+
+/// 单个声道的扬声器角色
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelRole {
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    Lfe,
+    RearLeft,
+    RearRight,
+    RearCenter,
+    SideLeft,
+    SideRight,
+    /// 前置中左/中右、顶部、宽声道等（不参与常规环绕布局）
+    Other,
+}
+
+impl ChannelRole {
+    /// 简写（界面显示）
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::FrontLeft => "L",
+            Self::FrontRight => "R",
+            Self::FrontCenter => "C",
+            Self::Lfe => "LFE",
+            Self::RearLeft => "Lr",
+            Self::RearRight => "Rr",
+            Self::RearCenter => "Cr",
+            Self::SideLeft => "Ls",
+            Self::SideRight => "Rs",
+            Self::Other => "?",
+        }
+    }
+
+    fn from_channel(channel: Channels) -> Self {
+        if channel == Channels::FRONT_LEFT {
+            Self::FrontLeft
+        } else if channel == Channels::FRONT_RIGHT {
+            Self::FrontRight
+        } else if channel == Channels::FRONT_CENTRE {
+            Self::FrontCenter
+        } else if channel == Channels::LFE1 || channel == Channels::LFE2 {
+            Self::Lfe
+        } else if channel == Channels::REAR_LEFT {
+            Self::RearLeft
+        } else if channel == Channels::REAR_RIGHT {
+            Self::RearRight
+        } else if channel == Channels::REAR_CENTRE {
+            Self::RearCenter
+        } else if channel == Channels::SIDE_LEFT {
+            Self::SideLeft
+        } else if channel == Channels::SIDE_RIGHT {
+            Self::SideRight
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// 容器声明的声道位掩码 → 角色（按交错顺序，即位从低到高）
+///
+/// 单独的 FRONT_LEFT 是 symphonia 对单声道的表示，按中置处理
+pub fn roles_from_channels(channels: Channels) -> Vec<ChannelRole> {
+    if channels == Channels::FRONT_LEFT {
+        return vec![ChannelRole::FrontCenter];
+    }
+    channels.iter().map(ChannelRole::from_channel).collect()
+}
+
+/// 未声明布局时按声道数推定（WAV 默认顺序：L R C LFE Lr Rr ...）
+pub fn positional_roles(count: usize) -> Vec<ChannelRole> {
+    use ChannelRole::*;
+    let roles: &[ChannelRole] = match count {
+        1 => &[FrontCenter],
+        2 => &[FrontLeft, FrontRight],
+        3 => &[FrontLeft, FrontRight, FrontCenter],
+        4 => &[FrontLeft, FrontRight, RearLeft, RearRight],
+        5 => &[FrontLeft, FrontRight, FrontCenter, RearLeft, RearRight],
+        6 => &[FrontLeft, FrontRight, FrontCenter, Lfe, RearLeft, RearRight],
+        7 => &[
+            FrontLeft,
+            FrontRight,
+            FrontCenter,
+            Lfe,
+            RearCenter,
+            SideLeft,
+            SideRight,
+        ],
+        8 => &[
+            FrontLeft,
+            FrontRight,
+            FrontCenter,
+            Lfe,
+            RearLeft,
+            RearRight,
+            SideLeft,
+            SideRight,
+        ],
+        _ => &[],
+    };
+    if roles.len() == count {
+        roles.to_vec()
+    } else {
+        vec![Other; count]
+    }
+}
+
+/// 布局简称（如 "2.0"、"4.0"、"3.1"、"5.1"）
+pub fn layout_name(roles: &[ChannelRole]) -> String {
+    let lfe = roles.iter().filter(|&&r| r == ChannelRole::Lfe).count();
+    format!("{}.{}", roles.len() - lfe, lfe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ChannelRole::*;
+
+    #[test]
+    fn test_roles_from_channels() {
+        // 3.1：L R C LFE
+        let mask =
+            Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE | Channels::LFE1;
+        let roles = roles_from_channels(mask);
+        assert_eq!(roles, [FrontLeft, FrontRight, FrontCenter, Lfe]);
+        assert_eq!(layout_name(&roles), "3.1");
+
+        // 四声道环绕：声道数相同，角色不同
+        let quad = Channels::FRONT_LEFT
+            | Channels::FRONT_RIGHT
+            | Channels::REAR_LEFT
+            | Channels::REAR_RIGHT;
+        let roles = roles_from_channels(quad);
+        assert_eq!(roles, [FrontLeft, FrontRight, RearLeft, RearRight]);
+        assert_eq!(layout_name(&roles), "4.0");
+
+        // 5.1(side)：环绕声道在侧面
+        let side = Channels::FRONT_LEFT
+            | Channels::FRONT_RIGHT
+            | Channels::FRONT_CENTRE
+            | Channels::LFE1
+            | Channels::SIDE_LEFT
+            | Channels::SIDE_RIGHT;
+        assert_eq!(
+            roles_from_channels(side),
+            [FrontLeft, FrontRight, FrontCenter, Lfe, SideLeft, SideRight]
+        );
+
+        assert_eq!(roles_from_channels(Channels::FRONT_LEFT), [FrontCenter]);
+        assert_eq!(roles_from_channels(Channels::TOP_CENTRE), [Other]);
+    }
+
+    #[test]
+    fn test_positional_roles() {
+        assert_eq!(positional_roles(2), [FrontLeft, FrontRight]);
+        assert_eq!(layout_name(&positional_roles(6)), "5.1");
+        assert_eq!(layout_name(&positional_roles(8)), "7.1");
+        assert_eq!(positional_roles(10), [Other; 10]);
+    }
+}
@@ -1,5 +1,116 @@
 //! 音频解码模块
 
+pub mod cue;
 pub mod decoder;
+pub mod follow;
+pub mod http;
+pub mod layout;
+pub mod pcm;
+pub mod scan;
 
-pub use decoder::{AudioDecoder, AudioInfo, DecodeError, DecoderIterator};
+pub use decoder::{is_stdin_path, AudioDecoder, AudioInfo, DecodeError, DecoderIterator};
+pub use pcm::RawPcmFormat;
+
+/// 生成 16-bit PCM WAV 字节流（测试用，样本为 0..1000 锯齿）
+#[cfg(test)]
+pub(crate) fn make_wav(sample_rate: u32, channels: u16, frames: u32) -> Vec<u8> {
+    let data_len = frames * channels as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+    wav.extend_from_slice(&(channels * 2).to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for i in 0..frames * channels as u32 {
+        wav.extend_from_slice(&((i % 1000) as i16).to_le_bytes());
+    }
+    wav
+}
+
+/// 生成 16-bit FLAC 字节流（测试用，VERBATIM 子帧，样本同 `make_wav`）
+#[cfg(test)]
+pub(crate) fn make_flac(sample_rate: u32, channels: u16, frames: u32) -> Vec<u8> {
+    make_flac_with_comments(sample_rate, channels, frames, &[])
+}
+
+/// 同 `make_flac`，另写入 Vorbis 注释块（如 `iTunSMPB=...`；每 4096 帧一块）
+#[cfg(test)]
+pub(crate) fn make_flac_with_comments(
+    sample_rate: u32,
+    channels: u16,
+    frames: u32,
+    comments: &[&str],
+) -> Vec<u8> {
+    const BLOCK: u32 = 4096;
+
+    fn crc8(data: &[u8]) -> u8 {
+        data.iter().fold(0, |crc, &b| {
+            (0..8).fold(crc ^ b, |c, _| if c & 0x80 != 0 { (c << 1) ^ 0x07 } else { c << 1 })
+        })
+    }
+    fn crc16(data: &[u8]) -> u16 {
+        data.iter().fold(0, |crc, &b| {
+            (0..8).fold(crc ^ (b as u16) << 8, |c, _| {
+                if c & 0x8000 != 0 { (c << 1) ^ 0x8005 } else { c << 1 }
+            })
+        })
+    }
+
+    let mut flac = b"fLaC".to_vec();
+    // STREAMINFO（无注释时为唯一的元数据块）：帧长与 MD5 未知
+    flac.push(if comments.is_empty() { 0x80 } else { 0x00 });
+    flac.extend_from_slice(&[0, 0, 34]);
+    flac.extend_from_slice(&(BLOCK as u16).to_be_bytes());
+    flac.extend_from_slice(&(BLOCK as u16).to_be_bytes());
+    flac.extend_from_slice(&[0; 6]);
+    let packed = (sample_rate as u64) << 44
+        | (channels as u64 - 1) << 41
+        | 15 << 36
+        | frames as u64;
+    flac.extend_from_slice(&packed.to_be_bytes());
+    flac.extend_from_slice(&[0; 16]);
+
+    if !comments.is_empty() {
+        // VORBIS_COMMENT（最后一个元数据块）：长度为小端，块头长度为大端 24 位
+        let vendor = b"roger";
+        let mut body = (vendor.len() as u32).to_le_bytes().to_vec();
+        body.extend_from_slice(vendor);
+        body.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+        for comment in comments {
+            body.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            body.extend_from_slice(comment.as_bytes());
+        }
+        flac.push(0x84);
+        flac.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        flac.extend_from_slice(&body);
+    }
+
+    for (index, start) in (0..frames).step_by(BLOCK as usize).enumerate() {
+        let block = BLOCK.min(frames - start);
+        let frame_start = flac.len();
+        // 固定块长；块长取帧头末尾的 16 位，采样率取 STREAMINFO；独立声道，16-bit
+        flac.extend_from_slice(&[0xFF, 0xF8, 0x70, (channels as u8 - 1) << 4 | 0x08]);
+        let mut utf8 = [0; 4];
+        let number = char::from_u32(index as u32).unwrap().encode_utf8(&mut utf8);
+        flac.extend_from_slice(number.as_bytes());
+        flac.extend_from_slice(&((block - 1) as u16).to_be_bytes());
+        flac.push(crc8(&flac[frame_start..]));
+        for ch in 0..channels as u32 {
+            flac.push(0x02); // VERBATIM
+            for frame in start..start + block {
+                let i = frame * channels as u32 + ch;
+                flac.extend_from_slice(&((i % 1000) as i16).to_be_bytes());
+            }
+        }
+        let crc = crc16(&flac[frame_start..]);
+        flac.extend_from_slice(&crc.to_be_bytes());
+    }
+    flac
+}
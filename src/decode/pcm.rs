@@ -0,0 +1,237 @@
+//! 原始 PCM（无文件头）读取器
+//!
+//! symphonia 无法探测无头 PCM，`.pcm` 文件按用户指定的格式直接解释：
+//! 有符号整数（默认 little-endian），交错排列，通过 `AudioFormat::bytes_to_samples`
+//! 转换到 i32 左对齐（与其他整数源一样保持 bit-perfect）
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::decoder::DecodeError;
+use crate::audio::{AudioFormat, Endian};
+
+/// 每次读取的帧数
+const FRAMES_PER_READ: usize = 4096;
+
+/// 原始 PCM 格式参数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawPcmFormat {
+    /// 采样率
+    pub sample_rate: u32,
+    /// 声道数
+    pub channels: u16,
+    /// 位深度（16/24/32）
+    pub bits_per_sample: u16,
+    /// 字节序（AIFF 数据块为 big-endian）
+    pub endian: Endian,
+}
+
+impl Default for RawPcmFormat {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44100,
+            channels: 2,
+            bits_per_sample: 16,
+            endian: Endian::Little,
+        }
+    }
+}
+
+impl RawPcmFormat {
+    /// 编解码器标签（如 `pcm_s24be`），字节序取自格式参数
+    pub fn codec_name(&self) -> String {
+        let endian = match self.endian {
+            Endian::Little => "le",
+            Endian::Big => "be",
+        };
+        format!("pcm_s{}{}", self.bits_per_sample, endian)
+    }
+
+    /// 码率（bps），按 u64 计算，超出 u32 时为 None
+    pub fn bitrate_bps(&self) -> Option<u32> {
+        let bps =
+            self.sample_rate as u64 * self.channels as u64 * self.bits_per_sample as u64;
+        u32::try_from(bps).ok()
+    }
+}
+
+/// 原始 PCM 读取器
+pub struct RawPcmReader {
+    file: File,
+    format: AudioFormat,
+    /// 字节缓冲区（预分配，避免播放时分配）
+    byte_buffer: Vec<u8>,
+    /// 上次读取剩余的不完整帧字节数（位于 byte_buffer 开头）
+    carry: usize,
+    /// 总帧数
+    total_frames: u64,
+}
+
+impl RawPcmReader {
+    /// 打开原始 PCM 文件
+    pub fn open<P: AsRef<Path>>(path: P, pcm: RawPcmFormat) -> Result<Self, DecodeError> {
+        if pcm.sample_rate == 0 || pcm.channels == 0 {
+            return Err(DecodeError::UnsupportedFormat);
+        }
+        if !matches!(pcm.bits_per_sample, 16 | 24 | 32) {
+            return Err(DecodeError::UnsupportedFormat);
+        }
+
+        let file = File::open(path).map_err(DecodeError::FileOpen)?;
+        let len = file.metadata().map_err(DecodeError::FileOpen)?.len();
+
+        let format = AudioFormat::new(pcm.sample_rate, pcm.channels, pcm.bits_per_sample)
+            .with_endian(pcm.endian);
+        let bytes_per_frame = format.bytes_per_frame();
+
+        Ok(Self {
+            file,
+            format,
+            byte_buffer: vec![0u8; FRAMES_PER_READ * bytes_per_frame],
+            carry: 0,
+            total_frames: len / bytes_per_frame as u64,
+        })
+    }
+
+    /// 音频格式
+    pub fn format(&self) -> &AudioFormat {
+        &self.format
+    }
+
+    /// 总帧数
+    pub fn total_frames(&self) -> u64 {
+        self.total_frames
+    }
+
+    /// 读取下一块样本到 output（按需调整长度），返回样本数，0 表示 EOF
+    ///
+    /// 只返回完整帧；文件末尾不足一帧的字节被丢弃
+    pub fn read_samples(&mut self, output: &mut Vec<i32>) -> Result<usize, DecodeError> {
+        let bytes_per_frame = self.format.bytes_per_frame();
+        let mut filled = self.carry;
+
+        // 尽量填满缓冲区（read 可能只返回部分数据）
+        while filled < self.byte_buffer.len() {
+            match self.file.read(&mut self.byte_buffer[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(DecodeError::DecodeFailed(e.to_string())),
+            }
+        }
+
+        let usable = filled - filled % bytes_per_frame;
+        let samples = usable / self.format.bytes_per_sample();
+        if output.len() < samples {
+            output.resize(samples, 0);
+        }
+        self.format
+            .bytes_to_samples(&self.byte_buffer[..usable], &mut output[..samples]);
+
+        // 保留不完整帧，下次读取时拼接
+        self.carry = filled - usable;
+        self.byte_buffer.copy_within(usable..filled, 0);
+
+        Ok(samples)
+    }
+
+    /// Seek 到指定帧
+    pub fn seek_frame(&mut self, frame: u64) -> Result<(), DecodeError> {
+        let frame = frame.min(self.total_frames);
+        let offset = frame * self.format.bytes_per_frame() as u64;
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| DecodeError::SeekFailed(e.to_string()))?;
+        self.carry = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("roger_{}_{}.pcm", name, std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_read_s24_stereo() {
+        // 2 帧 24-bit 立体声 + 1 个多余字节（不完整帧应被丢弃）
+        let bytes = [
+            0x01, 0x00, 0x00, 0xFF, 0xFF, 0xFF, // 1, -1
+            0x00, 0x00, 0x80, 0xFF, 0xFF, 0x7F, // min, max
+            0xAA,
+        ];
+        let path = write_temp("s24", &bytes);
+        let pcm = RawPcmFormat {
+            sample_rate: 48000,
+            channels: 2,
+            bits_per_sample: 24,
+            endian: Endian::Little,
+        };
+        let mut reader = RawPcmReader::open(&path, pcm).unwrap();
+        assert_eq!(reader.total_frames(), 2);
+
+        let mut out = Vec::new();
+        assert_eq!(reader.read_samples(&mut out).unwrap(), 4);
+        assert_eq!(&out[..4], &[1 << 8, -1 << 8, i32::MIN, 0x7FFFFF << 8]);
+        assert_eq!(reader.read_samples(&mut out).unwrap(), 0);
+
+        reader.seek_frame(1).unwrap();
+        assert_eq!(reader.read_samples(&mut out).unwrap(), 2);
+        assert_eq!(out[0], i32::MIN);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_read_s24_big_endian() {
+        let bytes = [
+            0x00, 0x00, 0x01, 0xFF, 0xFF, 0xFF, // 1, -1
+            0x80, 0x00, 0x00, 0x7F, 0xFF, 0xFF, // min, max
+        ];
+        let path = write_temp("s24be", &bytes);
+        let pcm = RawPcmFormat {
+            sample_rate: 48000,
+            channels: 2,
+            bits_per_sample: 24,
+            endian: Endian::Big,
+        };
+        let mut reader = RawPcmReader::open(&path, pcm).unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(reader.read_samples(&mut out).unwrap(), 4);
+        assert_eq!(&out[..4], &[1 << 8, -1 << 8, i32::MIN, 0x7FFFFF << 8]);
+        assert_eq!(pcm.codec_name(), "pcm_s24be");
+        assert_eq!(RawPcmFormat::default().codec_name(), "pcm_s16le");
+        assert_eq!(pcm.bitrate_bps(), Some(48000 * 2 * 24));
+        // 高采样率多声道不溢出
+        let wide = RawPcmFormat {
+            sample_rate: 768000,
+            channels: 256,
+            bits_per_sample: 32,
+            endian: Endian::Little,
+        };
+        assert_eq!(wide.bitrate_bps(), None);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_reject_unsupported_bits() {
+        let path = write_temp("bits", &[0u8; 8]);
+        let pcm = RawPcmFormat {
+            bits_per_sample: 20,
+            ..Default::default()
+        };
+        assert!(matches!(
+            RawPcmReader::open(&path, pcm),
+            Err(DecodeError::UnsupportedFormat)
+        ));
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,232 @@
+//! 目录扫描（构建播放列表）
+//!
+//! 文件名按自然顺序排序（"2" 在 "10" 之前）；递归模式下先列出本目录文件，
+//! 再按自然顺序进入子目录（Disc 1 在 Disc 2 之前），保持每个目录内的顺序。
+
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 支持的音频文件扩展名
+///
+/// CLI 与 TUI 共用的唯一列表（参数检查、目录扫描、帮助输出），
+/// 与 Cargo.toml 中启用的 symphonia 格式特性及原始 PCM 解码保持一致；
+/// 新增格式只改这里
+pub const AUDIO_EXTENSIONS: &[&str] = &["flac", "wav", "aiff", "aif", "mp3", "m4a", "alac", "pcm"];
+
+/// 检查文件是否为支持的音频格式
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// 自然排序比较：连续数字按数值比较，其余字符忽略大小写
+///
+/// 忽略大小写后相同的名字再按原始字节比较，保证排序稳定
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut ai, mut bi) = (a.char_indices().peekable(), b.char_indices().peekable());
+    loop {
+        let (ca, cb) = match (ai.peek(), bi.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(&(_, ca)), Some(&(_, cb))) => (ca, cb),
+        };
+
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let na = take_digits(a, &mut ai);
+            let nb = take_digits(b, &mut bi);
+            // 去掉前导零后先比位数，再逐位比较（不受 u64 溢出限制）
+            let (ta, tb) = (na.trim_start_matches('0'), nb.trim_start_matches('0'));
+            let ord = ta.len().cmp(&tb.len()).then_with(|| ta.cmp(tb));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        } else {
+            let ord = ca.to_lowercase().cmp(cb.to_lowercase());
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            ai.next();
+            bi.next();
+        }
+    }
+}
+
+/// 读取从当前位置开始的连续数字
+fn take_digits<'a>(
+    s: &'a str,
+    iter: &mut std::iter::Peekable<std::str::CharIndices<'a>>,
+) -> &'a str {
+    let start = iter.peek().map(|&(i, _)| i).unwrap_or(s.len());
+    let mut end = start;
+    while let Some(&(i, c)) = iter.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        end = i + c.len_utf8();
+        iter.next();
+    }
+    &s[start..end]
+}
+
+/// 按文件名自然排序
+fn sort_by_name(paths: &mut [PathBuf]) {
+    paths.sort_by(|a, b| {
+        let a = a.file_name().unwrap_or_default().to_string_lossy();
+        let b = b.file_name().unwrap_or_default().to_string_lossy();
+        natural_cmp(&a, &b)
+    });
+}
+
+/// 扫描目录中的音频文件
+///
+/// `recursive` 为 true 时遍历子目录（不跟随目录符号链接，避免循环）
+pub fn scan_audio_files(dir: &Path, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut subdirs = Vec::new();
+
+    for entry in std::fs::read_dir(dir)?.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if recursive && entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+            subdirs.push(path);
+        } else if path.is_file() && is_audio_file(&path) {
+            files.push(path);
+        }
+    }
+
+    sort_by_name(&mut files);
+    sort_by_name(&mut subdirs);
+    for subdir in subdirs {
+        // 子目录不可读时跳过，不影响其余曲目
+        match scan_audio_files(&subdir, true) {
+            Ok(mut nested) => files.append(&mut nested),
+            Err(e) => log::warn!("Skipping {}: {}", subdir.display(), e),
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "10 - Ten.flac",
+            "2 - Two.flac",
+            "1 - One.flac",
+            "track02.flac",
+            "Track1.flac",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "1 - One.flac",
+                "2 - Two.flac",
+                "10 - Ten.flac",
+                "Track1.flac",
+                "track02.flac"
+            ]
+        );
+
+        assert_eq!(natural_cmp("Disc 2", "Disc 10"), Ordering::Less);
+        assert_eq!(natural_cmp("a007", "a7"), Ordering::Less);
+        assert_eq!(natural_cmp("abc", "abc"), Ordering::Equal);
+        assert_eq!(natural_cmp("abc", "abcd"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_is_audio_file() {
+        // 扩展名不区分大小写；只看最后一个扩展名
+        for name in [
+            "a.flac", "B.FLAC", "c.Wav", "d.aif", "e.m4a", "f.alac", "g.pcm", "h.x.mp3",
+        ] {
+            assert!(is_audio_file(Path::new(name)), "{}", name);
+        }
+        for name in [
+            "cover.jpg",
+            "a.flac.bak",
+            "flac",
+            ".flac",
+            "a.",
+            "a.ogg",
+            "a.cue",
+        ] {
+            assert!(!is_audio_file(Path::new(name)), "{}", name);
+        }
+
+        // 目录扫描与单文件检查接受同一组文件
+        let root = std::env::temp_dir().join(format!("roger-ext-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let names = ["1.flac", "2.AIFF", "3.txt", "4.mp3", "5.flac.bak", "6.pcm"];
+        for name in names {
+            std::fs::write(root.join(name), b"").unwrap();
+        }
+        let scanned = scan_audio_files(&root, false).unwrap();
+        let accepted: Vec<PathBuf> = names
+            .iter()
+            .map(|name| root.join(name))
+            .filter(|path| is_audio_file(path))
+            .collect();
+        assert_eq!(scanned, accepted);
+        assert_eq!(scanned.len(), 4);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_recursive() {
+        let root = std::env::temp_dir().join(format!("roger-scan-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["Disc 10", "Disc 2", "Disc 1"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "Disc 1/10.flac",
+            "Disc 1/2.flac",
+            "Disc 2/1.flac",
+            "Disc 10/1.flac",
+            "Disc 10/cover.jpg",
+            "booklet.wav",
+        ] {
+            std::fs::write(root.join(file), b"").unwrap();
+        }
+
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(&root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            names(scan_audio_files(&root, false).unwrap()),
+            vec!["booklet.wav"]
+        );
+        assert_eq!(
+            names(scan_audio_files(&root, true).unwrap()),
+            vec![
+                "booklet.wav",
+                "Disc 1/2.flac",
+                "Disc 1/10.flac",
+                "Disc 2/1.flac",
+                "Disc 10/1.flac"
+            ]
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
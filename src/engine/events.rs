@@ -0,0 +1,85 @@
+//! 引擎事件流
+//!
+//! 嵌入方（GUI 等）通过 `Engine::events()` 订阅状态变化，无需轮询。
+//! 事件在控制线程或解码线程上发出，从不在 IO 回调中发送；
+//! 没有订阅者时不创建通道，发送为空操作
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+use super::StopReason;
+use crate::decode::AudioInfo;
+
+/// 引擎事件
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    /// 曲目开始解码（输出已启动）
+    TrackStarted(AudioInfo),
+    /// 曲目解码完毕且缓冲区已排空
+    TrackFinished,
+    /// 播放停止（设备断开为 `StopReason::DeviceLost`）
+    Stopped(StopReason),
+    /// 出现新的 underrun（本曲累计次数）
+    Underrun { total: u64 },
+    /// 中途解码失败（之后按曲目结束处理）
+    DecodeFailed(String),
+    /// 解码线程写出的数据是否与源逐位一致（软件处理开启/关闭时变化）
+    ///
+    /// 只反映解码端的处理，不含 CoreAudio SRC 等输出端因素
+    BitPerfectChanged(bool),
+}
+
+/// 事件发送端（订阅前为空）
+#[derive(Default)]
+pub(crate) struct EventSink {
+    sender: Mutex<Option<Sender<EngineEvent>>>,
+}
+
+impl EventSink {
+    /// 创建新通道并返回接收端（替换之前的订阅者）
+    pub(crate) fn subscribe(&self) -> Receiver<EngineEvent> {
+        let (tx, rx) = mpsc::channel();
+        *self.sender.lock().unwrap_or_else(|e| e.into_inner()) = Some(tx);
+        rx
+    }
+
+    /// 发送事件；接收端已丢弃时关闭通道
+    pub(crate) fn emit(&self, event: EngineEvent) {
+        let mut sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(tx) = sender.as_ref() {
+            if tx.send(event).is_err() {
+                *sender = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_sink() {
+        // 未订阅：发送为空操作
+        let sink = EventSink::default();
+        sink.emit(EngineEvent::TrackFinished);
+
+        let rx = sink.subscribe();
+        sink.emit(EngineEvent::Underrun { total: 1 });
+        sink.emit(EngineEvent::Stopped(StopReason::User));
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(EngineEvent::Underrun { total: 1 })
+        ));
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(EngineEvent::Stopped(StopReason::User))
+        ));
+        assert!(rx.try_recv().is_err());
+
+        // 接收端丢弃后自动关闭
+        drop(rx);
+        sink.emit(EngineEvent::TrackFinished);
+        assert!(sink.sender.lock().unwrap().is_none());
+    }
+}
@@ -3,13 +3,29 @@
 //! 整合解码、缓冲、输出各模块
 //! 核心设计：解码线程和输出回调完全解耦，通过 lock-free ring buffer 连接
 
-use std::path::Path;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+mod events;
+
+pub use events::EngineEvent;
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
-use crate::audio::{AudioFormat, AudioOutput, OutputConfig, PlaybackStats, RingBuffer};
-use crate::decode::{AudioDecoder, AudioInfo, DecoderIterator};
+use self::events::EventSink;
+use crate::audio::crossfeed::Crossfeed;
+use crate::audio::limiter::{self, Limiter};
+use crate::audio::loudness::{self, TruePeakMeter};
+use crate::audio::output::{clock_seed, is_cross_family, OutputFormatMode};
+use crate::audio::width::{self, StereoWidth};
+use crate::audio::{
+    AudioFormat, AudioOutput, OutputConfig, OutputError, PlaybackStats, RingBuffer,
+};
+use crate::decode::http;
+use crate::decode::{
+    is_stdin_path, AudioDecoder, AudioInfo, DecodeError, DecoderIterator, RawPcmFormat,
+};
 
 /// 播放状态
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,6 +36,51 @@ pub enum PlaybackState {
     Buffering,
 }
 
+/// 上一次停止的原因
+///
+/// 自动切歌与续播据此区分：正常播完清除续播位置，用户停止则保留
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// 解码到达 EOF 且缓冲区已排空
+    EndOfTrack,
+    /// 用户停止或切歌（曲目未播完）
+    User,
+    /// 解码中途失败
+    Error,
+    /// 输出设备消失（由检测到设备断开的调用方通过 `stop_with_reason` 报告）
+    DeviceLost,
+}
+
+/// 曲目间静音的上限（毫秒）
+pub const MAX_INTER_TRACK_GAP_MS: u32 = 60_000;
+
+/// 声道延迟的上限（样本数，约 85ms @ 48kHz），限制延迟线的内存
+pub const MAX_CHANNEL_DELAY: u32 = 4096;
+
+/// 支持设置延迟的最大声道数
+pub const MAX_DELAY_CHANNELS: usize = 8;
+
+/// Ring buffer 容量下限（样本数）：低于此值几乎必然欠载
+pub const MIN_BUFFER_FRAMES: usize = 1024;
+
+/// Ring buffer 容量上限（样本数，取整后 128MB；约 5.8 分钟 @ 48kHz 立体声）
+pub const MAX_BUFFER_FRAMES: usize = 1 << 25;
+
+/// 音频组亲和性标签（与 IO 线程相同；TUI 线程用 tag 2）
+pub const AUDIO_AFFINITY_TAG: i32 = 1;
+
+/// 上一章：进入当前章节超过该秒数时回到本章开头，否则跳到前一章
+const PREV_CHAPTER_RESTART_SECS: f64 = 3.0;
+
+/// 输出启动后等待首次 IO 回调的时限（超时视为设备静默拒绝了格式）
+const OUTPUT_WATCHDOG_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 排空缓冲区时，缓冲量持续这么久不减少视为输出已不再消费（暂停、设备断开）
+const DRAIN_STALL_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 解码耗时采样间隔：每 N 个读取块计时一次，避免每块都读时钟
+const DECODE_TIMING_INTERVAL: u32 = 16;
+
 /// 引擎配置
 #[derive(Clone, Debug)]
 pub struct EngineConfig {
@@ -28,9 +89,45 @@ pub struct EngineConfig {
     /// Ring buffer 大小（样本数，会被向上取整到 2 的幂）
     /// 越大越稳定，但延迟也越高
     pub buffer_frames: usize,
+    /// 固定缓冲时长（毫秒）：每首曲目按其采样率/声道数重建 ring buffer，
+    /// 使 44.1k 与 384k 缓冲的时长一致（`buffer_frames` 只用于首次创建）
+    pub buffer_ms: Option<u32>,
     /// 预缓冲比例（0.0-1.0）
     /// 开始播放前需要填充到这个比例
     pub prebuffer_ratio: f64,
+    /// 原始 PCM（`.pcm`）文件的格式
+    pub pcm_format: RawPcmFormat,
+    /// 强制的格式提示（如 "flac"），代替文件扩展名用于探测，None 按扩展名
+    pub force_format: Option<String>,
+    /// 多音轨容器播放的音频轨道（从 0 开始，顺序同 `AudioInfo.tracks`），文件中不存在时打开失败
+    pub track_index: usize,
+    /// 解码线程每次读取的帧数
+    ///
+    /// 高采样率（352.8k/384k）下调大可减少每轮循环开销；
+    /// 写入前要求的最小空闲空间按比例随之变化
+    pub decode_chunk_frames: usize,
+    /// 耳机交叉馈送初始强度（0.0-1.0，0 为关闭），运行中可用 `set_crossfeed` 调整
+    pub crossfeed: f64,
+    /// 立体声宽度初始值（M/S，1.0 为不变，0.0 为单声道，最大 2.0），运行中可用 `set_stereo_width` 调整
+    pub stereo_width: f64,
+    /// 峰值限幅器阈值（dBFS，None 为关闭），运行中可用 `set_limiter` 调整
+    pub limiter: Option<f32>,
+    /// 初始是否开启真峰值表（4 倍过采样测量采样点之间的过冲），运行中可用 `set_true_peak_meter` 调整
+    pub true_peak_meter: bool,
+    /// 解码线程在缓冲区满时的等待策略
+    pub wait_strategy: WaitStrategy,
+    /// 解码线程的 macOS 亲和性标签（默认与 IO 线程同组的 tag 1，None 不设置）
+    ///
+    /// 核心少或大小核调度下分组反而有害时可关闭
+    pub thread_affinity: Option<i32>,
+    /// 按音轨格式和来源自动确定缓冲区大小（忽略 `buffer_frames`，见 `auto_buffer_samples`）
+    pub auto_buffer: bool,
+    /// 跟随增长中的文件：读到末尾时等待新数据（见 `decode::follow`）
+    pub follow: bool,
+    /// 自动切歌前插入的静音（毫秒，0 为不插入）
+    ///
+    /// 引擎本身不使用，由播放列表推进逻辑通过 `inter_track_gap` 读取
+    pub inter_track_gap_ms: u32,
 }
 
 impl Default for EngineConfig {
@@ -39,8 +136,177 @@ impl Default for EngineConfig {
             output: OutputConfig::default(),
             // 2秒缓冲 @ 48kHz 立体声
             buffer_frames: 48000 * 2 * 2,
+            buffer_ms: None,
             // 50% 预缓冲
             prebuffer_ratio: 0.5,
+            pcm_format: RawPcmFormat::default(),
+            force_format: None,
+            track_index: 0,
+            follow: false,
+            decode_chunk_frames: 4096,
+            crossfeed: 0.0,
+            stereo_width: 1.0,
+            limiter: None,
+            true_peak_meter: false,
+            wait_strategy: WaitStrategy::Hybrid,
+            thread_affinity: Some(AUDIO_AFFINITY_TAG),
+            auto_buffer: false,
+            inter_track_gap_ms: 0,
+        }
+    }
+}
+
+/// 音频数据来源
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceKind {
+    /// 本地文件
+    Local,
+    /// HTTP 流（网络抖动需要更多余量）
+    Network,
+    /// stdin 管道（上游进程可能间歇停顿）
+    Stdin,
+}
+
+impl SourceKind {
+    /// 按播放路径判断来源
+    pub fn of(path: &Path) -> Self {
+        if is_stdin_path(path) {
+            SourceKind::Stdin
+        } else if http::is_url(path) {
+            SourceKind::Network
+        } else {
+            SourceKind::Local
+        }
+    }
+}
+
+/// 按格式和来源估算合适的缓冲区大小（样本数，未取整到 2 的幂）
+///
+/// 本地文件：≤48k 1 秒，≤96k 1.5 秒，≤192k 2 秒，更高 3 秒（高码率下单次
+/// 读盘/解码停顿占用的时长更长）；网络和管道来源再乘 3 以吸收抖动。
+/// 结果限制在 `MIN_BUFFER_FRAMES..=MAX_BUFFER_FRAMES`
+pub fn auto_buffer_samples(info: &AudioInfo, source: SourceKind) -> usize {
+    let local_ms: u64 = match info.sample_rate {
+        0..=48_000 => 1000,
+        48_001..=96_000 => 1500,
+        96_001..=192_000 => 2000,
+        _ => 3000,
+    };
+    let ms = match source {
+        SourceKind::Local => local_ms,
+        SourceKind::Network | SourceKind::Stdin => local_ms * 3,
+    };
+    let samples = ms * info.sample_rate as u64 * info.channels.max(1) as u64 / 1000;
+    (samples as usize).clamp(MIN_BUFFER_FRAMES, MAX_BUFFER_FRAMES)
+}
+
+/// 指定时长在该格式下对应的缓冲区大小（样本数，未取整到 2 的幂）
+///
+/// 结果限制在 `MIN_BUFFER_FRAMES..=MAX_BUFFER_FRAMES`
+pub fn buffer_samples_for_ms(buffer_ms: u32, info: &AudioInfo) -> usize {
+    let samples = buffer_ms as u64 * info.sample_rate as u64 * info.channels.max(1) as u64 / 1000;
+    (samples as usize).clamp(MIN_BUFFER_FRAMES, MAX_BUFFER_FRAMES)
+}
+
+/// 解码线程在 ring buffer 快满时的等待策略
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WaitStrategy {
+    /// 只自旋/yield，从不睡眠（唤醒最快，但持续占用一个核）
+    Spin,
+    /// 短等待自旋、中等等待 yield、长等待睡眠（默认，按 Apple Silicon 调优）
+    #[default]
+    Hybrid,
+    /// 以睡眠为主（省电，适合笔记本电池供电）
+    Sleep,
+}
+
+/// 一次等待的具体动作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WaitAction {
+    /// 自旋指定次数
+    Spin(u32),
+    /// yield 后自旋指定次数
+    YieldSpin(u32),
+    /// 睡眠（微秒）
+    Sleep(u64),
+}
+
+impl WaitStrategy {
+    /// 按预计等待时间（微秒）选择动作
+    fn action(self, wait_us: u64) -> WaitAction {
+        match self {
+            Self::Spin if wait_us < 50 => WaitAction::Spin(64),
+            Self::Spin => WaitAction::YieldSpin(32),
+            // - < 50µs: 仅自旋（避免 syscall 开销）
+            // - 50-500µs: yield + 短自旋
+            // - > 500µs: 睡眠 70% 的预计时间（留出余量）
+            Self::Hybrid if wait_us < 50 => WaitAction::Spin(64),
+            Self::Hybrid if wait_us < 500 => WaitAction::YieldSpin(32),
+            Self::Hybrid => WaitAction::Sleep((wait_us * 7 / 10).clamp(100, 10_000)),
+            // ring buffer 有数秒余量，按完整预计时间睡眠也不会欠载
+            Self::Sleep => WaitAction::Sleep(wait_us.clamp(500, 20_000)),
+        }
+    }
+}
+
+impl std::str::FromStr for WaitStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "spin" => Ok(Self::Spin),
+            "hybrid" => Ok(Self::Hybrid),
+            "sleep" => Ok(Self::Sleep),
+            _ => Err(format!("unknown wait strategy '{}' (spin, hybrid, sleep)", s)),
+        }
+    }
+}
+
+impl EngineConfig {
+    /// 检查配置是否合理
+    ///
+    /// 在分配 ring buffer 之前调用，把过大/过小的缓冲区转为可读的错误，
+    /// 而不是在分配时耗尽内存或触发 `RingBuffer::new` 的断言
+    pub fn validate(&self) -> Result<(), EngineError> {
+        if !(MIN_BUFFER_FRAMES..=MAX_BUFFER_FRAMES).contains(&self.buffer_frames) {
+            return Err(EngineError::InvalidConfig(format!(
+                "buffer size {} samples is outside {}..={} (check --buffer-ms)",
+                self.buffer_frames, MIN_BUFFER_FRAMES, MAX_BUFFER_FRAMES
+            )));
+        }
+        if !(self.prebuffer_ratio > 0.0 && self.prebuffer_ratio <= 1.0) {
+            return Err(EngineError::InvalidConfig(format!(
+                "prebuffer ratio {} must be in (0, 1]",
+                self.prebuffer_ratio
+            )));
+        }
+        // 立体声读取块须放得进缓冲区；否则空闲空间可能永远达不到写入阈值，解码线程停住
+        if self.decode_chunk_frames.saturating_mul(2) > self.buffer_frames {
+            return Err(EngineError::InvalidConfig(format!(
+                "decode chunk {} frames is too large for a {}-sample buffer, at most {} \
+                 (check --decode-chunk-frames or raise --buffer-ms)",
+                self.decode_chunk_frames,
+                self.buffer_frames,
+                self.buffer_frames / 2
+            )));
+        }
+        if self.inter_track_gap_ms > MAX_INTER_TRACK_GAP_MS {
+            return Err(EngineError::InvalidConfig(format!(
+                "inter-track gap {}ms exceeds {}ms (check --gap)",
+                self.inter_track_gap_ms, MAX_INTER_TRACK_GAP_MS
+            )));
+        }
+        Ok(())
+    }
+
+    /// 自动切到下一首前的静音时长（None 为立即切歌）
+    ///
+    /// 单曲循环（`repeat_track`）重播同一首，不插入间隔
+    pub fn inter_track_gap(&self, repeat_track: bool) -> Option<Duration> {
+        if repeat_track || self.inter_track_gap_ms == 0 {
+            None
+        } else {
+            Some(Duration::from_millis(self.inter_track_gap_ms as u64))
         }
     }
 }
@@ -51,6 +317,8 @@ pub enum EngineError {
     DecodeError(crate::decode::DecodeError),
     OutputError(crate::audio::OutputError),
     InvalidState(&'static str),
+    InvalidArgument(&'static str),
+    InvalidConfig(String),
 }
 
 impl std::fmt::Display for EngineError {
@@ -59,6 +327,8 @@ impl std::fmt::Display for EngineError {
             Self::DecodeError(e) => write!(f, "Decode error: {}", e),
             Self::OutputError(e) => write!(f, "Output error: {}", e),
             Self::InvalidState(s) => write!(f, "Invalid state: {}", s),
+            Self::InvalidArgument(s) => write!(f, "Invalid argument: {}", s),
+            Self::InvalidConfig(s) => write!(f, "Invalid config: {}", s),
         }
     }
 }
@@ -80,14 +350,34 @@ impl From<crate::audio::OutputError> for EngineError {
 /// 播放引擎统计
 #[derive(Debug, Clone)]
 pub struct EngineStats {
-    /// 缓冲区填充比例
+    /// 缓冲区填充比例（相对容量，容量按 2 的幂取整）
     pub buffer_fill_ratio: f64,
+    /// 相对预缓冲目标的填充比例（1.0 = 达到目标，可超过 1.0）
+    pub target_fill_ratio: f64,
     /// Underrun 次数
     pub underrun_count: u64,
+    /// 所有 underrun 累计缺失的音频时长（毫秒）
+    pub underrun_lost_ms: f64,
+    /// 写入 ring buffer 时空间不足的次数
+    pub write_overflow_count: u64,
     /// 已播放样本数
     pub samples_played: u64,
     /// 当前播放时间（秒）
     pub position_secs: f64,
+    /// 缓冲区中的音频时长（毫秒）
+    pub buffered_ms: f64,
+}
+
+/// 交给解码线程的共享对象
+struct DecoderShared {
+    ring_buffer: Arc<RingBuffer<i32>>,
+    stats: Arc<PlaybackStats>,
+    state: Arc<DecoderState>,
+    completion: Arc<TrackCompletion>,
+    prebuffer: Arc<PrebufferSignal>,
+    /// 预缓冲目标（样本数）
+    prebuffer_target: usize,
+    events: Arc<EventSink>,
 }
 
 /// 解码线程共享状态
@@ -100,6 +390,179 @@ struct DecoderState {
     paused: AtomicBool,
     /// 解码是否已到达 EOF
     eof_reached: AtomicBool,
+    /// 最后的样本是否已由设备播出（缓冲区排空且设备缓冲中的在途帧已播完）
+    device_drained: AtomicBool,
+    /// 输出的 IO 周期（帧），每次回调推进一个周期；0 为未知
+    output_period_frames: AtomicU32,
+    /// 样本被回调取走后到发声的延迟（帧，IO 缓冲 + 安全偏移 + 设备延迟）
+    output_tail_frames: AtomicU32,
+    /// 是否有待处理的 seek 请求（解码线程处理完后清除）
+    seek_pending: AtomicBool,
+    /// seek 目标时间（f64 秒的位表示）
+    seek_target: AtomicU64,
+    /// 最近一次 seek 是否失败
+    seek_failed: AtomicBool,
+    /// 解码线程是否以实时调度策略运行
+    realtime_active: AtomicBool,
+    /// 已写入 ring buffer 的样本数（用于估算解码吞吐）
+    samples_decoded: AtomicU64,
+    /// 采样块的累计解码耗时（纳秒）
+    decode_nanos: AtomicU64,
+    /// 采样块的累计解码帧数（与 decode_nanos 对应）
+    decode_frames: AtomicU64,
+    /// 反转极性（所有声道取反），跨曲目保持
+    invert_polarity: AtomicBool,
+    /// 交换左右声道，跨曲目保持
+    swap_channels: AtomicBool,
+    /// 单声道监听（L+R 平均后送回两个声道），跨曲目保持
+    mono_monitor: AtomicBool,
+    /// 软件增益（线性倍数的 f64 位表示，1.0 为直通），用于响度归一化
+    gain_bits: AtomicU64,
+    /// 耳机交叉馈送强度（0.0-1.0 的 f64 位表示，0 为直通），仅立体声生效
+    crossfeed_bits: AtomicU64,
+    /// 立体声宽度（f64 位表示，1.0 为直通），仅立体声生效
+    stereo_width_bits: AtomicU64,
+    /// 是否开启峰值限幅器（关闭时完全旁路）
+    limiter_enabled: AtomicBool,
+    /// 限幅阈值（dBFS 的 f32 位表示）
+    limiter_threshold_bits: AtomicU32,
+    /// 最近一块的最大衰减量（dB 的 f32 位表示），由解码线程写入
+    limiter_reduction_bits: AtomicU32,
+    /// 是否开启真峰值表（关闭时不做过采样）
+    true_peak_enabled: AtomicBool,
+    /// 本曲真峰值（线性 f64 位表示，0 为未测得），由解码线程写入
+    true_peak_bits: AtomicU64,
+    /// 每声道延迟（样本数，0 为直通），跨曲目保持
+    channel_delays: [AtomicU32; MAX_DELAY_CHANNELS],
+    /// 延迟设置已变更，解码线程重新加载并清空延迟线
+    delays_changed: AtomicBool,
+    /// 解码中途失败（损坏文件等），曲目按结束处理但可与正常 EOF 区分
+    error_occurred: AtomicBool,
+    /// 解码失败的错误信息（仅在出错时写入，不在热路径上）
+    last_error: Mutex<Option<String>>,
+}
+
+impl DecoderState {
+    /// 记录解码失败
+    fn set_error(&self, message: String) {
+        *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(message);
+        self.error_occurred.store(true, Ordering::Release);
+    }
+
+    /// 清除解码失败状态（新曲目或 seek 后）
+    fn clear_error(&self) {
+        self.error_occurred.store(false, Ordering::Release);
+        *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// 曲目完成回调
+type TrackFinishedCallback = Box<dyn Fn() + Send>;
+
+/// 曲目完成通知
+///
+/// 由解码线程在 EOF 且缓冲区排空后触发。
+/// 不在 IO 回调路径上，可以使用 Mutex/Condvar
+struct TrackCompletion {
+    /// 当前曲目是否已播放完毕
+    finished: Mutex<bool>,
+    /// 完成时唤醒 `Engine::wait_finished` 的等待者
+    cond: Condvar,
+    /// 完成回调（跨曲目保留）
+    callback: Mutex<Option<TrackFinishedCallback>>,
+}
+
+impl TrackCompletion {
+    fn new() -> Self {
+        Self {
+            finished: Mutex::new(false),
+            cond: Condvar::new(),
+            callback: Mutex::new(None),
+        }
+    }
+
+    /// 重置完成标志（开始播放或 seek 时调用）
+    fn reset(&self) {
+        *self.finished.lock().unwrap_or_else(|e| e.into_inner()) = false;
+    }
+
+    /// 调用回调，然后标记完成并唤醒所有等待者
+    fn complete(&self) {
+        if let Some(ref callback) = *self.callback.lock().unwrap_or_else(|e| e.into_inner()) {
+            callback();
+        }
+        *self.finished.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.cond.notify_all();
+    }
+
+    /// 等待完成，返回是否在超时前完成
+    fn wait(&self, timeout: Option<Duration>) -> bool {
+        let finished = self.finished.lock().unwrap_or_else(|e| e.into_inner());
+        let finished = match timeout {
+            None => self
+                .cond
+                .wait_while(finished, |f| !*f)
+                .unwrap_or_else(|e| e.into_inner()),
+            Some(timeout) => {
+                self.cond
+                    .wait_timeout_while(finished, timeout, |f| !*f)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0
+            }
+        };
+        *finished
+    }
+}
+
+/// 预缓冲完成信号
+///
+/// 解码线程在缓冲区首次达到预缓冲目标（或曲目提前结束）时置位，
+/// 唤醒 `Engine::wait_prebuffered` 的等待者
+struct PrebufferSignal {
+    reached: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl PrebufferSignal {
+    fn new() -> Self {
+        Self {
+            reached: Mutex::new(false),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// 重置（启动解码线程、停止时调用）
+    fn reset(&self) {
+        *self.reached.lock().unwrap_or_else(|e| e.into_inner()) = false;
+    }
+
+    /// 标记已达到目标并唤醒所有等待者
+    fn signal(&self) {
+        *self.reached.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.cond.notify_all();
+    }
+
+    fn is_reached(&self) -> bool {
+        *self.reached.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 等待置位，返回是否在超时前达到
+    fn wait(&self, timeout: Option<Duration>) -> bool {
+        let reached = self.reached.lock().unwrap_or_else(|e| e.into_inner());
+        let reached = match timeout {
+            None => self
+                .cond
+                .wait_while(reached, |r| !*r)
+                .unwrap_or_else(|e| e.into_inner()),
+            Some(timeout) => {
+                self.cond
+                    .wait_timeout_while(reached, timeout, |r| !*r)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0
+            }
+        };
+        *reached
+    }
 }
 
 /// 播放引擎
@@ -111,14 +574,266 @@ pub struct Engine {
     output: Option<AudioOutput>,
     decoder_thread: Option<JoinHandle<()>>,
     decoder_state: Arc<DecoderState>,
+    completion: Arc<TrackCompletion>,
+    prebuffer: Arc<PrebufferSignal>,
+    /// 解码线程启动时间（用于预缓冲 ETA）
+    decode_started: Option<Instant>,
     current_info: Option<AudioInfo>,
     current_format: Option<AudioFormat>,
+    /// 格式协商结果（start 后计算一次）
+    summary: Option<PlaybackSummary>,
+    /// 后台预打开的下一首
+    prefetch: Option<Prefetch>,
+    /// 跟随模式的等待开关（stop 时清除，结束读取端的等待）
+    follow_active: Arc<AtomicBool>,
+    /// 上一次停止的原因（尚未停止过时为 None）
+    last_stop_reason: Option<StopReason>,
+    /// 事件订阅（跨曲目保留）
+    events: Arc<EventSink>,
+}
+
+/// 下一首预打开状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrefetchStatus {
+    /// 仍在 probe
+    Pending,
+    /// 已打开，切歌时直接使用
+    Ready,
+    /// 打开失败（错误信息）
+    Failed(String),
+}
+
+/// 后台预打开的解码器
+struct Prefetch {
+    path: PathBuf,
+    rx: mpsc::Receiver<Result<AudioDecoder, DecodeError>>,
+    result: Option<Result<AudioDecoder, DecodeError>>,
+}
+
+impl Prefetch {
+    /// 非阻塞地收取预打开结果
+    fn poll(&mut self) {
+        if self.result.is_none() {
+            match self.rx.try_recv() {
+                Ok(result) => self.result = Some(result),
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.result =
+                        Some(Err(DecodeError::DecoderCreation("prefetch thread exited".into())));
+                }
+            }
+        }
+    }
+
+    /// 取出结果（尚未完成时等待 probe 结束，不会比重新打开更慢）
+    fn into_result(self) -> Result<AudioDecoder, DecodeError> {
+        match self.result {
+            Some(result) => result,
+            None => self.rx.recv().unwrap_or_else(|_| {
+                Err(DecodeError::DecoderCreation("prefetch thread exited".into()))
+            }),
+        }
+    }
+}
+
+/// 格式协商完成后的信号路径摘要
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaybackSummary {
+    /// 源格式名称（如 FLAC）
+    pub source_format: String,
+    /// 源采样率
+    pub source_rate: u32,
+    /// 源位深（有损格式未知）
+    pub source_bits: Option<u32>,
+    /// 设备采样率
+    pub device_rate: u32,
+    /// 输出样本格式
+    pub output_mode: OutputFormatMode,
+    /// 输出位深（设置物理格式时为设备原生位深）
+    pub output_bits: u16,
+    /// 是否 HAL 直接输出（否则经系统混音器）
+    pub hal: bool,
+    /// 是否独占设备
+    pub exclusive: bool,
+    /// 是否需要 CoreAudio 采样率转换
+    pub resampled: bool,
+    /// HAL 启动后回调从不触发，已回退到系统混音器
+    pub fell_back: bool,
+    /// 是否裁剪了编码器延迟/填充（gapless），seek 位置按裁剪后的时间轴计算
+    pub gapless: bool,
+}
+
+impl PlaybackSummary {
+    /// 由源信息与已启动的输出状态构造
+    pub fn new(
+        info: &AudioInfo,
+        device_format: AudioFormat,
+        output_mode: OutputFormatMode,
+        hal: bool,
+        exclusive: bool,
+    ) -> Self {
+        Self {
+            source_format: info.format.clone(),
+            source_rate: info.sample_rate,
+            source_bits: info.bit_depth,
+            device_rate: device_format.sample_rate,
+            output_mode,
+            output_bits: device_format.bits_per_sample,
+            hal,
+            exclusive,
+            resampled: info.sample_rate != device_format.sample_rate,
+            fell_back: false,
+            gapless: info.encoder_delay > 0 || info.encoder_padding > 0,
+        }
+    }
+
+    /// 是否跨采样率系列 SRC（如 44.1k → 48k，非整数比）
+    pub fn cross_family_src(&self) -> bool {
+        self.resampled && is_cross_family(self.source_rate, self.device_rate)
+    }
+
+    /// 单行描述，如 `FLAC 24/96 → Int32 HAL exclusive, no SRC`
+    pub fn signal_path(&self) -> String {
+        let source = match self.source_bits {
+            Some(bits) => format!("{} {}/{}", self.source_format, bits, khz(self.source_rate)),
+            None => format!("{} {}kHz", self.source_format, khz(self.source_rate)),
+        };
+        let backend = match (self.hal, self.exclusive, self.fell_back) {
+            (true, true, _) => "HAL exclusive",
+            (true, false, _) => "HAL shared",
+            (false, _, true) => "System Mixer (HAL fallback)",
+            (false, _, false) => "System Mixer",
+        };
+        let src = if self.resampled {
+            format!("SRC {}→{}kHz", khz(self.source_rate), khz(self.device_rate))
+        } else {
+            "no SRC".to_string()
+        };
+        let gapless = if self.gapless { ", gapless" } else { "" };
+        format!(
+            "{} → {:?} {}, {}{}",
+            source, self.output_mode, backend, src, gapless
+        )
+    }
+
+    /// 输出路径的 bit-perfect 判定（不含软件处理，见 `Engine::bit_perfect_reason`）
+    ///
+    /// 条件依次为：HAL 直接输出、独占、整数格式（Int16 仅在源不超过 16-bit 时）、无 SRC
+    pub fn bit_perfect_status(&self) -> BitPerfectStatus {
+        if !self.hal {
+            return BitPerfectStatus::SystemMixer;
+        }
+        if !self.exclusive {
+            return BitPerfectStatus::SharedMode;
+        }
+        match self.output_mode {
+            OutputFormatMode::Float32 => return BitPerfectStatus::FloatOutput,
+            // 有损源位深未知，按高位深处理
+            OutputFormatMode::Int16 if self.source_bits.is_none_or(|bits| bits > 16) => {
+                return BitPerfectStatus::Truncated {
+                    from_bits: self.source_bits.unwrap_or(24),
+                };
+            }
+            _ => {}
+        }
+        if self.resampled {
+            return BitPerfectStatus::Resampled {
+                from: self.source_rate,
+                to: self.device_rate,
+            };
+        }
+        BitPerfectStatus::BitPerfect
+    }
+}
+
+/// Bit-perfect 判定结果：不满足时给出第一个失败的条件
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitPerfectStatus {
+    BitPerfect,
+    /// 未在播放
+    Idle,
+    /// 软件处理改变了样本（极性反转、增益等）
+    Processing(&'static str),
+    /// 经系统混音器输出
+    SystemMixer,
+    /// HAL 输出但未获得独占
+    SharedMode,
+    /// 浮点输出格式
+    FloatOutput,
+    /// 高位深源降到 16-bit（需要 dither）
+    Truncated { from_bits: u32 },
+    /// CoreAudio 采样率转换
+    Resampled { from: u32, to: u32 },
+}
+
+impl BitPerfectStatus {
+    pub fn is_bit_perfect(self) -> bool {
+        self == Self::BitPerfect
+    }
+}
+
+impl std::fmt::Display for BitPerfectStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BitPerfect => write!(f, "bit-perfect"),
+            Self::Idle => write!(f, "not playing"),
+            Self::Processing(what) => write!(f, "{}", what),
+            Self::SystemMixer => write!(f, "system mixer"),
+            Self::SharedMode => write!(f, "shared mode"),
+            Self::FloatOutput => write!(f, "float output"),
+            Self::Truncated { from_bits } => write!(f, "{}→16-bit dither", from_bits),
+            Self::Resampled { from, to } => write!(f, "SRC {}→{}k", khz(*from), khz(*to)),
+        }
+    }
+}
+
+/// 按配置设置亲和性标签（None 时跳过），返回是否调用了 `set_tag`
+#[cfg(any(target_os = "macos", test))]
+fn apply_thread_affinity(affinity: Option<i32>, set_tag: impl FnOnce(i32)) -> bool {
+    match affinity {
+        Some(tag) => {
+            set_tag(tag);
+            true
+        }
+        None => {
+            log::debug!("Decoder thread affinity tagging disabled");
+            false
+        }
+    }
+}
+
+/// 曲目的输出配置：以源采样率请求
+fn track_output_config(config: &EngineConfig, sample_rate: u32) -> OutputConfig {
+    OutputConfig {
+        sample_rate,
+        ..config.output.clone()
+    }
+}
+
+/// 记录用户选择的输出模式，显式选择优先于按设备记住的设置
+fn apply_output_mode(config: &mut EngineConfig, use_hal: bool, exclusive: bool) {
+    config.output.use_hal = use_hal;
+    config.output.exclusive_mode = exclusive;
+    config.output.device_settings = false;
+}
+
+/// 采样率的 kHz 表示（44100 → "44.1"，96000 → "96"）
+fn khz(rate: u32) -> String {
+    if rate.is_multiple_of(1000) {
+        (rate / 1000).to_string()
+    } else {
+        format!("{:.1}", rate as f64 / 1000.0)
+    }
 }
 
 impl Engine {
     /// 创建新引擎
-    pub fn new(config: EngineConfig) -> Self {
-        // 向上取整到 2 的幂
+    ///
+    /// 配置不合理（见 `EngineConfig::validate`）时返回错误
+    pub fn new(config: EngineConfig) -> Result<Self, EngineError> {
+        config.validate()?;
+
+        // 向上取整到 2 的幂（上限已校验，不会溢出）
         let buffer_capacity = config.buffer_frames.next_power_of_two();
         let ring_buffer = Arc::new(RingBuffer::new(buffer_capacity));
         let stats = Arc::new(PlaybackStats::new());
@@ -126,9 +841,37 @@ impl Engine {
             running: AtomicBool::new(false),
             paused: AtomicBool::new(false),
             eof_reached: AtomicBool::new(false),
+            device_drained: AtomicBool::new(false),
+            output_period_frames: AtomicU32::new(0),
+            output_tail_frames: AtomicU32::new(0),
+            seek_pending: AtomicBool::new(false),
+            seek_target: AtomicU64::new(0),
+            seek_failed: AtomicBool::new(false),
+            realtime_active: AtomicBool::new(false),
+            samples_decoded: AtomicU64::new(0),
+            decode_nanos: AtomicU64::new(0),
+            decode_frames: AtomicU64::new(0),
+            invert_polarity: AtomicBool::new(false),
+            swap_channels: AtomicBool::new(false),
+            mono_monitor: AtomicBool::new(false),
+            gain_bits: AtomicU64::new(1.0f64.to_bits()),
+            crossfeed_bits: AtomicU64::new(crossfeed_level(config.crossfeed).to_bits()),
+            stereo_width_bits: AtomicU64::new(width::width_factor(config.stereo_width).to_bits()),
+            limiter_enabled: AtomicBool::new(config.limiter.is_some()),
+            limiter_threshold_bits: AtomicU32::new(
+                limiter::threshold_dbfs(config.limiter.unwrap_or(limiter::DEFAULT_THRESHOLD_DBFS))
+                    .to_bits(),
+            ),
+            limiter_reduction_bits: AtomicU32::new(0),
+            true_peak_enabled: AtomicBool::new(config.true_peak_meter),
+            true_peak_bits: AtomicU64::new(0),
+            channel_delays: Default::default(),
+            delays_changed: AtomicBool::new(false),
+            error_occurred: AtomicBool::new(false),
+            last_error: Mutex::new(None),
         });
 
-        Self {
+        Ok(Self {
             config,
             state: PlaybackState::Stopped,
             ring_buffer,
@@ -136,23 +879,54 @@ impl Engine {
             output: None,
             decoder_thread: None,
             decoder_state,
+            completion: Arc::new(TrackCompletion::new()),
+            prebuffer: Arc::new(PrebufferSignal::new()),
+            decode_started: None,
             current_info: None,
             current_format: None,
-        }
+            summary: None,
+            prefetch: None,
+            follow_active: Arc::new(AtomicBool::new(false)),
+            last_stop_reason: None,
+            events: Arc::new(EventSink::default()),
+        })
     }
 
     /// 加载并播放文件
     pub fn play<P: AsRef<Path>>(&mut self, path: P) -> Result<(), EngineError> {
-        // 如果正在播放，先停止
+        self.load(path.as_ref(), false)
+    }
+
+    /// 加载文件但保持暂停：输出在淡入前暂停，解码线程照常填满缓冲区
+    ///
+    /// 状态为 `Paused`，`toggle_pause()` 开始播放（无需再等待预缓冲）
+    pub fn play_paused<P: AsRef<Path>>(&mut self, path: P) -> Result<(), EngineError> {
+        self.load(path.as_ref(), true)
+    }
+
+    /// 加载曲目并启动输出与解码线程；`paused` 时输出启动后立即暂停
+    fn load(&mut self, path: &Path, paused: bool) -> Result<(), EngineError> {
+        // 正在播放时先停止解码线程并暂停输出（淡出）；输出暂时保留，下一首能沿用时不重建设备
+        let running_format = match self.state {
+            PlaybackState::Playing | PlaybackState::Buffering => self.current_format,
+            _ => None,
+        };
         if self.state != PlaybackState::Stopped {
-            self.stop()?;
+            self.stop_decoder_thread();
+            if let (Some(_), Some(output)) = (running_format, self.output.as_mut()) {
+                output.pause()?;
+            }
         }
 
-        let path = path.as_ref();
         log::info!("Loading: {}", path.display());
 
-        // 打开解码器
-        let decoder = AudioDecoder::open(path)?;
+        let decoder = match self.open_decoder(path) {
+            Ok(decoder) => decoder,
+            Err(e) => {
+                self.stop()?;
+                return Err(e);
+            }
+        };
         let info = decoder.info().clone();
 
         log::info!(
@@ -165,16 +939,24 @@ impl Engine {
             info.duration_secs.unwrap_or(0.0)
         );
 
+        if let Some(format) = running_format.filter(|f| can_upmix_mono(*f, &info)) {
+            if self.output.is_some() {
+                return self.play_upmixed(decoder, info, format, paused);
+            }
+        }
+        if self.state != PlaybackState::Stopped {
+            self.stop()?;
+        }
+
         // 创建音频格式
         let bit_depth = info.bit_depth.unwrap_or(24) as u16;
         let source_sample_rate = info.sample_rate;
 
         // 配置输出采样率为源文件采样率（作为请求）
-        let mut output_config = self.config.output.clone();
-        output_config.sample_rate = source_sample_rate;
+        let mut output_config = track_output_config(&self.config, source_sample_rate);
 
         // 创建输出
-        let mut output = AudioOutput::new(output_config)?;
+        let mut output = AudioOutput::new(output_config.clone())?;
 
         // 查询设备实际采样率
         let device_sample_rate = output.target_sample_rate(source_sample_rate);
@@ -188,8 +970,18 @@ impl Engine {
                 source_sample_rate, device_sample_rate
             );
         }
+        if is_cross_family(source_sample_rate, device_sample_rate) {
+            log::warn!(
+                "Cross-family resampling {}Hz → {}Hz: the device stays in the other rate family \
+                 (unlock its rate or pick a supported rate to avoid non-integer SRC)",
+                source_sample_rate, device_sample_rate
+            );
+        }
         let format = AudioFormat::new(source_sample_rate, info.channels as u16, bit_depth);
 
+        // 按本曲格式/来源调整 ring buffer 容量（输出和解码线程均已停止）
+        self.prepare_ring_buffer(&info, SourceKind::of(path));
+
         // 清空缓冲区
         self.ring_buffer.clear();
         self.stats.reset();
@@ -201,128 +993,708 @@ impl Engine {
             Arc::clone(&self.stats),
         )?;
 
-        // 启动解码线程
-        self.decoder_state.running.store(true, Ordering::Release);
-        self.decoder_state.paused.store(false, Ordering::Release);
-        self.decoder_state.eof_reached.store(false, Ordering::Release);
+        // 看门狗：HAL 启动成功但 IO 回调始终不触发时，回退到系统混音器重试
+        let mut fell_back = false;
+        if !self.wait_first_callback() {
+            let _ = output.stop();
+            if !output.is_hal_output() {
+                return Err(OutputError::NoCallback.into());
+            }
+            log::warn!(
+                "HALOutput started but no IO callback within {:?}, falling back to system mixer",
+                OUTPUT_WATCHDOG_TIMEOUT
+            );
+            output_config.use_hal = false;
+            output = AudioOutput::new(output_config)?;
+            self.ring_buffer.clear();
+            self.stats.reset();
+            output.start(
+                format,
+                Arc::clone(&self.ring_buffer),
+                Arc::clone(&self.stats),
+            )?;
+            if !self.wait_first_callback() {
+                let _ = output.stop();
+                return Err(OutputError::NoCallback.into());
+            }
+            fell_back = true;
+        }
 
-        let decoder_state = Arc::clone(&self.decoder_state);
-        let ring_buffer = Arc::clone(&self.ring_buffer);
-        let channels = info.channels as usize;
-        let sample_rate = source_sample_rate;
-        let buffer_frames = self.config.output.buffer_frames;
+        // 保持暂停：缓冲区仍为空，暂停时不会输出任何样本
+        if paused {
+            output.pause()?;
+            output.release_power_assertion();
+            log::info!("Loaded paused");
+        }
 
-        let decoder_thread = thread::Builder::new()
-            .name("decoder".to_string())
-            .spawn(move || {
-                Self::decoder_thread_main(
-                    decoder,
-                    ring_buffer,
-                    decoder_state,
-                    channels,
-                    sample_rate,
-                    buffer_frames,
-                );
-            })
-            .expect("Failed to spawn decoder thread");
+        // 启动解码线程
+        self.set_output_tail(&output);
+        self.spawn_decoder(decoder, info.channels as usize, source_sample_rate);
+
+        let mut summary = PlaybackSummary::new(
+            &info,
+            output.actual_format(),
+            output.output_format_mode().unwrap_or(OutputFormatMode::Float32),
+            output.is_hal_output(),
+            output.is_exclusive_mode(),
+        );
+        summary.fell_back = fell_back;
+        log::info!("Signal path: {}", summary.signal_path());
+        self.summary = Some(summary);
 
         self.output = Some(output);
-        self.decoder_thread = Some(decoder_thread);
         self.current_info = Some(info);
         self.current_format = Some(format);
-        self.state = PlaybackState::Buffering;
+        self.state = if paused {
+            PlaybackState::Paused
+        } else {
+            PlaybackState::Buffering
+        };
 
         Ok(())
     }
 
-    /// 解码线程主函数
+    /// 打开曲目的解码器（"-" 表示从 stdin 读取；已预打开的直接使用）
+    fn open_decoder(&mut self, path: &Path) -> Result<AudioDecoder, EngineError> {
+        let force_format = self.config.force_format.clone();
+        let force_format = force_format.as_deref();
+        let decoder = if is_stdin_path(path) {
+            AudioDecoder::open_reader(Box::new(std::io::stdin()), force_format)?
+        } else if self.config.follow {
+            self.follow_active.store(true, Ordering::Release);
+            AudioDecoder::open_follow(path, Arc::clone(&self.follow_active), force_format)?
+        } else if let Some(prefetched) = self.take_prefetch(path) {
+            prefetched?
+        } else {
+            AudioDecoder::open_track(
+                path,
+                self.config.pcm_format,
+                force_format,
+                self.config.track_index,
+            )?
+        };
+        Ok(decoder)
+    }
+
+    /// 沿用已暂停的立体声输出播放同采样率的单声道曲目（解码线程上混为立体声）
     ///
-    /// 使用整数直通路径：对于整数源格式，避免 f64 中间转换
-    /// SRC 由 CoreAudio 内部处理
-    fn decoder_thread_main(
+    /// 输出格式不变，不重新配置设备；`paused` 时输出保持暂停
+    fn play_upmixed(
+        &mut self,
         decoder: AudioDecoder,
-        ring_buffer: Arc<RingBuffer<i32>>,
-        state: Arc<DecoderState>,
-        channels: usize,
-        sample_rate: u32,
-        buffer_frames: u32,
-    ) {
-        // 设置较高的线程优先级（但不是实时，避免影响 CoreAudio IO 线程）
-        Self::set_decoder_thread_priority(buffer_frames, sample_rate);
-
-        let mut iter = DecoderIterator::new(decoder);
-
-        // 读取块大小
-        let read_chunk_size = 4096 * channels;
-
-        // 自适应等待参数（纯整数运算，避免热路径上的 f64 除法）
-        // ns_per_sample = 1_000_000_000 / (sample_rate * channels)
-        let ns_per_sample: u64 = 1_000_000_000 / (sample_rate as u64 * channels as u64);
-        let min_free_threshold = 1024 * channels;
-
+        info: AudioInfo,
+        format: AudioFormat,
+        paused: bool,
+    ) -> Result<(), EngineError> {
         log::info!(
-            "Decoder thread started, ~{}ns/sample",
-            ns_per_sample
+            "Mono track at {}Hz: upmixing to stereo, keeping the running output",
+            info.sample_rate
         );
+        // 输出已暂停，IO 回调不读取 ring buffer，可以安全清空上一首剩余的数据
+        self.ring_buffer.clear();
+        self.stats.reset();
+        self.spawn_decoder(decoder, format.channels as usize, info.sample_rate);
+        let Some(output) = self.output.as_mut() else {
+            return Err(EngineError::InvalidState("No active output"));
+        };
+        if paused {
+            output.release_power_assertion();
+        } else {
+            output.resume_track_start()?;
+        }
 
-        while state.running.load(Ordering::Acquire) {
-            // 检查暂停 - 使用 thread::park 阻塞等待，完全无锁
-            // park/unpark 无需 Mutex（避免优先级反转），恢复延迟 ~1-10µs
-            // 如果 unpark 在 park 之前调用，下次 park 立即返回（无丢失唤醒）
-            while state.paused.load(Ordering::Acquire) {
-                thread::park();
-            }
+        let summary = PlaybackSummary::new(
+            &info,
+            output.actual_format(),
+            output
+                .output_format_mode()
+                .unwrap_or(OutputFormatMode::Float32),
+            output.is_hal_output(),
+            output.is_exclusive_mode(),
+        );
+        log::info!("Signal path: {}", summary.signal_path());
+        self.summary = Some(summary);
+        self.current_info = Some(info);
+        self.current_format = Some(format);
+        self.state = if paused {
+            PlaybackState::Paused
+        } else {
+            PlaybackState::Buffering
+        };
+        Ok(())
+    }
 
-            // 检查缓冲区是否有空间
-            let available_write = ring_buffer.free_space();
+    /// 播放中切换输出模式（HALOutput/系统混音器、是否独占），不中断解码
+    ///
+    /// 只停止并重建输出，ring buffer 与播放计数保持不变，新输出从已缓冲的位置继续。
+    /// 新模式启动失败时恢复原模式并返回错误。暂停或停止时返回 `InvalidState`。
+    /// 之后的曲目沿用新模式（不再套用按设备记住的设置）
+    pub fn switch_output(&mut self, use_hal: bool, exclusive: bool) -> Result<(), EngineError> {
+        if self.state == PlaybackState::Stopped || self.state == PlaybackState::Paused {
+            return Err(EngineError::InvalidState("Not playing"));
+        }
+        let (Some(format), Some(info)) = (self.current_format, self.current_info.clone()) else {
+            return Err(EngineError::InvalidState("No track loaded"));
+        };
+        let Some(mut old_output) = self.output.take() else {
+            return Err(EngineError::InvalidState("No active output"));
+        };
 
-            if available_write < min_free_threshold {
-                // 缓冲区快满了 - 使用自适应等待策略
-                // 计算需要等待多久才能有足够空间
-                let samples_needed = min_free_threshold - available_write;
-                let wait_us = (samples_needed as u64 * ns_per_sample) / 1_000;
+        // 停止旧输出：之后 IO 回调不再读取 ring buffer，解码线程写满后自然等待
+        old_output.stop()?;
+        drop(old_output);
 
-                // 根据等待时间选择策略：
-                // - < 50µs: 仅自旋（避免 syscall 开销）
-                // - 50-500µs: yield + 短自旋
-                // - > 500µs: 睡眠（节省 CPU）
-                if wait_us < 50 {
-                    // 短等待：纯自旋
-                    for _ in 0..64 {
-                        std::hint::spin_loop();
+        let previous = self.config.output.clone();
+        apply_output_mode(&mut self.config, use_hal, exclusive);
+        let output = match self.start_switched_output(format) {
+            Ok(output) => output,
+            Err(e) => {
+                log::warn!("Output switch failed ({}), restoring previous mode", e);
+                self.config.output = previous;
+                match self.start_switched_output(format) {
+                    Ok(output) => {
+                        self.output = Some(output);
+                        return Err(e);
                     }
-                } else if wait_us < 500 {
-                    // 中等等待：yield 后短自旋
-                    thread::yield_now();
-                    for _ in 0..32 {
-                        std::hint::spin_loop();
+                    Err(restore) => {
+                        // 两种模式都无法启动：按设备断开处理，停止解码线程
+                        let _ = self.stop_with_reason(StopReason::DeviceLost);
+                        return Err(restore);
                     }
-                } else {
-                    // 长等待：睡眠 70% 的预计时间（留出余量）
-                    let sleep_us = (wait_us * 7 / 10).max(100).min(10_000);
-                    thread::sleep(std::time::Duration::from_micros(sleep_us));
                 }
-                continue;
             }
+        };
 
-            // 解码（整数直通路径）
-            // 对于 PCM 整数源，直接转换到 i32，避免 f64 中间表示
-            let samples_to_read = available_write.min(read_chunk_size);
-            match iter.read_i32(samples_to_read) {
-                Ok(samples) => {
-                    if samples.is_empty() {
-                        // EOF - 设置标志，让上层知道解码已完成
-                        state.eof_reached.store(true, Ordering::Release);
-                        log::info!("Decoder reached end of file");
-                        break;
-                    }
+        let summary = PlaybackSummary::new(
+            &info,
+            output.actual_format(),
+            output
+                .output_format_mode()
+                .unwrap_or(OutputFormatMode::Float32),
+            output.is_hal_output(),
+            output.is_exclusive_mode(),
+        );
+        log::info!("Signal path: {}", summary.signal_path());
+        self.summary = Some(summary);
+        self.set_output_tail(&output);
+        self.output = Some(output);
+        Ok(())
+    }
 
-                    // 直接写入 ring buffer（SRC 由 CoreAudio 处理）
-                    ring_buffer.write(samples);
-                }
+    /// 把输出的 IO 周期与延迟交给解码线程，用于判断最后的样本何时真正播出
+    fn set_output_tail(&self, output: &AudioOutput) {
+        self.decoder_state
+            .output_period_frames
+            .store(output.io_buffer_frames(), Ordering::Relaxed);
+        self.decoder_state
+            .output_tail_frames
+            .store(output.output_latency_frames(), Ordering::Relaxed);
+    }
+
+    /// 锁定设备采样率（见 `OutputConfig::lock_device_rate`），从下一首曲目起生效
+    pub fn set_lock_device_rate(&mut self, locked: bool) {
+        self.config.output.lock_device_rate = locked;
+    }
+
+    /// 是否锁定设备采样率
+    pub fn lock_device_rate(&self) -> bool {
+        self.config.output.lock_device_rate
+    }
+
+    /// 按当前配置创建并启动输出（复用现有 ring buffer 与统计，不清空）
+    fn start_switched_output(&self, format: AudioFormat) -> Result<AudioOutput, EngineError> {
+        let mut output = AudioOutput::new(track_output_config(&self.config, format.sample_rate))?;
+        let callbacks = self.stats.callback_count();
+        output.start(
+            format,
+            Arc::clone(&self.ring_buffer),
+            Arc::clone(&self.stats),
+        )?;
+
+        // 看门狗：只统计新输出的回调
+        let started = Instant::now();
+        loop {
+            let new_callbacks = self.stats.callback_count().saturating_sub(callbacks);
+            match watchdog_verdict(new_callbacks, started.elapsed()) {
+                WatchdogVerdict::Alive => return Ok(output),
+                WatchdogVerdict::Stalled => {
+                    let _ = output.stop();
+                    return Err(OutputError::NoCallback.into());
+                }
+                WatchdogVerdict::Waiting => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+    }
+
+    /// 按曲目格式重建 ring buffer（仅在停止状态调用：SPSC 缓冲区不能在使用中改变容量）
+    ///
+    /// 自动缓冲按格式/来源估算，指定 `buffer_ms` 时按本曲采样率/声道数换算；
+    /// 两者都未设置时保持创建时的容量
+    fn prepare_ring_buffer(&mut self, info: &AudioInfo, source: SourceKind) {
+        let samples = if self.config.auto_buffer {
+            auto_buffer_samples(info, source)
+        } else if let Some(ms) = self.config.buffer_ms {
+            buffer_samples_for_ms(ms, info)
+        } else {
+            return;
+        };
+        let capacity = samples.next_power_of_two();
+        if capacity != self.ring_buffer.capacity() {
+            log::info!(
+                "Ring buffer: {} samples ({:.0}ms)",
+                capacity,
+                buffered_ms(capacity, info.channels, info.sample_rate)
+            );
+            self.ring_buffer = Arc::new(RingBuffer::new(capacity));
+        }
+    }
+
+    /// 等待输出的首次 IO 回调，超时返回 false
+    ///
+    /// 部分设备 start 返回成功，但静默拒绝格式、回调从不触发
+    fn wait_first_callback(&self) -> bool {
+        let started = Instant::now();
+        loop {
+            match watchdog_verdict(self.stats.callback_count(), started.elapsed()) {
+                WatchdogVerdict::Alive => return true,
+                WatchdogVerdict::Stalled => return false,
+                WatchdogVerdict::Waiting => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+    }
+
+    /// 重置解码状态并启动解码线程
+    fn spawn_decoder(&mut self, decoder: AudioDecoder, channels: usize, sample_rate: u32) {
+        self.decoder_state.running.store(true, Ordering::Release);
+        self.decoder_state.paused.store(false, Ordering::Release);
+        self.decoder_state.eof_reached.store(false, Ordering::Release);
+        self.decoder_state.device_drained.store(false, Ordering::Release);
+        self.decoder_state.seek_pending.store(false, Ordering::Release);
+        self.decoder_state.realtime_active.store(false, Ordering::Release);
+        self.decoder_state.samples_decoded.store(0, Ordering::Relaxed);
+        self.decoder_state.decode_nanos.store(0, Ordering::Relaxed);
+        self.decoder_state.decode_frames.store(0, Ordering::Relaxed);
+        self.decoder_state.true_peak_bits.store(0, Ordering::Relaxed);
+        self.decoder_state.clear_error();
+        self.completion.reset();
+        self.prebuffer.reset();
+        self.decode_started = Some(Instant::now());
+
+        let shared = DecoderShared {
+            ring_buffer: Arc::clone(&self.ring_buffer),
+            stats: Arc::clone(&self.stats),
+            state: Arc::clone(&self.decoder_state),
+            completion: Arc::clone(&self.completion),
+            prebuffer: Arc::clone(&self.prebuffer),
+            prebuffer_target: self.prebuffer_target(),
+            events: Arc::clone(&self.events),
+        };
+        let config = self.config.clone();
+        self.events.emit(EngineEvent::TrackStarted(decoder.info().clone()));
+
+        let decoder_thread = thread::Builder::new()
+            .name("decoder".to_string())
+            .spawn(move || {
+                Self::decoder_thread_main(decoder, shared, channels, sample_rate, &config);
+            })
+            .expect("Failed to spawn decoder thread");
+
+        self.decoder_thread = Some(decoder_thread);
+    }
+
+    /// 在后台线程预打开下一首（只 probe 和读取元数据，不解码）
+    ///
+    /// 之后 `play` 同一路径时直接使用该解码器，切歌不再阻塞在 probe 上；
+    /// 不支持或损坏的文件可提前通过 `prefetch_status()` 得知。
+    /// 不影响当前播放，stdin 不预打开
+    pub fn prefetch<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+        if is_stdin_path(path) {
+            self.prefetch = None;
+            return;
+        }
+        if self.prefetch.as_ref().is_some_and(|p| p.path == path) {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let pcm_format = self.config.pcm_format;
+        let force_format = self.config.force_format.clone();
+        let track_index = self.config.track_index;
+        let thread_path = path.to_path_buf();
+        let spawned = thread::Builder::new()
+            .name("prefetch".to_string())
+            .spawn(move || {
+                let decoder = AudioDecoder::open_track(
+                    &thread_path,
+                    pcm_format,
+                    force_format.as_deref(),
+                    track_index,
+                );
+                // 接收端已丢弃（又预打开了别的曲目）时忽略
+                let _ = tx.send(decoder);
+            });
+        self.prefetch = match spawned {
+            Ok(_) => Some(Prefetch {
+                path: path.to_path_buf(),
+                rx,
+                result: None,
+            }),
+            Err(e) => {
+                log::warn!("Failed to spawn prefetch thread: {}", e);
+                None
+            }
+        };
+    }
+
+    /// 预打开的路径及其状态（未预打开时返回 None）
+    pub fn prefetch_status(&mut self) -> Option<(&Path, PrefetchStatus)> {
+        let prefetch = self.prefetch.as_mut()?;
+        prefetch.poll();
+        let status = match &prefetch.result {
+            None => PrefetchStatus::Pending,
+            Some(Ok(_)) => PrefetchStatus::Ready,
+            Some(Err(e)) => PrefetchStatus::Failed(e.to_string()),
+        };
+        Some((prefetch.path.as_path(), status))
+    }
+
+    /// 取出指定路径的预打开结果（路径不符时丢弃）
+    fn take_prefetch(&mut self, path: &Path) -> Option<Result<AudioDecoder, DecodeError>> {
+        let prefetch = self.prefetch.take()?;
+        if prefetch.path != path {
+            return None;
+        }
+        log::info!("Using prefetched decoder");
+        Some(prefetch.into_result())
+    }
+
+    /// 解码线程主函数
+    ///
+    /// 使用整数直通路径：对于整数源格式，避免 f64 中间转换
+    /// SRC 由 CoreAudio 内部处理。`channels` 为写入 ring buffer 的声道数，
+    /// 单声道源写入立体声输出时先上混
+    fn decoder_thread_main(
+        decoder: AudioDecoder,
+        shared: DecoderShared,
+        channels: usize,
+        sample_rate: u32,
+        config: &EngineConfig,
+    ) {
+        let DecoderShared {
+            ring_buffer,
+            stats,
+            state,
+            completion,
+            prebuffer,
+            prebuffer_target,
+            events,
+        } = shared;
+
+        // 设置较高的线程优先级（但不是实时，避免影响 CoreAudio IO 线程）
+        let realtime = Self::set_decoder_thread_priority(
+            config.output.buffer_frames,
+            sample_rate,
+            config.thread_affinity,
+        );
+        state.realtime_active.store(realtime, Ordering::Release);
+
+        let source_channels = decoder.info().channels as usize;
+        let upmix = source_channels == 1 && channels == 2;
+        let mut iter = DecoderIterator::new(decoder);
+
+        // 读取块大小与写入前要求的最小空闲空间（样本数）
+        let (read_chunk_size, min_free_threshold) =
+            decode_thresholds(config.decode_chunk_frames, channels);
+
+        // 单声道 → 立体声上混缓冲（仅上混时使用）
+        let mut upmix_buffer: Vec<i32> =
+            Vec::with_capacity(if upmix { read_chunk_size } else { 0 });
+
+        // 增益/极性反转/声道交换的工作缓冲（仅在开启时使用，关闭时零拷贝直写保持 bit-perfect）
+        let mut transform_buffer: Vec<i32> = Vec::with_capacity(read_chunk_size);
+
+        // 交叉馈送状态（仅立体声使用）
+        let mut crossfeed = Crossfeed::new(sample_rate);
+
+        // 立体声宽度状态（仅立体声使用，dither 种子同输出配置）
+        let mut stereo_width = StereoWidth::new(config.output.dither_seed.unwrap_or_else(clock_seed));
+
+        // 峰值限幅器状态
+        let mut limiter = Limiter::new(sample_rate, channels);
+
+        // 真峰值表（测量最终写入 ring buffer 的数据）
+        let mut true_peak = TruePeakMeter::new(channels);
+
+        // 声道延迟线（预分配，解码循环内不分配）
+        let mut delay_lines = DelayLines::new(channels);
+        delay_lines.load(&state.channel_delays);
+        state.delays_changed.store(false, Ordering::Relaxed);
+
+        // EOF 后还需送入延迟线的静音帧数（None 表示尚未到达 EOF），及其缓冲
+        let mut delay_tail: Option<usize> = None;
+        let mut silence: Vec<i32> = Vec::with_capacity(read_chunk_size);
+
+        // 自适应等待参数（纯整数运算，避免热路径上的 f64 除法）
+        // ns_per_sample = 1_000_000_000 / (sample_rate * channels)
+        let ns_per_sample: u64 = 1_000_000_000 / (sample_rate as u64 * channels as u64);
+
+        // 解码吞吐采样计数（每 DECODE_TIMING_INTERVAL 块计时一次）
+        let mut timing_counter: u32 = 0;
+
+        // 已通过事件报告的状态（只在变化时发送）
+        let mut reported_underruns: u64 = 0;
+        let mut reported_bit_perfect: Option<bool> = None;
+        let mut prebuffered = false;
+        let thread_started = Instant::now();
+
+        log::info!(
+            "Decoder thread started, ~{}ns/sample",
+            ns_per_sample
+        );
+
+        while state.running.load(Ordering::Acquire) {
+            // 检查暂停 - 使用 thread::park 阻塞等待，完全无锁
+            // park/unpark 无需 Mutex（避免优先级反转），恢复延迟 ~1-10µs
+            // 如果 unpark 在 park 之前调用，下次 park 立即返回（无丢失唤醒）
+            while state.paused.load(Ordering::Acquire)
+                && !state.seek_pending.load(Ordering::Acquire)
+            {
+                thread::park();
+            }
+
+            // 处理 seek 请求
+            // 此时输出已被 Engine::seek 暂停，IO 回调不会读取 ring buffer，
+            // 解码线程可以安全地清空缓冲区
+            if state.seek_pending.load(Ordering::Acquire) {
+                let target = f64::from_bits(state.seek_target.load(Ordering::Relaxed));
+                if let Err(e) = iter.seek(target) {
+                    log::warn!("Seek to {:.1}s failed: {}", target, e);
+                    state.seek_failed.store(true, Ordering::Relaxed);
+                }
+                ring_buffer.clear();
+                delay_lines.clear();
+                delay_tail = None;
+                crossfeed.reset();
+                limiter.reset();
+                true_peak.clear_history();
+                // 从 EOF 跳回：重新开始统计（预缓冲尚未完成时仍由上面的预缓冲判断启用）
+                if prebuffered {
+                    stats.arm_underruns();
+                }
+                state.eof_reached.store(false, Ordering::Release);
+                state.device_drained.store(false, Ordering::Release);
+                state.clear_error();
+                completion.reset();
+                state.seek_pending.store(false, Ordering::Release);
+                continue;
+            }
+
+            // 检查缓冲区是否有空间
+            let available_write = ring_buffer.free_space();
+
+            if available_write < min_free_threshold {
+                // 缓冲区快满了 - 使用自适应等待策略
+                // 计算需要等待多久才能有足够空间
+                let samples_needed = min_free_threshold - available_write;
+                let wait_us = (samples_needed as u64 * ns_per_sample) / 1_000;
+
+                wait_for_space(config.wait_strategy.action(wait_us));
+                continue;
+            }
+
+            // 解码（整数直通路径）
+            // 对于 PCM 整数源，直接转换到 i32，避免 f64 中间表示
+            // 按整帧读取，保证声道交换时块边界不会切开一帧
+            let samples_to_read =
+                available_write.min(read_chunk_size) / channels * source_channels.max(1);
+            timing_counter = timing_counter.wrapping_add(1);
+            let timed = timing_counter.is_multiple_of(DECODE_TIMING_INTERVAL);
+            let read_started = timed.then(Instant::now);
+            match iter.read_i32(samples_to_read) {
+                Ok(samples) => {
+                    if let Some(started) = read_started.filter(|_| !samples.is_empty()) {
+                        state
+                            .decode_nanos
+                            .fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                        state.decode_frames.fetch_add(
+                            (samples.len() / source_channels.max(1)) as u64,
+                            Ordering::Relaxed,
+                        );
+                    }
+                    let samples = if !samples.is_empty() {
+                        samples
+                    } else {
+                        // 延迟声道最后的样本还留在延迟线中：先送入静音把它们推出来
+                        let tail = delay_tail.get_or_insert_with(|| {
+                            if state.samples_decoded.load(Ordering::Relaxed) > 0 {
+                                delay_lines.tail_frames()
+                            } else {
+                                0
+                            }
+                        });
+                        if *tail == 0 {
+                            // EOF - 设置标志，让上层知道解码已完成
+                            log::info!("Decoder reached end of file");
+                            Self::end_of_track(
+                                &ring_buffer,
+                                &stats,
+                                &state,
+                                &completion,
+                                &prebuffer,
+                                &events,
+                                ns_per_sample,
+                            );
+                            continue;
+                        }
+                        let frames = (*tail).min(samples_to_read / source_channels.max(1));
+                        *tail -= frames;
+                        silence.clear();
+                        silence.resize(frames * source_channels.max(1), 0);
+                        &silence[..]
+                    };
+                    let samples = if upmix {
+                        upmix_mono(samples, &mut upmix_buffer);
+                        &upmix_buffer[..]
+                    } else {
+                        samples
+                    };
+
+                    let invert = state.invert_polarity.load(Ordering::Relaxed);
+                    let swap = state.swap_channels.load(Ordering::Relaxed) && channels >= 2;
+                    let mono = state.mono_monitor.load(Ordering::Relaxed) && channels >= 2;
+                    let gain = f64::from_bits(state.gain_bits.load(Ordering::Relaxed));
+                    if state.delays_changed.swap(false, Ordering::Relaxed) {
+                        delay_lines.load(&state.channel_delays);
+                    }
+                    let crossfeed_level = if channels == 2 {
+                        f64::from_bits(state.crossfeed_bits.load(Ordering::Relaxed))
+                    } else {
+                        0.0
+                    };
+                    let width = if channels == 2 {
+                        f64::from_bits(state.stereo_width_bits.load(Ordering::Relaxed))
+                    } else {
+                        1.0
+                    };
+                    let delay = delay_lines.is_active();
+                    let limit = state.limiter_enabled.load(Ordering::Relaxed);
+                    let mut altered = invert
+                        || swap
+                        || mono
+                        || gain != 1.0
+                        || crossfeed_level > 0.0
+                        || width != 1.0
+                        || delay;
+                    let samples = if altered || limit {
+                        transform_buffer.clear();
+                        transform_buffer.extend_from_slice(samples);
+                        // 开启限幅时增益推迟到限幅器内（f32 域）施加，避免先饱和再限幅
+                        if gain != 1.0 && !limit {
+                            apply_gain(&mut transform_buffer, gain);
+                        }
+                        if invert {
+                            invert_polarity(&mut transform_buffer);
+                        }
+                        if swap {
+                            swap_channels(&mut transform_buffer, channels);
+                        }
+                        if width != 1.0 {
+                            stereo_width.process(&mut transform_buffer, width);
+                        }
+                        if mono {
+                            fold_to_mono(&mut transform_buffer, channels);
+                        }
+                        if crossfeed_level > 0.0 {
+                            crossfeed.process(&mut transform_buffer, crossfeed_level);
+                        }
+                        // 延迟对应扬声器（输出声道），放在声道交换之后
+                        if delay {
+                            delay_lines.process(&mut transform_buffer);
+                        }
+                        if limit {
+                            let threshold = f32::from_bits(
+                                state.limiter_threshold_bits.load(Ordering::Relaxed),
+                            );
+                            let reduction =
+                                limiter.process(&mut transform_buffer, gain as f32, threshold);
+                            state
+                                .limiter_reduction_bits
+                                .store(reduction.to_bits(), Ordering::Relaxed);
+                            altered |= reduction > 0.0;
+                        }
+                        &transform_buffer[..]
+                    } else {
+                        samples
+                    };
+
+                    if state.true_peak_enabled.load(Ordering::Relaxed) {
+                        true_peak.process(samples);
+                        state
+                            .true_peak_bits
+                            .store(true_peak.true_peak().to_bits(), Ordering::Relaxed);
+                    }
+
+                    // 直接写入 ring buffer（SRC 由 CoreAudio 处理）
+                    let written = write_samples(&ring_buffer, &stats, samples);
+                    state
+                        .samples_decoded
+                        .fetch_add(written as u64, Ordering::Relaxed);
+                    if !prebuffered && ring_buffer.available() >= prebuffer_target {
+                        prebuffered = true;
+                        log::info!(
+                            "Prebuffer complete in {:.0}ms",
+                            thread_started.elapsed().as_secs_f64() * 1000.0
+                        );
+                        // 此前输出已在运行（看门狗等待首次回调），读空缓冲区是正常的启动过程
+                        stats.arm_underruns();
+                        prebuffer.signal();
+                    }
+
+                    if reported_bit_perfect != Some(!altered) {
+                        reported_bit_perfect = Some(!altered);
+                        events.emit(EngineEvent::BitPerfectChanged(!altered));
+                    }
+                    let underruns = stats.underrun_count();
+                    if underruns != reported_underruns {
+                        if underruns > reported_underruns {
+                            events.emit(EngineEvent::Underrun { total: underruns });
+                        }
+                        reported_underruns = underruns;
+                    }
+                }
+                Err(DecodeError::FormatChanged { sample_rate: new_rate, channels: new_channels }) => {
+                    // 输出按旧格式运行，无法直接续播：按曲目结束处理，
+                    // 由上层切到下一首（或重新打开）时以新格式重建输出
+                    log::warn!(
+                        "Stream format changed ({}Hz/{}ch -> {}Hz/{}ch), ending track",
+                        sample_rate, channels, new_rate, new_channels
+                    );
+                    Self::end_of_track(
+                        &ring_buffer,
+                        &stats,
+                        &state,
+                        &completion,
+                        &prebuffer,
+                        &events,
+                        ns_per_sample,
+                    );
+                }
                 Err(e) => {
+                    // 中途解码失败：记录错误，播完已缓冲的数据后按曲目结束处理，
+                    // 上层通过 last_error() 区分损坏文件与正常结束
                     log::error!("Decode error: {}", e);
-                    break;
+                    state.set_error(e.to_string());
+                    events.emit(EngineEvent::DecodeFailed(e.to_string()));
+                    Self::end_of_track(
+                        &ring_buffer,
+                        &stats,
+                        &state,
+                        &completion,
+                        &prebuffer,
+                        &events,
+                        ns_per_sample,
+                    );
                 }
             }
         }
@@ -330,24 +1702,106 @@ impl Engine {
         log::info!("Decoder thread finished");
     }
 
+    /// 曲目结束处理（在解码线程上运行）
+    ///
+    /// 设置 eof_reached，等待 IO 回调消费完缓冲区、设备播完在途帧后通知完成，
+    /// 然后保持线程存活，等待 seek 或停止。
+    /// 完成回调因此总在解码线程上调用，不会阻塞 IO 线程
+    fn end_of_track(
+        ring_buffer: &RingBuffer<i32>,
+        stats: &PlaybackStats,
+        state: &DecoderState,
+        completion: &TrackCompletion,
+        prebuffer: &PrebufferSignal,
+        events: &EventSink,
+        ns_per_sample: u64,
+    ) {
+        state.eof_reached.store(true, Ordering::Release);
+        // 之后不会再有数据写入，排空缓冲区时的读空不是断音
+        stats.disarm_underruns();
+        // 曲目短于预缓冲目标：缓冲区不会再增长，视为预缓冲完成
+        prebuffer.signal();
+
+        // 按剩余样本估算排空时间，分段睡眠（可被 seek/停止唤醒）
+        let mut drained = false;
+        while state.running.load(Ordering::Acquire)
+            && !state.seek_pending.load(Ordering::Acquire)
+        {
+            let available = ring_buffer.available();
+            if available == 0 {
+                drained = true;
+                break;
+            }
+            let wait_us = (available as u64 * ns_per_sample / 1_000).clamp(1_000, 50_000);
+            thread::park_timeout(Duration::from_micros(wait_us));
+        }
+
+        // 最后的样本已被回调取走但仍在设备缓冲中：之后每次回调推进一个 IO 周期，
+        // 按回调计数等设备播完（暂停时回调停止，等待随之顺延）
+        if drained {
+            let period = state.output_period_frames.load(Ordering::Relaxed);
+            let tail = state.output_tail_frames.load(Ordering::Relaxed);
+            let callbacks = stats.callback_count();
+            // 按单声道周期估算轮询间隔（偏短，多轮询几次而不会晚于实际播完）
+            let wait_us = (period as u64 * ns_per_sample / 1_000).clamp(1_000, 50_000);
+            drained = false;
+            while state.running.load(Ordering::Acquire)
+                && !state.seek_pending.load(Ordering::Acquire)
+            {
+                let elapsed = stats.callback_count().saturating_sub(callbacks);
+                if device_tail_played(elapsed, period, tail) {
+                    drained = true;
+                    break;
+                }
+                thread::park_timeout(Duration::from_micros(wait_us));
+            }
+        }
+
+        if drained {
+            state.device_drained.store(true, Ordering::Release);
+            completion.complete();
+            events.emit(EngineEvent::TrackFinished);
+        }
+
+        while state.running.load(Ordering::Acquire)
+            && !state.seek_pending.load(Ordering::Acquire)
+        {
+            thread::park();
+        }
+    }
+
     /// 设置解码线程优先级
     ///
     /// 优化策略（按优先级顺序）：
     /// 1. QoS 标记 - 告诉系统这是用户交互敏感任务（总是成功）
     /// 2. 实时调度策略 - 使用 Mach THREAD_TIME_CONSTRAINT_POLICY（不需要 root）
     /// 3. 后备方案 - nice 值
-    fn set_decoder_thread_priority(buffer_frames: u32, sample_rate: u32) {
+    ///
+    /// `affinity` 为 None 时不设置亲和性标签。返回实时调度策略是否设置成功
+    fn set_decoder_thread_priority(
+        buffer_frames: u32,
+        sample_rate: u32,
+        affinity: Option<i32>,
+    ) -> bool {
         #[cfg(target_os = "macos")]
         {
             // === 1. 设置 QoS 类（总是成功，无需权限）===
             Self::set_qos_class();
 
             // === 2. 设置实时调度策略（不需要 root）===
-            Self::set_realtime_priority(buffer_frames, sample_rate);
+            let realtime = Self::set_realtime_priority(buffer_frames, sample_rate);
 
-            // === 3. 设置线程亲和性标签（音频组 tag 1）===
+            // === 3. 设置线程亲和性标签（默认音频组 tag 1）===
             // 与 TUI 线程（tag 2）分离，减少 cache 干扰
-            Self::set_audio_thread_affinity();
+            apply_thread_affinity(affinity, Self::set_audio_thread_affinity);
+
+            realtime
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        {
+            let _ = affinity;
+            false
         }
     }
 
@@ -373,14 +1827,14 @@ impl Engine {
         }
     }
 
-    /// 设置线程亲和性标签（音频组 tag 1）
+    /// 设置线程亲和性标签（默认音频组 tag 1）
     ///
     /// 将解码线程标记为音频组，与 IO 回调线程同组（tag 1），
     /// 与 TUI 线程（tag 2）分离。macOS 调度器会：
     /// - 将同 tag 线程调度到相邻核心（共享 L2 cache，有利于 ring buffer 访问）
     /// - 将不同 tag 线程调度到不同核心组（减少 cache pollution）
     #[cfg(target_os = "macos")]
-    fn set_audio_thread_affinity() {
+    fn set_audio_thread_affinity(tag: i32) {
         const THREAD_AFFINITY_POLICY: u32 = 4;
 
         #[repr(C)]
@@ -400,7 +1854,7 @@ impl Engine {
 
         unsafe {
             let thread = pthread_mach_thread_np(libc::pthread_self());
-            let policy = ThreadAffinityPolicy { affinity_tag: 1 };
+            let policy = ThreadAffinityPolicy { affinity_tag: tag };
             let result = thread_policy_set(
                 thread,
                 THREAD_AFFINITY_POLICY,
@@ -408,7 +1862,7 @@ impl Engine {
                 1,
             );
             if result == 0 {
-                log::debug!("Decoder thread affinity tag set to 1 (audio group)");
+                log::debug!("Decoder thread affinity tag set to {}", tag);
             }
         }
     }
@@ -419,7 +1873,7 @@ impl Engine {
     /// 使用 timing::ns_to_mach_ticks 正确转换纳秒到 Mach ticks
     /// （Apple Silicon 上 1 tick ≈ 41.67ns，不等于 1ns）。
     #[cfg(target_os = "macos")]
-    fn set_realtime_priority(buffer_frames: u32, sample_rate: u32) {
+    fn set_realtime_priority(buffer_frames: u32, sample_rate: u32) -> bool {
         use crate::audio::timing::ns_to_mach_ticks;
 
         #[repr(C)]
@@ -473,30 +1927,41 @@ impl Engine {
                     "Realtime priority set: period={}µs, computation={}µs (from {}frames@{}Hz)",
                     period_ns / 1000, computation_ns / 1000, buffer_frames, sample_rate
                 );
+                true
             } else {
-                log::debug!(
+                log::warn!(
                     "Failed to set realtime priority (kern_return: {}), using default scheduling",
                     result
                 );
                 libc::setpriority(libc::PRIO_PROCESS, 0, -10);
+                false
             }
         }
     }
 
-    /// 停止播放
-    pub fn stop(&mut self) -> Result<(), EngineError> {
-        // 停止解码线程
-        self.decoder_state.running.store(false, Ordering::Release);
-        // 解除暂停状态（如果有），确保解码线程能退出
-        self.decoder_state.paused.store(false, Ordering::Release);
-        // 唤醒可能 park 的解码线程
-        if let Some(ref handle) = self.decoder_thread {
-            handle.thread().unpark();
-        }
+    /// 停止播放，返回停止原因
+    ///
+    /// 原因由停止时的状态推断：解码出错为 `Error`，已播完为 `EndOfTrack`，
+    /// 否则为 `User`。未在播放时不改变上一次记录的原因
+    pub fn stop(&mut self) -> Result<StopReason, EngineError> {
+        let reason = if self.last_error().is_some() {
+            StopReason::Error
+        } else if self.is_track_finished() {
+            StopReason::EndOfTrack
+        } else {
+            StopReason::User
+        };
+        self.stop_with_reason(reason)
+    }
 
-        if let Some(thread) = self.decoder_thread.take() {
-            let _ = thread.join();
+    /// 以指定原因停止播放（如检测到设备断开时使用 `DeviceLost`）
+    pub fn stop_with_reason(&mut self, reason: StopReason) -> Result<StopReason, EngineError> {
+        if self.decoder_thread.is_none() && self.output.is_none() {
+            return Ok(self.last_stop_reason.unwrap_or(reason));
         }
+        self.last_stop_reason = Some(reason);
+
+        self.stop_decoder_thread();
 
         // 停止输出
         if let Some(mut output) = self.output.take() {
@@ -505,39 +1970,83 @@ impl Engine {
 
         self.ring_buffer.clear();
         self.state = PlaybackState::Stopped;
+        self.decode_started = None;
         self.current_info = None;
         self.current_format = None;
+        self.summary = None;
 
-        log::info!("Playback stopped");
+        log::info!("Playback stopped ({:?})", reason);
+        self.events.emit(EngineEvent::Stopped(reason));
 
-        Ok(())
+        Ok(reason)
+    }
+
+    /// 停止解码，等已缓冲的音频播完后再停止输出（“播完再停”），返回停止原因
+    ///
+    /// 输出继续运行直到 ring buffer 排空，然后按 `stop()` 关闭设备；等待期间不分配内存。
+    /// 暂停中或输出不再消费（缓冲量长时间不减少）时不等待。跳曲/退出仍用 `stop()`
+    pub fn stop_after_drain(&mut self) -> Result<StopReason, EngineError> {
+        if self.state != PlaybackState::Paused && self.decoder_thread.is_some() {
+            self.stop_decoder_thread();
+            if !wait_drained(&self.ring_buffer, DRAIN_STALL_TIMEOUT) {
+                log::warn!(
+                    "Output stopped consuming with {} samples buffered, stopping anyway",
+                    self.ring_buffer.available()
+                );
+            }
+        }
+        self.stop()
+    }
+
+    /// 停止并回收解码线程（ring buffer 中已有的数据保持不变）
+    fn stop_decoder_thread(&mut self) {
+        self.decoder_state.running.store(false, Ordering::Release);
+        self.follow_active.store(false, Ordering::Release);
+        // 解除暂停状态（如果有），确保解码线程能退出
+        self.decoder_state.paused.store(false, Ordering::Release);
+        // 唤醒可能 park 的解码线程
+        if let Some(ref handle) = self.decoder_thread {
+            handle.thread().unpark();
+        }
+
+        if let Some(thread) = self.decoder_thread.take() {
+            let _ = thread.join();
+        }
+        self.prebuffer.reset();
+    }
+
+    /// 上一次停止的原因
+    pub fn last_stop_reason(&self) -> Option<StopReason> {
+        self.last_stop_reason
     }
 
     /// 暂停/恢复
     pub fn toggle_pause(&mut self) -> Result<(), EngineError> {
         // 先同步状态：如果缓冲已完成但内部状态仍是 Buffering，更新为 Playing
-        if self.state == PlaybackState::Buffering {
-            let fill_ratio = self.ring_buffer.fill_ratio();
-            if fill_ratio >= self.config.prebuffer_ratio {
-                self.state = PlaybackState::Playing;
-            }
+        if self.state == PlaybackState::Buffering && self.prebuffer_complete() {
+            self.state = PlaybackState::Playing;
         }
 
         match self.state {
             PlaybackState::Playing => {
                 // 暂停解码线程
                 self.decoder_state.paused.store(true, Ordering::Release);
-                // 暂停音频输出（立即静音）
+                // 暂停音频输出（等功率淡出到静音后停止 IO），暂停期间允许系统睡眠
                 if let Some(ref mut output) = self.output {
                     output.pause()?;
+                    output.release_power_assertion();
                 }
                 self.state = PlaybackState::Paused;
                 log::info!("Paused");
             }
             PlaybackState::Paused | PlaybackState::Buffering => {
-                // 恢复音频输出
+                // 恢复音频输出（尚未播放任何样本时是曲目开头，不淡入）
                 if let Some(ref mut output) = self.output {
-                    output.resume()?;
+                    if self.stats.samples_played() == 0 {
+                        output.resume_track_start()?;
+                    } else {
+                        output.resume()?;
+                    }
                 }
                 // 恢复解码线程
                 self.decoder_state.paused.store(false, Ordering::Release);
@@ -555,14 +2064,88 @@ impl Engine {
         Ok(())
     }
 
+    /// Seek 到指定时间（秒）
+    ///
+    /// 流程：暂停输出（保证 IO 回调不再读取）→ 通知解码线程 seek 并清空缓冲区
+    /// → 更新播放位置 → 恢复输出。
+    /// 不可 seek 的源（如 stdin）返回错误，播放不受影响。
+    pub fn seek(&mut self, position_secs: f64) -> Result<(), EngineError> {
+        if self.state == PlaybackState::Stopped {
+            return Err(EngineError::InvalidState("Cannot seek when stopped"));
+        }
+
+        let info = self
+            .current_info
+            .as_ref()
+            .ok_or(EngineError::InvalidState("No track loaded"))?;
+        if !info.seekable {
+            return Err(EngineError::InvalidState("Source is not seekable"));
+        }
+
+        let mut target = position_secs.max(0.0);
+        if let Some(duration) = info.duration_secs {
+            target = target.min(duration);
+        }
+        let channels = self.ring_channels() as u64;
+        let sample_rate = info.sample_rate as f64;
+
+        let output = self
+            .output
+            .as_mut()
+            .ok_or(EngineError::InvalidState("No active output"))?;
+        let was_paused = output.is_paused();
+        output.pause()?;
+
+        // 通知解码线程
+        self.decoder_state.seek_failed.store(false, Ordering::Relaxed);
+        self.decoder_state
+            .seek_target
+            .store(target.to_bits(), Ordering::Relaxed);
+        self.decoder_state.seek_pending.store(true, Ordering::Release);
+
+        // 等待解码线程完成 seek（通常 < 10ms）
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        let mut result = Ok(());
+        while self.decoder_state.seek_pending.load(Ordering::Acquire) {
+            match self.decoder_thread {
+                Some(ref handle) if !handle.is_finished() => handle.thread().unpark(),
+                _ => {
+                    self.decoder_state.seek_pending.store(false, Ordering::Release);
+                    result = Err(EngineError::InvalidState("Decoder thread not running"));
+                    break;
+                }
+            }
+            if std::time::Instant::now() >= deadline {
+                result = Err(EngineError::InvalidState("Seek timed out"));
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        if result.is_ok() {
+            if self.decoder_state.seek_failed.load(Ordering::Relaxed) {
+                result = Err(EngineError::InvalidState("Seek failed"));
+            } else {
+                let frames = (target * sample_rate) as u64;
+                self.stats.set_samples_played(frames * channels);
+                log::info!("Seek to {:.1}s", target);
+            }
+        }
+
+        if !was_paused {
+            if let Some(ref mut output) = self.output {
+                output.resume()?;
+            }
+        }
+
+        result
+    }
+
     /// 获取当前状态
     pub fn state(&self) -> PlaybackState {
         // 检查是否从 Buffering 转为 Playing
-        if self.state == PlaybackState::Buffering {
-            let fill_ratio = self.ring_buffer.fill_ratio();
-            if fill_ratio >= self.config.prebuffer_ratio {
-                return PlaybackState::Playing;
-            }
+        if self.state == PlaybackState::Buffering && self.prebuffer_complete() {
+            return PlaybackState::Playing;
         }
         self.state
     }
@@ -570,71 +2153,809 @@ impl Engine {
     /// 获取统计信息
     pub fn stats(&self) -> EngineStats {
         let buffer_fill_ratio = self.ring_buffer.fill_ratio();
+        let target_fill_ratio =
+            target_fill_ratio(self.ring_buffer.available(), self.prebuffer_target());
         let underrun_count = self.stats.underrun_count();
+        let write_overflow_count = self.stats.write_overflow_count();
         let samples_played = self.stats.samples_played();
         let sample_rate = self
             .current_info
             .as_ref()
             .map(|i| i.sample_rate)
             .unwrap_or(48000);
-        let channels = self.current_info.as_ref().map(|i| i.channels).unwrap_or(2);
+        let channels = self.ring_channels();
         let frames_played = samples_played / channels as u64;
         let position_secs = frames_played as f64 / sample_rate as f64;
+        let underrun_lost_ms =
+            buffered_ms(self.stats.underrun_samples_total() as usize, channels, sample_rate);
+        let buffered_ms = buffered_ms(self.ring_buffer.available(), channels, sample_rate);
+
+        if let Some(ref output) = self.output {
+            output.report_oversized_requests();
+        }
 
         EngineStats {
             buffer_fill_ratio,
+            target_fill_ratio,
             underrun_count,
+            underrun_lost_ms,
+            write_overflow_count,
             samples_played,
             position_secs,
+            buffered_ms,
         }
     }
 
-    /// 获取当前文件信息
-    pub fn current_info(&self) -> Option<&AudioInfo> {
-        self.current_info.as_ref()
+    /// 最近几次 underrun 发生时的播放位置（秒），按发生顺序排列
+    ///
+    /// 原始 host time 见 `PlaybackStats::underrun_times_ns()`
+    pub fn underrun_positions_secs(&self) -> Vec<f64> {
+        // ring buffer 按输出声道计数（单声道上混时与源声道数不同）
+        let sample_rate = self.current_info.as_ref().map_or(48000, |i| i.sample_rate);
+        let channels = self.ring_channels();
+        self.stats
+            .underrun_positions()
+            .into_iter()
+            .map(|samples| samples as f64 / channels as f64 / sample_rate as f64)
+            .collect()
     }
 
-    /// 检查是否正在播放
-    pub fn is_playing(&self) -> bool {
-        matches!(
-            self.state(),
-            PlaybackState::Playing | PlaybackState::Buffering
-        )
+    /// 当前实际听到的位置（秒）
+    ///
+    /// `stats().position_secs` 按回调取走的样本计算，比扬声器早一个输出延迟
+    /// （IO 缓冲 + 安全偏移 + 设备延迟）；这里扣除该延迟，用于精确的界面同步。
+    /// 暂停时输出管线已排空，两者相同
+    pub fn audible_position_secs(&self) -> f64 {
+        let position = self.stats().position_secs;
+        let latency = self
+            .output
+            .as_ref()
+            .map(|o| o.output_latency_secs())
+            .unwrap_or(0.0);
+        audible_position(position, latency, self.state == PlaybackState::Paused)
     }
 
-    /// 检查当前音轨是否已播放完毕
+    /// 缓冲区中的音频时长（毫秒）
     ///
-    /// 条件：解码到达 EOF 且缓冲区已被消费完
-    pub fn is_track_finished(&self) -> bool {
-        self.decoder_state.eof_reached.load(Ordering::Acquire)
-            && self.ring_buffer.available() == 0
+    /// 比 `buffer_fill_ratio` 更直观：与缓冲区容量无关，可直接用于调整 `--buffer-ms`。
+    /// 未加载音轨时返回 0
+    pub fn buffered_ms(&self) -> f64 {
+        match self.current_info {
+            Some(ref info) => {
+                buffered_ms(self.ring_buffer.available(), self.ring_channels(), info.sample_rate)
+            }
+            None => 0.0,
+        }
     }
 
-    /// 获取输出模式信息
+    /// 预缓冲剩余时间估计（秒）
     ///
-    /// 返回 (是否为HAL直接输出, 是否为独占模式)
-    pub fn output_mode(&self) -> Option<(bool, bool)> {
-        self.output.as_ref().map(|o| (o.is_hal_output(), o.is_exclusive_mode()))
+    /// 仅在 Buffering 状态下返回；按解码吞吐减去播放消耗的净填充速率估算。
+    /// 尚无足够数据估算时返回 None
+    pub fn buffering_eta_secs(&self) -> Option<f64> {
+        if self.state() != PlaybackState::Buffering {
+            return None;
+        }
+        let elapsed = self.decode_started?.elapsed().as_secs_f64();
+        buffering_eta(
+            self.decoder_state.samples_decoded.load(Ordering::Relaxed),
+            self.stats.samples_played(),
+            elapsed,
+            self.ring_buffer.available(),
+            self.prebuffer_target(),
+        )
     }
 
-    /// 检查是否为 bit-perfect 输出
+    /// 当前曲目的预缓冲是否已完成
     ///
-    /// Bit-perfect 意味着：
-    /// - HAL 直接输出（绕过系统混音器）
-    /// - 独占模式
-    /// - 整数格式（无浮点转换）
-    /// - 无采样率转换（SRC）
-    pub fn is_bit_perfect(&self) -> bool {
-        let source_rate = self.current_info
+    /// 解码线程写入后缓冲区首次达到预缓冲目标时置位（曲目比目标短时在 EOF 置位），
+    /// 之后不随缓冲区消耗而回退；停止后为 false
+    pub fn prebuffer_complete(&self) -> bool {
+        self.prebuffer.is_reached()
+    }
+
+    /// 阻塞等待预缓冲完成（由解码线程唤醒，无需轮询）
+    ///
+    /// `timeout` 为 None 时一直等待。返回是否已完成；未加载音轨时立即返回 false
+    pub fn wait_prebuffered(&self, timeout: Option<Duration>) -> bool {
+        if self.decoder_thread.is_none() {
+            return false;
+        }
+        self.prebuffer.wait(timeout)
+    }
+
+    /// ring buffer 中每帧的样本数（单声道上混时为输出的 2 声道，而非源声道数）
+    fn ring_channels(&self) -> u32 {
+        self.current_format
+            .map(|f| f.channels as u32)
+            .or(self.current_info.as_ref().map(|i| i.channels))
+            .unwrap_or(2)
+            .max(1)
+    }
+
+    /// 预缓冲目标（样本数）
+    fn prebuffer_target(&self) -> usize {
+        (self.ring_buffer.capacity() as f64 * self.config.prebuffer_ratio) as usize
+    }
+
+    /// 当前曲目的章节标记 `(起始秒, 标题)`，无章节时为空
+    pub fn chapters(&self) -> &[(f64, String)] {
+        self.current_info
             .as_ref()
-            .map(|i| i.sample_rate)
-            .unwrap_or(0);
+            .map(|i| i.chapters.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// 指定位置所在章节的序号（位于第一个章节之前时为 None）
+    pub fn current_chapter(&self, position_secs: f64) -> Option<usize> {
+        current_marker(self.chapters(), position_secs)
+    }
+
+    /// 跳到下一章节（已是最后一章时返回错误）
+    pub fn next_chapter(&mut self) -> Result<(), EngineError> {
+        let position = self.stats().position_secs;
+        let index = next_marker(self.chapters(), position)
+            .ok_or(EngineError::InvalidState("No next chapter"))?;
+        let target = self.chapters()[index].0;
+        self.seek(target)
+    }
+
+    /// 跳到上一章节
+    ///
+    /// 进入当前章节超过 3 秒时回到本章开头（与上一首的习惯一致）
+    pub fn prev_chapter(&mut self) -> Result<(), EngineError> {
+        let position = self.stats().position_secs;
+        let index = prev_marker(self.chapters(), position)
+            .ok_or(EngineError::InvalidState("No previous chapter"))?;
+        let target = self.chapters()[index].0;
+        self.seek(target)
+    }
+
+    /// 当前曲目的信号路径摘要（未播放时为 None）
+    pub fn playback_summary(&self) -> Option<&PlaybackSummary> {
+        self.summary.as_ref()
+    }
+
+    /// 获取当前文件信息
+    pub fn current_info(&self) -> Option<&AudioInfo> {
+        self.current_info.as_ref()
+    }
+
+    /// 检查是否正在播放
+    pub fn is_playing(&self) -> bool {
+        matches!(
+            self.state(),
+            PlaybackState::Playing | PlaybackState::Buffering
+        )
+    }
+
+    /// 检查当前音轨是否已播放完毕
+    ///
+    /// 条件：解码到达 EOF、缓冲区已被消费完，且设备缓冲中的在途帧
+    /// （IO 缓冲 + 安全偏移 + 设备延迟）也已播完，不会在最后的声音播出前切歌
+    pub fn is_track_finished(&self) -> bool {
+        self.decoder_state.eof_reached.load(Ordering::Acquire)
+            && self.decoder_state.device_drained.load(Ordering::Acquire)
+            && self.ring_buffer.available() == 0
+    }
+
+    /// 当前音轨是否为空（格式有效但解码不出任何样本）
+    ///
+    /// 解码线程在 EOF 时即视为预缓冲完成，不会停在 Buffering；
+    /// 供上层将其与正常播放完毕区分提示。中途解码失败不算空曲目
+    pub fn is_empty_track(&self) -> bool {
+        self.decoder_state.eof_reached.load(Ordering::Acquire)
+            && self.decoder_state.samples_decoded.load(Ordering::Relaxed) == 0
+            && !self.decoder_state.error_occurred.load(Ordering::Acquire)
+    }
+
+    /// 当前曲目是否跨采样率系列重采样（如 44.1k 源在锁定 48k 的设备上）
+    ///
+    /// 供界面醒目提示；未播放时为 false
+    pub fn cross_family_src(&self) -> bool {
+        self.summary.as_ref().is_some_and(PlaybackSummary::cross_family_src)
+    }
+
+    /// 当前音轨中途解码失败的错误信息
+    ///
+    /// 失败时曲目同样按结束处理（`is_track_finished()` 为 true），
+    /// 借此区分损坏文件与正常播放完毕
+    pub fn last_error(&self) -> Option<String> {
+        if !self.decoder_state.error_occurred.load(Ordering::Acquire) {
+            return None;
+        }
+        self.decoder_state
+            .last_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// 阻塞等待当前音轨播放完毕（EOF 且缓冲区已排空）
+    ///
+    /// `timeout` 为 None 时一直等待。返回是否已播放完毕；
+    /// 未加载音轨时立即返回 false
+    pub fn wait_finished(&self, timeout: Option<Duration>) -> bool {
+        if self.decoder_thread.is_none() {
+            return false;
+        }
+        self.completion.wait(timeout)
+    }
+
+    /// 注册曲目完成回调
+    ///
+    /// 每首曲目播放完毕（EOF 且缓冲区已排空）时在解码线程上调用，
+    /// 不在 IO 线程上运行。回调应尽快返回，新注册的回调替换旧回调
+    pub fn on_track_finished<F>(&mut self, callback: F)
+    where
+        F: Fn() + Send + 'static,
+    {
+        *self.completion.callback.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(Box::new(callback));
+    }
+
+    /// 订阅引擎事件（曲目开始/结束、停止、underrun、bit-perfect 变化等）
+    ///
+    /// 首次调用时才创建通道；再次调用会替换之前的订阅者（旧接收端不再收到事件）。
+    /// 事件不在 IO 线程上发送，接收端不读取时事件在通道中累积
+    pub fn events(&self) -> mpsc::Receiver<EngineEvent> {
+        self.events.subscribe()
+    }
 
-        self.output
+    /// 实时调度状态
+    ///
+    /// 返回 (IO 线程, 解码线程) 是否成功设置时间约束策略。
+    /// 失败时线程退回普通调度，是播放抖动的常见原因
+    pub fn realtime_active(&self) -> (bool, bool) {
+        let io_thread = self
+            .output
             .as_ref()
-            .map(|o| o.is_bit_perfect(source_rate))
-            .unwrap_or(false)
+            .map(|o| o.io_thread_realtime())
+            .unwrap_or(false);
+        let decoder_thread = self.decoder_state.realtime_active.load(Ordering::Acquire);
+        (io_thread, decoder_thread)
+    }
+
+    /// 解码吞吐相对实时播放的倍数
+    ///
+    /// 例如 25.0 表示解码速度是播放速度的 25 倍；接近 1 时容易欠载。
+    /// 按解码线程定期采样的耗时估算，尚无样本时返回 None
+    pub fn realtime_factor(&self) -> Option<f64> {
+        let sample_rate = self.current_info.as_ref()?.sample_rate;
+        let nanos = self.decoder_state.decode_nanos.load(Ordering::Relaxed);
+        let frames = self.decoder_state.decode_frames.load(Ordering::Relaxed);
+        realtime_factor(nanos as f64 / 1e9, frames as f64 / sample_rate as f64)
+    }
+
+    /// 获取输出模式信息
+    ///
+    /// 返回 (是否为HAL直接输出, 是否为独占模式)
+    pub fn output_mode(&self) -> Option<(bool, bool)> {
+        self.output.as_ref().map(|o| (o.is_hal_output(), o.is_exclusive_mode()))
+    }
+
+    /// 独占模式获取失败的原因
+    ///
+    /// 请求了独占模式但未获取时返回（如被其他进程持有），播放以共享模式继续
+    pub fn hog_mode_failure(&self) -> Option<&crate::audio::OutputError> {
+        self.output.as_ref().and_then(|o| o.hog_mode_failure())
+    }
+
+    /// 设置 IO 回调内的漂移校正量（ppm，0 为旁路），见 `AudioOutput::set_drift_ppm`
+    ///
+    /// 需在 `EngineConfig::output` 中开启 `callback_drift_correction`；
+    /// 未开启或没有输出时返回 false。切歌重建输出后需重新设置
+    pub fn set_drift_ppm(&self, ppm: f64) -> bool {
+        self.output.as_ref().is_some_and(|o| o.set_drift_ppm(ppm))
+    }
+
+    /// 当前漂移校正量（ppm，未开启时为 None）
+    pub fn drift_ppm(&self) -> Option<f64> {
+        self.output.as_ref().and_then(|o| o.drift_ppm())
+    }
+
+    /// 当前输出请求的配置与实际结果的对照（见 `NegotiationReport`）
+    pub fn negotiation_report(&self) -> Option<crate::audio::output::NegotiationReport> {
+        self.output.as_ref().and_then(|o| o.negotiation_report())
+    }
+
+    /// 当前输出设备支持的 IO 缓冲帧数范围（最小/最大）
+    pub fn buffer_size_range(&self) -> Option<(u32, u32)> {
+        self.output.as_ref().and_then(|o| o.buffer_size_range())
+    }
+
+    /// 设置软件增益（dB，0 为直通）
+    ///
+    /// 用于响度归一化；在解码线程写入 ring buffer 前生效，已缓冲的数据不受影响
+    pub fn set_gain_db(&self, gain_db: f64) {
+        let gain = if gain_db == 0.0 { 1.0 } else { 10f64.powf(gain_db / 20.0) };
+        self.decoder_state.gain_bits.store(gain.to_bits(), Ordering::Relaxed);
+    }
+
+    /// 当前软件增益（dB）
+    pub fn gain_db(&self) -> f64 {
+        let gain = f64::from_bits(self.decoder_state.gain_bits.load(Ordering::Relaxed));
+        20.0 * gain.log10()
+    }
+
+    /// 设置极性反转（所有声道取反）
+    ///
+    /// 在解码线程写入 ring buffer 前生效，已缓冲的数据不受影响
+    pub fn set_invert_polarity(&self, enabled: bool) {
+        self.decoder_state.invert_polarity.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 是否开启极性反转
+    pub fn invert_polarity(&self) -> bool {
+        self.decoder_state.invert_polarity.load(Ordering::Relaxed)
+    }
+
+    /// 设置左右声道交换（多声道时交换前两个声道，单声道无效）
+    ///
+    /// 在解码线程写入 ring buffer 前生效，已缓冲的数据不受影响
+    pub fn set_swap_channels(&self, enabled: bool) {
+        self.decoder_state.swap_channels.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 是否开启左右声道交换
+    pub fn swap_channels(&self) -> bool {
+        self.decoder_state.swap_channels.load(Ordering::Relaxed)
+    }
+
+    /// 设置单声道监听：前两个声道取平均后送回两个声道（检查相位/平衡用）
+    ///
+    /// 输出仍为原声道数，关闭后恢复原始立体声（bit-perfect）。
+    /// 在解码线程写入 ring buffer 前生效，已缓冲的数据不受影响
+    pub fn set_mono_monitor(&self, enabled: bool) {
+        self.decoder_state.mono_monitor.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 是否开启单声道监听
+    pub fn mono_monitor(&self) -> bool {
+        self.decoder_state.mono_monitor.load(Ordering::Relaxed)
+    }
+
+    /// 设置耳机交叉馈送强度（0.0-1.0，0 为关闭）
+    ///
+    /// 仅对立体声生效，单声道/多声道旁路。
+    /// 在解码线程写入 ring buffer 前生效，已缓冲的数据不受影响
+    pub fn set_crossfeed(&self, level: f64) {
+        self.decoder_state
+            .crossfeed_bits
+            .store(crossfeed_level(level).to_bits(), Ordering::Relaxed);
+    }
+
+    /// 当前交叉馈送强度
+    pub fn crossfeed(&self) -> f64 {
+        f64::from_bits(self.decoder_state.crossfeed_bits.load(Ordering::Relaxed))
+    }
+
+    /// 设置立体声宽度（M/S：1.0 不变，0.0 单声道，> 1.0 展宽，限制到 0.0-2.0）
+    ///
+    /// 仅对立体声生效，1.0 时旁路（bit-perfect）。
+    /// 在解码线程写入 ring buffer 前生效，已缓冲的数据不受影响
+    pub fn set_stereo_width(&self, factor: f64) {
+        self.decoder_state
+            .stereo_width_bits
+            .store(width::width_factor(factor).to_bits(), Ordering::Relaxed);
+    }
+
+    /// 当前立体声宽度
+    pub fn stereo_width(&self) -> f64 {
+        f64::from_bits(self.decoder_state.stereo_width_bits.load(Ordering::Relaxed))
+    }
+
+    /// 开关峰值限幅器并设置阈值（dBFS，限制到 -24-0）
+    ///
+    /// 开启后软件增益在限幅器内施加，超过阈值的峰值被压到阈值以下；
+    /// 关闭时完全旁路。在解码线程写入 ring buffer 前生效
+    pub fn set_limiter(&self, enabled: bool, threshold_dbfs: f32) {
+        self.decoder_state
+            .limiter_threshold_bits
+            .store(limiter::threshold_dbfs(threshold_dbfs).to_bits(), Ordering::Relaxed);
+        self.decoder_state.limiter_enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.decoder_state.limiter_reduction_bits.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// 限幅器阈值（dBFS），关闭时返回 None
+    pub fn limiter(&self) -> Option<f32> {
+        self.decoder_state
+            .limiter_enabled
+            .load(Ordering::Relaxed)
+            .then(|| f32::from_bits(self.decoder_state.limiter_threshold_bits.load(Ordering::Relaxed)))
+    }
+
+    /// 开关真峰值表
+    ///
+    /// 开启后解码线程对写入 ring buffer 的数据做 4 倍过采样，记录本曲最大真峰值；
+    /// 关闭时不做任何计算，已测得的峰值保留到下一首
+    pub fn set_true_peak_meter(&self, enabled: bool) {
+        self.decoder_state.true_peak_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 真峰值表是否开启
+    pub fn true_peak_meter(&self) -> bool {
+        self.decoder_state.true_peak_enabled.load(Ordering::Relaxed)
     }
+
+    /// 本曲目前为止的真峰值（dBTP，> 0 表示 DAC 重建后会过载），未测得时返回 None
+    pub fn true_peak_dbtp(&self) -> Option<f64> {
+        loudness::peak_to_db(f64::from_bits(
+            self.decoder_state.true_peak_bits.load(Ordering::Relaxed),
+        ))
+    }
+
+    /// 限幅器最近一块的最大衰减量（dB，未衰减或关闭时为 0）
+    pub fn limiter_reduction_db(&self) -> f32 {
+        f32::from_bits(self.decoder_state.limiter_reduction_bits.load(Ordering::Relaxed))
+    }
+
+    /// 限幅器是否正在衰减
+    pub fn limiter_active(&self) -> bool {
+        self.limiter().is_some() && self.limiter_reduction_db() > 0.0
+    }
+
+    /// 设置每声道延迟（样本数），用于扬声器时间对齐
+    ///
+    /// `delays[i]` 为第 i 个声道的延迟，未列出的声道不延迟；全 0 为直通。
+    /// 超过 `MAX_CHANNEL_DELAY` 或 `MAX_DELAY_CHANNELS` 时拒绝。
+    /// 在解码线程写入 ring buffer 前生效，已缓冲的数据不受影响
+    pub fn set_channel_delays(&self, delays: &[u32]) -> Result<(), EngineError> {
+        if delays.len() > MAX_DELAY_CHANNELS {
+            return Err(EngineError::InvalidArgument("too many channel delays"));
+        }
+        if delays.iter().any(|&d| d > MAX_CHANNEL_DELAY) {
+            return Err(EngineError::InvalidArgument("channel delay exceeds maximum"));
+        }
+        for (ch, slot) in self.decoder_state.channel_delays.iter().enumerate() {
+            slot.store(delays.get(ch).copied().unwrap_or(0), Ordering::Relaxed);
+        }
+        self.decoder_state.delays_changed.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// 当前每声道延迟（样本数）
+    pub fn channel_delays(&self) -> [u32; MAX_DELAY_CHANNELS] {
+        std::array::from_fn(|ch| self.decoder_state.channel_delays[ch].load(Ordering::Relaxed))
+    }
+
+    /// 检查是否为 bit-perfect 输出
+    ///
+    /// Bit-perfect 意味着：
+    /// - HAL 直接输出（绕过系统混音器）
+    /// - 独占模式
+    /// - 整数格式（无浮点转换）
+    /// - 无采样率转换（SRC）
+    /// - 未开启极性反转/声道交换/软件增益/声道延迟/交叉馈送（立体声），限幅器未在衰减
+    pub fn is_bit_perfect(&self) -> bool {
+        self.bit_perfect_reason().is_bit_perfect()
+    }
+
+    /// 不满足 bit-perfect 的第一个原因（满足时为 `BitPerfect`）
+    pub fn bit_perfect_reason(&self) -> BitPerfectStatus {
+        // 单声道上混时交叉馈送作用于输出的立体声
+        let stereo = self.current_info.is_some() && self.ring_channels() == 2;
+        let processing = [
+            (self.invert_polarity(), "polarity invert"),
+            (self.swap_channels(), "L/R swap"),
+            (self.mono_monitor(), "mono monitor"),
+            (self.gain_db() != 0.0, "software gain"),
+            (self.channel_delays().iter().any(|&d| d > 0), "channel delay"),
+            (stereo && self.crossfeed() > 0.0, "crossfeed"),
+            (stereo && self.stereo_width() != 1.0, "stereo width"),
+            (self.limiter_active(), "limiter"),
+            (self.drift_ppm().is_some_and(|ppm| ppm != 0.0), "drift correction"),
+        ];
+        if let Some(&(_, what)) = processing.iter().find(|(active, _)| *active) {
+            return BitPerfectStatus::Processing(what);
+        }
+
+        match (&self.output, &self.summary) {
+            (Some(_), Some(summary)) => summary.bit_perfect_status(),
+            _ => BitPerfectStatus::Idle,
+        }
+    }
+}
+
+/// 极性反转：所有样本取反
+///
+/// `i32::MIN` 无法取反，饱和到 `i32::MAX`
+fn invert_polarity(samples: &mut [i32]) {
+    for sample in samples.iter_mut() {
+        *sample = sample.saturating_neg();
+    }
+}
+
+/// 软件增益（超出 i32 范围时饱和）
+fn apply_gain(samples: &mut [i32], gain: f64) {
+    for sample in samples.iter_mut() {
+        // f64 → i32 的 `as` 转换本身即饱和
+        *sample = (*sample as f64 * gain) as i32;
+    }
+}
+
+/// 交换每帧的前两个声道（交织数据，立体声即 L/R 互换）
+fn swap_channels(samples: &mut [i32], channels: usize) {
+    for frame in samples.chunks_exact_mut(channels) {
+        frame.swap(0, 1);
+    }
+}
+
+/// 单声道上混为立体声：每个样本复制到左右声道
+fn upmix_mono(mono: &[i32], stereo: &mut Vec<i32>) {
+    stereo.clear();
+    stereo.extend(mono.iter().flat_map(|&s| [s, s]));
+}
+
+/// 下一首能否沿用当前输出：同采样率的单声道曲目接在立体声输出之后
+///
+/// 上混后的格式与当前输出一致；位深不超过当前输出所按的位深（不增加截断）
+fn can_upmix_mono(current: AudioFormat, info: &AudioInfo) -> bool {
+    current.channels == 2
+        && info.channels == 1
+        && current.sample_rate == info.sample_rate
+        && info.bit_depth.unwrap_or(24) <= current.bits_per_sample as u32
+}
+
+/// 单声道折叠：每帧前两个声道替换为二者的平均（向下取整）
+fn fold_to_mono(samples: &mut [i32], channels: usize) {
+    for frame in samples.chunks_exact_mut(channels) {
+        let mid = ((frame[0] as i64 + frame[1] as i64) >> 1) as i32;
+        frame[0] = mid;
+        frame[1] = mid;
+    }
+}
+
+/// 每声道延迟线
+///
+/// 每声道一段长度为 `MAX_CHANNEL_DELAY + 1` 的环形历史，共用写位置；
+/// 创建时一次性分配，处理时不分配
+struct DelayLines {
+    channels: usize,
+    /// 各声道延迟（样本数）
+    delays: [usize; MAX_DELAY_CHANNELS],
+    /// 声道 ch 的历史位于 `[ch * LEN, (ch + 1) * LEN)`
+    history: Vec<i32>,
+    /// 当前写位置
+    pos: usize,
+}
+
+impl DelayLines {
+    const LEN: usize = MAX_CHANNEL_DELAY as usize + 1;
+
+    fn new(channels: usize) -> Self {
+        let lines = channels.min(MAX_DELAY_CHANNELS);
+        Self {
+            channels,
+            delays: [0; MAX_DELAY_CHANNELS],
+            history: vec![0; lines * Self::LEN],
+            pos: 0,
+        }
+    }
+
+    /// 重新加载延迟设置并清空历史（延迟变化时旧数据不再对齐）
+    fn load(&mut self, delays: &[AtomicU32; MAX_DELAY_CHANNELS]) {
+        for (slot, delay) in self.delays.iter_mut().zip(delays) {
+            *slot = (delay.load(Ordering::Relaxed) as usize).min(MAX_CHANNEL_DELAY as usize);
+        }
+        self.clear();
+    }
+
+    /// 清空历史（seek 后不把旧位置的声音带到新位置）
+    fn clear(&mut self) {
+        self.history.fill(0);
+        self.pos = 0;
+    }
+
+    /// 是否有声道设置了延迟
+    fn is_active(&self) -> bool {
+        self.delays[..self.channels.min(MAX_DELAY_CHANNELS)]
+            .iter()
+            .any(|&d| d > 0)
+    }
+
+    /// 最大延迟（帧），即 EOF 后仍留在延迟线中的帧数
+    fn tail_frames(&self) -> usize {
+        self.delays[..self.channels.min(MAX_DELAY_CHANNELS)]
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 对交织样本施加延迟（前 `delay` 个样本输出静音）
+    fn process(&mut self, samples: &mut [i32]) {
+        let lines = self.channels.min(MAX_DELAY_CHANNELS);
+        for frame in samples.chunks_exact_mut(self.channels) {
+            for (ch, sample) in frame.iter_mut().enumerate().take(lines) {
+                let delay = self.delays[ch];
+                if delay == 0 {
+                    continue;
+                }
+                let line = &mut self.history[ch * Self::LEN..(ch + 1) * Self::LEN];
+                line[self.pos] = *sample;
+                *sample = line[(self.pos + Self::LEN - delay) % Self::LEN];
+            }
+            self.pos = (self.pos + 1) % Self::LEN;
+        }
+    }
+}
+
+/// 写入 ring buffer，空间不足时记录写入溢出
+///
+/// 溢出说明解码快于消费（或 IO 线程停滞），未写入的样本被丢弃
+fn write_samples(ring_buffer: &RingBuffer<i32>, stats: &PlaybackStats, samples: &[i32]) -> usize {
+    let written = ring_buffer.write(samples);
+    if written < samples.len() {
+        stats.record_write_overflow();
+    }
+    written
+}
+
+/// 交叉馈送强度限制到 0.0-1.0（非法值视为关闭）
+fn crossfeed_level(level: f64) -> f64 {
+    if level.is_finite() {
+        level.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// 位置所在的标记序号（最后一个起点 <= position 的标记）
+fn current_marker(markers: &[(f64, String)], position_secs: f64) -> Option<usize> {
+    markers
+        .partition_point(|(start, _)| *start <= position_secs)
+        .checked_sub(1)
+}
+
+/// 下一个标记：起点严格晚于当前位置的第一个标记
+fn next_marker(markers: &[(f64, String)], position_secs: f64) -> Option<usize> {
+    let index = markers.partition_point(|(start, _)| *start <= position_secs);
+    (index < markers.len()).then_some(index)
+}
+
+/// 上一个标记：进入当前标记超过 `PREV_CHAPTER_RESTART_SECS` 时为当前标记，否则为前一个
+fn prev_marker(markers: &[(f64, String)], position_secs: f64) -> Option<usize> {
+    let current = current_marker(markers, position_secs)?;
+    if position_secs - markers[current].0 > PREV_CHAPTER_RESTART_SECS || current == 0 {
+        Some(current)
+    } else {
+        Some(current - 1)
+    }
+}
+
+/// 执行一次等待动作
+fn wait_for_space(action: WaitAction) {
+    match action {
+        WaitAction::Spin(count) => {
+            for _ in 0..count {
+                std::hint::spin_loop();
+            }
+        }
+        WaitAction::YieldSpin(count) => {
+            thread::yield_now();
+            for _ in 0..count {
+                std::hint::spin_loop();
+            }
+        }
+        WaitAction::Sleep(us) => thread::sleep(Duration::from_micros(us)),
+    }
+}
+
+/// 解码线程的读取块大小与最小空闲阈值（样本数）
+///
+/// 空闲空间不足阈值时等待；阈值为读取块的 1/4（默认 4096 帧对应 1024 帧）
+fn decode_thresholds(chunk_frames: usize, channels: usize) -> (usize, usize) {
+    let chunk_frames = chunk_frames.max(4);
+    (chunk_frames * channels, chunk_frames / 4 * channels)
+}
+
+/// 输出看门狗判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WatchdogVerdict {
+    /// 回调已触发
+    Alive,
+    /// 尚未触发，继续等待
+    Waiting,
+    /// 超时仍未触发
+    Stalled,
+}
+
+/// 根据回调次数和启动后经过的时间判定输出是否正常
+fn watchdog_verdict(callback_count: u64, elapsed: Duration) -> WatchdogVerdict {
+    if callback_count > 0 {
+        WatchdogVerdict::Alive
+    } else if elapsed >= OUTPUT_WATCHDOG_TIMEOUT {
+        WatchdogVerdict::Stalled
+    } else {
+        WatchdogVerdict::Waiting
+    }
+}
+
+/// 最后的样本被回调取走后，设备缓冲中的在途帧是否已播完
+///
+/// `callbacks` 为之后的回调次数，每次推进 `period_frames` 帧；
+/// 周期未知（系统混音器）或没有延迟时视为已播完
+fn device_tail_played(callbacks: u64, period_frames: u32, tail_frames: u32) -> bool {
+    period_frames == 0 || callbacks * period_frames as u64 >= tail_frames as u64
+}
+
+/// 扣除输出延迟后的可闻位置（开头不小于 0；暂停时不扣除）
+fn audible_position(position_secs: f64, latency_secs: f64, paused: bool) -> f64 {
+    if paused {
+        position_secs
+    } else {
+        (position_secs - latency_secs).max(0.0)
+    }
+}
+
+/// 等待 ring buffer 被消费端读空
+///
+/// 缓冲量持续 `stall` 不减少时放弃等待，返回是否已排空
+fn wait_drained(ring_buffer: &RingBuffer<i32>, stall: Duration) -> bool {
+    let mut last = ring_buffer.available();
+    let mut last_progress = Instant::now();
+    while last > 0 {
+        thread::sleep(Duration::from_millis(2));
+        let available = ring_buffer.available();
+        if available < last {
+            last_progress = Instant::now();
+        } else if last_progress.elapsed() >= stall {
+            return false;
+        }
+        last = available;
+    }
+    true
+}
+
+/// 缓冲样本数换算为毫秒
+fn buffered_ms(available: usize, channels: u32, sample_rate: u32) -> f64 {
+    if channels == 0 || sample_rate == 0 {
+        return 0.0;
+    }
+    available as f64 / channels as f64 / sample_rate as f64 * 1000.0
+}
+
+/// 相对预缓冲目标的填充比例（目标为 0 时返回 0）
+fn target_fill_ratio(available: usize, target: usize) -> f64 {
+    if target == 0 {
+        return 0.0;
+    }
+    available as f64 / target as f64
+}
+
+/// 预缓冲 ETA 计算
+///
+/// 净填充速率 = (已解码 - 已播放) / 已用时间，
+/// ETA = 距目标的剩余样本数 / 净填充速率
+fn buffering_eta(
+    samples_decoded: u64,
+    samples_played: u64,
+    elapsed_secs: f64,
+    buffered: usize,
+    target: usize,
+) -> Option<f64> {
+    if buffered >= target {
+        return Some(0.0);
+    }
+    if elapsed_secs <= 0.0 {
+        return None;
+    }
+    let fill_rate = samples_decoded.saturating_sub(samples_played) as f64 / elapsed_secs;
+    if fill_rate <= 0.0 {
+        return None;
+    }
+    Some((target - buffered) as f64 / fill_rate)
+}
+
+/// 实时倍数 = 音频时长 / 解码耗时（任一为 0 时无法估算）
+fn realtime_factor(decode_secs: f64, audio_secs: f64) -> Option<f64> {
+    if decode_secs <= 0.0 || audio_secs <= 0.0 {
+        return None;
+    }
+    Some(audio_secs / decode_secs)
 }
 
 impl Drop for Engine {
@@ -646,6 +2967,7 @@ impl Drop for Engine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::decode::make_wav;
 
     #[test]
     fn test_engine_config_default() {
@@ -653,4 +2975,1185 @@ mod tests {
         assert_eq!(config.buffer_frames, 48000 * 2 * 2);
         assert_eq!(config.prebuffer_ratio, 0.5);
     }
+
+    #[test]
+    fn test_realtime_active_reflects_flags() {
+        let engine = Engine::new(EngineConfig::default()).unwrap();
+        assert_eq!(engine.realtime_active(), (false, false));
+
+        engine.decoder_state.realtime_active.store(true, Ordering::Release);
+        // 无输出时 IO 线程状态为 false
+        assert_eq!(engine.realtime_active(), (false, true));
+    }
+
+    #[test]
+    fn test_buffered_ms() {
+        // 96000 样本 @ 48kHz 立体声 = 48000 帧 = 1000ms
+        assert!((buffered_ms(96_000, 2, 48_000) - 1000.0).abs() < 1e-9);
+        // 4410 样本 @ 44.1kHz 单声道 = 100ms
+        assert!((buffered_ms(4_410, 1, 44_100) - 100.0).abs() < 1e-9);
+        assert_eq!(buffered_ms(0, 2, 48_000), 0.0);
+        assert_eq!(buffered_ms(1024, 0, 48_000), 0.0);
+    }
+
+    #[test]
+    fn test_target_fill_ratio() {
+        // 容量 2^20、预缓冲 0.5 → 目标 524288 样本
+        let target = (1_048_576_f64 * 0.5) as usize;
+        assert_eq!(target_fill_ratio(target, target), 1.0);
+        assert_eq!(target_fill_ratio(target / 2, target), 0.5);
+        // 稳态下解码线程会填满缓冲区，超过目标
+        assert_eq!(target_fill_ratio(target * 2, target), 2.0);
+        assert_eq!(target_fill_ratio(0, target), 0.0);
+        assert_eq!(target_fill_ratio(1024, 0), 0.0);
+    }
+
+    #[test]
+    fn test_buffering_eta() {
+        // 0.5 秒解码 100k 样本、播放 0 → 200k 样本/秒；剩余 100k → 0.5 秒
+        let eta = buffering_eta(100_000, 0, 0.5, 100_000, 200_000).unwrap();
+        assert!((eta - 0.5).abs() < 1e-9);
+
+        // 播放消耗一半吞吐：净速率 100k 样本/秒 → 1 秒
+        let eta = buffering_eta(100_000, 50_000, 0.5, 50_000, 150_000).unwrap();
+        assert!((eta - 1.0).abs() < 1e-9);
+
+        // 已达目标
+        assert_eq!(buffering_eta(0, 0, 0.0, 200_000, 200_000), Some(0.0));
+        // 尚无数据 / 无净增长
+        assert_eq!(buffering_eta(0, 0, 0.0, 0, 200_000), None);
+        assert_eq!(buffering_eta(1000, 1000, 1.0, 0, 200_000), None);
+    }
+
+    #[test]
+    fn test_realtime_factor() {
+        // 0.4 秒解码出 10 秒音频 → 25 倍
+        let factor = realtime_factor(0.4, 10.0).unwrap();
+        assert!((factor - 25.0).abs() < 1e-9);
+        // 解码比播放慢
+        let factor = realtime_factor(2.0, 1.0).unwrap();
+        assert!((factor - 0.5).abs() < 1e-9);
+        // 尚无采样
+        assert_eq!(realtime_factor(0.0, 0.0), None);
+        assert_eq!(realtime_factor(0.1, 0.0), None);
+    }
+
+    #[test]
+    fn test_invert_polarity() {
+        let mut samples = [0, 1, -1, 1 << 30, i32::MAX, i32::MIN];
+        invert_polarity(&mut samples);
+        assert_eq!(samples, [0, -1, 1, -(1 << 30), -i32::MAX, i32::MAX]);
+    }
+
+    #[test]
+    fn test_apply_gain() {
+        let mut samples = [1 << 20, -(1 << 20), i32::MAX / 2, i32::MIN];
+        apply_gain(&mut samples, 0.5);
+        assert_eq!(samples, [1 << 19, -(1 << 19), i32::MAX / 4, i32::MIN / 2]);
+
+        // 提升饱和
+        let mut samples = [i32::MAX / 2 + 1, i32::MIN];
+        apply_gain(&mut samples, 4.0);
+        assert_eq!(samples, [i32::MAX, i32::MIN]);
+
+        let engine = Engine::new(EngineConfig::default()).unwrap();
+        assert_eq!(engine.gain_db(), 0.0);
+        engine.set_gain_db(-6.0);
+        assert!((engine.gain_db() - -6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_swap_channels() {
+        let mut stereo = [1, 2, 3, 4, 5, 6];
+        swap_channels(&mut stereo, 2);
+        assert_eq!(stereo, [2, 1, 4, 3, 6, 5]);
+
+        // 多声道只交换前两个声道
+        let mut surround = [1, 2, 3, 4, 5, 6];
+        swap_channels(&mut surround, 3);
+        assert_eq!(surround, [2, 1, 3, 5, 4, 6]);
+
+        // 开关默认关闭
+        let engine = Engine::new(EngineConfig::default()).unwrap();
+        assert!(!engine.invert_polarity());
+        assert!(!engine.swap_channels());
+        engine.set_swap_channels(true);
+        assert!(engine.swap_channels());
+    }
+
+    #[test]
+    fn test_fold_to_mono() {
+        let input = [100, 300, i32::MAX, i32::MAX, i32::MIN, i32::MAX, -3, 0];
+        let mut stereo = input;
+        fold_to_mono(&mut stereo, 2);
+        for (frame, original) in stereo.chunks_exact(2).zip(input.chunks_exact(2)) {
+            let average = ((original[0] as i64 + original[1] as i64) as f64 / 2.0).floor();
+            assert_eq!(frame[0], frame[1]);
+            assert_eq!(frame[0] as f64, average);
+        }
+
+        // 多声道只折叠前两个声道
+        let mut surround = [2, 4, 9, 6, 8, 9];
+        fold_to_mono(&mut surround, 3);
+        assert_eq!(surround, [3, 3, 9, 7, 7, 9]);
+
+        // 关闭时为直通：不参与 bit-perfect 判定
+        let engine = Engine::new(EngineConfig::default()).unwrap();
+        assert!(!engine.mono_monitor());
+        assert_eq!(engine.bit_perfect_reason(), BitPerfectStatus::Idle);
+        engine.set_mono_monitor(true);
+        assert_eq!(engine.bit_perfect_reason(), BitPerfectStatus::Processing("mono monitor"));
+        engine.set_mono_monitor(false);
+        assert_eq!(engine.bit_perfect_reason(), BitPerfectStatus::Idle);
+    }
+
+    #[test]
+    fn test_write_overflow_recorded() {
+        let ring_buffer = RingBuffer::new(8);
+        let stats = PlaybackStats::new();
+
+        assert_eq!(write_samples(&ring_buffer, &stats, &[1; 4]), 4);
+        assert_eq!(stats.write_overflow_count(), 0);
+
+        // 剩余空间不足：只写入一部分并记录溢出
+        let free = ring_buffer.free_space();
+        assert_eq!(write_samples(&ring_buffer, &stats, &[2; 8]), free);
+        assert_eq!(stats.write_overflow_count(), 1);
+
+        // 已满时写入同样记录
+        assert_eq!(write_samples(&ring_buffer, &stats, &[3; 2]), 0);
+        assert_eq!(stats.write_overflow_count(), 2);
+
+        stats.reset();
+        assert_eq!(stats.write_overflow_count(), 0);
+    }
+
+    #[test]
+    fn test_crossfeed_level() {
+        let engine = Engine::new(EngineConfig {
+            crossfeed: 0.5,
+            ..EngineConfig::default()
+        })
+        .unwrap();
+        assert_eq!(engine.crossfeed(), 0.5);
+        engine.set_crossfeed(2.0);
+        assert_eq!(engine.crossfeed(), 1.0);
+        engine.set_crossfeed(f64::NAN);
+        assert_eq!(engine.crossfeed(), 0.0);
+    }
+
+    #[test]
+    fn test_stereo_width_factor() {
+        let engine = Engine::new(EngineConfig {
+            stereo_width: 0.0,
+            ..EngineConfig::default()
+        })
+        .unwrap();
+        assert_eq!(engine.stereo_width(), 0.0);
+        engine.set_stereo_width(1.5);
+        assert_eq!(engine.stereo_width(), 1.5);
+        engine.set_stereo_width(10.0);
+        assert_eq!(engine.stereo_width(), width::MAX_WIDTH);
+        engine.set_stereo_width(f64::NAN);
+        assert_eq!(engine.stereo_width(), 1.0);
+    }
+
+    #[test]
+    fn test_limiter_settings() {
+        let engine = Engine::new(EngineConfig::default()).unwrap();
+        assert_eq!(engine.limiter(), None);
+        assert!(!engine.limiter_active());
+
+        engine.set_limiter(true, -3.0);
+        assert_eq!(engine.limiter(), Some(-3.0));
+        // 开启但未衰减：仍为 bit-perfect 数据通路
+        assert!(!engine.limiter_active());
+        assert_eq!(engine.bit_perfect_reason(), BitPerfectStatus::Idle);
+
+        engine.decoder_state.limiter_reduction_bits.store(2.0f32.to_bits(), Ordering::Relaxed);
+        assert!(engine.limiter_active());
+        assert_eq!(engine.bit_perfect_reason(), BitPerfectStatus::Processing("limiter"));
+
+        engine.set_limiter(false, 0.0);
+        assert_eq!(engine.limiter(), None);
+        assert_eq!(engine.limiter_reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn test_thread_affinity_override() {
+        // 默认保持音频组 tag 1
+        let mut applied = None;
+        let config = EngineConfig::default();
+        assert!(apply_thread_affinity(config.thread_affinity, |tag| applied = Some(tag)));
+        assert_eq!(applied, Some(AUDIO_AFFINITY_TAG));
+
+        let mut applied = None;
+        assert!(apply_thread_affinity(Some(3), |tag| applied = Some(tag)));
+        assert_eq!(applied, Some(3));
+
+        // 关闭：不调用 thread_policy_set
+        let mut called = false;
+        assert!(!apply_thread_affinity(None, |_| called = true));
+        assert!(!called);
+    }
+
+    #[test]
+    fn test_true_peak_settings() {
+        let engine = Engine::new(EngineConfig {
+            true_peak_meter: true,
+            ..EngineConfig::default()
+        })
+        .unwrap();
+        assert!(engine.true_peak_meter());
+        assert_eq!(engine.true_peak_dbtp(), None);
+
+        // 真峰值表只测量，不影响 bit-perfect
+        engine.decoder_state.true_peak_bits.store(2.0f64.to_bits(), Ordering::Relaxed);
+        let dbtp = engine.true_peak_dbtp().unwrap();
+        assert!((dbtp - 6.02).abs() < 0.01, "{}", dbtp);
+        assert_eq!(engine.bit_perfect_reason(), BitPerfectStatus::Idle);
+
+        engine.set_true_peak_meter(false);
+        assert!(!engine.true_peak_meter());
+    }
+
+    #[test]
+    fn test_switch_output_config() {
+        let mut config = EngineConfig::default();
+        config.output.fade_ms = 20;
+        config.output.dither_seed = Some(7);
+        config.output.device_settings = true;
+        config.output.buffer_frames = 256;
+
+        apply_output_mode(&mut config, false, false);
+        let output = track_output_config(&config, 96000);
+        assert!(!output.use_hal);
+        assert!(!output.exclusive_mode);
+        // 显式选择不被设备缓存覆盖，其余输出设置保持不变
+        assert!(!output.device_settings);
+        assert_eq!(output.buffer_frames, 256);
+        assert_eq!(output.sample_rate, 96000);
+        assert_eq!(output.fade_ms, 20);
+        assert_eq!(output.dither_seed, Some(7));
+
+        // 停止时拒绝切换：配置与播放位置均不变
+        let mut engine = Engine::new(config).unwrap();
+        engine.stats.set_samples_played(48000 * 2);
+        let before = engine.stats().position_secs;
+        assert!(matches!(
+            engine.switch_output(true, true),
+            Err(EngineError::InvalidState(_))
+        ));
+        assert!(!engine.config.output.use_hal);
+        assert_eq!(engine.stats().position_secs, before);
+    }
+
+    #[test]
+    fn test_decode_thresholds() {
+        // 默认值与原硬编码一致
+        assert_eq!(decode_thresholds(4096, 2), (8192, 2048));
+        assert_eq!(decode_thresholds(16384, 2), (32768, 8192));
+        assert_eq!(decode_thresholds(8192, 6), (49152, 12288));
+        // 过小的配置至少保留一帧阈值
+        assert_eq!(decode_thresholds(0, 2), (8, 2));
+        assert_eq!(EngineConfig::default().decode_chunk_frames, 4096);
+    }
+
+    fn info(sample_rate: u32, channels: u32) -> AudioInfo {
+        AudioInfo {
+            sample_rate,
+            channels,
+            channel_layout: Vec::new(),
+            bit_depth: Some(24),
+            total_frames: None,
+            duration_secs: None,
+            format: "FLAC".to_string(),
+            codec: "flac".to_string(),
+            seekable: true,
+            bitrate_bps: None,
+            lossless: true,
+            encoder_delay: 0,
+            encoder_padding: 0,
+            tracks: Vec::new(),
+            track_index: 0,
+            chapters: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_auto_buffer_samples() {
+
+        // 16/44.1 本地：1 秒
+        assert_eq!(auto_buffer_samples(&info(44100, 2), SourceKind::Local), 88_200);
+        // 24/96 本地：1.5 秒
+        assert_eq!(auto_buffer_samples(&info(96000, 2), SourceKind::Local), 288_000);
+        // 32/384 本地：3 秒
+        assert_eq!(auto_buffer_samples(&info(384000, 2), SourceKind::Local), 2_304_000);
+        // 网络流和管道：3 倍余量
+        assert_eq!(auto_buffer_samples(&info(44100, 2), SourceKind::Network), 264_600);
+        assert_eq!(auto_buffer_samples(&info(48000, 1), SourceKind::Stdin), 144_000);
+        // 极端声道数 × 高采样率的网络流不超过上限
+        assert_eq!(
+            auto_buffer_samples(&info(384000, 16), SourceKind::Network),
+            MAX_BUFFER_FRAMES
+        );
+        // 采样率未知时至少为下限
+        assert_eq!(auto_buffer_samples(&info(0, 0), SourceKind::Local), MIN_BUFFER_FRAMES);
+
+        assert_eq!(SourceKind::of(Path::new("-")), SourceKind::Stdin);
+        assert_eq!(SourceKind::of(Path::new("http://host/a.flac")), SourceKind::Network);
+        assert_eq!(SourceKind::of(Path::new("/music/a.flac")), SourceKind::Local);
+    }
+
+    #[test]
+    fn test_ring_buffer_follows_track_rate() {
+        let mut engine = Engine::new(EngineConfig {
+            buffer_ms: Some(500),
+            ..EngineConfig::default()
+        })
+        .unwrap();
+
+        // 500ms @ 44.1k 立体声 = 44100 样本 → 65536
+        engine.prepare_ring_buffer(&info(44100, 2), SourceKind::Local);
+        assert_eq!(engine.ring_buffer.capacity(), 65536);
+        // 500ms @ 384k 立体声 = 384000 样本 → 524288
+        engine.prepare_ring_buffer(&info(384000, 2), SourceKind::Local);
+        assert_eq!(engine.ring_buffer.capacity(), 524288);
+        // 切回低采样率时缩小
+        engine.prepare_ring_buffer(&info(48000, 2), SourceKind::Local);
+        assert_eq!(engine.ring_buffer.capacity(), 65536);
+
+        // 未指定时长也未开启自动缓冲：保持创建时的容量
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        let initial = engine.ring_buffer.capacity();
+        engine.prepare_ring_buffer(&info(384000, 2), SourceKind::Local);
+        assert_eq!(engine.ring_buffer.capacity(), initial);
+    }
+
+    #[test]
+    fn test_watchdog_verdict() {
+        let ms = Duration::from_millis;
+        assert_eq!(watchdog_verdict(0, ms(0)), WatchdogVerdict::Waiting);
+        assert_eq!(watchdog_verdict(0, ms(999)), WatchdogVerdict::Waiting);
+        assert_eq!(watchdog_verdict(0, ms(1000)), WatchdogVerdict::Stalled);
+        assert_eq!(watchdog_verdict(0, ms(5000)), WatchdogVerdict::Stalled);
+        // 回调一旦触发即视为正常，与耗时无关
+        assert_eq!(watchdog_verdict(1, ms(10)), WatchdogVerdict::Alive);
+        assert_eq!(watchdog_verdict(3, ms(5000)), WatchdogVerdict::Alive);
+    }
+
+    #[test]
+    fn test_playback_summary() {
+        let mut info = AudioInfo {
+            sample_rate: 96000,
+            channels: 2,
+            channel_layout: Vec::new(),
+            bit_depth: Some(24),
+            total_frames: None,
+            duration_secs: None,
+            format: "FLAC".to_string(),
+            codec: "flac".to_string(),
+            seekable: true,
+            bitrate_bps: None,
+            lossless: true,
+            encoder_delay: 0,
+            encoder_padding: 0,
+            tracks: Vec::new(),
+            track_index: 0,
+            chapters: Vec::new(),
+        };
+
+        let summary = PlaybackSummary::new(
+            &info,
+            AudioFormat::new(96000, 2, 32),
+            OutputFormatMode::Int32,
+            true,
+            true,
+        );
+        assert!(!summary.resampled);
+        assert!(!summary.cross_family_src());
+        assert_eq!(summary.output_bits, 32);
+        assert_eq!(summary.signal_path(), "FLAC 24/96 → Int32 HAL exclusive, no SRC");
+
+        // 有损源 + 系统混音器 + SRC
+        info.format = "MP3".to_string();
+        info.bit_depth = None;
+        info.sample_rate = 44100;
+        let mut summary = PlaybackSummary::new(
+            &info,
+            AudioFormat::new(48000, 2, 32),
+            OutputFormatMode::Float32,
+            false,
+            false,
+        );
+        assert!(summary.resampled);
+        assert!(summary.cross_family_src());
+        assert_eq!(summary.signal_path(), "MP3 44.1kHz → Float32 System Mixer, SRC 44.1→48kHz");
+
+        // HAL 回调不触发后回退到系统混音器
+        summary.fell_back = true;
+        assert_eq!(
+            summary.signal_path(),
+            "MP3 44.1kHz → Float32 System Mixer (HAL fallback), SRC 44.1→48kHz"
+        );
+
+        // 裁剪了编码器延迟
+        info.encoder_delay = 1105;
+        let summary = PlaybackSummary::new(
+            &info,
+            AudioFormat::new(44100, 2, 32),
+            OutputFormatMode::Float32,
+            false,
+            false,
+        );
+        assert!(summary.gapless);
+        assert!(!summary.cross_family_src());
+        assert_eq!(
+            summary.signal_path(),
+            "MP3 44.1kHz → Float32 System Mixer, no SRC, gapless"
+        );
+    }
+
+    #[test]
+    fn test_bit_perfect_status() {
+        let info = AudioInfo {
+            sample_rate: 44100,
+            channels: 2,
+            channel_layout: Vec::new(),
+            bit_depth: Some(16),
+            total_frames: None,
+            duration_secs: None,
+            format: "FLAC".to_string(),
+            codec: "flac".to_string(),
+            seekable: true,
+            bitrate_bps: None,
+            lossless: true,
+            encoder_delay: 0,
+            encoder_padding: 0,
+            tracks: Vec::new(),
+            track_index: 0,
+            chapters: Vec::new(),
+        };
+        let status = |rate, mode, hal, exclusive| {
+            PlaybackSummary::new(&info, AudioFormat::new(rate, 2, 32), mode, hal, exclusive)
+                .bit_perfect_status()
+        };
+
+        assert_eq!(
+            status(44100, OutputFormatMode::Int32, true, true),
+            BitPerfectStatus::BitPerfect
+        );
+        // 16-bit 源输出 Int16 无需 dither
+        assert_eq!(
+            status(44100, OutputFormatMode::Int16, true, true),
+            BitPerfectStatus::BitPerfect
+        );
+        assert_eq!(
+            status(44100, OutputFormatMode::Float32, false, false),
+            BitPerfectStatus::SystemMixer
+        );
+        assert_eq!(
+            status(44100, OutputFormatMode::Int32, true, false),
+            BitPerfectStatus::SharedMode
+        );
+        assert_eq!(
+            status(44100, OutputFormatMode::Float32, true, true),
+            BitPerfectStatus::FloatOutput
+        );
+
+        let resampled = status(48000, OutputFormatMode::Int32, true, true);
+        assert_eq!(resampled, BitPerfectStatus::Resampled { from: 44100, to: 48000 });
+        assert_eq!(resampled.to_string(), "SRC 44.1→48k");
+
+        let mut summary = PlaybackSummary::new(
+            &info,
+            AudioFormat::new(44100, 2, 16),
+            OutputFormatMode::Int16,
+            true,
+            true,
+        );
+        summary.source_bits = Some(24);
+        assert_eq!(summary.bit_perfect_status(), BitPerfectStatus::Truncated { from_bits: 24 });
+        assert_eq!(summary.bit_perfect_status().to_string(), "24→16-bit dither");
+    }
+
+    #[test]
+    fn test_bit_perfect_reason_reports_processing() {
+        let engine = Engine::new(EngineConfig::default()).unwrap();
+        assert_eq!(engine.bit_perfect_reason(), BitPerfectStatus::Idle);
+        assert!(!engine.is_bit_perfect());
+
+        engine.set_invert_polarity(true);
+        assert_eq!(
+            engine.bit_perfect_reason(),
+            BitPerfectStatus::Processing("polarity invert")
+        );
+    }
+
+    #[test]
+    fn test_config_validate_buffer_bounds() {
+        let with_buffer = |buffer_frames| EngineConfig {
+            buffer_frames,
+            decode_chunk_frames: MIN_BUFFER_FRAMES / 2,
+            ..EngineConfig::default()
+        };
+        assert!(EngineConfig::default().validate().is_ok());
+        assert!(with_buffer(MIN_BUFFER_FRAMES).validate().is_ok());
+        assert!(with_buffer(MAX_BUFFER_FRAMES).validate().is_ok());
+        assert!(with_buffer(MIN_BUFFER_FRAMES - 1).validate().is_err());
+        assert!(with_buffer(MAX_BUFFER_FRAMES + 1).validate().is_err());
+        assert!(with_buffer(0).validate().is_err());
+        // next_power_of_two 会溢出的值不能到达 Engine::new 的分配
+        assert!(matches!(
+            Engine::new(with_buffer(usize::MAX)),
+            Err(EngineError::InvalidConfig(_))
+        ));
+
+        let with_ratio = |prebuffer_ratio| EngineConfig {
+            prebuffer_ratio,
+            ..EngineConfig::default()
+        };
+        assert!(with_ratio(1.0).validate().is_ok());
+        assert!(with_ratio(0.0).validate().is_err());
+        assert!(with_ratio(1.5).validate().is_err());
+        assert!(with_ratio(f64::NAN).validate().is_err());
+
+        // 立体声读取块须放得进缓冲区（默认 4096 帧需要至少 8192 样本）
+        let with_chunk = |decode_chunk_frames, buffer_frames| EngineConfig {
+            decode_chunk_frames,
+            buffer_frames,
+            ..EngineConfig::default()
+        };
+        assert!(with_chunk(4096, 8192).validate().is_ok());
+        assert!(with_chunk(4096, 8191).validate().is_err());
+        assert!(with_chunk(usize::MAX, MAX_BUFFER_FRAMES).validate().is_err());
+        let err = with_chunk(65536, 96000).validate().unwrap_err().to_string();
+        assert!(err.contains("--decode-chunk-frames"), "{}", err);
+    }
+
+    #[test]
+    fn test_inter_track_gap() {
+        let with_gap = |inter_track_gap_ms| EngineConfig {
+            inter_track_gap_ms,
+            ..EngineConfig::default()
+        };
+        assert_eq!(EngineConfig::default().inter_track_gap(false), None);
+        assert_eq!(
+            with_gap(2000).inter_track_gap(false),
+            Some(Duration::from_secs(2))
+        );
+        // 单曲循环不插入间隔
+        assert_eq!(with_gap(2000).inter_track_gap(true), None);
+
+        assert!(with_gap(MAX_INTER_TRACK_GAP_MS).validate().is_ok());
+        assert!(with_gap(MAX_INTER_TRACK_GAP_MS + 1).validate().is_err());
+    }
+
+    #[test]
+    fn test_wait_strategy_action() {
+        // Hybrid 与原硬编码阈值一致
+        let hybrid = WaitStrategy::default();
+        assert_eq!(hybrid, WaitStrategy::Hybrid);
+        assert_eq!(hybrid.action(10), WaitAction::Spin(64));
+        assert_eq!(hybrid.action(49), WaitAction::Spin(64));
+        assert_eq!(hybrid.action(50), WaitAction::YieldSpin(32));
+        assert_eq!(hybrid.action(499), WaitAction::YieldSpin(32));
+        assert_eq!(hybrid.action(500), WaitAction::Sleep(350));
+        assert_eq!(hybrid.action(100_000), WaitAction::Sleep(10_000));
+
+        // Spin 从不睡眠
+        assert_eq!(WaitStrategy::Spin.action(10), WaitAction::Spin(64));
+        assert_eq!(WaitStrategy::Spin.action(100_000), WaitAction::YieldSpin(32));
+
+        // Sleep 总是睡眠，短等待也至少 500µs
+        assert_eq!(WaitStrategy::Sleep.action(10), WaitAction::Sleep(500));
+        assert_eq!(WaitStrategy::Sleep.action(2_000), WaitAction::Sleep(2_000));
+        assert_eq!(WaitStrategy::Sleep.action(100_000), WaitAction::Sleep(20_000));
+
+        assert_eq!("SLEEP".parse::<WaitStrategy>(), Ok(WaitStrategy::Sleep));
+        assert!("busy".parse::<WaitStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_audible_position() {
+        // 512 帧 IO 缓冲 + 32 帧安全偏移 + 24 帧设备延迟 @ 48kHz
+        let latency = (512 + 32 + 24) as f64 / 48000.0;
+        let audible = audible_position(10.0, latency, false);
+        assert!((audible - (10.0 - 0.011_833)).abs() < 1e-6, "audible = {}", audible);
+
+        // 刚开始播放：不小于 0
+        assert_eq!(audible_position(0.005, latency, false), 0.0);
+        // 暂停：输出已排空，位置不扣除延迟
+        assert_eq!(audible_position(10.0, latency, true), 10.0);
+        assert_eq!(audible_position(10.0, 0.0, false), 10.0);
+    }
+
+    #[test]
+    fn test_finished_waits_for_device_tail() {
+        // 512 帧周期、568 帧在途：缓冲区排空后还需 2 次回调
+        assert!(!device_tail_played(0, 512, 568));
+        assert!(!device_tail_played(1, 512, 568));
+        assert!(device_tail_played(2, 512, 568));
+        // 系统混音器（周期未知）或没有延迟：排空即结束
+        assert!(device_tail_played(0, 0, 568));
+        assert!(device_tail_played(0, 512, 0));
+
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        engine.decoder_state.output_period_frames.store(512, Ordering::Relaxed);
+        engine.decoder_state.output_tail_frames.store(568, Ordering::Relaxed);
+        let wav = make_wav(44100, 2, 100);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+
+        // 模拟 IO 回调取走全部样本，但之后回调尚未推进
+        assert!(engine.wait_prebuffered(Some(Duration::from_secs(2))));
+        let mut buf = vec![0i32; 1024];
+        while engine.ring_buffer.read(&mut buf) > 0 {}
+        assert!(!engine.wait_finished(Some(Duration::from_millis(50))));
+        assert!(!engine.is_track_finished());
+
+        // 设备又播放了两个周期：在途帧已播出
+        engine.stats.record_callback();
+        engine.stats.record_callback();
+        assert!(engine.wait_finished(Some(Duration::from_secs(2))));
+        assert!(engine.is_track_finished());
+        assert_eq!(engine.stop().unwrap(), StopReason::EndOfTrack);
+    }
+
+    #[test]
+    fn test_chapter_markers() {
+        let markers: Vec<(f64, String)> = [(0.0, "A"), (60.0, "B"), (150.0, "C")]
+            .iter()
+            .map(|(t, s)| (*t, s.to_string()))
+            .collect();
+
+        assert_eq!(current_marker(&markers, 0.0), Some(0));
+        assert_eq!(current_marker(&markers, 59.9), Some(0));
+        assert_eq!(current_marker(&markers, 60.0), Some(1));
+        assert_eq!(current_marker(&markers, 500.0), Some(2));
+
+        assert_eq!(next_marker(&markers, 0.0), Some(1));
+        assert_eq!(next_marker(&markers, 60.0), Some(2));
+        assert_eq!(next_marker(&markers, 150.0), None);
+
+        // 刚进入本章：跳到上一章；已播放一段：回到本章开头
+        assert_eq!(prev_marker(&markers, 61.0), Some(0));
+        assert_eq!(prev_marker(&markers, 100.0), Some(1));
+        assert_eq!(prev_marker(&markers, 1.0), Some(0));
+
+        // 第一个章节之前，或没有章节
+        let late: Vec<(f64, String)> = vec![(10.0, "A".to_string())];
+        assert_eq!(current_marker(&late, 5.0), None);
+        assert_eq!(prev_marker(&late, 5.0), None);
+        assert_eq!(next_marker(&late, 5.0), Some(0));
+        assert_eq!(next_marker(&[], 5.0), None);
+    }
+
+    #[test]
+    fn test_channel_delay() {
+        // 右声道延迟 3 个样本，左声道不变；分两块处理验证跨块连续
+        let delays: [AtomicU32; MAX_DELAY_CHANNELS] = Default::default();
+        delays[1].store(3, Ordering::Relaxed);
+        let mut lines = DelayLines::new(2);
+        lines.load(&delays);
+        assert!(lines.is_active());
+
+        let input: Vec<i32> = (1..=10).flat_map(|i| [i, -i]).collect();
+        let mut output = input.clone();
+        let (first, second) = output.split_at_mut(8);
+        lines.process(first);
+        lines.process(second);
+
+        for frame in 0..10 {
+            assert_eq!(output[frame * 2], input[frame * 2]);
+            let expected = if frame < 3 { 0 } else { input[(frame - 3) * 2 + 1] };
+            assert_eq!(output[frame * 2 + 1], expected, "frame {}", frame);
+        }
+
+        // 上限：超出的延迟被拒绝
+        let engine = Engine::new(EngineConfig::default()).unwrap();
+        assert!(engine.set_channel_delays(&[0, MAX_CHANNEL_DELAY]).is_ok());
+        assert!(engine.set_channel_delays(&[MAX_CHANNEL_DELAY + 1]).is_err());
+        assert!(engine.set_channel_delays(&[1; MAX_DELAY_CHANNELS + 1]).is_err());
+        assert_eq!(engine.channel_delays()[1], MAX_CHANNEL_DELAY);
+        assert!(engine.set_channel_delays(&[]).is_ok());
+        assert_eq!(engine.channel_delays(), [0; MAX_DELAY_CHANNELS]);
+    }
+
+    #[test]
+    fn test_wait_finished_short_track() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        assert!(!engine.wait_finished(Some(Duration::from_millis(10))));
+
+        let callbacks = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&callbacks);
+        engine.on_track_finished(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        // 50ms @ 44.1kHz 立体声
+        let wav = make_wav(44100, 2, 2205);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+
+        // 模拟 IO 回调：持续消费 ring buffer
+        let ring_buffer = Arc::clone(&engine.ring_buffer);
+        let draining = Arc::new(AtomicBool::new(true));
+        let drain_flag = Arc::clone(&draining);
+        let drainer = thread::spawn(move || {
+            let mut buf = vec![0i32; 1024];
+            while drain_flag.load(Ordering::Acquire) {
+                if ring_buffer.read(&mut buf) == 0 {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        });
+
+        let start = std::time::Instant::now();
+        assert!(engine.wait_finished(Some(Duration::from_secs(2))));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(engine.is_track_finished());
+        assert!(engine.last_error().is_none());
+        assert_eq!(callbacks.load(Ordering::SeqCst), 1);
+
+        draining.store(false, Ordering::Release);
+        drainer.join().unwrap();
+        assert_eq!(engine.stop().unwrap(), StopReason::EndOfTrack);
+        assert_eq!(engine.last_stop_reason(), Some(StopReason::EndOfTrack));
+    }
+
+    #[test]
+    fn test_events_track_started_then_finished() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        let events = engine.events();
+
+        // 50ms @ 44.1kHz 立体声，模拟 IO 回调持续消费
+        let wav = make_wav(44100, 2, 2205);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        let ring_buffer = Arc::clone(&engine.ring_buffer);
+        let draining = Arc::new(AtomicBool::new(true));
+        let drain_flag = Arc::clone(&draining);
+        let drainer = thread::spawn(move || {
+            let mut buf = vec![0i32; 1024];
+            while drain_flag.load(Ordering::Acquire) {
+                if ring_buffer.read(&mut buf) == 0 {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        });
+        assert!(engine.wait_finished(Some(Duration::from_secs(2))));
+        draining.store(false, Ordering::Release);
+        drainer.join().unwrap();
+        engine.stop().unwrap();
+
+        let received: Vec<EngineEvent> = events.try_iter().collect();
+        assert!(
+            matches!(received.first(), Some(EngineEvent::TrackStarted(info)) if info.channels == 2),
+            "{:?}",
+            received
+        );
+        // 未开启任何处理：数据通路逐位一致
+        assert!(received
+            .iter()
+            .any(|e| matches!(e, EngineEvent::BitPerfectChanged(true))));
+        let finished = received
+            .iter()
+            .position(|e| matches!(e, EngineEvent::TrackFinished))
+            .expect("TrackFinished missing");
+        assert!(matches!(
+            received[finished + 1..],
+            [EngineEvent::Stopped(StopReason::EndOfTrack)]
+        ));
+    }
+
+    #[test]
+    fn test_wait_drained() {
+        // 模拟输出：分块慢速读取
+        let ring = Arc::new(RingBuffer::new(8192));
+        assert_eq!(ring.write(&[1i32; 6000]), 6000);
+        let consumer_ring = Arc::clone(&ring);
+        let consumer = thread::spawn(move || {
+            let mut buf = [0i32; 512];
+            let mut total = 0;
+            while total < 6000 {
+                total += consumer_ring.read(&mut buf);
+                thread::sleep(Duration::from_millis(1));
+            }
+            total
+        });
+        assert!(wait_drained(&ring, Duration::from_millis(500)));
+        assert_eq!(ring.available(), 0);
+        assert_eq!(consumer.join().unwrap(), 6000);
+
+        // 无人消费：超时放弃，数据保持不变
+        ring.write(&[1i32; 100]);
+        assert!(!wait_drained(&ring, Duration::from_millis(20)));
+        assert_eq!(ring.available(), 100);
+    }
+
+    #[test]
+    fn test_stop_after_drain() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+
+        // 长曲目：解码线程填满缓冲区后等待
+        let wav = make_wav(44100, 2, 441_000);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        let mut buffered = 0;
+        loop {
+            thread::sleep(Duration::from_millis(20));
+            let available = engine.ring_buffer.available();
+            if available > 0 && available == buffered {
+                break;
+            }
+            buffered = available;
+        }
+
+        // 模拟输出：慢速消费（立即停止时只来得及读出一小部分）
+        let ring_buffer = Arc::clone(&engine.ring_buffer);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stopped_flag = Arc::clone(&stopped);
+        let sink = thread::spawn(move || {
+            let mut buf = vec![0i32; 2048];
+            let mut total = 0;
+            while !stopped_flag.load(Ordering::Acquire) {
+                total += ring_buffer.read(&mut buf);
+                thread::sleep(Duration::from_millis(1));
+            }
+            total
+        });
+
+        assert_eq!(engine.stop_after_drain().unwrap(), StopReason::User);
+        assert_eq!(engine.state(), PlaybackState::Stopped);
+        stopped.store(true, Ordering::Release);
+        // 关闭前缓冲区已被读空：读出的样本不少于停止解码时已缓冲的量
+        assert!(sink.join().unwrap() >= buffered);
+    }
+
+    #[test]
+    fn test_clean_start_reports_no_underruns() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+
+        // 同 load：输出先启动，看门狗等待首次回调时缓冲区为空
+        engine.stats.reset();
+        engine.stats.record_callback();
+        engine.stats.record_underrun(1_000, 1024);
+
+        let wav = make_wav(44100, 2, 441_000);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        assert!(engine.wait_prebuffered(Some(Duration::from_secs(5))));
+        assert_eq!(engine.stats().underrun_count, 0);
+        assert_eq!(engine.stats.underrun_samples_total(), 0);
+
+        // 预缓冲完成后的断音照常统计
+        engine.stats.record_underrun(2_000, 512);
+        assert_eq!(engine.stats().underrun_count, 1);
+        engine.stop().unwrap();
+    }
+
+    #[test]
+    fn test_drain_after_eof_reports_no_underruns() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        engine.stats.reset();
+
+        // 能整首放进缓冲区、又越过预缓冲目标的曲目：先启用统计再到达 EOF
+        let frames = (engine.ring_buffer.capacity() / 2 * 3 / 4) as u32;
+        assert!(frames as usize * 2 > engine.prebuffer_target());
+        let wav = make_wav(44100, 2, frames);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !engine.decoder_state.eof_reached.load(Ordering::Acquire) {
+            assert!(Instant::now() < deadline, "decoder never reached EOF");
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // EOF 后回调读空缓冲区（排空、等设备播完在途帧）不计入 underrun
+        engine.stats.record_underrun(1_000, 1024);
+        assert_eq!(engine.stats().underrun_count, 0);
+        assert_eq!(engine.stats.underrun_samples_total(), 0);
+        engine.stop().unwrap();
+    }
+
+    #[test]
+    fn test_wait_prebuffered() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        // 未加载音轨：立即返回
+        assert!(!engine.wait_prebuffered(None));
+        assert!(!engine.prebuffer_complete());
+
+        // 长曲目：没有输出消费，缓冲区越过目标时被唤醒
+        let wav = make_wav(44100, 2, 441_000);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        assert!(engine.wait_prebuffered(Some(Duration::from_secs(5))));
+        assert!(engine.prebuffer_complete());
+        assert!(engine.ring_buffer.available() >= engine.prebuffer_target());
+        engine.stop().unwrap();
+        assert!(!engine.prebuffer_complete());
+
+        // 短于目标的曲目：EOF 时完成
+        let wav = make_wav(44100, 2, 100);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        assert!(engine.wait_prebuffered(Some(Duration::from_secs(5))));
+        assert!(engine.ring_buffer.available() < engine.prebuffer_target());
+        engine.stop().unwrap();
+    }
+
+    #[test]
+    fn test_empty_track_does_not_stall_buffering() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+
+        // 有效格式、零样本：EOF 先于预缓冲目标到达，立即结束缓冲
+        let wav = make_wav(44100, 2, 0);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        engine.state = PlaybackState::Buffering;
+        assert!(engine.wait_prebuffered(Some(Duration::from_secs(2))));
+        assert_eq!(engine.state(), PlaybackState::Playing);
+        assert!(engine.wait_finished(Some(Duration::from_secs(2))));
+        assert!(engine.is_empty_track());
+        assert!(engine.last_error().is_none());
+        assert_eq!(engine.stop().unwrap(), StopReason::EndOfTrack);
+
+        // 有内容的短曲目不算空
+        let wav = make_wav(44100, 2, 100);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        assert!(engine.wait_prebuffered(Some(Duration::from_secs(2))));
+        assert!(!engine.is_empty_track());
+        engine.stop().unwrap();
+    }
+
+    #[test]
+    fn test_channel_delay_flushed_at_eof() {
+        // 右声道延迟 50 帧：EOF 后延迟线中的尾部也写入 ring，不丢样本
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        engine.set_channel_delays(&[0, 50]).unwrap();
+        let wav = make_wav(44100, 2, 100);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !engine.decoder_state.eof_reached.load(Ordering::Acquire) {
+            assert!(Instant::now() < deadline, "decoder did not reach EOF");
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let mut output = vec![0; 1000];
+        let read = engine.ring_buffer.read(&mut output);
+        assert_eq!(read, (100 + 50) * 2);
+        let input: Vec<i32> = (0..200).map(|i| (i % 1000) << 16).collect();
+        let left: Vec<i32> = output[..read].iter().step_by(2).copied().collect();
+        let right: Vec<i32> = output[..read].iter().skip(1).step_by(2).copied().collect();
+        // 每个声道去掉前导延迟后与输入等长且逐位相同，其余为静音
+        let input_left: Vec<i32> = input.iter().step_by(2).copied().collect();
+        let input_right: Vec<i32> = input.iter().skip(1).step_by(2).copied().collect();
+        assert_eq!(left[..100], input_left[..]);
+        assert!(left[100..].iter().all(|&s| s == 0));
+        assert!(right[..50].iter().all(|&s| s == 0));
+        assert_eq!(right[50..], input_right[..]);
+        engine.stop().unwrap();
+    }
+
+    #[test]
+    #[ignore] // 需要音频设备
+    fn test_play_paused() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        let path = std::env::temp_dir().join(format!("roger_paused_{}.wav", std::process::id()));
+        // 60 秒，长于任何缓冲区容量
+        std::fs::write(&path, make_wav(44100, 2, 2_646_000)).unwrap();
+        let result = engine.play_paused(&path);
+        std::fs::remove_file(&path).ok();
+        result.unwrap();
+        assert_eq!(engine.state(), PlaybackState::Paused);
+
+        // 解码线程填满缓冲区后等待，输出一个样本也没有消费
+        let mut buffered = 0;
+        loop {
+            thread::sleep(Duration::from_millis(20));
+            let available = engine.ring_buffer.available();
+            if available > 0 && available == buffered {
+                break;
+            }
+            buffered = available;
+        }
+        assert!(engine.ring_buffer.fill_ratio() >= engine.config.prebuffer_ratio);
+        assert_eq!(engine.stats().samples_played, 0);
+        assert_eq!(engine.state(), PlaybackState::Paused);
+
+        engine.toggle_pause().unwrap();
+        assert_eq!(engine.state(), PlaybackState::Playing);
+        engine.stop().unwrap();
+    }
+
+    #[test]
+    fn test_upmix_mono() {
+        let mut stereo = Vec::new();
+        upmix_mono(&[1, -2, i32::MIN, i32::MAX], &mut stereo);
+        assert_eq!(stereo, [1, 1, -2, -2, i32::MIN, i32::MIN, i32::MAX, i32::MAX]);
+
+        // 只有同采样率、不更高位深的单声道能接在立体声输出之后
+        let stereo_out = AudioFormat::new(44100, 2, 24);
+        assert!(can_upmix_mono(stereo_out, &info(44100, 1)));
+        assert!(!can_upmix_mono(stereo_out, &info(48000, 1)));
+        assert!(!can_upmix_mono(stereo_out, &info(44100, 2)));
+        assert!(!can_upmix_mono(AudioFormat::new(44100, 6, 24), &info(44100, 1)));
+        assert!(!can_upmix_mono(AudioFormat::new(44100, 2, 16), &info(44100, 1)));
+
+        // 解码线程：单声道源写入立体声 ring buffer，左右声道相同
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        let mut wav = make_wav(44100, 1, 3000);
+        for (i, sample) in wav[44..].chunks_exact_mut(2).enumerate() {
+            sample.copy_from_slice(&((i % 1000) as i16).to_le_bytes());
+        }
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        let mut output = Vec::new();
+        let mut buf = vec![0i32; 1024];
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while output.len() < 6000 && Instant::now() < deadline {
+            let n = engine.ring_buffer.read(&mut buf);
+            output.extend_from_slice(&buf[..n]);
+        }
+        engine.stop().unwrap();
+
+        let expected: Vec<i32> = (0..3000).flat_map(|i| [(i % 1000) << 16; 2]).collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_stop_reasons() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        assert_eq!(engine.last_stop_reason(), None);
+
+        // 未播完就停止：用户停止（无人消费，解码线程在缓冲区满时等待）
+        let wav = make_wav(44100, 2, 441_000);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        assert_eq!(engine.stop().unwrap(), StopReason::User);
+
+        // 调用方检测到设备断开
+        let wav = make_wav(44100, 2, 441_000);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+        assert_eq!(
+            engine.stop_with_reason(StopReason::DeviceLost).unwrap(),
+            StopReason::DeviceLost
+        );
+
+        // 已停止时再次 stop 不覆盖上一次的原因
+        assert_eq!(engine.stop().unwrap(), StopReason::DeviceLost);
+        assert_eq!(engine.last_stop_reason(), Some(StopReason::DeviceLost));
+    }
+
+    #[test]
+    fn test_prefetch_reports_unsupported() {
+        let dir = std::env::temp_dir().join(format!("roger-prefetch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let broken = dir.join("broken.flac");
+        let good = dir.join("good.wav");
+        std::fs::write(&broken, b"not really flac").unwrap();
+        std::fs::write(&good, make_wav(44100, 2, 2205)).unwrap();
+
+        // 当前曲目在解码线程上播放
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        let wav = make_wav(44100, 2, 44100);
+        let decoder =
+            AudioDecoder::open_reader(Box::new(std::io::Cursor::new(wav)), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+
+        let wait_status = |engine: &mut Engine| loop {
+            match engine.prefetch_status() {
+                Some((_, PrefetchStatus::Pending)) => thread::sleep(Duration::from_millis(1)),
+                Some((_, status)) => return status,
+                None => panic!("prefetch missing"),
+            }
+        };
+
+        engine.prefetch(&broken);
+        assert!(matches!(wait_status(&mut engine), PrefetchStatus::Failed(_)));
+        // 当前播放不受影响
+        assert!(engine.last_error().is_none());
+        assert!(engine.decoder_state.running.load(Ordering::Acquire));
+
+        // 换成可播放的文件：结果可被取出，路径不符时丢弃
+        engine.prefetch(&good);
+        assert_eq!(wait_status(&mut engine), PrefetchStatus::Ready);
+        assert!(engine.take_prefetch(&broken).is_none());
+        assert!(engine.prefetch_status().is_none());
+        engine.prefetch(&good);
+        let decoder = engine.take_prefetch(&good).unwrap().unwrap();
+        assert_eq!(decoder.info().sample_rate, 44100);
+
+        engine.stop().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 读到指定偏移后返回 IO 错误的字节流（模拟中途损坏的文件）
+    struct FailingReader {
+        data: std::io::Cursor<Vec<u8>>,
+        fail_at: u64,
+    }
+
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let remaining = self.fail_at.saturating_sub(self.data.position()) as usize;
+            if remaining == 0 {
+                return Err(std::io::Error::other("corrupt"));
+            }
+            let len = buf.len().min(remaining);
+            self.data.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_mid_stream_error_sets_flag() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+
+        // 头部声明 1 秒，读到约 0.25 秒处出错
+        let reader = FailingReader {
+            data: std::io::Cursor::new(make_wav(44100, 2, 44100)),
+            fail_at: 44 + 44100,
+        };
+        let decoder = AudioDecoder::open_reader(Box::new(reader), None).unwrap();
+        engine.spawn_decoder(decoder, 2, 44100);
+
+        let ring_buffer = Arc::clone(&engine.ring_buffer);
+        let draining = Arc::new(AtomicBool::new(true));
+        let drain_flag = Arc::clone(&draining);
+        let drainer = thread::spawn(move || {
+            let mut buf = vec![0i32; 1024];
+            while drain_flag.load(Ordering::Acquire) {
+                if ring_buffer.read(&mut buf) == 0 {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        });
+
+        // 出错的曲目同样会结束（不会卡住等待者），但带有错误信息
+        assert!(engine.wait_finished(Some(Duration::from_secs(2))));
+        assert!(engine.is_track_finished());
+        assert!(engine.last_error().unwrap().contains("corrupt"));
+
+        draining.store(false, Ordering::Release);
+        drainer.join().unwrap();
+        assert_eq!(engine.stop().unwrap(), StopReason::Error);
+    }
 }
@@ -1,9 +1,28 @@
 //! Roger Player Library
 //!
 //! Audio playback engine for high-fidelity audio playback.
+//!
+//! 嵌入方通常只需要 crate 根导出的类型：
+//!
+//! ```no_run
+//! use roger_player::{Engine, EngineConfig};
+//!
+//! let mut engine = Engine::new(EngineConfig::default())?;
+//! engine.play("music.flac")?;
+//! engine.wait_finished(None);
+//! engine.stop()?;
+//! # Ok::<(), roger_player::EngineError>(())
+//! ```
+//!
+//! 完整示例见 `examples/play.rs`
 
 #![allow(dead_code, unused_mut)]
 
 pub mod audio;
 pub mod decode;
 pub mod engine;
+
+pub use decode::AudioInfo;
+pub use engine::{
+    Engine, EngineConfig, EngineError, EngineEvent, EngineStats, PlaybackState, StopReason,
+};
@@ -0,0 +1,138 @@
+//! 日志文件输出
+//!
+//! TUI 模式下 stderr 会破坏界面，日志改写到文件（如 `~/Library/Logs/roger-player.log`），
+//! 便于排查设备协商等问题。
+//!
+//! 写文件在独立的 `log-writer` 线程上进行：记录日志的线程（包括解码线程）
+//! 只把格式化好的字节发到 channel，不在音频相关线程上做文件 IO。
+//! 文件超过 `MAX_LOG_BYTES` 时轮转为 `<name>.1`（只保留一份旧日志）。
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+/// 单个日志文件上限，超过后轮转
+const MAX_LOG_BYTES: u64 = 4 * 1024 * 1024;
+
+/// 日志文件写入端（交给 `env_logger` 的 `Target::Pipe`）
+///
+/// `write` 只投递到后台线程，不触碰文件
+pub struct LogFileWriter {
+    tx: Sender<Vec<u8>>,
+}
+
+impl LogFileWriter {
+    /// 打开（必要时创建/轮转）日志文件并启动后台写线程
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = RotatingFile::open(path.to_path_buf(), MAX_LOG_BYTES)?;
+
+        let (tx, rx) = mpsc::channel::<Vec<u8>>();
+        thread::Builder::new()
+            .name("log-writer".to_string())
+            .spawn(move || {
+                // 所有发送端释放后 recv 返回 Err，线程退出
+                while let Ok(bytes) = rx.recv() {
+                    let _ = file.write_all(&bytes);
+                }
+            })?;
+
+        Ok(Self { tx })
+    }
+}
+
+impl Write for LogFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tx
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "log writer stopped"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 按大小轮转的追加写文件
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        let mut rotating = Self {
+            path,
+            file,
+            written,
+            max_bytes,
+        };
+        if rotating.written >= max_bytes {
+            rotating.rotate()?;
+        }
+        Ok(rotating)
+    }
+
+    /// 当前文件改名为 `<name>.1`，重新创建空文件
+    fn rotate(&mut self) -> io::Result<()> {
+        let mut backup = self.path.clone().into_os_string();
+        backup.push(".1");
+        std::fs::rename(&self.path, &backup)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if self.written > 0 && self.written + bytes.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(bytes)?;
+        self.written += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotating_file() {
+        let dir = std::env::temp_dir().join(format!("roger_logfile_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.log");
+        let backup = dir.join("test.log.1");
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&backup);
+
+        let mut file = RotatingFile::open(path.clone(), 16).unwrap();
+        file.write_all(b"0123456789\n").unwrap();
+        // 超过上限：旧内容轮转到 .1
+        file.write_all(b"abcdefghij\n").unwrap();
+        assert_eq!(std::fs::read(&backup).unwrap(), b"0123456789\n");
+        assert_eq!(std::fs::read(&path).unwrap(), b"abcdefghij\n");
+
+        // 重新打开时已满的文件立即轮转
+        drop(file);
+        std::fs::write(&path, b"0123456789abcdefghij\n").unwrap();
+        let file = RotatingFile::open(path.clone(), 16).unwrap();
+        assert_eq!(file.written, 0);
+        assert_eq!(std::fs::read(&backup).unwrap(), b"0123456789abcdefghij\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -9,25 +9,62 @@
 
 mod alloc;
 mod audio;
+mod config;
 mod decode;
 mod engine;
+mod logfile;
 mod tui;
 
 #[global_allocator]
 static GLOBAL: alloc::TuiIsolatedAllocator = alloc::TuiIsolatedAllocator;
 
-use std::ffi::OsStr;
 use std::io::{self, Read as IoRead, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use clap::{Parser, Subcommand};
 use rand::seq::SliceRandom;
 
-use crate::audio::AudioOutput;
-use crate::engine::{Engine, EngineConfig, PlaybackState};
+use crate::audio::loudness::LoudnessMeter;
+use crate::audio::tone::{ToneGenerator, ToneKind};
+use crate::audio::{AudioFormat, AudioOutput, PlaybackStats, RingBuffer};
+use crate::config::{
+    file_mtime, LoudnessCache, LoudnessEntry, RepeatMode, UserConfig, DEFAULT_BUFFER_MS,
+};
+use crate::decode::scan::{is_audio_file, scan_audio_files, AUDIO_EXTENSIONS};
+use crate::decode::{is_stdin_path, AudioDecoder, RawPcmFormat};
+use crate::engine::{
+    BitPerfectStatus, Engine, EngineConfig, EngineStats, PlaybackState, WaitStrategy,
+    AUDIO_AFFINITY_TAG,
+};
+use crate::tui::model::SessionSettings;
+use crate::tui::theme::ThemeName;
+use crate::tui::view::{progress_bar, spinner_bar};
+
+/// IO 缓冲帧数默认值（`--io-buffer-frames`）
+const DEFAULT_IO_BUFFER_FRAMES: u32 = 512;
+
+/// `--require-bit-perfect` 下输出路径不满足 bit-perfect（中止播放而不是跳到下一首）
+#[derive(Debug)]
+struct NotBitPerfect(BitPerfectStatus);
+
+impl std::fmt::Display for NotBitPerfect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "output is not bit-perfect ({}), refusing to play (--require-bit-perfect)", self.0)
+    }
+}
+
+impl std::error::Error for NotBitPerfect {}
+
+/// 启动后检查 bit-perfect：`required` 且不满足时返回错误，否则放行
+fn check_bit_perfect(required: bool, status: BitPerfectStatus) -> Result<(), NotBitPerfect> {
+    if required && !status.is_bit_perfect() {
+        return Err(NotBitPerfect(status));
+    }
+    Ok(())
+}
 
 /// 曲目跳转命令
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -118,6 +155,29 @@ fn read_key_nonblocking() -> Option<KeyPress> {
     }
 }
 
+/// 按键对应的播放控制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyAction {
+    /// 忽略
+    None,
+    /// 暂停/播放
+    TogglePause,
+    /// 结束当前曲目并跳转
+    Skip(SkipCommand),
+}
+
+/// 按键 → 播放控制（缓冲与播放阶段共用）
+///
+/// Space = 暂停/播放；→/← = 下一首/上一首，仅目录模式（`keyboard_control`）生效
+fn key_action(key: KeyPress, keyboard_control: bool) -> KeyAction {
+    match key {
+        KeyPress::Space => KeyAction::TogglePause,
+        KeyPress::Right if keyboard_control => KeyAction::Skip(SkipCommand::Next),
+        KeyPress::Left if keyboard_control => KeyAction::Skip(SkipCommand::Previous),
+        _ => KeyAction::None,
+    }
+}
+
 /// Roger Player - High-fidelity audio player
 #[derive(Parser)]
 #[command(name = "roger-player")]
@@ -126,13 +186,13 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// Audio file or directory to play
+    /// Audio files or directories to play, in order ('-' reads from stdin)
     #[arg(value_name = "PATH")]
-    file: Option<PathBuf>,
+    files: Vec<PathBuf>,
 
-    /// Buffer size in milliseconds
-    #[arg(short, long, default_value = "2000")]
-    buffer_ms: u32,
+    /// Buffer size in milliseconds [default: auto by sample rate and source]
+    #[arg(short, long)]
+    buffer_ms: Option<u32>,
 
     /// Disable exclusive (hog) mode
     #[arg(long)]
@@ -146,7 +206,19 @@ struct Cli {
     #[arg(long, conflicts_with = "hal_on")]
     hal_off: bool,
 
-    /// Select output device by name or ID (use 'info' command to list devices)
+    /// Never switch the device sample rate; resample to its current rate instead
+    #[arg(long)]
+    lock_rate: bool,
+
+    /// Abort instead of playing when the output path is not bit-perfect
+    #[arg(long)]
+    require_bit_perfect: bool,
+
+    /// Print one machine-parseable status line per change instead of the progress bar
+    #[arg(long)]
+    status_line: bool,
+
+    /// Select output device by name, ID or uid:<UID> (use 'info' command to list devices)
     #[arg(short, long)]
     device: Option<String>,
 
@@ -158,9 +230,117 @@ struct Cli {
     #[arg(short, long)]
     shuffle: bool,
 
+    /// Don't shuffle, even if the config file enables it
+    #[arg(long, conflicts_with = "shuffle")]
+    no_shuffle: bool,
+
     /// Repeat playback (loop directory or single track)
     #[arg(short, long)]
     repeat: bool,
+
+    /// Don't repeat, even if the config file enables it
+    #[arg(long, conflicts_with = "repeat")]
+    no_repeat: bool,
+
+    /// Include audio files in subdirectories (directory mode)
+    #[arg(short = 'R', long)]
+    recursive: bool,
+
+    /// Don't include subdirectories, even if the config file enables it
+    #[arg(long, conflicts_with = "recursive")]
+    no_recursive: bool,
+
+    /// TUI: load the first track paused (press Space to start)
+    #[arg(long)]
+    paused: bool,
+
+    /// TUI: start playing right away, even if the config file sets start_paused
+    #[arg(long, conflicts_with = "paused")]
+    no_paused: bool,
+
+    /// Keep reading a file that is still being written (stops after 5s without growth)
+    #[arg(long)]
+    follow: bool,
+
+    /// Probe files as this format regardless of extension (e.g. flac, wav, mp3, pcm)
+    #[arg(long, value_name = "EXT")]
+    force_format: Option<String>,
+
+    /// Play this audio track of multi-track containers (1-based, as listed at startup)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    track: Option<u32>,
+
+    /// TUI color theme: dark, light or high-contrast [default: from config]
+    #[arg(long, value_name = "THEME")]
+    theme: Option<ThemeName>,
+
+    /// Sample rate of raw .pcm files
+    #[arg(long, default_value = "44100")]
+    pcm_rate: u32,
+
+    /// Channel count of raw .pcm files
+    #[arg(long, default_value = "2")]
+    pcm_channels: u16,
+
+    /// Bit depth of raw .pcm files (16, 24 or 32, signed)
+    #[arg(long, default_value = "16")]
+    pcm_bits: u16,
+
+    /// Raw .pcm files are big-endian (e.g. AIFF sound data)
+    #[arg(long)]
+    pcm_big_endian: bool,
+
+    /// Fade length on pause/resume/seek/stop in milliseconds (0 disables)
+    #[arg(long, default_value = "20")]
+    fade_ms: u32,
+
+    /// Silence inserted before auto-advancing to the next track, in milliseconds
+    #[arg(long, value_name = "MS", default_value = "0")]
+    gap: u32,
+
+    /// IO buffer size in frames (clamped to the device range; smaller = lower latency)
+    #[arg(long, value_name = "FRAMES", default_value_t = DEFAULT_IO_BUFFER_FRAMES)]
+    io_buffer_frames: u32,
+
+    /// Frames decoded per iteration (larger reduces overhead for 352.8k/384k content)
+    #[arg(long, value_name = "FRAMES", default_value = "4096")]
+    decode_chunk_frames: usize,
+
+    /// Headphone crossfeed level 0.0-1.0 (stereo only; 0 = off, bit-perfect)
+    #[arg(long, value_name = "LEVEL", default_value = "0")]
+    crossfeed: f64,
+
+    /// Stereo width via mid/side 0.0-2.0 (stereo only; 0 = mono, 1 = off, bit-perfect)
+    #[arg(long, value_name = "FACTOR", default_value = "1")]
+    stereo_width: f64,
+
+    /// Enable the peak limiter with this ceiling in dBFS (-24 to 0; catches gain > 1)
+    #[arg(long, value_name = "DBFS", allow_negative_numbers = true)]
+    limiter: Option<f32>,
+
+    /// Measure intersample (true) peaks with 4x oversampling and show overs in dBTP
+    #[arg(long)]
+    true_peak: bool,
+
+    /// Decoder wait strategy when the buffer is full: spin, hybrid or sleep (power saving)
+    #[arg(long, value_name = "MODE", default_value = "hybrid")]
+    wait_strategy: WaitStrategy,
+
+    /// Don't tag the decoder thread into the audio affinity group (few cores / E-core scheduling)
+    #[arg(long)]
+    no_thread_affinity: bool,
+
+    /// Fixed dither RNG seed for reproducible output (default: clock-based)
+    #[arg(long, value_name = "SEED")]
+    dither_seed: Option<u32>,
+
+    /// Normalize playback to this loudness target, e.g. -14 (requires 'scan')
+    #[arg(long, value_name = "LUFS", allow_hyphen_values = true)]
+    normalize: Option<f64>,
+
+    /// Write logs to this file instead of stderr (works in TUI mode)
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -182,20 +362,57 @@ enum Commands {
 
     /// Terminal UI mode
     Tui {
+        /// Audio files or directories
+        files: Vec<PathBuf>,
+    },
+
+    /// Measure loudness (EBU R128) for normalization
+    Scan {
         /// Audio file or directory
-        file: Option<PathBuf>,
+        path: PathBuf,
+    },
+
+    /// Play a synthesized test signal to verify the output path (no decoder)
+    TestTone {
+        /// sine (1kHz -6dBFS), square (1kHz full scale), black (dithered silence),
+        /// ramp (1kHz square, one step per bit up to full scale)
+        #[arg(default_value = "sine")]
+        kind: ToneKind,
+
+        /// Sample rate in Hz
+        #[arg(long, default_value = "48000")]
+        rate: u32,
+
+        /// Bit depth (16, 24 or 32)
+        #[arg(long, default_value = "24")]
+        bits: u16,
+
+        /// Duration in seconds
+        #[arg(long, default_value = "10")]
+        seconds: f64,
+
+        /// Only play on this channel (1 = left, 2 = right)
+        #[arg(long)]
+        channel: Option<usize>,
     },
 }
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
-    // 初始化日志
-    if cli.verbose {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-    } else {
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    // 加载用户配置（CLI 参数优先）
+    let user_config = UserConfig::load();
+    apply_user_config(&mut cli, &user_config);
+
+    // 初始化日志（日志文件路径可能来自配置文件）
+    let logging_to_file = init_logging(&cli);
+
+    // 缓冲区大小在开始播放前统一校验（目录模式下否则每首曲目都报同样的错）
+    EngineConfig {
+        buffer_frames: engine_buffer_frames(&cli),
+        ..EngineConfig::default()
     }
+    .validate()?;
 
     match cli.command {
         Some(Commands::Info) => {
@@ -207,38 +424,87 @@ fn main() -> anyhow::Result<()> {
         Some(Commands::Play { ref file }) => {
             simple_play(file, &cli)?;
         }
-        Some(Commands::Tui { ref file }) => {
-            // TUI 模式下禁用日志输出到 stderr，避免干扰界面
-            log::set_max_level(log::LevelFilter::Off);
+        Some(Commands::Scan { ref path }) => {
+            scan_loudness(path, &cli)?;
+        }
+        Some(Commands::TestTone {
+            kind,
+            rate,
+            bits,
+            seconds,
+            channel,
+        }) => {
+            play_test_tone(&cli, kind, rate, bits, seconds, channel)?;
+        }
+        Some(Commands::Tui { ref files }) => {
+            // TUI 模式下禁用日志输出到 stderr，避免干扰界面（写日志文件时保留）
+            if !logging_to_file {
+                log::set_max_level(log::LevelFilter::Off);
+            }
 
-            let path = file.as_ref().or(cli.file.as_ref());
-            if let Some(p) = path {
-                tui_play(p, &cli)?;
+            let paths = if files.is_empty() { &cli.files } else { files };
+            if !paths.is_empty() {
+                tui_play(paths, &cli, &user_config)?;
             } else {
                 // 无参数启动，显示空界面等待拖拽
-                tui_play_empty(&cli)?;
+                tui_play_empty(&cli, &user_config)?;
             }
         }
         None => {
-            if let Some(ref file) = cli.file {
+            if let [ref file] = cli.files[..] {
                 simple_play(file, &cli)?;
+            } else if !cli.files.is_empty() {
+                play_paths(&cli.files, &cli)?;
             } else {
                 // 没有参数，显示帮助
                 println!("Roger Player - Extreme quality audio player\n");
-                println!("Usage: roger-player [OPTIONS] <FILE|DIR>");
+                println!("Usage: roger-player [OPTIONS] <FILE|DIR>...");
+                println!("       <decoder> | roger-player [OPTIONS] -");
                 println!("       roger-player info");
-                println!("       roger-player tui <FILE|DIR>");
+                println!("       roger-player tui <FILE|DIR>...");
                 println!("       roger-player interactive <FILE>");
+                println!("       roger-player scan <FILE|DIR>");
+                println!("       roger-player test-tone [sine|square|black|ramp] [--rate HZ] [--bits N] [--channel 1|2]");
                 println!("\nOptions:");
-                println!("  -b, --buffer-ms <MS>   Buffer size in milliseconds [default: 2000]");
-                println!("  -d, --device <ID|NAME> Select output device (use 'info' to list)");
+                println!("  -b, --buffer-ms <MS>   Buffer size in milliseconds [default: auto, 1-9s by rate/source]");
+                println!("  -d, --device <ID|NAME|uid:UID> Select output device (use 'info' to list)");
                 println!("  -s, --shuffle          Shuffle playback order (directory mode)");
                 println!("  -r, --repeat           Loop playback (directory or single track)");
+                println!("  -R, --recursive        Include subdirectories (directory mode, e.g. multi-disc albums)");
+                println!("  --paused               TUI: load the first track paused, Space starts playback");
+                println!("  --no-shuffle, --no-repeat, --no-recursive, --no-paused");
+                println!("                         Turn off a setting the config file enables (this run only)");
+                println!("  --follow               Play a file that is still growing (duration unknown)");
+                println!("  --force-format <EXT>   Probe as this format, ignoring the extension (mislabeled files)");
+                println!("  --track <N>            Play track N of multi-track containers [default: 1]");
+                println!("  --theme <THEME>        TUI colors: dark/light/high-contrast (cycle with 't')");
                 println!("  --no-exclusive         Disable exclusive mode");
                 println!("  --no-hal               Use system mixer (recommended for Bluetooth)");
+                println!("  --lock-rate            Keep the device at its current rate, resample instead (no relocks)");
+                println!("  --require-bit-perfect  Abort instead of playing if the output is not bit-perfect");
+                println!("  --status-line          Print key=value status lines for scripts (state=playing pos=12.3 ...)");
+                println!("  --fade-ms <MS>         Fade on pause/resume/seek/stop, 0 disables [default: 20]");
+                println!("  --gap <MS>             Silence between tracks when auto-advancing [default: 0]");
+                println!("  --io-buffer-frames <N> IO buffer size, clamped to device range [default: 512]");
+                println!("  --decode-chunk-frames <N> Frames decoded per iteration [default: 4096]");
+                println!("  --crossfeed <LEVEL>    Headphone crossfeed 0.0-1.0, stereo only [default: 0]");
+                println!("  --stereo-width <FACTOR> Mid/side width 0.0-2.0, 0 = mono, stereo only [default: 1]");
+                println!("  --limiter <DBFS>       Soft-knee peak limiter ceiling, e.g. -1 [default: off]");
+                println!("  --true-peak            Meter intersample peaks (dBTP, 4x oversampling)");
+                println!("  --dither-seed <SEED>   Fixed dither seed for reproducible output");
+                println!("  --wait-strategy <MODE> Decoder wait: spin/hybrid/sleep (sleep saves battery) [default: hybrid]");
+                println!("  --no-thread-affinity   Don't group the decoder thread with the IO thread");
+                println!("  --pcm-rate <HZ>        Raw .pcm sample rate [default: 44100]");
+                println!("  --pcm-channels <N>     Raw .pcm channel count [default: 2]");
+                println!("  --pcm-bits <BITS>      Raw .pcm bit depth: 16/24/32 [default: 16]");
+                println!("  --pcm-big-endian       Raw .pcm samples are big-endian");
+                println!("  --normalize <LUFS>     Normalize to a loudness target, e.g. -14 (run 'scan' first)");
+                println!("  --log-file <PATH>      Write logs to a file (e.g. ~/Library/Logs/roger-player.log)");
                 println!("  -v, --verbose          Show verbose output");
                 println!("\nSupported formats: {}", AUDIO_EXTENSIONS.join(", "));
                 println!("If PATH is a directory, all audio files will be played in order.");
+                println!("If PATH is '-', audio is read from stdin (seek unavailable).");
+                println!("If PATH is an http:// or https:// URL, it is streamed (seek needs server Range support).");
                 println!("\nPress Ctrl+C to stop playback");
             }
         }
@@ -247,6 +513,76 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// 合并用户配置：CLI 参数 > 配置文件 > 内置默认值
+fn apply_user_config(cli: &mut Cli, user_config: &UserConfig) {
+    // 配置文件也未设置时交给自动缓冲
+    if cli.buffer_ms.is_none() {
+        cli.buffer_ms = user_config.buffer_ms;
+    }
+    // 未显式指定 --hal-on/--hal-off 时使用配置文件
+    if !cli.hal_on && !cli.hal_off {
+        cli.hal_off = !user_config.use_hal;
+    }
+    // --no-* 关闭配置文件中打开的开关（只作用于本次运行）
+    cli.shuffle |= !cli.no_shuffle && user_config.shuffle;
+    cli.repeat |= !cli.no_repeat && user_config.repeat != RepeatMode::Off;
+    cli.recursive |= !cli.no_recursive && user_config.recursive;
+    cli.paused |= !cli.no_paused && user_config.start_paused;
+    if cli.log_file.is_none() {
+        cli.log_file = user_config.log_file.clone();
+    }
+    if cli.normalize.is_none() {
+        cli.normalize = user_config.normalize_lufs;
+    }
+    if cli.device.is_none() {
+        cli.device = user_config.device.clone();
+    }
+}
+
+/// 初始化日志，返回是否写入日志文件
+///
+/// 指定日志文件时默认级别为 info（便于附在 bug 报告中），否则输出到 stderr。
+/// 文件打不开时回退到 stderr
+fn init_logging(cli: &Cli) -> bool {
+    let to_file = cli.log_file.as_ref().and_then(|path| {
+        logfile::LogFileWriter::open(path)
+            .map_err(|e| eprintln!("Failed to open log file {}: {}", path.display(), e))
+            .ok()
+    });
+
+    let default_level = if cli.verbose || to_file.is_some() { "info" } else { "warn" };
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level));
+
+    let logging_to_file = to_file.is_some();
+    if let Some(writer) = to_file {
+        builder
+            .target(env_logger::Target::Pipe(Box::new(writer)))
+            .write_style(env_logger::WriteStyle::Never);
+    }
+    builder.init();
+    logging_to_file
+}
+
+/// TUI 会话设置（CLI 参数已与配置文件合并，只作用于本次会话，不写回配置文件）
+fn tui_session(cli: &Cli, user_config: &UserConfig) -> SessionSettings {
+    // --repeat 沿用配置文件的单曲循环，否则为列表循环
+    let repeat = match (cli.repeat, user_config.repeat) {
+        (false, _) => RepeatMode::Off,
+        (true, RepeatMode::Off) => RepeatMode::All,
+        (true, mode) => mode,
+    };
+    SessionSettings {
+        shuffle: cli.shuffle,
+        repeat,
+        recursive: cli.recursive,
+        start_paused: cli.paused,
+        theme: cli.theme.unwrap_or(user_config.theme),
+        normalize_lufs: cli.normalize,
+        require_bit_perfect: cli.require_bit_perfect,
+    }
+}
+
 /// 显示设备信息
 fn show_device_info() -> anyhow::Result<()> {
     println!("=== Audio Output Devices ===\n");
@@ -260,6 +596,12 @@ fn show_device_info() -> anyhow::Result<()> {
         let type_str = if device.is_bluetooth { "BT" } else { "USB" };
 
         println!("[{:>3}] {} ({}){}", device.id, device.name, type_str, default_mark);
+        if let Some(ref uid) = device.uid {
+            println!("      UID: {}", uid);
+        }
+        if let Some((min, max)) = AudioOutput::get_buffer_size_range(device.id) {
+            println!("      IO buffer: {}-{} frames", min, max);
+        }
     }
 
     println!();
@@ -267,41 +609,204 @@ fn show_device_info() -> anyhow::Result<()> {
     println!("BT = Bluetooth (auto system mixer), USB = Wired/USB\n");
     println!("Select device: roger-player -d <ID> <file>");
     println!("Example: roger-player -d {} <file>", default_device.id);
+    if let Some(ref uid) = default_device.uid {
+        println!("Stable across reboots: roger-player -d 'uid:{}' <file>", uid);
+        println!("Or set device = \"uid:{}\" in the config file", uid);
+    }
 
     Ok(())
 }
 
-/// 支持的音频文件扩展名
-const AUDIO_EXTENSIONS: &[&str] = &["flac", "wav", "aiff", "aif", "mp3", "pcm"];
+/// 测试信号：发生器在普通生产者线程中写入 ring buffer，不经解码器
+///
+/// 走与播放完全相同的输出路径，用于确认声道映射、极性和是否有 SRC/音量处理
+fn play_test_tone(
+    cli: &Cli,
+    kind: ToneKind,
+    rate: u32,
+    bits: u16,
+    seconds: f64,
+    channel: Option<usize>,
+) -> anyhow::Result<()> {
+    const CHANNELS: usize = 2;
+    if !matches!(bits, 16 | 24 | 32) {
+        anyhow::bail!("--bits must be 16, 24 or 32");
+    }
+    if rate == 0 || !seconds.is_finite() || seconds <= 0.0 {
+        anyhow::bail!("--rate and --seconds must be positive");
+    }
+    if channel.is_some_and(|ch| !(1..=CHANNELS).contains(&ch)) {
+        anyhow::bail!("--channel must be 1 (left) or 2 (right)");
+    }
+
+    let config = create_engine_config(cli);
+    let mut output_config = config.output;
+    output_config.sample_rate = rate;
+    let mut output = AudioOutput::new(output_config)?;
+
+    // 约 0.5 秒缓冲
+    let capacity = (rate as usize * CHANNELS / 2).next_power_of_two();
+    let ring = Arc::new(RingBuffer::new(capacity));
+    let stats = Arc::new(PlaybackStats::new());
+    let mut generator = ToneGenerator::new(kind, rate, CHANNELS, bits as u32)
+        .with_channel(channel.map(|ch| ch - 1));
+
+    // 启动前先填满，避免开头欠载
+    let mut chunk = vec![0i32; 1024 * CHANNELS];
+    let total_samples = (seconds * rate as f64) as usize * CHANNELS;
+    let mut remaining = total_samples;
+    while remaining > 0 && ring.free_space() >= chunk.len() {
+        let n = chunk.len().min(remaining);
+        generator.fill(&mut chunk[..n]);
+        ring.write(&chunk[..n]);
+        remaining -= n;
+    }
+
+    output.start(
+        AudioFormat::new(rate, CHANNELS as u16, bits),
+        Arc::clone(&ring),
+        Arc::clone(&stats),
+    )?;
+
+    let device = output.actual_format();
+    println!("Test tone: {:?} {}Hz {}-bit, {:.0}s", kind, rate, bits, seconds);
+    println!(
+        "Output: {} {} {}Hz{}",
+        if output.is_hal_output() { "HAL" } else { "System Mixer" },
+        output.output_format_mode().map_or("?".to_string(), |m| format!("{:?}", m)),
+        device.sample_rate,
+        if output.is_exclusive_mode() { " (exclusive)" } else { "" }
+    );
+    if device.sample_rate != rate {
+        println!("Warning: device runs at {}Hz, CoreAudio SRC is active", device.sample_rate);
+    }
+    if let Some(report) = output.negotiation_report().filter(|r| !r.all_as_requested()) {
+        println!("{}", report);
+    }
+
+    let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    install_signal_handler(running.clone())?;
+
+    let producer = {
+        let ring = Arc::clone(&ring);
+        let running = Arc::clone(&running);
+        std::thread::Builder::new()
+            .name("tone".to_string())
+            .spawn(move || {
+                while remaining > 0 && running.load(Ordering::SeqCst) {
+                    let n = chunk.len().min(remaining);
+                    generator.fill(&mut chunk[..n]);
+                    let mut written = 0;
+                    while written < n && running.load(Ordering::SeqCst) {
+                        written += ring.write(&chunk[written..n]);
+                        if written < n {
+                            std::thread::sleep(Duration::from_millis(5));
+                        }
+                    }
+                    remaining -= n;
+                }
+            })?
+    };
 
-/// 检查文件是否为支持的音频格式
-fn is_audio_file(path: &PathBuf) -> bool {
-    path.extension()
-        .and_then(OsStr::to_str)
-        .map(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
-        .unwrap_or(false)
+    // 等待生成结束且缓冲区播完
+    while running.load(Ordering::SeqCst) && (!producer.is_finished() || ring.available() > 0) {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    running.store(false, Ordering::SeqCst);
+    let _ = producer.join();
+    output.stop()?;
+
+    let underruns = stats.underrun_count();
+    if underruns > 0 {
+        let lost_ms =
+            stats.underrun_samples_total() as f64 / CHANNELS as f64 / rate as f64 * 1000.0;
+        println!("Underruns: {} ({:.1}ms lost)", underruns, lost_ms);
+    }
+    Ok(())
 }
 
-/// 扫描目录中的音频文件（按文件名排序）
-fn scan_audio_files(dir: &PathBuf) -> anyhow::Result<Vec<PathBuf>> {
-    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
-        .filter_map(|entry| entry.ok())
-        .map(|entry| entry.path())
-        .filter(|path| path.is_file() && is_audio_file(path))
-        .collect();
+/// 响度扫描：测量每个文件的积分响度和真峰值，写入响度缓存
+///
+/// 已扫描且未修改（mtime 相同）的文件直接使用缓存结果
+fn scan_loudness(path: &Path, cli: &Cli) -> anyhow::Result<()> {
+    let files = if path.is_dir() {
+        scan_audio_files(path, cli.recursive)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+    if files.is_empty() {
+        println!("No audio files found in: {}", path.display());
+        return Ok(());
+    }
 
-    // 按文件名排序
-    files.sort_by(|a, b| {
-        a.file_name()
-            .unwrap_or_default()
-            .cmp(b.file_name().unwrap_or_default())
-    });
+    let pcm_format = raw_pcm_format(cli);
+    let mut cache = LoudnessCache::load();
+    let mut scanned = 0;
 
-    Ok(files)
+    for (i, file) in files.iter().enumerate() {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        let Some(mtime) = file_mtime(file) else {
+            eprintln!("[{}/{}] {}: cannot read file", i + 1, files.len(), name);
+            continue;
+        };
+
+        if let Some(entry) = cache.get(file, mtime) {
+            println!(
+                "[{}/{}] {}: {:.1} LUFS, {:.1} dBTP (cached)",
+                i + 1, files.len(), name, entry.integrated_lufs, entry.true_peak_dbtp
+            );
+            continue;
+        }
+
+        match measure_loudness(file, pcm_format, cli.force_format.as_deref()) {
+            Ok((integrated_lufs, true_peak_dbtp)) => {
+                println!(
+                    "[{}/{}] {}: {:.1} LUFS, {:.1} dBTP",
+                    i + 1, files.len(), name, integrated_lufs, true_peak_dbtp
+                );
+                cache.insert(file, LoudnessEntry { mtime, integrated_lufs, true_peak_dbtp });
+                scanned += 1;
+            }
+            Err(e) => eprintln!("[{}/{}] {}: {}", i + 1, files.len(), name, e),
+        }
+    }
+
+    cache.save()?;
+    println!("\nScanned {} new file(s) of {}.", scanned, files.len());
+    Ok(())
+}
+
+/// 解码整个文件并测量积分响度（LUFS）和真峰值（dBTP）
+fn measure_loudness(
+    file: &PathBuf,
+    pcm_format: RawPcmFormat,
+    force_format: Option<&str>,
+) -> anyhow::Result<(f64, f64)> {
+    let mut decoder = AudioDecoder::open_with_options(file, pcm_format, force_format)?;
+    let info = decoder.info();
+    let mut meter = LoudnessMeter::new(info.sample_rate, info.channels as usize);
+
+    loop {
+        let samples = decoder.decode_next_i32()?;
+        if samples.is_empty() {
+            break;
+        }
+        meter.add_samples(samples);
+    }
+
+    match (meter.integrated_lufs(), meter.true_peak_dbtp()) {
+        (Some(lufs), Some(peak)) => Ok((lufs, peak)),
+        _ => anyhow::bail!("too short or silent to measure"),
+    }
 }
 
 /// 简单播放模式
 fn simple_play(path: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
+    // stdin 只能读取一次，不支持单曲循环
+    if is_stdin_path(path) {
+        return play_single_file(path, cli, None);
+    }
+
     // 检查是文件还是目录
     if path.is_dir() {
         return play_directory(path, cli);
@@ -318,29 +823,28 @@ fn simple_play(path: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
 /// 单曲循环播放
 fn play_single_file_repeat(file: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
     let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
-    let r = running.clone();
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-    })?;
+    install_signal_handler(running.clone())?;
 
-    println!("Roger Player - Single Track Repeat Mode");
-    println!("Press Ctrl+C to stop.\n");
+    notice(cli, format_args!("Roger Player - Single Track Repeat Mode"));
+    notice(cli, format_args!("Press Ctrl+C to stop.\n"));
 
     let mut play_count = 0u64;
+    // 整个循环共用一个引擎：播完时输出不停止，下一遍由 load() 沿用
+    let mut engine = Engine::new(create_engine_config(cli))?;
 
     loop {
         if !running.load(Ordering::SeqCst) {
-            println!("\nPlayback interrupted.");
+            notice(cli, format_args!("\nPlayback interrupted."));
             break;
         }
 
         play_count += 1;
         let track_info = Some((play_count as usize, 0)); // 0 表示无限循环
 
-        match play_single_file_with_running(file, cli, track_info, running.clone(), false) {
+        match play_single_file_with_running(&mut engine, file, cli, track_info, running.clone(), false) {
             Ok(SkipCommand::None) => {
                 // 正常结束，继续循环
-                println!("\n--- Repeating track ---\n");
+                notice(cli, format_args!("\n--- Repeating track ---\n"));
             }
             Ok(_) => {
                 // 用户跳过（单曲模式下忽略跳转命令）
@@ -355,13 +859,39 @@ fn play_single_file_repeat(file: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// 按参数顺序展开播放列表：目录替换为其中的音频文件（自然排序），文件原样保留
+fn expand_paths(paths: &[PathBuf], recursive: bool) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            files.extend(scan_audio_files(path, recursive)?);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+/// 播放多个参数组成的临时播放列表（目录展开，按参数顺序）
+fn play_paths(paths: &[PathBuf], cli: &Cli) -> anyhow::Result<()> {
+    if paths.iter().any(|p| is_stdin_path(p)) {
+        anyhow::bail!("'-' (stdin) cannot be combined with other paths");
+    }
+    let files = expand_paths(paths, cli.recursive)?;
+    play_files(files, &format!("{} arguments", paths.len()), cli)
+}
+
 /// 播放目录中的所有音频文件
-fn play_directory(dir: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
-    let mut files = scan_audio_files(dir)?;
+fn play_directory(dir: &Path, cli: &Cli) -> anyhow::Result<()> {
+    let files = scan_audio_files(dir, cli.recursive)?;
+    play_files(files, &dir.display().to_string(), cli)
+}
 
+/// 按列表顺序播放（`source` 为列表来源描述，如目录路径）
+fn play_files(mut files: Vec<PathBuf>, source: &str, cli: &Cli) -> anyhow::Result<()> {
     if files.is_empty() {
-        println!("No audio files found in: {}", dir.display());
-        println!("Supported formats: {}", AUDIO_EXTENSIONS.join(", "));
+        notice(cli, format_args!("No audio files found in: {}", source));
+        notice(cli, format_args!("Supported formats: {}", AUDIO_EXTENSIONS.join(", ")));
         return Ok(());
     }
 
@@ -382,31 +912,36 @@ fn play_directory(dir: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
         format!(" [{}]", mode_flags.join(", "))
     };
 
-    println!("Roger Player - Directory Mode{}", mode_str);
-    println!("Found {} audio files in: {}\n", files.len(), dir.display());
+    notice(cli, format_args!("Roger Player - Directory Mode{}", mode_str));
+    notice(cli, format_args!("Found {} audio files in: {}\n", files.len(), source));
 
     for (i, file) in files.iter().enumerate() {
-        println!(
-            "  [{}] {}",
-            i + 1,
-            file.file_name().unwrap_or_default().to_string_lossy()
+        notice(
+            cli,
+            format_args!(
+                "  [{}] {}",
+                i + 1,
+                file.file_name().unwrap_or_default().to_string_lossy()
+            ),
         );
     }
-    println!();
-    println!("Controls: [Space] pause/play | [→] next | [←] previous | [Ctrl+C] quit\n");
+    notice(cli, format_args!(""));
+    notice(
+        cli,
+        format_args!("Controls: [Space] pause/play | [→] next | [←] previous | [Ctrl+C] quit\n"),
+    );
 
     // 设置 Ctrl+C 处理（在播放开始前设置一次）
     let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
-    let r = running.clone();
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-    })?;
+    install_signal_handler(running.clone())?;
 
     // 进入终端原始模式（用于键盘控制）
     let _raw_guard = RawModeGuard::enter();
 
     // 使用索引循环，支持前后跳转
     let mut current_index: usize = 0;
+    // 所有曲目共用一个引擎：自然播完时输出不停止，下一首由 load() 决定能否沿用
+    let mut engine = Engine::new(create_engine_config(cli))?;
 
     loop {
         // 检查是否已播放完所有曲目
@@ -414,7 +949,7 @@ fn play_directory(dir: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
             if cli.repeat {
                 // 循环模式：重新开始
                 current_index = 0;
-                println!("\n--- Playlist restarting ---\n");
+                notice(cli, format_args!("\n--- Playlist restarting ---\n"));
             } else {
                 // 非循环模式：结束
                 break;
@@ -422,14 +957,14 @@ fn play_directory(dir: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
         }
 
         if !running.load(Ordering::SeqCst) {
-            println!("\nPlayback interrupted.");
+            notice(cli, format_args!("\nPlayback interrupted."));
             break;
         }
 
         let file = &files[current_index];
         let track_info = Some((current_index + 1, files.len()));
 
-        match play_single_file_with_running(file, cli, track_info, running.clone(), true) {
+        match play_single_file_with_running(&mut engine, file, cli, track_info, running.clone(), true) {
             Ok(skip_command) => {
                 match skip_command {
                     SkipCommand::Next => {
@@ -448,11 +983,16 @@ fn play_directory(dir: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
                         }
                     }
                     SkipCommand::None => {
-                        // 正常结束，继续下一首
+                        // 正常结束，继续下一首（有下一首时先插入曲目间静音）
                         current_index += 1;
+                        if current_index < files.len() || cli.repeat {
+                            wait_inter_track_gap(&mut engine, cli, &running)?;
+                        }
                     }
                 }
             }
+            // 后续曲目的输出路径相同，不再逐首尝试
+            Err(e) if e.is::<NotBitPerfect>() => return Err(e),
             Err(e) => {
                 eprintln!("Error playing {}: {}", file.display(), e);
                 // 出错时继续下一首
@@ -461,10 +1001,111 @@ fn play_directory(dir: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
         }
     }
 
-    println!("Playlist finished.");
+    notice(cli, format_args!("Playlist finished."));
+    Ok(())
+}
+
+/// 曲目间静音（`--gap`），Ctrl+C 时提前结束
+///
+/// 有静音时先停止输出（静音期间设备不空转）；无静音时输出保持运行，留给下一首沿用
+fn wait_inter_track_gap(
+    engine: &mut Engine,
+    cli: &Cli,
+    running: &std::sync::atomic::AtomicBool,
+) -> anyhow::Result<()> {
+    // 目录模式的 --repeat 是列表循环，不是单曲循环
+    let config = EngineConfig {
+        inter_track_gap_ms: cli.gap,
+        ..EngineConfig::default()
+    };
+    let Some(gap) = config.inter_track_gap(false) else {
+        return Ok(());
+    };
+    engine.stop()?;
+    let deadline = Instant::now() + gap;
+    while running.load(Ordering::SeqCst) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
     Ok(())
 }
 
+/// 播放过程中的提示信息：`--status-line` 时写到 stderr，stdout 只留状态行
+fn notice(cli: &Cli, message: std::fmt::Arguments) {
+    if cli.status_line {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// `--status-line` 的一行状态（空格分隔的 key=value，供脚本/状态栏解析）
+///
+/// 如 `state=playing pos=12.3 dur=240.0 buf=0.87 underruns=0 bitperfect=1`；时长未知时 `dur=-`
+fn status_line(
+    state: PlaybackState,
+    stats: &EngineStats,
+    total_secs: Option<f64>,
+    bit_perfect: BitPerfectStatus,
+) -> String {
+    let state = match state {
+        PlaybackState::Stopped => "stopped",
+        PlaybackState::Playing => "playing",
+        PlaybackState::Paused => "paused",
+        PlaybackState::Buffering => "buffering",
+    };
+    let duration = total_secs
+        .filter(|&t| t > 0.0)
+        .map_or("-".to_string(), |t| format!("{:.1}", t));
+    format!(
+        "state={} pos={:.1} dur={} buf={:.2} underruns={} bitperfect={}",
+        state,
+        stats.position_secs,
+        duration,
+        stats.buffer_fill_ratio,
+        stats.underrun_count,
+        u8::from(bit_perfect.is_bit_perfect())
+    )
+}
+
+/// 单文件播放的状态行：时间 + 进度条 + 剩余时间 + 缓冲/underrun
+///
+/// 进度条占满终端剩余宽度（过窄时省略）；时长未知时显示不定进度条
+fn progress_line(
+    stats: &EngineStats,
+    total_secs: Option<f64>,
+    columns: usize,
+    tick: usize,
+) -> String {
+    let clock = |secs: f64| format!("{:02}:{:02}", (secs / 60.0) as u32, (secs % 60.0) as u32);
+    let position = stats.position_secs;
+
+    let (time, remaining) = match total_secs.filter(|&t| t > 0.0) {
+        Some(total) => (
+            format!("{} / {}", clock(position), clock(total)),
+            format!(" -{}", clock((total - position).max(0.0))),
+        ),
+        None => (format!("{} / --:--", clock(position)), String::new()),
+    };
+    let status = format!(
+        "  |  Buffer: {:5.1}%  |  Underruns: {}",
+        stats.buffer_fill_ratio * 100.0,
+        stats.underrun_count
+    );
+
+    // "  " + 时间 + " [" + 进度条 + "]" + 剩余时间 + 状态，末尾留 1 列避免自动换行
+    let fixed =
+        2 + time.chars().count() + 3 + remaining.chars().count() + status.chars().count() + 1;
+    let width = columns.saturating_sub(fixed);
+    if width < 10 {
+        return format!("  {}{}{} ", time, remaining, status);
+    }
+    let bar = match total_secs.filter(|&t| t > 0.0) {
+        Some(total) => progress_bar(width, position / total),
+        None => spinner_bar(width, tick),
+    };
+    format!("  {} [{}]{}{}", time, bar, remaining, status)
+}
+
 /// 播放单个文件（带可选的曲目信息）
 fn play_single_file(
     file: &PathBuf,
@@ -472,88 +1113,205 @@ fn play_single_file(
     track_info: Option<(usize, usize)>,
 ) -> anyhow::Result<()> {
     let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
-    let r = running.clone();
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-    })?;
+    install_signal_handler(running.clone())?;
 
     // 进入终端原始模式（用于键盘控制）
-    let _raw_guard = RawModeGuard::enter();
+    // stdin 作为音频输入时不能读取按键
+    let _raw_guard = if is_stdin_path(file) {
+        None
+    } else {
+        RawModeGuard::enter()
+    };
 
-    play_single_file_with_running(file, cli, track_info, running, false)?;
+    let mut engine = Engine::new(create_engine_config(cli))?;
+    play_single_file_with_running(&mut engine, file, cli, track_info, running, false)?;
+    engine.stop()?;
     Ok(())
 }
 
 /// 播放单个文件（使用已存在的 running 标志）
 ///
 /// 参数 `keyboard_control` 为 true 时启用键盘控制（空格切换曲目）
-/// 返回 SkipCommand 指示是否需要跳转
+/// 返回 SkipCommand 指示是否需要跳转。自然播完时不停止输出，
+/// 由调用方播放下一首（`load()` 决定能否沿用设备）或调用 `engine.stop()`
 fn play_single_file_with_running(
+    engine: &mut Engine,
     file: &PathBuf,
     cli: &Cli,
     track_info: Option<(usize, usize)>,
     running: Arc<std::sync::atomic::AtomicBool>,
     keyboard_control: bool,
 ) -> anyhow::Result<SkipCommand> {
-    let config = create_engine_config(cli);
-    let mut engine = Engine::new(config);
+    // stdin 作为音频输入时，键盘输入不可用
+    let stdin_source = is_stdin_path(file);
 
     // 显示播放信息
-    let file_name = file
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy();
+    let file_name = if stdin_source {
+        "<stdin>".into()
+    } else {
+        file.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+    };
 
     if let Some((current, total)) = track_info {
         // 换曲时加空行分隔（第一首除外）
         // 需要两个换行：一个结束状态行（\r 覆盖的行），一个创建空行
-        if current > 1 {
+        if current > 1 && !cli.status_line {
             println!("\n");
         }
         if total == 0 {
             // 单曲循环模式：显示播放次数
-            println!("[Play #{}] Loading: {}", current, file_name);
+            notice(cli, format_args!("[Play #{}] Loading: {}", current, file_name));
         } else {
-            println!("[{}/{}] Loading: {}", current, total, file_name);
+            notice(cli, format_args!("[{}/{}] Loading: {}", current, total, file_name));
         }
+    } else if stdin_source {
+        notice(cli, format_args!("Roger Player - Loading: {}", file_name));
     } else {
-        println!("Roger Player - Loading: {}", file.display());
+        notice(cli, format_args!("Roger Player - Loading: {}", file.display()));
+    }
+
+    // 响度归一化（需先 `roger-player scan`）
+    if let Some(target) = cli.normalize {
+        match LoudnessCache::load().lookup(file) {
+            Some(entry) => {
+                let gain = entry.gain_db(target);
+                engine.set_gain_db(gain);
+                notice(
+                    cli,
+                    format_args!(
+                        "Normalize: {:+.1} dB ({:.1} LUFS -> {:.1} LUFS)",
+                        gain, entry.integrated_lufs, target
+                    ),
+                );
+            }
+            None => {
+                // 引擎在曲目间复用，清掉上一首的增益
+                engine.set_gain_db(0.0);
+                notice(cli, format_args!("Normalize: not scanned (run 'roger-player scan')"));
+            }
+        }
     }
 
     engine.play(file)?;
+    if let Err(e) = check_bit_perfect(cli.require_bit_perfect, engine.bit_perfect_reason()) {
+        engine.stop()?;
+        return Err(e.into());
+    }
 
     // 等待预缓冲完成
-    print!("Buffering...");
-    io::stdout().flush()?;
+    if !cli.status_line {
+        print!("Buffering...");
+        io::stdout().flush()?;
+    }
+
+    // 缓冲期间按 Space：缓冲完成后立即暂停（引擎在缓冲中无法暂停）
+    let mut pause_when_ready = false;
+    // --status-line：只在内容变化时输出
+    let mut last_status = String::new();
 
     while engine.state() == PlaybackState::Buffering {
         if !running.load(Ordering::SeqCst) {
             engine.stop()?;
             return Ok(SkipCommand::None);
         }
+
+        // 缓冲中同样响应按键：加载慢的曲目可以直接跳过
+        let key = if stdin_source { None } else { read_key_nonblocking() };
+        match key.map_or(KeyAction::None, |k| key_action(k, keyboard_control)) {
+            KeyAction::Skip(command) => {
+                engine.stop()?;
+                if cli.status_line {
+                    notice(cli, format_args!("Skipped while buffering."));
+                } else {
+                    println!("\rSkipped while buffering.        ");
+                }
+                return Ok(command);
+            }
+            KeyAction::TogglePause => pause_when_ready = !pause_when_ready,
+            KeyAction::None => {}
+        }
+
         let stats = engine.stats();
-        print!("\rBuffering... {:.0}%", stats.buffer_fill_ratio * 100.0);
+        if cli.status_line {
+            let total_secs = engine.current_info().and_then(|i| i.duration_secs);
+            let line = status_line(engine.state(), &stats, total_secs, engine.bit_perfect_reason());
+            if line != last_status {
+                println!("{}", line);
+                last_status = line;
+            }
+        } else {
+            match engine.buffering_eta_secs() {
+                Some(eta) => print!(
+                    "\rBuffering... {:.0}% (~{:.1}s)  ",
+                    stats.target_fill_ratio.min(1.0) * 100.0,
+                    eta
+                ),
+                None => print!(
+                    "\rBuffering... {:.0}%        ",
+                    stats.target_fill_ratio.min(1.0) * 100.0
+                ),
+            }
+        }
         io::stdout().flush()?;
-        std::thread::sleep(Duration::from_millis(100));
+        // 解码线程达到预缓冲目标时立即唤醒；超时用于响应按键和刷新进度
+        engine.wait_prebuffered(Some(Duration::from_millis(50)));
+    }
+
+    if pause_when_ready {
+        let _ = engine.toggle_pause();
     }
 
     // 显示输出模式状态
     if let Some((is_hal, is_exclusive)) = engine.output_mode() {
         let mode = if is_hal { "HALOutput (bit-perfect)" } else { "DefaultOutput (mixer)" };
         let exclusive = if is_exclusive { " | exclusive" } else { "" };
-        print!("\rOutput: {}{}", mode, exclusive);
-        // 补齐空格清除 Buffering 残留
-        println!("                    ");
-    } else {
+        if cli.status_line {
+            notice(cli, format_args!("Output: {}{}", mode, exclusive));
+        } else {
+            print!("\rOutput: {}{}", mode, exclusive);
+            // 补齐空格清除 Buffering 残留
+            println!("                    ");
+        }
+        if let Some(reason) = engine.hog_mode_failure() {
+            notice(cli, format_args!("Exclusive mode unavailable: {}", reason));
+        }
+        if let Some(report) = engine.negotiation_report().filter(|r| !r.all_as_requested()) {
+            notice(cli, format_args!("{}", report));
+        }
+    } else if !cli.status_line {
         println!("\rBuffering complete.     ");
     }
 
+    // 多音轨容器：列出可用轨道（* 为正在播放的轨道，`--track N` 选择）
+    if let Some(info) = engine.current_info().filter(|i| i.tracks.len() > 1) {
+        for (i, track) in info.tracks.iter().enumerate() {
+            let marker = if i == info.track_index { "*" } else { " " };
+            notice(
+                cli,
+                format_args!(
+                    "{} Track {}: {} {} {}",
+                    marker,
+                    i + 1,
+                    track.codec,
+                    track.channels.map(|c| format!("{}ch", c)).unwrap_or_default(),
+                    track.language.as_deref().unwrap_or("")
+                ),
+            );
+        }
+    }
+
     // 播放循环
-    if track_info.is_none() {
-        println!("Playing. [Space] pause/play | [Ctrl+C] quit\n");
+    if stdin_source {
+        notice(cli, format_args!("Playing from stdin. [Ctrl+C] quit\n"));
+    } else if track_info.is_none() {
+        notice(cli, format_args!("Playing. [Space] pause/play | [Ctrl+C] quit\n"));
     }
 
     let mut skip_command = SkipCommand::None;
+    // 不定进度条的动画帧
+    let mut tick: usize = 0;
 
     loop {
         // 检查用户中断
@@ -561,62 +1319,68 @@ fn play_single_file_with_running(
             break;
         }
 
-        // 检查音轨是否播放完毕
-        if engine.is_track_finished() {
-            break;
-        }
-
         // 键盘控制
         // Space = 暂停/播放, → = 下一首, ← = 上一首
-        if let Some(key) = read_key_nonblocking() {
-            match key {
-                KeyPress::Space => {
-                    // 空格：暂停/播放
-                    let _ = engine.toggle_pause();
-                }
-                KeyPress::Right if keyboard_control => {
-                    // →：下一首
-                    skip_command = SkipCommand::Next;
-                    break;
-                }
-                KeyPress::Left if keyboard_control => {
-                    // ←：上一首
-                    skip_command = SkipCommand::Previous;
-                    break;
-                }
-                _ => {}
+        let key = if stdin_source { None } else { read_key_nonblocking() };
+        match key.map_or(KeyAction::None, |k| key_action(k, keyboard_control)) {
+            KeyAction::TogglePause => {
+                let _ = engine.toggle_pause();
             }
+            KeyAction::Skip(command) => {
+                skip_command = command;
+                break;
+            }
+            KeyAction::None => {}
         }
 
         let stats = engine.stats();
+        let total_secs = engine.current_info().and_then(|i| i.duration_secs);
+        if cli.status_line {
+            let line = status_line(engine.state(), &stats, total_secs, engine.bit_perfect_reason());
+            if line != last_status {
+                println!("{}", line);
+                last_status = line;
+            }
+        } else {
+            let columns = crossterm::terminal::size().map(|(c, _)| c as usize).unwrap_or(80);
+            print!("\r{}", progress_line(&stats, total_secs, columns, tick));
+        }
+        io::stdout().flush()?;
+        tick += 1;
 
-        // 格式化时间
-        let pos_mins = (stats.position_secs / 60.0) as u32;
-        let pos_secs = stats.position_secs % 60.0;
-
-        let total_secs = engine
-            .current_info()
-            .and_then(|i| i.duration_secs)
-            .unwrap_or(0.0);
-        let total_mins = (total_secs / 60.0) as u32;
-        let total_secs_rem = total_secs % 60.0;
+        // 等待播放完毕（50ms 超时，保持键盘响应）
+        if engine.wait_finished(Some(Duration::from_millis(50))) {
+            break;
+        }
+    }
 
-        print!(
-            "\r  {:02}:{:05.2} / {:02}:{:05.2}  |  Buffer: {:5.1}%  |  Underruns: {}  ",
-            pos_mins,
-            pos_secs,
-            total_mins,
-            total_secs_rem,
-            stats.buffer_fill_ratio * 100.0,
-            stats.underrun_count
-        );
-        io::stdout().flush()?;
+    if cli.status_line {
+        // 结束时补一行 stopped，脚本无需靠超时判断
+        let total_secs = engine.current_info().and_then(|i| i.duration_secs);
+        let status = engine.bit_perfect_reason();
+        println!("{}", status_line(PlaybackState::Stopped, &engine.stats(), total_secs, status));
+    } else {
+        println!();
+    }
 
-        std::thread::sleep(Duration::from_millis(50)); // 更快响应键盘
+    // 中途解码失败：与正常结束区分提示（目录模式下继续下一首）
+    if let Some(err) = engine.last_error() {
+        if keyboard_control {
+            notice(cli, format_args!("Skipped (corrupt): {} ({})", file_name, err));
+        } else {
+            eprintln!("Playback ended early: {}", err);
+        }
+    } else if engine.is_empty_track() {
+        // 格式有效但没有音频数据：缓冲立即结束，提示后跳过
+        notice(cli, format_args!("Skipped (empty): {}", file_name));
     }
 
-    println!();
-    engine.stop()?;
+    let finished = skip_command == SkipCommand::None
+        && engine.last_error().is_none()
+        && engine.is_track_finished();
+    if !finished {
+        engine.stop()?;
+    }
 
     Ok(skip_command)
 }
@@ -624,27 +1388,26 @@ fn play_single_file_with_running(
 /// 交互式播放模式
 fn interactive_play(file: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
     let config = create_engine_config(cli);
-    let mut engine = Engine::new(config);
+    let mut engine = Engine::new(config)?;
 
     println!("Roger Player - Interactive Mode");
     println!("Loading: {}", file.display());
 
     engine.play(file)?;
+    if let Err(e) = check_bit_perfect(cli.require_bit_perfect, engine.bit_perfect_reason()) {
+        engine.stop()?;
+        return Err(e.into());
+    }
 
     // 等待预缓冲
-    while engine.state() == PlaybackState::Buffering {
-        std::thread::sleep(Duration::from_millis(50));
-    }
+    engine.wait_prebuffered(None);
 
     println!("\nCommands: [space]=pause/resume  [q]=quit  [i]=info\n");
 
     // 简单的命令行交互
     // 注意：这需要 terminal raw mode，这里简化为轮询
     let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
-    let r = running.clone();
-    ctrlc::set_handler(move || {
-        r.store(false, std::sync::atomic::Ordering::SeqCst);
-    })?;
+    install_signal_handler(running.clone())?;
 
     while running.load(std::sync::atomic::Ordering::SeqCst) && engine.is_playing() {
         let stats = engine.stats();
@@ -657,16 +1420,25 @@ fn interactive_play(file: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
             PlaybackState::Stopped => "⏹",
         };
 
+        let decode = engine
+            .realtime_factor()
+            .map_or_else(|| "-".to_string(), |f| format!("{:.0}x", f));
+
         print!(
-            "\r{} {:.1}s | Buffer: {:.0}% | Underruns: {}    ",
+            "\r{} {:.1}s | Buffer: {:.0}% | Underruns: {} | Overflows: {} | Decode: {}    ",
             state_str,
             stats.position_secs,
             stats.buffer_fill_ratio * 100.0,
-            stats.underrun_count
+            stats.underrun_count,
+            stats.write_overflow_count,
+            decode
         );
         io::stdout().flush()?;
 
-        std::thread::sleep(Duration::from_millis(100));
+        // 等待播放完毕（100ms 超时，刷新状态行）
+        if engine.wait_finished(Some(Duration::from_millis(100))) {
+            break;
+        }
     }
 
     println!("\n");
@@ -675,21 +1447,17 @@ fn interactive_play(file: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
     Ok(())
 }
 
-/// TUI 播放模式
-fn tui_play(path: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
+/// TUI 播放模式（多个参数按顺序组成播放列表）
+fn tui_play(paths: &[PathBuf], cli: &Cli, user_config: &UserConfig) -> anyhow::Result<()> {
     // 扫描文件
-    let mut files = if path.is_dir() {
-        scan_audio_files(path)?
-    } else {
-        if is_audio_file(path) {
-            vec![path.clone()]
-        } else {
-            return Err(anyhow::anyhow!("Not a supported audio file: {}", path.display()));
-        }
-    };
+    if let Some(path) = paths.iter().find(|p| !p.is_dir() && !is_audio_file(p)) {
+        return Err(anyhow::anyhow!("Not a supported audio file: {}", path.display()));
+    }
+    let mut files = expand_paths(paths, cli.recursive)?;
 
     if files.is_empty() {
-        return Err(anyhow::anyhow!("No audio files found in: {}", path.display()));
+        let sources: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+        return Err(anyhow::anyhow!("No audio files found in: {}", sources.join(", ")));
     }
 
     // Shuffle
@@ -699,29 +1467,89 @@ fn tui_play(path: &PathBuf, cli: &Cli) -> anyhow::Result<()> {
     }
 
     let config = create_engine_config(cli);
-    let app = crate::tui::model::App::new(config, files);
-
-    crate::tui::controller::run(app)?;
+    let app = crate::tui::model::App::new(
+        config,
+        files,
+        user_config.clone(),
+        tui_session(cli, user_config),
+    )?;
+
+    // 原始模式下 Ctrl+C 是按键；这里处理 SIGTERM/SIGHUP
+    let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    install_signal_handler(running.clone())?;
+    crate::tui::controller::run(app, running)?;
 
     Ok(())
 }
 
 /// TUI 空启动模式（无参数，等待拖拽文件）
-fn tui_play_empty(cli: &Cli) -> anyhow::Result<()> {
+fn tui_play_empty(cli: &Cli, user_config: &UserConfig) -> anyhow::Result<()> {
     let config = create_engine_config(cli);
-    let app = crate::tui::model::App::new_empty(config);
+    let app = crate::tui::model::App::new_empty(
+        config,
+        user_config.clone(),
+        tui_session(cli, user_config),
+    )?;
 
-    crate::tui::controller::run(app)?;
+    let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    install_signal_handler(running.clone())?;
+    crate::tui::controller::run(app, running)?;
+
+    Ok(())
+}
+
+/// 收到退出信号后等待主循环正常退出的时间，超时则兜底释放独占模式并退出
+const SIGNAL_EXIT_GRACE: Duration = Duration::from_secs(3);
 
+/// 安装 SIGINT/SIGTERM/SIGHUP 处理
+///
+/// 收到信号时清除 `running`，主循环随之退出，由 Drop 正常释放独占模式。
+/// 若主循环在 `SIGNAL_EXIT_GRACE` 内没有退出（或再次收到信号），
+/// 兜底按设备 ID 释放独占模式后直接退出。
+///
+/// ctrlc 的原始信号处理函数只写 self-pipe，回调在专用线程上运行，
+/// 因此这里可以调用 CoreAudio。SIGKILL 无法捕获，被强杀时仍可能残留独占
+fn install_signal_handler(running: Arc<std::sync::atomic::AtomicBool>) -> anyhow::Result<()> {
+    ctrlc::set_handler(move || {
+        if !running.swap(false, Ordering::SeqCst) {
+            // 第二次信号：不再等待
+            AudioOutput::release_hog_mode_on_exit();
+            std::process::exit(130);
+        }
+        let _ = std::thread::Builder::new()
+            .name("signal-exit".to_string())
+            .spawn(|| {
+                std::thread::sleep(SIGNAL_EXIT_GRACE);
+                log::warn!("Did not shut down after signal, releasing exclusive mode");
+                AudioOutput::release_hog_mode_on_exit();
+                std::process::exit(130);
+            });
+    })?;
     Ok(())
 }
 
+/// `--buffer-ms` 换算为 ring buffer 样本数
+fn engine_buffer_frames(cli: &Cli) -> usize {
+    let buffer_ms = cli.buffer_ms.unwrap_or(DEFAULT_BUFFER_MS);
+    (buffer_ms as usize * 48) + 1000 // 近似，实际会根据采样率调整
+}
+
 /// 创建引擎配置
 fn create_engine_config(cli: &Cli) -> EngineConfig {
-    let buffer_frames = (cli.buffer_ms as usize * 48) + 1000; // 近似，实际会根据采样率调整
+    let buffer_frames = engine_buffer_frames(cli);
 
     // 解析设备选择
     let device_id = cli.device.as_ref().and_then(|d| {
+        // uid:<UID> 形式：按稳定 UID 精确查找（ID 重启后会变）
+        if let Some(uid) = crate::audio::parse_device_uid(d) {
+            if let Some(device) = AudioOutput::find_device_by_uid(uid) {
+                println!("Found device: {} (ID: {})", device.name, device.id);
+                return Some(device.id);
+            }
+            eprintln!("Warning: Device with UID '{}' not found, using system default", uid);
+            return None;
+        }
+
         // 先尝试解析为设备 ID
         if let Ok(id) = d.parse::<u32>() {
             println!("Using device ID: {}", id);
@@ -741,13 +1569,228 @@ fn create_engine_config(cli: &Cli) -> EngineConfig {
     EngineConfig {
         output: crate::audio::OutputConfig {
             sample_rate: 48000, // 会被文件采样率覆盖
-            buffer_frames: 512,
+            buffer_frames: cli.io_buffer_frames,
             exclusive_mode: !cli.no_exclusive,
             integer_mode: true,
             use_hal: !cli.hal_off,
             device_id,
+            fade_ms: cli.fade_ms,
+            dither_seed: cli.dither_seed,
+            // 显式指定输出参数时不套用设备缓存
+            device_settings: !(cli.hal_on
+                || cli.hal_off
+                || cli.no_exclusive
+                || cli.io_buffer_frames != DEFAULT_IO_BUFFER_FRAMES),
+            lock_device_rate: cli.lock_rate,
+            // 漂移量由嵌入方通过 Engine::set_drift_ppm 设置，CLI 不开启
+            callback_drift_correction: false,
         },
         buffer_frames,
+        // 指定 --buffer-ms 时按每首曲目的采样率换算，缓冲时长在不同格式间保持一致
+        buffer_ms: cli.buffer_ms,
         prebuffer_ratio: 0.5,
+        decode_chunk_frames: cli.decode_chunk_frames,
+        crossfeed: cli.crossfeed,
+        stereo_width: cli.stereo_width,
+        limiter: cli.limiter,
+        true_peak_meter: cli.true_peak,
+        wait_strategy: cli.wait_strategy,
+        thread_affinity: (!cli.no_thread_affinity).then_some(AUDIO_AFFINITY_TAG),
+        // 未指定 --buffer-ms（配置文件也未设置）时按格式/来源自动确定
+        auto_buffer: cli.buffer_ms.is_none(),
+        pcm_format: raw_pcm_format(cli),
+        force_format: cli.force_format.clone(),
+        track_index: cli.track.map_or(0, |n| n as usize - 1),
+        follow: cli.follow,
+        inter_track_gap_ms: cli.gap,
+    }
+}
+
+/// 原始 PCM（`.pcm`）文件格式（来自 `--pcm-*` 参数）
+fn raw_pcm_format(cli: &Cli) -> RawPcmFormat {
+    RawPcmFormat {
+        sample_rate: cli.pcm_rate,
+        channels: cli.pcm_channels,
+        bits_per_sample: cli.pcm_bits,
+        endian: if cli.pcm_big_endian {
+            crate::audio::Endian::Big
+        } else {
+            crate::audio::Endian::Little
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_line() {
+        let stats = EngineStats {
+            buffer_fill_ratio: 0.873,
+            target_fill_ratio: 1.0,
+            underrun_count: 2,
+            underrun_lost_ms: 1.5,
+            write_overflow_count: 0,
+            samples_played: 0,
+            position_secs: 12.34,
+            buffered_ms: 500.0,
+        };
+        let summary = test_summary(96000, 24, 96000);
+        assert_eq!(
+            status_line(PlaybackState::Playing, &stats, Some(240.0), summary.bit_perfect_status()),
+            "state=playing pos=12.3 dur=240.0 buf=0.87 underruns=2 bitperfect=1"
+        );
+
+        // 共享模式、时长未知（流）
+        let shared = crate::engine::PlaybackSummary {
+            exclusive: false,
+            ..summary
+        };
+        assert_eq!(
+            status_line(PlaybackState::Paused, &stats, None, shared.bit_perfect_status()),
+            "state=paused pos=12.3 dur=- buf=0.87 underruns=2 bitperfect=0"
+        );
+        assert!(status_line(PlaybackState::Buffering, &stats, Some(0.0), BitPerfectStatus::Idle)
+            .starts_with("state=buffering pos=12.3 dur=- "));
+    }
+
+    /// HAL 独占、Int32 输出的 FLAC 播放摘要（采样率不同时经 SRC）
+    fn test_summary(source_rate: u32, source_bits: u32, device_rate: u32) -> crate::engine::PlaybackSummary {
+        crate::engine::PlaybackSummary {
+            source_format: "FLAC".to_string(),
+            source_rate,
+            source_bits: Some(source_bits),
+            device_rate,
+            output_mode: crate::audio::output::OutputFormatMode::Int32,
+            output_bits: 32,
+            hal: true,
+            exclusive: true,
+            resampled: source_rate != device_rate,
+            fell_back: false,
+            gapless: false,
+        }
+    }
+
+    #[test]
+    fn test_require_bit_perfect() {
+        // 独占被占用、44.1k 源在 48k 设备上 SRC
+        let mut summary = test_summary(44100, 16, 48000);
+        let status = summary.bit_perfect_status();
+        assert!(!status.is_bit_perfect());
+
+        // 未要求：照常播放
+        assert!(check_bit_perfect(false, status).is_ok());
+        // 要求：拒绝，错误信息列出原因
+        let err = check_bit_perfect(true, status).unwrap_err();
+        assert!(err.to_string().contains("SRC 44.1→48k"), "{}", err);
+        // 作为 anyhow 错误仍可识别（目录模式据此中止而不是跳到下一首）
+        assert!(anyhow::Error::from(err).is::<NotBitPerfect>());
+
+        summary.device_rate = 44100;
+        summary.resampled = false;
+        assert!(check_bit_perfect(true, summary.bit_perfect_status()).is_ok());
+        // 未开始播放也不满足
+        assert!(check_bit_perfect(true, BitPerfectStatus::Idle).is_err());
+    }
+
+    #[test]
+    fn test_key_action() {
+        // Space 在任何模式下都切换暂停
+        assert_eq!(key_action(KeyPress::Space, false), KeyAction::TogglePause);
+        assert_eq!(key_action(KeyPress::Space, true), KeyAction::TogglePause);
+
+        // 方向键仅在目录模式下跳转
+        assert_eq!(key_action(KeyPress::Right, true), KeyAction::Skip(SkipCommand::Next));
+        assert_eq!(key_action(KeyPress::Left, true), KeyAction::Skip(SkipCommand::Previous));
+        assert_eq!(key_action(KeyPress::Right, false), KeyAction::None);
+        assert_eq!(key_action(KeyPress::Left, false), KeyAction::None);
+
+        assert_eq!(key_action(KeyPress::Other(b'x'), true), KeyAction::None);
+    }
+
+    #[test]
+    fn test_cli_overrides_are_session_only() {
+        let user_config = UserConfig {
+            shuffle: true,
+            repeat: RepeatMode::Track,
+            start_paused: true,
+            ..UserConfig::default()
+        };
+        let session = |args: &[&str]| {
+            let mut cli = Cli::try_parse_from(std::iter::once("roger-player").chain(args.iter().copied()))
+                    .unwrap();
+            apply_user_config(&mut cli, &user_config);
+            tui_session(&cli, &user_config)
+        };
+
+        // 未指定时沿用配置文件
+        let defaults = session(&[]);
+        assert!(defaults.shuffle && defaults.start_paused && !defaults.require_bit_perfect);
+        assert!(session(&["--require-bit-perfect"]).require_bit_perfect);
+        assert_eq!(defaults.repeat, RepeatMode::Track);
+
+        // --no-* 关闭配置文件中打开的开关
+        let off = session(&["--no-shuffle", "--no-repeat", "--no-paused", "-R"]);
+        assert!(!off.shuffle && !off.start_paused && off.recursive);
+        assert_eq!(off.repeat, RepeatMode::Off);
+        assert!(Cli::try_parse_from(["roger-player", "-s", "--no-shuffle"]).is_err());
+
+        // 配置文件未开循环时 --repeat 为列表循环
+        let mut cli = Cli::try_parse_from(["roger-player", "-r"]).unwrap();
+        apply_user_config(&mut cli, &UserConfig::default());
+        assert_eq!(tui_session(&cli, &UserConfig::default()).repeat, RepeatMode::All);
+
+        // 配置文件显式写的缓冲时长即使等于默认值也生效，未写时自动缓冲
+        let buffered = UserConfig {
+            buffer_ms: Some(DEFAULT_BUFFER_MS),
+            ..UserConfig::default()
+        };
+        let mut cli = Cli::try_parse_from(["roger-player"]).unwrap();
+        apply_user_config(&mut cli, &buffered);
+        assert_eq!(cli.buffer_ms, Some(DEFAULT_BUFFER_MS));
+        assert!(!create_engine_config(&cli).auto_buffer);
+        let mut cli = Cli::try_parse_from(["roger-player"]).unwrap();
+        apply_user_config(&mut cli, &UserConfig::default());
+        assert!(create_engine_config(&cli).auto_buffer);
+    }
+
+    #[test]
+    fn test_hal_off_mixer_is_as_requested() {
+        use crate::audio::output::{NegotiationReport, OutputFormatMode};
+
+        // --hal-off 生成的配置：系统混音器的 Float32/共享输出不算妥协
+        let cli = Cli::try_parse_from(["roger-player", "--hal-off"]).unwrap();
+        let config = create_engine_config(&cli);
+        assert!(!config.output.use_hal);
+        let report = NegotiationReport::new(&config.output, 48000, OutputFormatMode::Float32, false, false);
+        assert!(report.all_as_requested(), "{}", report);
+
+        // 默认 HAL 配置下 Float32 仍是妥协
+        let cli = Cli::try_parse_from(["roger-player"]).unwrap();
+        let config = create_engine_config(&cli);
+        let report = NegotiationReport::new(&config.output, 48000, OutputFormatMode::Float32, false, true);
+        assert!(!report.all_as_requested());
+    }
+
+    #[test]
+    fn test_expand_paths() {
+        let root = std::env::temp_dir().join(format!("roger_expand_{}", std::process::id()));
+        let album = root.join("album");
+        std::fs::create_dir_all(&album).unwrap();
+        for name in ["10.flac", "2.flac", "cover.jpg"] {
+            std::fs::write(album.join(name), b"").unwrap();
+        }
+        let single = root.join("z.wav");
+        let other = root.join("a.mp3");
+
+        // 按参数顺序，目录原地展开为其中的音频文件（自然排序）
+        let args = [single.clone(), album.clone(), other.clone()];
+        let files = expand_paths(&args, false).unwrap();
+        std::fs::remove_dir_all(&root).ok();
+        assert_eq!(
+            files,
+            [single, album.join("2.flac"), album.join("10.flac"), other]
+        );
     }
 }
@@ -1,14 +1,20 @@
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 use super::{
+    keymap::Action,
     model::{App, DialogState},
     view,
 };
@@ -33,7 +39,9 @@ const DRAW_INTERVAL_IDLE_MS: u64 = 100;
 const INPUT_POLL_MS: u64 = 50;
 
 /// TUI 运行入口
-pub fn run(mut app: App) -> io::Result<()> {
+///
+/// `running` 被信号处理清除时（SIGTERM/SIGHUP）按正常退出流程收尾
+pub fn run(mut app: App, running: Arc<AtomicBool>) -> io::Result<()> {
     // =======================================================
     // 隔离措施 0: TUI 线程堆内存隔离
     // =======================================================
@@ -71,8 +79,12 @@ pub fn run(mut app: App) -> io::Result<()> {
     // - 输入轮询：每 50ms（保持键盘响应性，poll 是轻量 syscall）
     // - 绘制：播放中 500ms / 空闲 100ms（减少 stdout I/O 和内存分配）
     // - 统计读取：仅在绘制前（减少对音频线程 cache line 的访问）
+    // - 终端尺寸变化：立即用缓存的统计重绘一帧，不打乱上面的节奏
     let mut last_draw = Instant::now();
+    // 上次绘制时显示的整秒（秒数变化时只重绘、不读取统计）
+    let mut drawn_second = 0u64;
     let mut needs_redraw = true;
+    let mut resized = false;
 
     // 自动播放第一首
     if !app.playlist.is_empty() {
@@ -96,21 +108,31 @@ pub fn run(mut app: App) -> io::Result<()> {
         let poll_timeout = Duration::from_millis(INPUT_POLL_MS).min(time_to_draw);
 
         if crossterm::event::poll(poll_timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    handle_key_event(&mut app, key.code);
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    handle_key_event(&mut app, key.code, key.modifiers);
                     needs_redraw = true;
                 }
+                Event::Mouse(mouse) => {
+                    needs_redraw |= handle_mouse_event(&mut app, mouse);
+                }
+                Event::Resize(_, _) => resized = true,
+                _ => {}
             }
         }
 
         // === 轻量级曲目结束检测 ===
-        // is_track_finished() 仅读取一个 AtomicBool (eof_reached)
-        // 只有 eof_reached=true 时才会进一步检查 ring_buffer.available()
-        // 所以播放过程中几乎零开销
+        // 引擎在解码线程上发送完成事件，这里仅非阻塞检查 channel
         if app.check_track_end() {
             needs_redraw = true;
         }
+        if app.check_advance() {
+            needs_redraw = true;
+        }
+        // 波形包络在后台线程计算，完成后重绘
+        if app.waveforms.poll() {
+            needs_redraw = true;
+        }
 
         // === 目录变化检测（非阻塞检查 channel）===
         if app.check_dir_changes() {
@@ -131,9 +153,16 @@ pub fn run(mut app: App) -> io::Result<()> {
             terminal.draw(|f| view::draw(f, &mut app))?;
             last_draw = Instant::now();
             needs_redraw = false;
+            drawn_second = app.display_position_secs() as u64;
+        } else if resized || (is_active && app.display_position_secs() as u64 != drawn_second) {
+            // 布局随尺寸即时更新、推算的时钟跨过整秒时重绘；
+            // 不读取统计、不重置 last_draw，统计读取节奏不变
+            terminal.draw(|f| view::draw(f, &mut app))?;
+            drawn_second = app.display_position_secs() as u64;
         }
+        resized = false;
 
-        if app.should_quit {
+        if app.should_quit || !running.load(Ordering::SeqCst) {
             break;
         }
     }
@@ -147,14 +176,35 @@ pub fn run(mut app: App) -> io::Result<()> {
     )?;
     terminal.show_cursor()?;
 
-    // 停止播放引擎
+    // 记住停止位置后停止播放引擎
+    app.remember_position();
     let _ = app.engine.stop();
 
     Ok(())
 }
 
+/// 处理鼠标事件（仅左键单击），返回是否需要重绘
+///
+/// 弹窗、帮助、搜索、路径输入时忽略鼠标
+fn handle_mouse_event(app: &mut App, mouse: MouseEvent) -> bool {
+    if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+        return false;
+    }
+    if !matches!(app.dialog, DialogState::None)
+        || app.show_help
+        || app.search_mode
+        || app.input_mode
+        || app.goto_mode
+    {
+        return false;
+    }
+
+    app.handle_click(mouse.column, mouse.row);
+    true
+}
+
 /// 处理按键事件（从主循环中提取，减少主循环复杂度）
-fn handle_key_event(app: &mut App, code: KeyCode) {
+fn handle_key_event(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
     // 弹窗模式优先处理
     if !matches!(app.dialog, DialogState::None) {
         match code {
@@ -252,6 +302,20 @@ fn handle_key_event(app: &mut App, code: KeyCode) {
         return;
     }
 
+    // 跳转输入模式：数字累积到 goto_input，Enter 跳转
+    if app.goto_mode {
+        match code {
+            KeyCode::Enter => app.goto_confirm(),
+            KeyCode::Esc => app.exit_goto(),
+            KeyCode::Backspace => {
+                app.goto_input.pop();
+            }
+            KeyCode::Char(c) => app.goto_push(c),
+            _ => {}
+        }
+        return;
+    }
+
     // 正常模式下的按键处理
 
     // 数字键：累积 pending_count（vim 风格 {n}G 跳转）
@@ -264,24 +328,40 @@ fn handle_key_event(app: &mut App, code: KeyCode) {
     // 非数字键：消费或清除 pending_count
     let count = app.pending_count.take();
 
-    match code {
-        KeyCode::Char('q') | KeyCode::Esc => {
+    // 按键映射查表（默认与内置按键一致，可由配置文件 [keys] 覆盖）
+    let Some(action) = app.keymap.action(code) else {
+        return;
+    };
+
+    match action {
+        Action::Quit => {
             app.should_quit = true;
         }
-        KeyCode::Char(' ') => {
+        Action::TogglePause => {
             if let Err(e) = app.engine.toggle_pause() {
                 app.log(format!("Error: {}", e));
             }
         }
-        KeyCode::Char('n') => app.next_track(),
-        KeyCode::Char('p') => app.prev_track(),
-        KeyCode::Char('o') => {
+        Action::NextTrack => app.next_track(),
+        Action::PrevTrack => app.prev_track(),
+        Action::Open => {
             app.input_mode = true;
             app.path_input.clear();
         }
-        KeyCode::Char('s') => app.toggle_shuffle(),
-        KeyCode::Char('r') => app.cycle_repeat(),
-        KeyCode::Down | KeyCode::Char('j') => {
+        Action::Shuffle => app.toggle_shuffle(),
+        Action::Repeat => app.cycle_repeat(),
+        Action::InvertPolarity => app.toggle_invert_polarity(),
+        Action::SwapChannels => app.toggle_swap_channels(),
+        Action::MonoMonitor => app.toggle_mono_monitor(),
+        Action::OutputMode => app.toggle_output_mode(),
+        Action::LockRate => app.toggle_lock_device_rate(),
+        Action::Theme => app.cycle_theme(),
+        Action::NextChapter => app.next_chapter(),
+        Action::PrevChapter => app.prev_chapter(),
+        Action::PrecisionSeek => app.toggle_precision_seek(),
+        Action::SeekBack => app.seek_step(false, modifiers.contains(KeyModifiers::SHIFT)),
+        Action::SeekForward => app.seek_step(true, modifiers.contains(KeyModifiers::SHIFT)),
+        Action::SelectDown => {
             if !app.playlist.is_empty() {
                 app.last_selection_time = Some(Instant::now());
                 app.show_cursor = true;
@@ -292,7 +372,7 @@ fn handle_key_event(app: &mut App, code: KeyCode) {
                 app.playlist_state.select(Some(new_index));
             }
         }
-        KeyCode::Up | KeyCode::Char('k') => {
+        Action::SelectUp => {
             if !app.playlist.is_empty() {
                 app.last_selection_time = Some(Instant::now());
                 app.show_cursor = true;
@@ -303,14 +383,14 @@ fn handle_key_event(app: &mut App, code: KeyCode) {
                 app.playlist_state.select(Some(new_index));
             }
         }
-        KeyCode::Char('g') => {
+        Action::MoveDown => app.move_selected(true),
+        Action::MoveUp => app.move_selected(false),
+        Action::Goto => {
             if !app.playlist.is_empty() {
-                app.last_selection_time = Some(Instant::now());
-                app.show_cursor = true;
-                app.playlist_state.select(Some(0));
+                app.enter_goto();
             }
         }
-        KeyCode::Char('G') => {
+        Action::JumpLast => {
             if !app.playlist.is_empty() {
                 app.last_selection_time = Some(Instant::now());
                 app.show_cursor = true;
@@ -324,20 +404,19 @@ fn handle_key_event(app: &mut App, code: KeyCode) {
                 app.playlist_state.select(Some(target));
             }
         }
-        KeyCode::Enter => {
+        Action::PlaySelected => {
             if let Some(i) = app.playlist_state.selected() {
                 app.current_index = i;
                 app.play_current();
             }
         }
-        KeyCode::Char('/') => {
+        Action::Search => {
             app.search_mode = true;
             app.search_input.clear();
         }
-        KeyCode::Char('h') => {
+        Action::Help => {
             app.show_help = true;
         }
-        _ => {}
     }
 }
 
@@ -377,6 +456,15 @@ fn set_tui_thread_low_priority() {
     }
 }
 
+/// TUI 派生的后台线程（如波形计算）采用与 TUI 线程相同的隔离措施
+///
+/// 使用 TUI 堆、最低优先级、TUI 亲和性标签，不与音频线程争抢
+pub(super) fn isolate_background_thread() {
+    crate::alloc::platform::mark_tui_thread();
+    set_tui_thread_low_priority();
+    set_thread_affinity_tag(2);
+}
+
 /// 设置 macOS 线程亲和性标签
 ///
 /// macOS 调度器使用 affinity tag 进行核心分组调度：
@@ -422,3 +510,43 @@ fn set_thread_affinity_tag(tag: i32) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::config::UserConfig;
+    use crate::engine::EngineConfig;
+    use crate::tui::model::SessionSettings;
+
+    #[test]
+    fn test_key_event_uses_keymap() {
+        let table: BTreeMap<String, String> = toml::from_str(
+            r#"
+            "?" = "help"
+            h = "search"
+            "#,
+        )
+        .unwrap();
+        let user_config = UserConfig {
+            keys: table,
+            ..UserConfig::default()
+        };
+        let session = SessionSettings::from_config(&user_config);
+        let mut app = App::new(EngineConfig::default(), Vec::new(), user_config, session).unwrap();
+        app.input_mode = false;
+
+        handle_key_event(&mut app, KeyCode::Char('?'), KeyModifiers::NONE);
+        assert!(app.show_help);
+        handle_key_event(&mut app, KeyCode::Char('?'), KeyModifiers::NONE);
+        assert!(!app.show_help);
+
+        // h 改为搜索，未映射的按键忽略
+        handle_key_event(&mut app, KeyCode::Char('z'), KeyModifiers::NONE);
+        assert!(!app.search_mode && !app.show_help);
+        handle_key_event(&mut app, KeyCode::Char('h'), KeyModifiers::NONE);
+        assert!(app.search_mode);
+        assert!(!app.show_help);
+    }
+}
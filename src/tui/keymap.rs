@@ -0,0 +1,242 @@
+//! TUI 按键映射
+//!
+//! 正常模式下的按键由 `KeyMap` 查表得到动作，再由 controller 分发。
+//! 默认映射与内置按键一致；配置文件 `[keys]` 表按 键 → 动作 覆盖或追加，
+//! 如 `h = "seek-back"` 把 h 改为后退（原先的帮助键随之失效）。
+//! 动作名写错、按键冲突或退出键被全部改掉时整体回退到默认映射。
+//! 数字键保留给 vim 风格的数字前缀，不能重新映射；
+//! 弹窗、搜索、路径输入与跳转输入模式的按键不受影响。
+
+use std::collections::{BTreeMap, HashMap};
+
+use crossterm::event::KeyCode;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+
+/// 可映射的动作（配置文件中使用 kebab-case 名称）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    TogglePause,
+    NextTrack,
+    PrevTrack,
+    Open,
+    Shuffle,
+    Repeat,
+    InvertPolarity,
+    SwapChannels,
+    MonoMonitor,
+    OutputMode,
+    LockRate,
+    Theme,
+    NextChapter,
+    PrevChapter,
+    PrecisionSeek,
+    SeekBack,
+    SeekForward,
+    SelectDown,
+    SelectUp,
+    MoveDown,
+    MoveUp,
+    Goto,
+    JumpLast,
+    PlaySelected,
+    Search,
+    Help,
+}
+
+impl Action {
+    /// 按配置文件中的 kebab-case 名称解析
+    pub fn from_name(name: &str) -> Option<Self> {
+        let deserializer: serde::de::value::StrDeserializer<serde::de::value::Error> =
+            name.into_deserializer();
+        Self::deserialize(deserializer).ok()
+    }
+}
+
+/// 内置按键（与映射表引入前的硬编码按键一致）
+const DEFAULT_BINDINGS: &[(KeyCode, Action)] = &[
+    (KeyCode::Char('q'), Action::Quit),
+    (KeyCode::Esc, Action::Quit),
+    (KeyCode::Char(' '), Action::TogglePause),
+    (KeyCode::Char('n'), Action::NextTrack),
+    (KeyCode::Char('p'), Action::PrevTrack),
+    (KeyCode::Char('o'), Action::Open),
+    (KeyCode::Char('s'), Action::Shuffle),
+    (KeyCode::Char('r'), Action::Repeat),
+    (KeyCode::Char('i'), Action::InvertPolarity),
+    (KeyCode::Char('x'), Action::SwapChannels),
+    (KeyCode::Char('m'), Action::MonoMonitor),
+    (KeyCode::Char('e'), Action::OutputMode),
+    (KeyCode::Char('l'), Action::LockRate),
+    (KeyCode::Char('t'), Action::Theme),
+    (KeyCode::Char('>'), Action::NextChapter),
+    (KeyCode::Char('<'), Action::PrevChapter),
+    (KeyCode::Char('f'), Action::PrecisionSeek),
+    (KeyCode::Left, Action::SeekBack),
+    (KeyCode::Right, Action::SeekForward),
+    (KeyCode::Down, Action::SelectDown),
+    (KeyCode::Char('j'), Action::SelectDown),
+    (KeyCode::Up, Action::SelectUp),
+    (KeyCode::Char('k'), Action::SelectUp),
+    (KeyCode::Char('J'), Action::MoveDown),
+    (KeyCode::Char('K'), Action::MoveUp),
+    (KeyCode::Char('g'), Action::Goto),
+    (KeyCode::Char('G'), Action::JumpLast),
+    (KeyCode::Enter, Action::PlaySelected),
+    (KeyCode::Char('/'), Action::Search),
+    (KeyCode::Char('h'), Action::Help),
+];
+
+/// 命名按键（不区分大小写）
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+/// 解析按键名：单个字符（区分大小写）或命名按键
+///
+/// 数字键保留给数字前缀，返回错误
+pub fn parse_key(name: &str) -> Result<KeyCode, String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_digit() {
+            return Err(format!("key '{}' is reserved for count prefixes", name));
+        }
+        return Ok(KeyCode::Char(c));
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|&(_, code)| code)
+        .ok_or_else(|| format!("unknown key '{}'", name))
+}
+
+/// 按键的显示名（帮助页）
+pub fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "SPACE".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// 按键 → 动作
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    bindings: HashMap<KeyCode, Action>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: DEFAULT_BINDINGS.iter().copied().collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    /// 在默认映射上应用配置文件的 `[keys]` 表
+    ///
+    /// 按键名或动作名无效、数字键、两个写法指向同一按键（如 `space` 与 `" "`）、
+    /// 或退出动作不再有任何按键时返回错误
+    pub fn with_overrides(overrides: &BTreeMap<String, String>) -> Result<Self, String> {
+        let mut keymap = Self::default();
+        let mut seen: HashMap<KeyCode, &str> = HashMap::new();
+        for (name, action_name) in overrides {
+            let code = parse_key(name)?;
+            let action = Action::from_name(action_name)
+                .ok_or_else(|| format!("unknown action '{}' for key '{}'", action_name, name))?;
+            if let Some(previous) = seen.insert(code, name) {
+                return Err(format!("keys '{}' and '{}' are the same key", previous, name));
+            }
+            keymap.bindings.insert(code, action);
+        }
+        if !keymap.bindings.values().any(|&a| a == Action::Quit) {
+            return Err("no key is bound to quit".to_string());
+        }
+        Ok(keymap)
+    }
+
+    /// 按键对应的动作
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings.get(&code).copied()
+    }
+
+    /// 动作绑定的按键（帮助页显示，命名按键在前），未绑定时为 "-"
+    pub fn keys_for(&self, action: Action) -> String {
+        let mut keys: Vec<KeyCode> = self
+            .bindings
+            .iter()
+            .filter(|&(_, &a)| a == action)
+            .map(|(&code, _)| code)
+            .collect();
+        keys.sort_by_key(|&code| (matches!(code, KeyCode::Char(c) if c != ' '), key_label(code)));
+        if keys.is_empty() {
+            return "-".to_string();
+        }
+        keys.into_iter().map(key_label).collect::<Vec<_>>().join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keymap_overrides() {
+        let keymap = KeyMap::default();
+        assert_eq!(keymap.action(KeyCode::Char('h')), Some(Action::Help));
+        assert_eq!(keymap.action(KeyCode::Esc), Some(Action::Quit));
+        assert_eq!(keymap.action(KeyCode::Char('z')), None);
+        assert_eq!(keymap.keys_for(Action::Quit), "Esc,q");
+        assert_eq!(keymap.keys_for(Action::SelectDown), "↓,j");
+
+        // vim 风格：h/l 改为 seek，帮助移到 ?
+        let table: BTreeMap<String, String> = toml::from_str(
+            r#"
+            h = "seek-back"
+            l = "seek-forward"
+            "?" = "help"
+            Space = "toggle-pause"
+            "#,
+        )
+        .unwrap();
+        let keymap = KeyMap::with_overrides(&table).unwrap();
+        assert_eq!(keymap.action(KeyCode::Char('h')), Some(Action::SeekBack));
+        assert_eq!(keymap.action(KeyCode::Char('l')), Some(Action::SeekForward));
+        assert_eq!(keymap.action(KeyCode::Char('?')), Some(Action::Help));
+        assert_eq!(keymap.keys_for(Action::Help), "?");
+        assert_eq!(keymap.keys_for(Action::LockRate), "-");
+        // 未覆盖的默认按键保留
+        assert_eq!(keymap.action(KeyCode::Left), Some(Action::SeekBack));
+
+        // 无效按键、数字键、同一按键的两种写法、未知动作、退出键全被改掉
+        let bad = |text: &str| {
+            let table: BTreeMap<String, String> = toml::from_str(text).unwrap();
+            KeyMap::with_overrides(&table).unwrap_err()
+        };
+        assert!(bad(r#"F13 = "quit""#).contains("unknown key"));
+        assert!(bad(r#"5 = "quit""#).contains("reserved"));
+        assert!(bad("space = \"quit\"\n\" \" = \"help\"").contains("same key"));
+        assert!(bad(r#"h = "seekback""#).contains("unknown action 'seekback'"));
+        assert!(bad("q = \"help\"\nEsc = \"search\"").contains("quit"));
+        assert_eq!(Action::from_name("seek-back"), Some(Action::SeekBack));
+    }
+}
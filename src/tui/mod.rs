@@ -1,3 +1,6 @@
 pub mod model;
 pub mod view;
 pub mod controller;
+pub mod keymap;
+pub mod theme;
+pub mod waveform;
@@ -1,24 +1,49 @@
-use std::ffi::OsStr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver};
 use std::time::{Duration, Instant};
 
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
 use rand::seq::SliceRandom;
+use ratatui::layout::Rect;
 
+use crate::audio::device_settings::{DeviceSettings, DeviceSettingsCache};
 use crate::audio::AudioOutput;
-use crate::engine::{Engine, EngineConfig, EngineStats};
-
-/// 支持的音频文件扩展名
-const AUDIO_EXTENSIONS: &[&str] = &["flac", "wav", "aiff", "aif", "mp3", "pcm"];
+use crate::config::{LoudnessCache, RepeatMode, ResumePositions, UserConfig};
+use crate::decode::scan::{is_audio_file, scan_audio_files};
+use crate::engine::{
+    Engine, EngineConfig, EngineError, EngineStats, PlaybackState, PrefetchStatus, StopReason,
+};
+
+use super::keymap::KeyMap;
+use super::theme::ThemeName;
+use super::waveform::Waveforms;
+
+/// 本次会话的播放设置（CLI 参数叠加配置文件，不写回配置文件）
+#[derive(Clone, Debug, Default)]
+pub struct SessionSettings {
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+    pub recursive: bool,
+    pub start_paused: bool,
+    pub theme: ThemeName,
+    pub normalize_lufs: Option<f64>,
+    /// `--require-bit-perfect`：输出路径不满足时拒绝播放（只能由 CLI 指定）
+    pub require_bit_perfect: bool,
+}
 
-/// 循环播放模式
-#[derive(Clone, Copy, PartialEq, Default)]
-pub enum RepeatMode {
-    #[default]
-    Off,   // 播放完列表后停止
-    All,   // 列表循环
-    Track, // 单曲循环
+impl SessionSettings {
+    /// 直接取配置文件的设置
+    pub fn from_config(user_config: &UserConfig) -> Self {
+        Self {
+            shuffle: user_config.shuffle,
+            repeat: user_config.repeat,
+            recursive: user_config.recursive,
+            start_paused: user_config.start_paused,
+            theme: user_config.theme,
+            normalize_lufs: user_config.normalize_lufs,
+            require_bit_perfect: false,
+        }
+    }
 }
 
 /// 输出模式选择
@@ -49,6 +74,21 @@ pub struct App {
     /// 引擎配置（保存以便重新创建）
     config: EngineConfig,
 
+    /// 用户配置（只在 TUI 中切换 shuffle/repeat/主题时写回对应项）
+    user_config: UserConfig,
+
+    /// 目录是否包含子目录
+    recursive: bool,
+
+    /// 响度归一化目标（LUFS）
+    normalize_lufs: Option<f64>,
+
+    /// 输出路径不满足 bit-perfect 时拒绝播放
+    require_bit_perfect: bool,
+
+    /// 弹窗选择的输出模式待写回设备缓存（输出确认可用后写入）
+    device_settings_pending: bool,
+
     /// 播放列表文件
     pub playlist: Vec<PathBuf>,
 
@@ -67,6 +107,12 @@ pub struct App {
     /// 缓存的统计信息（避免过度刷新）
     pub cached_stats: EngineStats,
 
+    /// 读取 `cached_stats` 的时刻（两次读取之间按墙钟推算播放位置）
+    pub stats_read_at: Instant,
+
+    /// 预打开的路径及其状态（随统计信息一起刷新）
+    next_status: Option<(PathBuf, PrefetchStatus)>,
+
     /// 是否处于路径输入模式
     pub input_mode: bool,
 
@@ -79,12 +125,27 @@ pub struct App {
     /// 循环播放模式
     pub repeat_mode: RepeatMode,
 
+    /// 配色主题
+    pub theme: ThemeName,
+
+    /// 正常模式按键映射
+    pub keymap: KeyMap,
+
     /// 随机播放顺序（shuffle 模式下使用）
     shuffle_order: Vec<usize>,
 
     /// 上次切歌时间（防抖用，防止快速切歌导致 AudioUnit 错误）
     last_switch_time: Option<Instant>,
 
+    /// 曲目间静音后的自动切歌
+    advance: AdvanceScheduler,
+
+    /// 下一次播放只加载不开始（`--paused`：首曲加载后等待按空格）
+    hold_next_play: bool,
+
+    /// 精细 seek 模式：方向键 1s，Shift+方向键 0.1s（否则 5s）
+    pub precision_seek: bool,
+
     /// 弹窗状态
     pub dialog: DialogState,
 
@@ -112,23 +173,115 @@ pub struct App {
     /// Vim 风格数字前缀（用于 {n}G 跳转）
     pub pending_count: Option<usize>,
 
+    /// 是否处于跳转输入模式（`g` 触发）
+    pub goto_mode: bool,
+
+    /// 跳转输入的曲目编号（1-based）
+    pub goto_input: String,
+
+    /// 进度条在屏幕上的区域（由 view 绘制时记录，用于鼠标点击 seek）
+    pub progress_bar_area: Option<Rect>,
+
+    /// 播放列表在屏幕上的区域（含边框，用于鼠标点击选曲）
+    pub playlist_area: Option<Rect>,
+
+    /// 波形条在屏幕上的区域（用于鼠标点击 seek）
+    pub waveform_area: Option<Rect>,
+
+    /// 整曲波形包络（后台计算，按路径缓存）
+    pub waveforms: Waveforms,
+
     /// 当前监听的目录路径（用于目录变化时刷新播放列表）
     watched_dir: Option<PathBuf>,
 
     /// 目录变化事件接收器
     dir_watcher_rx: Option<Receiver<()>>,
 
+    /// 曲目完成事件接收器（由引擎在解码线程上发送）
+    track_end_rx: Receiver<()>,
+
+    /// 续播位置（切歌/退出时记录，播放同一文件时恢复）
+    resume: ResumePositions,
+
+    /// 引擎当前加载的文件（用于记录续播位置）
+    playing_path: Option<PathBuf>,
+
+    /// 响度扫描结果（用于归一化）
+    loudness: LoudnessCache,
+
     /// 文件系统监听器（需要保持存活）
     #[allow(dead_code)]
     dir_watcher: Option<RecommendedWatcher>,
 }
 
+/// 方向键 seek 步长（秒）
+const SEEK_STEP_SECS: f64 = 5.0;
+
+/// 精细模式下方向键的 seek 步长（秒）
+const FINE_SEEK_STEP_SECS: f64 = 1.0;
+
+/// 精细模式下 Shift+方向键的 seek 步长（秒）
+const SUB_SECOND_SEEK_STEP_SECS: f64 = 0.1;
+
 /// 切歌防抖间隔（毫秒）
 const TRACK_SWITCH_DEBOUNCE_MS: u64 = 200;
 
+/// 自动切歌调度：曲目结束后等待设置的静音间隔再切到下一首
+#[derive(Debug, Default)]
+struct AdvanceScheduler {
+    /// 到期时间（None 表示没有待切的曲目）
+    due: Option<Instant>,
+}
+
+impl AdvanceScheduler {
+    /// 曲目结束：无间隔时返回 true（立即切歌），否则在 `gap` 后到期
+    fn track_ended(&mut self, now: Instant, gap: Option<Duration>) -> bool {
+        match gap {
+            Some(gap) => {
+                self.due = Some(now + gap);
+                false
+            }
+            None => {
+                self.due = None;
+                true
+            }
+        }
+    }
+
+    /// 到期时返回 true（每次调度只触发一次）
+    fn poll(&mut self, now: Instant) -> bool {
+        if self.due.is_some_and(|due| now >= due) {
+            self.due = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 距到期的剩余时间
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.due.map(|due| due.saturating_duration_since(now))
+    }
+
+    /// 取消（手动切歌时）
+    fn cancel(&mut self) {
+        self.due = None;
+    }
+}
+
 impl App {
-    pub fn new(config: EngineConfig, playlist: Vec<PathBuf>) -> Self {
-        let engine = Engine::new(config.clone());
+    pub fn new(
+        config: EngineConfig,
+        playlist: Vec<PathBuf>,
+        user_config: UserConfig,
+        session: SessionSettings,
+    ) -> Result<Self, EngineError> {
+        let mut engine = Engine::new(config.clone())?;
+        let (track_end_tx, track_end_rx) = mpsc::channel();
+        engine.on_track_finished(move || {
+            // 忽略发送失败（App 已退出）
+            let _ = track_end_tx.send(());
+        });
         let mut playlist_state = ratatui::widgets::ListState::default();
         let input_mode = playlist.is_empty();
         if !playlist.is_empty() {
@@ -136,10 +289,25 @@ impl App {
         }
 
         let shuffle_order = (0..playlist.len()).collect();
+        let hold_next_play = session.start_paused;
+        // 按键配置冲突或无效时整体回退到默认映射，启动后提示
+        let (keymap, keymap_error) = match KeyMap::with_overrides(&user_config.keys) {
+            Ok(keymap) => (keymap, None),
+            Err(e) => (KeyMap::default(), Some(e)),
+        };
 
-        Self {
+        let mut app = Self {
             engine,
             config,
+            shuffle: session.shuffle,
+            repeat_mode: session.repeat,
+            theme: session.theme,
+            keymap,
+            user_config,
+            recursive: session.recursive,
+            normalize_lufs: session.normalize_lufs,
+            require_bit_perfect: session.require_bit_perfect,
+            device_settings_pending: false,
             playlist,
             current_index: 0,
             playlist_state,
@@ -147,16 +315,23 @@ impl App {
             should_quit: false,
             cached_stats: EngineStats {
                 buffer_fill_ratio: 0.0,
+                target_fill_ratio: 0.0,
                 underrun_count: 0,
+                underrun_lost_ms: 0.0,
+                write_overflow_count: 0,
                 samples_played: 0,
                 position_secs: 0.0,
+                buffered_ms: 0.0,
             },
+            stats_read_at: Instant::now(),
+            next_status: None,
             input_mode,
             path_input: String::new(),
-            shuffle: false,
-            repeat_mode: RepeatMode::default(),
             shuffle_order,
             last_switch_time: None,
+            advance: AdvanceScheduler::default(),
+            hold_next_play,
+            precision_seek: false,
             dialog: DialogState::None,
             last_selection_time: None,
             show_cursor: false,
@@ -166,15 +341,37 @@ impl App {
             search_result_index: 0,
             show_help: false,
             pending_count: None,
+            goto_mode: false,
+            goto_input: String::new(),
+            progress_bar_area: None,
+            playlist_area: None,
+            waveform_area: None,
+            waveforms: Waveforms::default(),
             watched_dir: None,
             dir_watcher_rx: None,
+            track_end_rx,
+            resume: ResumePositions::load(),
+            playing_path: None,
+            loudness: LoudnessCache::load(),
             dir_watcher: None,
+        };
+
+        if app.shuffle {
+            app.generate_shuffle_order();
         }
+        if let Some(e) = keymap_error {
+            app.log(format!("Invalid [keys] config ({}), using default key bindings", e));
+        }
+        Ok(app)
     }
 
     /// 创建空播放列表的 App（用于无参数启动）
-    pub fn new_empty(config: EngineConfig) -> Self {
-        Self::new(config, Vec::new())
+    pub fn new_empty(
+        config: EngineConfig,
+        user_config: UserConfig,
+        session: SessionSettings,
+    ) -> Result<Self, EngineError> {
+        Self::new(config, Vec::new(), user_config, session)
     }
 
     /// 从路径加载播放列表
@@ -193,7 +390,7 @@ impl App {
         }
 
         // 检查是否是支持的音频文件或目录
-        if !path.is_dir() && !Self::is_audio_file(&path) {
+        if !path.is_dir() && !is_audio_file(&path) {
             self.log(format!("Not a supported audio file: {}", path_str));
             return;
         }
@@ -223,14 +420,14 @@ impl App {
         let path = PathBuf::from(path_str);
 
         let (files, dir_to_watch) = if path.is_dir() {
-            match Self::scan_audio_files(&path) {
+            match scan_audio_files(&path, self.recursive) {
                 Ok(f) => (f, Some(path.clone())),
                 Err(e) => {
                     self.log(format!("Error scanning directory: {}", e));
                     return;
                 }
             }
-        } else if Self::is_audio_file(&path) {
+        } else if is_audio_file(&path) {
             // 单文件：监听其父目录
             let parent = path.parent().map(|p| p.to_path_buf());
             (vec![path], parent)
@@ -294,32 +491,6 @@ impl App {
         result
     }
 
-    /// 检查文件是否为支持的音频格式
-    fn is_audio_file(path: &PathBuf) -> bool {
-        path.extension()
-            .and_then(OsStr::to_str)
-            .map(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
-            .unwrap_or(false)
-    }
-
-    /// 扫描目录中的音频文件（按文件名排序）
-    fn scan_audio_files(dir: &PathBuf) -> std::io::Result<Vec<PathBuf>> {
-        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.path())
-            .filter(|path| path.is_file() && Self::is_audio_file(path))
-            .collect();
-
-        // 按文件名排序
-        files.sort_by(|a, b| {
-            a.file_name()
-                .unwrap_or_default()
-                .cmp(b.file_name().unwrap_or_default())
-        });
-
-        Ok(files)
-    }
-
     /// 添加日志
     pub fn log(&mut self, message: String) {
         // 保留最近 50 条日志
@@ -330,6 +501,11 @@ impl App {
         self.logs.push(format!("[{}] {}", timestamp, message));
     }
 
+    /// 当前播放曲目的波形包络（尚未计算完成时为 None）
+    pub fn current_waveform(&self) -> Option<&[f32]> {
+        self.waveforms.get(self.playing_path.as_deref()?)
+    }
+
     /// 获取当前播放的文件名
     pub fn current_track_name(&self) -> String {
         if self.current_index < self.playlist.len() {
@@ -373,7 +549,20 @@ impl App {
             return;
         }
 
-        let next_index = if self.shuffle {
+        if let Some(idx) = self.next_index() {
+            self.current_index = idx;
+            self.playlist_state.select(Some(self.current_index));
+            self.play_current();
+        } else {
+            // 播放结束，停止
+            let _ = self.engine.stop();
+            self.log("Playlist finished".to_string());
+        }
+    }
+
+    /// 按 shuffle/repeat 计算下一首的索引（None 表示播放列表结束）
+    fn next_index(&self) -> Option<usize> {
+        if self.shuffle {
             // Shuffle 模式：找到当前在 shuffle_order 中的位置，然后取下一个
             if let Some(pos) = self.current_shuffle_position() {
                 let next_pos = pos + 1;
@@ -398,16 +587,61 @@ impl App {
             } else {
                 None // 播放完毕
             }
-        };
+        }
+    }
 
-        if let Some(idx) = next_index {
-            self.current_index = idx;
-            self.playlist_state.select(Some(self.current_index));
-            self.play_current();
+    /// 后台预打开下一首，切歌时无需等待 probe，不支持的文件提前提示
+    fn prefetch_next(&mut self) {
+        self.next_status = None;
+        if let Some(idx) = self.next_index() {
+            let path = self.playlist[idx].clone();
+            self.engine.prefetch(&path);
+        }
+    }
+
+    /// 当前曲目播完后接着播放的曲目（单曲循环为当前曲目，列表结束时为 None）
+    ///
+    /// 与自动切歌（`go_to_next(true)`）使用相同的规则
+    pub fn next_up(&self) -> Option<usize> {
+        if self.playlist.is_empty() {
+            None
+        } else if self.repeat_mode == RepeatMode::Track {
+            Some(self.current_index)
         } else {
-            // 播放结束，停止
-            let _ = self.engine.stop();
-            self.log("Playlist finished".to_string());
+            self.next_index()
+        }
+    }
+
+    /// `next_up()` 曲目的预打开状态（预打开的不是该曲目时为 None）
+    pub fn next_up_status(&self) -> Option<&PrefetchStatus> {
+        let next = self.next_up()?;
+        self.next_status
+            .as_ref()
+            .filter(|(path, _)| *path == self.playlist[next])
+            .map(|(_, status)| status)
+    }
+
+    /// 把选中的条目上移/下移一位（首尾不循环）
+    ///
+    /// 当前曲目、随机顺序与搜索结果按条目跟随移动，正在播放的曲目不受影响
+    pub fn move_selected(&mut self, down: bool) {
+        let Some(from) = self.playlist_state.selected() else {
+            return;
+        };
+        let Some(to) = move_target(from, self.playlist.len(), down) else {
+            return;
+        };
+        self.playlist.swap(from, to);
+        self.current_index = swap_index(self.current_index, from, to);
+        for index in self.shuffle_order.iter_mut().chain(self.search_results.iter_mut()) {
+            *index = swap_index(*index, from, to);
+        }
+        self.playlist_state.select(Some(to));
+        self.last_selection_time = Some(Instant::now());
+        self.show_cursor = true;
+        // 下一首可能改变，重新预打开
+        if self.engine.is_playing() {
+            self.prefetch_next();
         }
     }
 
@@ -453,19 +687,102 @@ impl App {
 
     /// 播放当前选中的曲目
     pub fn play_current(&mut self) {
+        self.advance.cancel();
         if self.current_index < self.playlist.len() {
             // 更新切歌时间戳（用于防抖）
             self.last_switch_time = Some(Instant::now());
 
-            let path = &self.playlist[self.current_index];
-            if let Err(e) = self.engine.play(path) {
+            // 记住上一首的停止位置
+            self.remember_position();
+
+            let path = self.playlist[self.current_index].clone();
+            let gain_db = self.apply_normalization(&path);
+            let hold = std::mem::take(&mut self.hold_next_play);
+            let result = if hold {
+                self.engine.play_paused(&path)
+            } else {
+                self.engine.play(&path)
+            };
+            // 丢弃上一首遗留的完成事件，避免误切歌
+            while self.track_end_rx.try_recv().is_ok() {}
+            if result.is_ok() {
+                self.confirm_device_settings();
+            }
+            let status = self.engine.bit_perfect_reason();
+            if let Err(e) = result {
                 self.log(format!("Error playing: {}", e));
+            } else if self.require_bit_perfect && !status.is_bit_perfect() {
+                // 停在这里而不是跳到下一首：后续曲目的输出路径相同
+                if let Err(e) = self.engine.stop() {
+                    self.log(format!("Stop failed: {}", e));
+                }
+                self.log(format!("Refused (not bit-perfect: {}): {}", status, path.display()));
+            } else if hold {
+                self.log(format!("Loaded (paused, Space to play): {}", path.display()));
             } else {
                 self.log(format!("Playing: {}", path.display()));
+                if gain_db != 0.0 {
+                    self.log(format!("Normalize: {:+.1} dB", gain_db));
+                }
+                self.restore_position(&path);
+                self.waveforms.request(
+                    &path,
+                    self.config.pcm_format,
+                    self.config.force_format.as_deref(),
+                );
+                self.playing_path = Some(path);
+                self.prefetch_next();
             }
         }
     }
 
+    /// 按响度缓存设置归一化增益，返回增益（dB）
+    ///
+    /// 未启用归一化或文件未扫描时恢复直通（0 dB）
+    fn apply_normalization(&mut self, path: &Path) -> f64 {
+        let gain_db = self
+            .normalize_lufs
+            .and_then(|target| self.loudness.lookup(path).map(|e| e.gain_db(target)))
+            .unwrap_or(0.0);
+        self.engine.set_gain_db(gain_db);
+        gain_db
+    }
+
+    /// 记录当前曲目的停止位置（切歌、退出时调用）
+    pub fn remember_position(&mut self) {
+        let Some(path) = self.playing_path.take() else {
+            return;
+        };
+        if self.engine.state() == PlaybackState::Stopped {
+            return;
+        }
+        let position = self.engine.stats().position_secs;
+        self.resume.record_stop(&path, position);
+        self.save_resume_positions();
+    }
+
+    /// 从上次停止的位置继续播放
+    fn restore_position(&mut self, path: &Path) {
+        let Some(position) = self.resume.get(path) else {
+            return;
+        };
+        match self.engine.seek(position) {
+            Ok(()) => self.log(format!(
+                "Resumed at {:02}:{:02}",
+                (position / 60.0) as u32,
+                (position % 60.0) as u32
+            )),
+            Err(e) => self.log(format!("Resume failed: {}", e)),
+        }
+    }
+
+    /// 保存续播位置
+    fn save_resume_positions(&mut self) {
+        if let Err(e) = self.resume.save() {
+            self.log(format!("Failed to save resume positions: {}", e));
+        }
+    }
+
     /// 切换随机播放模式
     pub fn toggle_shuffle(&mut self) {
         self.shuffle = !self.shuffle;
@@ -475,6 +792,60 @@ impl App {
         } else {
             self.log("Shuffle: OFF".to_string());
         }
+        self.user_config.shuffle = self.shuffle;
+        self.save_user_config();
+    }
+
+    /// 切换极性反转
+    pub fn toggle_invert_polarity(&mut self) {
+        let enabled = !self.engine.invert_polarity();
+        self.engine.set_invert_polarity(enabled);
+        self.log(format!("Invert polarity: {}", if enabled { "ON" } else { "OFF" }));
+    }
+
+    /// 切换左右声道交换
+    pub fn toggle_swap_channels(&mut self) {
+        let enabled = !self.engine.swap_channels();
+        self.engine.set_swap_channels(enabled);
+        self.log(format!("Swap L/R: {}", if enabled { "ON" } else { "OFF" }));
+    }
+
+    /// 播放中切换输出模式（HAL 独占 ↔ 系统混音器），从当前位置继续，便于 A/B 对比
+    ///
+    /// 以当前实际输出模式为准取反（HAL 可能已回退到系统混音器），无输出时按配置
+    pub fn toggle_output_mode(&mut self) {
+        let current_hal = self
+            .engine
+            .output_mode()
+            .map_or(self.config.output.use_hal, |(hal, _)| hal);
+        let use_hal = !current_hal;
+        match self.engine.switch_output(use_hal, use_hal) {
+            Ok(()) => {
+                self.config.output.use_hal = use_hal;
+                self.config.output.exclusive_mode = use_hal;
+                let mode_str = if use_hal { "HAL (Exclusive)" } else { "System Mixer" };
+                self.log(format!("Output mode: {}", mode_str));
+            }
+            Err(e) => self.log(format!("Output switch failed: {}", e)),
+        }
+    }
+
+    /// 切换单声道监听
+    pub fn toggle_mono_monitor(&mut self) {
+        let enabled = !self.engine.mono_monitor();
+        self.engine.set_mono_monitor(enabled);
+        self.log(format!("Mono check: {}", if enabled { "ON" } else { "OFF" }));
+    }
+
+    /// 切换设备采样率锁定（从下一首曲目起生效）
+    pub fn toggle_lock_device_rate(&mut self) {
+        let locked = !self.engine.lock_device_rate();
+        self.engine.set_lock_device_rate(locked);
+        self.config.output.lock_device_rate = locked;
+        self.log(format!(
+            "Device rate lock: {} (from next track)",
+            if locked { "ON" } else { "OFF" }
+        ));
     }
 
     /// 循环切换重复模式 (Off -> All -> Track -> Off)
@@ -490,6 +861,53 @@ impl App {
             RepeatMode::Track => "TRACK",
         };
         self.log(format!("Repeat: {}", mode_str));
+        self.user_config.repeat = self.repeat_mode;
+        self.save_user_config();
+    }
+
+    /// 循环切换配色主题 (Dark -> Light -> HighContrast -> Dark)
+    pub fn cycle_theme(&mut self) {
+        self.theme = self.theme.next();
+        self.log(format!("Theme: {}", self.theme.as_str()));
+        self.user_config.theme = self.theme;
+        self.save_user_config();
+    }
+
+    /// 写回配置文件（失败只记录日志）
+    ///
+    /// 只包含 TUI 中切换过的项；CLI 参数只作用于本次会话
+    fn save_user_config(&mut self) {
+        if let Err(e) = self.user_config.save() {
+            self.log(format!("Failed to save config: {}", e));
+        }
+    }
+
+    /// 输出启动后（已等到首次回调）写回弹窗选择的输出设置
+    ///
+    /// 看门狗回退到系统混音器时选择的模式不可用，不写回
+    fn confirm_device_settings(&mut self) {
+        if !self.device_settings_pending {
+            return;
+        }
+        self.device_settings_pending = false;
+        if self.engine.playback_summary().is_some_and(|s| !s.fell_back) {
+            self.save_device_settings();
+        } else {
+            self.log("Output fell back, not remembering this mode for the device".to_string());
+        }
+    }
+
+    /// 将当前输出设置写回目标设备的缓存（失败只记录日志）
+    fn save_device_settings(&mut self) {
+        let target = AudioOutput::target_device(self.config.output.device_id);
+        let Some(uid) = target.ok().and_then(|device| device.uid) else {
+            return;
+        };
+        let mut cache = DeviceSettingsCache::load();
+        cache.insert(&uid, DeviceSettings::from_config(&self.config.output));
+        if let Err(e) = cache.save() {
+            self.log(format!("Failed to save device settings: {}", e));
+        }
     }
 
     // ========== 弹窗相关方法 ==========
@@ -525,12 +943,22 @@ impl App {
             let path = pending_path.clone();
             let use_hal = *selected == OutputModeChoice::HalExclusive;
 
-            // 更新配置
+            // 更新配置（显式选择优先于设备缓存）
             self.config.output.use_hal = use_hal;
             self.config.output.exclusive_mode = use_hal;
+            self.config.output.device_settings = false;
+            // 输出确认可用后再记住该设备的选择
+            self.device_settings_pending = true;
 
             // 重新创建引擎（使用新配置）
-            self.engine = Engine::new(self.config.clone());
+            self.engine = match Engine::new(self.config.clone()) {
+                Ok(engine) => engine,
+                Err(e) => {
+                    self.log(format!("Engine error: {}", e));
+                    self.dialog = DialogState::None;
+                    return;
+                }
+            };
 
             let mode_str = if use_hal { "HAL (Exclusive)" } else { "System Mixer" };
             self.log(format!("Output mode: {}", mode_str));
@@ -561,14 +989,73 @@ impl App {
         self.shuffle_order.iter().position(|&i| i == self.current_index)
     }
 
+    /// 当前曲目的文件名（用于日志）
+    fn current_file_name(&self) -> String {
+        self.playlist
+            .get(self.current_index)
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
     /// 轻量级曲目结束检测
     ///
-    /// 仅读取 eof_reached (AtomicBool)，播放中几乎零开销。
-    /// 只有 eof_reached=true 时才进一步检查 ring_buffer.available()。
+    /// 非阻塞检查引擎完成回调发来的事件，播放中不访问引擎状态。
     /// 从主循环高频调用（每次输入轮询），不读取统计信息。
+    /// 收到事件后停止引擎，按停止原因决定是否清除续播位置
     pub fn check_track_end(&mut self) -> bool {
-        if self.engine.is_track_finished() {
-            self.log("Track finished".to_string());
+        if self.track_end_rx.try_recv().is_ok() {
+            // 曲目间静音（单曲循环与播放列表结束时不插入）
+            let gap = self
+                .config
+                .inter_track_gap(self.repeat_mode == RepeatMode::Track)
+                .filter(|_| self.next_index().is_some());
+            let reason = if gap.is_none()
+                && self.engine.last_error().is_none()
+                && self.engine.is_track_finished()
+            {
+                // 正常播完且立即切歌：不先停止输出，由 load() 决定能否沿用设备
+                Some(StopReason::EndOfTrack)
+            } else {
+                // stop 失败时原因也已记录
+                if let Err(e) = self.engine.stop() {
+                    self.log(format!("Stop failed: {}", e));
+                }
+                self.engine.last_stop_reason()
+            };
+            match reason {
+                Some(StopReason::Error) => {
+                    let name = self.current_file_name();
+                    let err = self.engine.last_error().unwrap_or_default();
+                    self.log(format!("Skipped (corrupt): {} ({})", name, err));
+                }
+                Some(StopReason::EndOfTrack) if self.engine.is_empty_track() => {
+                    let name = self.current_file_name();
+                    self.log(format!("Skipped (empty): {}", name));
+                }
+                Some(StopReason::EndOfTrack) => {
+                    self.log("Track finished".to_string());
+                    // 完整播放结束，下次从头播放
+                    if let Some(path) = self.playing_path.take() {
+                        if self.resume.record_finished(&path) {
+                            self.save_resume_positions();
+                        }
+                    }
+                }
+                Some(StopReason::User | StopReason::DeviceLost) | None => {}
+            }
+            if self.advance.track_ended(Instant::now(), gap) {
+                self.go_to_next(true);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 曲目间静音结束时切到下一首，返回是否切歌
+    pub fn check_advance(&mut self) -> bool {
+        if self.advance.poll(Instant::now()) {
             self.go_to_next(true);
             true
         } else {
@@ -576,6 +1063,125 @@ impl App {
         }
     }
 
+    /// 曲目间静音的剩余时间（未在等待时为 None）
+    pub fn advance_remaining(&self) -> Option<Duration> {
+        self.advance.remaining(Instant::now())
+    }
+
+    /// 跳转到当前曲目的指定比例位置（0.0-1.0）
+    pub fn seek_to_fraction(&mut self, fraction: f64) {
+        let duration = match self.engine.current_info().and_then(|i| i.duration_secs) {
+            Some(d) => d,
+            None => return,
+        };
+        let target = duration * fraction.clamp(0.0, 1.0);
+        match self.engine.seek(target) {
+            Ok(()) => {
+                self.update_stats();
+                self.log(format!(
+                    "Seek: {:02}:{:02}",
+                    (target / 60.0) as u32,
+                    (target % 60.0) as u32
+                ));
+            }
+            Err(e) => self.log(format!("Seek failed: {}", e)),
+        }
+    }
+
+    /// 切换精细 seek 模式
+    pub fn toggle_precision_seek(&mut self) {
+        self.precision_seek = !self.precision_seek;
+        let message = if self.precision_seek {
+            "Precision seek: ←/→ 1s, Shift 0.1s"
+        } else {
+            "Precision seek off: ←/→ 5s"
+        };
+        self.log(message.to_string());
+    }
+
+    /// 方向键相对 seek（`forward` 为向后，`shift` 在精细模式下选 0.1s 步长）
+    pub fn seek_step(&mut self, forward: bool, shift: bool) {
+        let Some(info) = self.engine.current_info() else {
+            return;
+        };
+        let duration = info.duration_secs;
+        let step = seek_step_secs(self.precision_seek, shift);
+        let delta = if forward { step } else { -step };
+        let target = relative_seek_target(self.display_position_secs(), delta, duration);
+        match self.engine.seek(target) {
+            Ok(()) => {
+                self.update_stats();
+                self.log(format!(
+                    "Seek: {:02}:{:04.1}",
+                    (target / 60.0) as u32,
+                    target % 60.0
+                ));
+            }
+            Err(e) => self.log(format!("Seek failed: {}", e)),
+        }
+    }
+
+    /// 跳到下一章节
+    pub fn next_chapter(&mut self) {
+        match self.engine.next_chapter() {
+            Ok(()) => {
+                self.update_stats();
+                self.log_chapter();
+            }
+            Err(e) => self.log(format!("Chapter skip failed: {}", e)),
+        }
+    }
+
+    /// 跳到上一章节（或本章开头）
+    pub fn prev_chapter(&mut self) {
+        match self.engine.prev_chapter() {
+            Ok(()) => {
+                self.update_stats();
+                self.log_chapter();
+            }
+            Err(e) => self.log(format!("Chapter skip failed: {}", e)),
+        }
+    }
+
+    /// 记录当前章节标题
+    fn log_chapter(&mut self) {
+        let position = self.display_position_secs();
+        if let Some(index) = self.engine.current_chapter(position) {
+            let title = self.engine.chapters()[index].1.clone();
+            self.log(format!("Chapter: {}", title));
+        }
+    }
+
+    /// 鼠标点击处理（column/row 为终端坐标）
+    ///
+    /// - 点击进度条或波形条：按 x 偏移 seek
+    /// - 点击播放列表行：选中并播放
+    pub fn handle_click(&mut self, column: u16, row: u16) {
+        for bar in [self.progress_bar_area, self.waveform_area].into_iter().flatten() {
+            if row == bar.y && column >= bar.x && column < bar.x + bar.width {
+                let fraction = (column - bar.x) as f64 / bar.width.max(1) as f64;
+                self.seek_to_fraction(fraction);
+                return;
+            }
+        }
+
+        if let Some(area) = self.playlist_area {
+            // 排除边框
+            let inside = column > area.x
+                && column < area.x + area.width.saturating_sub(1)
+                && row > area.y
+                && row < area.y + area.height.saturating_sub(1);
+            if inside {
+                let index = self.playlist_state.offset() + (row - area.y - 1) as usize;
+                if index < self.playlist.len() {
+                    self.current_index = index;
+                    self.playlist_state.select(Some(index));
+                    self.play_current();
+                }
+            }
+        }
+    }
+
     /// 选曲光标超时检查（纯本地状态，无原子操作）
     pub fn check_cursor_timeout(&mut self) {
         if let Some(last_time) = self.last_selection_time {
@@ -594,6 +1200,24 @@ impl App {
     /// 播放中约每 500ms 一次（而非之前的 50ms），减少 10 倍 cache 干扰。
     pub fn update_stats(&mut self) {
         self.cached_stats = self.engine.stats();
+        self.stats_read_at = Instant::now();
+        self.next_status = self
+            .engine
+            .prefetch_status()
+            .map(|(path, status)| (path.to_path_buf(), status));
+    }
+
+    /// 显示用的播放位置（秒）
+    ///
+    /// 播放中在上次读取的统计值上加读取后经过的墙钟时间，时钟平滑走动；
+    /// 每次真正读取统计时回到引擎给出的位置。不额外访问 ring buffer
+    pub fn display_position_secs(&self) -> f64 {
+        let position = self.cached_stats.position_secs;
+        if self.engine.state() != PlaybackState::Playing {
+            return position;
+        }
+        let duration = self.engine.current_info().and_then(|info| info.duration_secs);
+        interpolate_position(position, self.stats_read_at.elapsed(), duration)
     }
 
     /// 执行搜索
@@ -658,6 +1282,40 @@ impl App {
         self.search_result_index = 0;
     }
 
+    /// 进入跳转输入模式
+    pub fn enter_goto(&mut self) {
+        self.goto_mode = true;
+        self.goto_input.clear();
+    }
+
+    /// 跳转输入追加一位数字（超长输入忽略）
+    pub fn goto_push(&mut self, digit: char) {
+        if digit.is_ascii_digit() && self.goto_input.len() < GOTO_MAX_DIGITS {
+            self.goto_input.push(digit);
+        }
+    }
+
+    /// 确认跳转：选中第 N 首（输入为空时跳到第一首），并退出跳转模式
+    pub fn goto_confirm(&mut self) {
+        let target = if self.goto_input.is_empty() {
+            (!self.playlist.is_empty()).then_some(0)
+        } else {
+            parse_track_number(&self.goto_input, self.playlist.len())
+        };
+        if let Some(index) = target {
+            self.playlist_state.select(Some(index));
+            self.show_cursor = true;
+            self.last_selection_time = Some(Instant::now());
+        }
+        self.exit_goto();
+    }
+
+    /// 退出跳转输入模式
+    pub fn exit_goto(&mut self) {
+        self.goto_mode = false;
+        self.goto_input.clear();
+    }
+
     // ========== 目录监听相关方法 ==========
 
     /// 启动目录监听
@@ -690,8 +1348,13 @@ impl App {
 
         match watcher_result {
             Ok(mut watcher) => {
-                // 监听目录（非递归）
-                if let Err(e) = watcher.watch(&dir_clone, RecursiveMode::NonRecursive) {
+                // 递归扫描时同时监听子目录
+                let mode = if self.recursive {
+                    RecursiveMode::Recursive
+                } else {
+                    RecursiveMode::NonRecursive
+                };
+                if let Err(e) = watcher.watch(&dir_clone, mode) {
                     self.log(format!("Failed to watch directory: {}", e));
                     return;
                 }
@@ -752,7 +1415,7 @@ impl App {
         };
 
         // 重新扫描目录
-        let new_files = match Self::scan_audio_files(dir) {
+        let new_files = match scan_audio_files(dir, self.recursive) {
             Ok(f) => f,
             Err(e) => {
                 self.log(format!("Error refreshing directory: {}", e));
@@ -810,3 +1473,221 @@ impl App {
         }
     }
 }
+
+/// 跳转输入的最大位数
+const GOTO_MAX_DIGITS: usize = 6;
+
+/// 解析 1-based 曲目编号为播放列表索引（超出范围 clamp 到首/尾）
+///
+/// 空列表或无法解析时返回 None
+pub fn parse_track_number(input: &str, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let n: usize = input.trim().parse().ok()?;
+    Some(n.saturating_sub(1).min(len - 1))
+}
+
+/// 条目上移/下移一位后的位置（已在首/尾或越界时为 None）
+fn move_target(index: usize, len: usize, down: bool) -> Option<usize> {
+    if index >= len {
+        return None;
+    }
+    if down {
+        (index + 1 < len).then_some(index + 1)
+    } else {
+        index.checked_sub(1)
+    }
+}
+
+/// 交换 a、b 两个条目后，原索引 `index` 指向的条目的新索引
+fn swap_index(index: usize, a: usize, b: usize) -> usize {
+    if index == a {
+        b
+    } else if index == b {
+        a
+    } else {
+        index
+    }
+}
+
+/// 方向键 seek 步长：普通 5s；精细模式下 1s，Shift 0.1s（普通模式忽略 Shift）
+pub fn seek_step_secs(precision: bool, shift: bool) -> f64 {
+    match (precision, shift) {
+        (false, _) => SEEK_STEP_SECS,
+        (true, false) => FINE_SEEK_STEP_SECS,
+        (true, true) => SUB_SECOND_SEEK_STEP_SECS,
+    }
+}
+
+/// 相对 seek 的目标位置，限制在曲目范围内（时长未知时只限制下界）
+fn relative_seek_target(position_secs: f64, delta_secs: f64, duration_secs: Option<f64>) -> f64 {
+    let target = (position_secs + delta_secs).max(0.0);
+    match duration_secs {
+        Some(duration) if duration > 0.0 => target.min(duration),
+        _ => target,
+    }
+}
+
+/// 从上次读取的位置按经过时间推算当前位置，不超过曲目时长
+fn interpolate_position(last_secs: f64, elapsed: Duration, duration_secs: Option<f64>) -> f64 {
+    let projected = last_secs + elapsed.as_secs_f64();
+    match duration_secs {
+        Some(duration) if duration > 0.0 => projected.min(duration.max(last_secs)),
+        _ => projected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_track_number() {
+        assert_eq!(parse_track_number("1", 10), Some(0));
+        assert_eq!(parse_track_number("7", 10), Some(6));
+        // 超出范围 clamp
+        assert_eq!(parse_track_number("0", 10), Some(0));
+        assert_eq!(parse_track_number("250", 10), Some(9));
+        // 无效输入 / 空列表
+        assert_eq!(parse_track_number("", 10), None);
+        assert_eq!(parse_track_number("abc", 10), None);
+        assert_eq!(parse_track_number("3", 0), None);
+    }
+
+    #[test]
+    fn test_move_entry_bookkeeping() {
+        // 首尾不循环，越界无操作
+        assert_eq!(move_target(0, 3, false), None);
+        assert_eq!(move_target(0, 3, true), Some(1));
+        assert_eq!(move_target(2, 3, true), None);
+        assert_eq!(move_target(2, 3, false), Some(1));
+        assert_eq!(move_target(0, 1, true), None);
+        assert_eq!(move_target(5, 3, false), None);
+
+        let playlist: Vec<PathBuf> = ["a", "b", "c", "d", "e"].iter().map(PathBuf::from).collect();
+        let user_config = UserConfig::default();
+        let session = SessionSettings::from_config(&user_config);
+        let mut app = App::new(EngineConfig::default(), playlist, user_config, session).unwrap();
+        app.current_index = 1;
+        app.shuffle_order = vec![4, 0, 2, 1, 3];
+        app.search_results = vec![1, 3];
+        let names = |app: &App, indices: &[usize]| -> Vec<String> {
+            indices.iter().map(|&i| app.playlist[i].display().to_string()).collect()
+        };
+        let order = names(&app, &app.shuffle_order);
+
+        // 当前曲目（索引 1）下移：索引、随机顺序和搜索结果跟随条目
+        app.playlist_state.select(Some(1));
+        app.move_selected(true);
+        assert_eq!(names(&app, &[0, 1, 2, 3, 4]), ["a", "c", "b", "d", "e"]);
+        assert_eq!(app.current_index, 2);
+        assert_eq!(app.playlist_state.selected(), Some(2));
+        assert_eq!(app.shuffle_order, [4, 0, 1, 2, 3]);
+        assert_eq!(names(&app, &app.shuffle_order), order);
+        assert_eq!(names(&app, &app.search_results), ["b", "d"]);
+
+        // 首行上移、末行下移：无操作
+        app.playlist_state.select(Some(0));
+        app.move_selected(false);
+        app.playlist_state.select(Some(4));
+        app.move_selected(true);
+        assert_eq!(names(&app, &[0, 1, 2, 3, 4]), ["a", "c", "b", "d", "e"]);
+        assert_eq!(app.playlist_state.selected(), Some(4));
+
+        // 末行上移：当前曲目不动，其余索引照常跟随
+        app.move_selected(false);
+        assert_eq!(names(&app, &[0, 1, 2, 3, 4]), ["a", "c", "b", "e", "d"]);
+        assert_eq!(app.current_index, 2);
+        assert_eq!(app.playlist_state.selected(), Some(3));
+        assert_eq!(names(&app, &app.shuffle_order), order);
+        assert_eq!(names(&app, &app.search_results), ["b", "d"]);
+
+        // 首行下移
+        app.playlist_state.select(Some(0));
+        app.move_selected(true);
+        assert_eq!(names(&app, &[0, 1, 2, 3, 4]), ["c", "a", "b", "e", "d"]);
+        assert_eq!(app.playlist_state.selected(), Some(1));
+        assert_eq!(names(&app, &app.shuffle_order), order);
+    }
+
+    #[test]
+    fn test_next_up_status_matches_path() {
+        let playlist: Vec<PathBuf> = ["a", "b", "c"].iter().map(PathBuf::from).collect();
+        let user_config = UserConfig::default();
+        let session = SessionSettings::from_config(&user_config);
+        let mut app = App::new(EngineConfig::default(), playlist, user_config, session).unwrap();
+        app.next_status = Some((PathBuf::from("c"), PrefetchStatus::Failed("bad".to_string())));
+
+        // 预打开的是别的曲目（如移动条目后）：不把失败安到 Next 上
+        assert_eq!(app.next_up(), Some(1));
+        assert!(app.next_up_status().is_none());
+
+        app.current_index = 1;
+        assert!(matches!(app.next_up_status(), Some(PrefetchStatus::Failed(_))));
+    }
+
+    #[test]
+    fn test_advance_scheduler_gap() {
+        let start = Instant::now();
+        let gap = EngineConfig {
+            inter_track_gap_ms: 2000,
+            ..EngineConfig::default()
+        };
+
+        // 2 秒间隔：到期前不切歌，到期后只触发一次
+        let mut advance = AdvanceScheduler::default();
+        assert!(!advance.track_ended(start, gap.inter_track_gap(false)));
+        assert!(!advance.poll(start + Duration::from_millis(1999)));
+        assert_eq!(
+            advance.remaining(start + Duration::from_millis(500)),
+            Some(Duration::from_millis(1500))
+        );
+        assert!(advance.poll(start + Duration::from_secs(2)));
+        assert!(!advance.poll(start + Duration::from_secs(3)));
+        assert_eq!(advance.remaining(start), None);
+
+        // 单曲循环与未设置间隔时立即切歌
+        assert!(advance.track_ended(start, gap.inter_track_gap(true)));
+        assert!(advance.track_ended(start, EngineConfig::default().inter_track_gap(false)));
+        assert!(!advance.poll(start + Duration::from_secs(10)));
+
+        // 手动切歌取消待切
+        advance.track_ended(start, gap.inter_track_gap(false));
+        advance.cancel();
+        assert!(!advance.poll(start + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_interpolate_position() {
+        let half = Duration::from_millis(500);
+        assert_eq!(interpolate_position(10.0, half, Some(60.0)), 10.5);
+        assert_eq!(interpolate_position(10.0, Duration::ZERO, Some(60.0)), 10.0);
+        // 不超过曲目时长
+        assert_eq!(interpolate_position(59.8, half, Some(60.0)), 60.0);
+        // 时长未知（流）：不限制
+        assert_eq!(interpolate_position(100.0, half, None), 100.5);
+        assert_eq!(interpolate_position(100.0, half, Some(0.0)), 100.5);
+        // 统计位置已超过时长（时长为估计值）：不回退
+        assert_eq!(interpolate_position(61.0, half, Some(60.0)), 61.0);
+    }
+
+    #[test]
+    fn test_seek_step() {
+        // 普通模式 5s（忽略 Shift），精细模式 1s / Shift 0.1s
+        assert_eq!(seek_step_secs(false, false), 5.0);
+        assert_eq!(seek_step_secs(false, true), 5.0);
+        assert_eq!(seek_step_secs(true, false), 1.0);
+        assert_eq!(seek_step_secs(true, true), 0.1);
+
+        assert_eq!(relative_seek_target(30.0, 5.0, Some(60.0)), 35.0);
+        assert_eq!(relative_seek_target(30.0, -1.0, Some(60.0)), 29.0);
+        assert!((relative_seek_target(30.0, 0.1, Some(60.0)) - 30.1).abs() < 1e-9);
+        // 不越过曲目边界
+        assert_eq!(relative_seek_target(2.0, -5.0, Some(60.0)), 0.0);
+        assert_eq!(relative_seek_target(58.0, 5.0, Some(60.0)), 60.0);
+        // 时长未知：只限制下界
+        assert_eq!(relative_seek_target(58.0, 5.0, None), 63.0);
+        assert_eq!(relative_seek_target(0.05, -0.1, None), 0.0);
+    }
+}
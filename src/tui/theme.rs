@@ -0,0 +1,164 @@
+//! TUI 配色主题
+//!
+//! 界面各处只引用语义角色（强调、正在播放、警告……），具体颜色由主题决定。
+//! 默认 Dark 与原先的配色一致；Light 适配浅色终端背景，
+//! HighContrast 只用高亮色，便于低视力或投影场景。
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+/// 主题名称（配置文件与 `--theme` 使用）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl ThemeName {
+    pub const ALL: [ThemeName; 3] = [Self::Dark, Self::Light, Self::HighContrast];
+
+    /// 循环切换 (Dark -> Light -> HighContrast -> Dark)
+    pub fn next(self) -> Self {
+        match self {
+            Self::Dark => Self::Light,
+            Self::Light => Self::HighContrast,
+            Self::HighContrast => Self::Dark,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+            Self::HighContrast => "high-contrast",
+        }
+    }
+
+    /// 对应的配色
+    pub fn theme(self) -> Theme {
+        match self {
+            Self::Dark => Theme::DARK,
+            Self::Light => Theme::LIGHT,
+            Self::HighContrast => Theme::HIGH_CONTRAST,
+        }
+    }
+}
+
+impl std::str::FromStr for ThemeName {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|name| name.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown theme '{}' (dark, light, high-contrast)", s))
+    }
+}
+
+/// 语义颜色角色
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// 边框、进度条、输入提示、选中项
+    pub accent: Color,
+    /// 播放列表中正在播放的曲目
+    pub playing: Color,
+    /// 强调色背景上的文字（光标行）
+    pub on_accent: Color,
+    /// 警告（非 bit-perfect、下一首不支持等）
+    pub warning: Color,
+    /// 正常状态（BIT-PERFECT、无欠载）
+    pub ok: Color,
+    /// 错误（欠载）
+    pub error: Color,
+    /// 正文
+    pub text: Color,
+    /// 次要信息（日志、说明文字）
+    pub secondary: Color,
+    /// 提示文字
+    pub dim: Color,
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        accent: Color::Cyan,
+        playing: Color::Cyan,
+        on_accent: Color::White,
+        warning: Color::Yellow,
+        ok: Color::Green,
+        error: Color::Red,
+        text: Color::White,
+        secondary: Color::Gray,
+        dim: Color::DarkGray,
+    };
+
+    /// 浅色背景：避免黄色/青色等在白底上看不清的颜色
+    pub const LIGHT: Theme = Theme {
+        accent: Color::Rgb(0, 95, 175),
+        playing: Color::Rgb(0, 95, 175),
+        on_accent: Color::White,
+        warning: Color::Rgb(175, 95, 0),
+        ok: Color::Rgb(0, 128, 0),
+        error: Color::Rgb(175, 0, 0),
+        text: Color::Black,
+        secondary: Color::DarkGray,
+        dim: Color::Gray,
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        accent: Color::LightCyan,
+        playing: Color::LightYellow,
+        on_accent: Color::Black,
+        warning: Color::LightYellow,
+        ok: Color::LightGreen,
+        error: Color::LightRed,
+        text: Color::White,
+        secondary: Color::White,
+        dim: Color::Gray,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roles(theme: &Theme) -> [Color; 9] {
+        [
+            theme.accent,
+            theme.playing,
+            theme.on_accent,
+            theme.warning,
+            theme.ok,
+            theme.error,
+            theme.text,
+            theme.secondary,
+            theme.dim,
+        ]
+    }
+
+    #[test]
+    fn test_presets_define_all_roles() {
+        for name in ThemeName::ALL {
+            let theme = name.theme();
+            // Reset 会退回终端默认色，等于该角色没有定义
+            assert!(
+                roles(&theme).iter().all(|&c| c != Color::Reset),
+                "{} leaks a default color",
+                name.as_str()
+            );
+            assert_ne!(theme.on_accent, theme.accent, "{}", name.as_str());
+            assert_ne!(theme.text, theme.dim, "{}", name.as_str());
+
+            assert_eq!(name.as_str().parse::<ThemeName>(), Ok(name));
+        }
+
+        assert_eq!(ThemeName::Dark.next().next().next(), ThemeName::Dark);
+        assert_eq!(
+            "High-Contrast".parse::<ThemeName>(),
+            Ok(ThemeName::HighContrast)
+        );
+        assert!("solarized".parse::<ThemeName>().is_err());
+    }
+}
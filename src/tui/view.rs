@@ -6,10 +6,17 @@ use ratatui::{
     Frame,
 };
 
-use super::model::{App, DialogState, OutputModeChoice, RepeatMode};
-use crate::engine::PlaybackState;
+use super::keymap::{Action, KeyMap};
+use super::model::{seek_step_secs, App, DialogState, OutputModeChoice};
+use super::theme::Theme;
+use super::waveform::waveform_line;
+use crate::config::RepeatMode;
+use crate::decode::layout::layout_name;
+use crate::engine::{PlaybackState, PrefetchStatus};
 
 pub fn draw(f: &mut Frame, app: &mut App) {
+    let theme = app.theme.theme();
+
     // 垂直布局：Header, Main (Playlist + Info), Logs, Footer
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -21,28 +28,38 @@ pub fn draw(f: &mut Frame, app: &mut App) {
         ])
         .split(f.size());
 
-    draw_header(f, app, chunks[0]);
-    draw_main(f, app, chunks[1]);
-    draw_logs(f, app, chunks[2]);
-    draw_footer(f, app, chunks[3]);
+    draw_header(f, app, &theme, chunks[0]);
+    draw_main(f, app, &theme, chunks[1]);
+    draw_logs(f, app, &theme, chunks[2]);
+    draw_footer(f, app, &theme, chunks[3]);
 
     // 如果有弹窗，渲染在最上层
     if !matches!(app.dialog, DialogState::None) {
-        draw_dialog(f, app);
+        draw_dialog(f, app, &theme);
     }
 
     // 帮助页面
     if app.show_help {
-        draw_help(f);
+        draw_help(f, &app.keymap, &theme);
     }
 }
 
-fn draw_header(f: &mut Frame, app: &App, area: Rect) {
+fn draw_header(f: &mut Frame, app: &App, theme: &Theme, area: Rect) {
     let state_str = match app.engine.state() {
-        PlaybackState::Playing => "[RUNNING]",
-        PlaybackState::Paused => "[PAUSED]",
-        PlaybackState::Stopped => "[STOPPED]",
-        PlaybackState::Buffering => "[BUFFERING]",
+        PlaybackState::Playing => "[RUNNING]".to_string(),
+        PlaybackState::Paused => "[PAUSED]".to_string(),
+        PlaybackState::Stopped => match app.advance_remaining() {
+            Some(remaining) => format!("[GAP {:.1}s]", remaining.as_secs_f64()),
+            None => "[STOPPED]".to_string(),
+        },
+        PlaybackState::Buffering => match app.engine.buffering_eta_secs() {
+            Some(eta) => format!(
+                "[BUFFERING {:.0}% ~{:.1}s]",
+                app.cached_stats.target_fill_ratio.min(1.0) * 100.0,
+                eta
+            ),
+            None => "[BUFFERING]".to_string(),
+        },
     };
 
     // 构建模式标签
@@ -55,33 +72,54 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
         RepeatMode::Track => mode_tags.push("[REPEAT:1]"),
         RepeatMode::Off => {}
     }
+    if app.engine.mono_monitor() {
+        mode_tags.push("[MONO]");
+    }
+    if app.engine.lock_device_rate() {
+        mode_tags.push("[RATE LOCK]");
+    }
     let modes_str = mode_tags.join(" ");
 
     // 单行显示：Roger Player vX.Y.Z  (h: Help)    [SHUFFLE] [REPEAT:ALL]    [RUNNING]
     let title = concat!("Roger Player v", env!("CARGO_PKG_VERSION"));
-    let help_hint = "(h: Help)";
+    let help_hint = format!("({}: Help)", app.keymap.keys_for(Action::Help));
     let right_part = if modes_str.is_empty() {
-        state_str.to_string()
+        state_str
     } else {
         format!("{} {}", modes_str, state_str)
     };
     let left_part = format!("{}  {}", title, help_hint);
-    let spaces = " ".repeat(area.width.saturating_sub(left_part.len() as u16 + right_part.len() as u16 + 2) as usize);
 
-    let header_line = Line::from(vec![
+    // 实时调度徽标：两个线程都拿到时间约束策略为绿色，否则黄色
+    let rt_badge = if app.engine.state() != PlaybackState::Stopped {
+        let (io_rt, decoder_rt) = app.engine.realtime_active();
+        let color = if io_rt && decoder_rt { theme.ok } else { theme.warning };
+        Some(Span::styled("[RT] ", Style::default().fg(color)))
+    } else {
+        None
+    };
+    let badge_len = rt_badge.as_ref().map_or(0, |s| s.content.len()) as u16;
+    let spaces = " ".repeat(
+        area.width
+            .saturating_sub(left_part.len() as u16 + badge_len + right_part.len() as u16 + 2) as usize,
+    );
+
+    let mut spans = vec![
         Span::raw(title),
         Span::raw("  "),
-        Span::styled(help_hint, Style::default().fg(Color::DarkGray)),
+        Span::styled(help_hint, Style::default().fg(theme.dim)),
         Span::raw(spaces),
-        Span::raw(right_part),
-    ]);
+    ];
+    spans.extend(rt_badge);
+    spans.push(Span::raw(right_part));
+    let header_line = Line::from(spans);
 
     let block = Block::default().borders(Borders::ALL);
     let paragraph = Paragraph::new(header_line).block(block);
     f.render_widget(paragraph, area);
 }
 
-fn draw_main(f: &mut Frame, app: &mut App, area: Rect) {
+fn draw_main(f: &mut Frame, app: &mut App, theme: &Theme, area: Rect) {
     // 水平分割：左边播放列表，右边详细信息
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -91,16 +129,18 @@ fn draw_main(f: &mut Frame, app: &mut App, area: Rect) {
         ])
         .split(area);
 
-    draw_playlist(f, app, chunks[0]);
-    draw_now_playing(f, app, chunks[1]);
+    draw_playlist(f, app, theme, chunks[0]);
+    draw_now_playing(f, app, theme, chunks[1]);
 }
 
-fn draw_playlist(f: &mut Frame, app: &mut App, area: Rect) {
+fn draw_playlist(f: &mut Frame, app: &mut App, theme: &Theme, area: Rect) {
     // 如果在输入模式，显示路径输入界面
     if app.input_mode {
-        draw_path_input(f, app, area);
+        app.playlist_area = None;
+        draw_path_input(f, app, theme, area);
         return;
     }
+    app.playlist_area = Some(area);
 
     let items: Vec<ListItem> = app
         .playlist
@@ -114,7 +154,7 @@ fn draw_playlist(f: &mut Frame, app: &mut App, area: Rect) {
             let content = format!("{}{}{}", prefix, num, name);
 
             let style = if i == app.current_index {
-                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                Style::default().fg(theme.playing).add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
             };
@@ -132,9 +172,9 @@ fn draw_playlist(f: &mut Frame, app: &mut App, area: Rect) {
         // 当光标在正在播放曲目时，字体变白色
         let cursor_on_current = app.playlist_state.selected() == Some(app.current_index);
         let highlight_style = if cursor_on_current {
-            Style::default().bg(Color::Cyan).fg(Color::White).add_modifier(Modifier::BOLD)
+            Style::default().bg(theme.accent).fg(theme.on_accent).add_modifier(Modifier::BOLD)
         } else {
-            Style::default().bg(Color::Cyan)
+            Style::default().bg(theme.accent)
         };
         playlist = playlist.highlight_style(highlight_style);
     }
@@ -142,7 +182,7 @@ fn draw_playlist(f: &mut Frame, app: &mut App, area: Rect) {
     f.render_stateful_widget(playlist, area, &mut app.playlist_state);
 }
 
-fn draw_path_input(f: &mut Frame, app: &App, area: Rect) {
+fn draw_path_input(f: &mut Frame, app: &App, theme: &Theme, area: Rect) {
     let block = Block::default()
         .borders(Borders::ALL)
         .title("Drop Path Here");
@@ -162,21 +202,21 @@ fn draw_path_input(f: &mut Frame, app: &App, area: Rect) {
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
         "Drag and drop a file or folder here",
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme.dim),
     )));
     lines.push(Line::from(Span::styled(
         "or type/paste the path manually:",
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme.dim),
     )));
     lines.push(Line::from(""));
 
     // 输入框
     let input_display = if app.path_input.is_empty() {
-        Span::styled("_", Style::default().fg(Color::Cyan).add_modifier(Modifier::SLOW_BLINK))
+        Span::styled("_", Style::default().fg(theme.accent).add_modifier(Modifier::SLOW_BLINK))
     } else {
         // 显示输入内容 + 光标
         let display = format!("{}_", app.path_input);
-        Span::styled(display, Style::default().fg(Color::Cyan))
+        Span::styled(display, Style::default().fg(theme.accent))
     };
     lines.push(Line::from(vec![
         Span::raw("> "),
@@ -186,21 +226,21 @@ fn draw_path_input(f: &mut Frame, app: &App, area: Rect) {
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
         "Press Enter to load, Esc to cancel",
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme.dim),
     )));
 
     // 支持的格式
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
-        "Supported: flac, wav, aiff, mp3, pcm",
-        Style::default().fg(Color::DarkGray),
+        "Supported: flac, wav, aiff, mp3, m4a, pcm",
+        Style::default().fg(theme.dim),
     )));
 
     let paragraph = Paragraph::new(lines);
     f.render_widget(paragraph, inner_area);
 }
 
-fn draw_now_playing(f: &mut Frame, app: &App, area: Rect) {
+fn draw_now_playing(f: &mut Frame, app: &mut App, theme: &Theme, area: Rect) {
     let outer_block = Block::default().borders(Borders::ALL).title("Now Playing");
     f.render_widget(outer_block, area);
 
@@ -213,10 +253,12 @@ fn draw_now_playing(f: &mut Frame, app: &App, area: Rect) {
     };
 
     // 获取统计信息
+    // 播放位置按墙钟推算，两次统计读取之间时钟平滑走动
+    let position_secs = app.display_position_secs();
     let stats = &app.cached_stats;
     let total_secs = app.engine.current_info().map(|i| i.duration_secs.unwrap_or(0.0)).unwrap_or(0.0);
     let progress_ratio = if total_secs > 0.0 {
-        (stats.position_secs / total_secs).min(1.0)
+        (position_secs / total_secs).min(1.0)
     } else {
         0.0
     };
@@ -228,31 +270,49 @@ fn draw_now_playing(f: &mut Frame, app: &App, area: Rect) {
     let time_str = if total_secs > 0.0 {
         format!(
             "Time: {:02}:{:02} / {:02}:{:02}",
-            (stats.position_secs / 60.0) as u32,
-            (stats.position_secs % 60.0) as u32,
+            (position_secs / 60.0) as u32,
+            (position_secs % 60.0) as u32,
             (total_secs / 60.0) as u32,
             (total_secs % 60.0) as u32
         )
     } else {
         format!(
             "Time: {:02}:{:02} / ??:??",
-            (stats.position_secs / 60.0) as u32,
-            (stats.position_secs % 60.0) as u32
+            (position_secs / 60.0) as u32,
+            (position_secs % 60.0) as u32
         )
     };
     lines.push(Line::from(time_str));
 
     // 2. 进度条（文本样式）
     let bar_width = (inner_area.width as usize).saturating_sub(10); // 留空间给百分比
-    let filled = (bar_width as f64 * progress_ratio) as usize;
-    let empty = bar_width.saturating_sub(filled);
+    // 记录进度条区域（"[" 之后），供鼠标点击 seek 使用
+    app.progress_bar_area = if total_secs > 0.0 && inner_area.height > 1 && bar_width > 0 {
+        Some(Rect::new(inner_area.x + 1, inner_area.y + 1, bar_width as u16, 1))
+    } else {
+        None
+    };
     let progress_bar = format!(
-        "[{}{}] {:>3}%",
-        "█".repeat(filled),
-        "░".repeat(empty),
+        "[{}] {:>3}%",
+        progress_bar(bar_width, progress_ratio),
         (progress_ratio * 100.0) as u32
     );
-    lines.push(Line::from(Span::styled(progress_bar, Style::default().fg(Color::Cyan))));
+    lines.push(Line::from(Span::styled(progress_bar, Style::default().fg(theme.accent))));
+
+    // 波形概览（后台计算完成后显示，与进度条对齐，可点击 seek）
+    let waveform = app
+        .current_waveform()
+        .filter(|_| app.progress_bar_area.is_some() && inner_area.height > 2)
+        .map(|peaks| waveform_line(peaks, bar_width, progress_ratio));
+    app.waveform_area = waveform
+        .as_ref()
+        .map(|_| Rect::new(inner_area.x + 1, inner_area.y + 2, bar_width as u16, 1));
+    if let Some(waveform) = waveform {
+        lines.push(Line::from(Span::styled(
+            format!(" {}", waveform),
+            Style::default().fg(theme.accent),
+        )));
+    }
 
     // 3. 格式信息
     if let Some(info) = app.engine.current_info() {
@@ -271,17 +331,65 @@ fn draw_now_playing(f: &mut Frame, app: &App, area: Rect) {
             .map(|d| format!("{}", d))
             .unwrap_or_else(|| "N/A".to_string());
 
-        let format_line = format!(
-            "Format: {} {}kHz/{}bit",
-            format_str,
-            info.sample_rate / 1000,
-            bit_depth_str
-        );
-        lines.push(Line::from(Span::styled(format_line, Style::default().fg(Color::White))));
+        // 有损：显示码率；无损：显示位深度/采样率
+        let format_line = if info.lossless {
+            format!(
+                "Format: {} {}kHz/{}bit lossless",
+                format_str,
+                info.sample_rate / 1000,
+                bit_depth_str
+            )
+        } else {
+            let bitrate_str = info.bitrate_bps
+                .map(|b| format!("{}kbps", (b as u64 + 500) / 1000))
+                .unwrap_or_else(|| "?kbps".to_string());
+            format!(
+                "Format: {} {} {}kHz",
+                format_str,
+                bitrate_str,
+                info.sample_rate / 1000
+            )
+        };
+        lines.push(Line::from(Span::styled(format_line, Style::default().fg(theme.text))));
+
+        // 多声道：显示布局（4.0 与 3.1 声道数相同，角色不同）
+        if info.channels > 2 && !info.channel_layout.is_empty() {
+            let labels: Vec<&str> = info.channel_layout.iter().map(|r| r.label()).collect();
+            let layout_line = format!(
+                "Channels: {} ({})",
+                layout_name(&info.channel_layout),
+                labels.join(" ")
+            );
+            lines.push(Line::from(Span::styled(layout_line, Style::default().fg(theme.text))));
+        }
+
+        // 章节（容器 cue 或同名 .cue 文件）
+        if let Some(index) = app.engine.current_chapter(position_secs) {
+            let chapters = app.engine.chapters();
+            let chapter_line = format!(
+                "Chapter: {} ({}/{})",
+                chapters[index].1,
+                index + 1,
+                chapters.len()
+            );
+            lines.push(Line::from(Span::styled(chapter_line, Style::default().fg(theme.accent))));
+        }
+
+        // 接下来播放的曲目（与自动切歌规则一致）；预打开失败时提前提示
+        if let Some(next) = app.next_up() {
+            let name = app.playlist[next].file_name().unwrap_or_default().to_string_lossy();
+            let (next_line, color) = match app.next_up_status() {
+                Some(PrefetchStatus::Failed(e)) => {
+                    (format!("Next: {} (unsupported: {})", name, e), theme.warning)
+                }
+                _ => (format!("Next: {}", name), theme.text),
+            };
+            lines.push(Line::from(Span::styled(next_line, Style::default().fg(color))));
+        }
 
         // 4. 输出模式 + Bit-Perfect 状态
         let (hal, exclusive) = app.engine.output_mode().unwrap_or((false, false));
-        let bit_perfect = app.engine.is_bit_perfect();
+        let bit_perfect = app.engine.bit_perfect_reason();
         let output_mode = if hal {
             if exclusive {
                 "HAL (Exclusive)"
@@ -292,14 +400,37 @@ fn draw_now_playing(f: &mut Frame, app: &App, area: Rect) {
             "System Mixer"
         };
         let output_line = format!("Output: {}", output_mode);
-        lines.push(Line::from(Span::styled(output_line, Style::default().fg(Color::White))));
+        lines.push(Line::from(Span::styled(output_line, Style::default().fg(theme.text))));
+        if let Some(summary) = app.engine.playback_summary() {
+            // 跨系列 SRC（如 44.1k → 48k）醒目提示
+            let (signal_line, signal_color) = if summary.cross_family_src() {
+                (format!("Signal: {} (cross-family SRC)", summary.signal_path()), theme.warning)
+            } else {
+                (format!("Signal: {}", summary.signal_path()), theme.text)
+            };
+            lines.push(Line::from(Span::styled(signal_line, Style::default().fg(signal_color))));
+        }
+        if let Some(reason) = app.engine.hog_mode_failure() {
+            lines.push(Line::from(Span::styled(
+                format!("Shared: {}", reason),
+                Style::default().fg(theme.warning),
+            )));
+        }
+        // 协商有妥协时列出请求与实际的差异
+        if let Some(report) = app.engine.negotiation_report().filter(|r| !r.all_as_requested()) {
+            lines.push(Line::from(Span::styled(
+                report.to_string(),
+                Style::default().fg(theme.warning),
+            )));
+        }
         lines.push(Line::from("")); // 空行
 
         // Bit-Perfect 状态（使用醒目颜色）
-        let (bp_text, bp_color) = if bit_perfect {
-            ("BIT-PERFECT", Color::Green)
+        // 不满足时附上第一个失败的条件，如 "Not Bit-Perfect (SRC 44.1→48k)"
+        let (bp_text, bp_color) = if bit_perfect.is_bit_perfect() {
+            ("BIT-PERFECT".to_string(), theme.ok)
         } else {
-            ("Not Bit-Perfect", Color::Yellow)
+            (format!("Not Bit-Perfect ({})", bit_perfect), theme.warning)
         };
         lines.push(Line::from(Span::styled(bp_text, Style::default().fg(bp_color).add_modifier(Modifier::BOLD))));
         lines.push(Line::from("")); // 空行
@@ -308,27 +439,78 @@ fn draw_now_playing(f: &mut Frame, app: &App, area: Rect) {
         lines.push(Line::from(Span::styled("System Stats", Style::default().add_modifier(Modifier::BOLD))));
 
         // Buffer 条形图（动态宽度适应面板）
-        let buffer_ratio = stats.buffer_fill_ratio.min(1.0);
-        // "Buffer: [" = 9, "] " = 2, "100%" = 4, 共 15 固定字符
-        let buffer_bar_width = (inner_area.width as usize).saturating_sub(15).max(5);
+        // 以预缓冲目标为满格：100% 表示达到目标
+        let buffer_ratio = stats.target_fill_ratio.min(1.0);
+        // "Buffer: [" = 9, "] " = 2, "100%" = 4, " 99999ms" = 8, 共 23 固定字符
+        let buffer_bar_width = (inner_area.width as usize).saturating_sub(23).max(5);
         let buffer_filled = (buffer_bar_width as f64 * buffer_ratio) as usize;
         let buffer_empty = buffer_bar_width.saturating_sub(buffer_filled);
         let buffer_line = format!(
-            "Buffer: [{}{}] {:>3}%",
+            "Buffer: [{}{}] {:>3}% {:>5.0}ms",
             "|".repeat(buffer_filled),
             " ".repeat(buffer_empty),
-            (buffer_ratio * 100.0) as u32
+            (buffer_ratio * 100.0) as u32,
+            stats.buffered_ms
         );
-        lines.push(Line::from(Span::styled(buffer_line, Style::default().fg(Color::White))));
+        lines.push(Line::from(Span::styled(buffer_line, Style::default().fg(theme.text))));
 
         // Underruns
         let underrun_color = if stats.underrun_count > 0 {
-            Color::Red
+            theme.error
         } else {
-            Color::Green
+            theme.ok
         };
-        let underrun_line = format!("Underruns: {}", stats.underrun_count);
+        let mut underrun_line = format!("Underruns: {}", stats.underrun_count);
+        // 累计缺失时长区分轻微毛刺与严重断音
+        if stats.underrun_count > 0 {
+            underrun_line.push_str(&format!(" ({:.1}ms lost)", stats.underrun_lost_ms));
+        }
+        underrun_line.push_str(&format!("  Overflows: {}", stats.write_overflow_count));
+        // 最近几次 underrun 的播放位置（便于定位“几分钟处卡了一下”）
+        let positions = app.engine.underrun_positions_secs();
+        if !positions.is_empty() {
+            let recent: Vec<String> = positions
+                .iter()
+                .rev()
+                .take(3)
+                .rev()
+                .map(|secs| format!("{:.1}s", secs))
+                .collect();
+            underrun_line.push_str(&format!("  at {}", recent.join(", ")));
+        }
         lines.push(Line::from(Span::styled(underrun_line, Style::default().fg(underrun_color))));
+
+        // 解码吞吐：接近实时（< 2×）时提示 CPU 余量不足
+        if let Some(factor) = app.engine.realtime_factor() {
+            let color = if factor < 2.0 { theme.warning } else { theme.text };
+            lines.push(Line::from(Span::styled(
+                format!("Decode: {:.1}x realtime", factor),
+                Style::default().fg(color),
+            )));
+        }
+
+        // 限幅器：正在衰减时显示衰减量
+        if let Some(threshold) = app.engine.limiter() {
+            let (text, color) = if app.engine.limiter_active() {
+                let reduction = app.engine.limiter_reduction_db();
+                (format!("Limiter: {:.1} dBFS  GR -{:.1} dB", threshold, reduction), theme.warning)
+            } else {
+                (format!("Limiter: {:.1} dBFS", threshold), theme.text)
+            };
+            lines.push(Line::from(Span::styled(text, Style::default().fg(color))));
+        }
+
+        // 真峰值：超过 0 dBTP 时 DAC 重建后会过载
+        if app.engine.true_peak_meter() {
+            let (text, color) = match app.engine.true_peak_dbtp() {
+                Some(dbtp) if dbtp > 0.0 => {
+                    (format!("True peak: {:+.2} dBTP (over)", dbtp), theme.warning)
+                }
+                Some(dbtp) => (format!("True peak: {:+.2} dBTP", dbtp), theme.text),
+                None => ("True peak: -".to_string(), theme.text),
+            };
+            lines.push(Line::from(Span::styled(text, Style::default().fg(color))));
+        }
     } else {
         lines.push(Line::from("No track loaded"));
     }
@@ -337,7 +519,31 @@ fn draw_now_playing(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, inner_area);
 }
 
-fn draw_logs(f: &mut Frame, app: &App, area: Rect) {
+/// 文本进度条：已播放部分 `█`，剩余部分 `░`（TUI 与 CLI 共用）
+pub fn progress_bar(width: usize, ratio: f64) -> String {
+    let filled = ((width as f64 * ratio.clamp(0.0, 1.0)) as usize).min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// 时长未知时的不定进度条：3 格滑块随 `tick` 来回移动
+pub fn spinner_bar(width: usize, tick: usize) -> String {
+    let block = width.min(3);
+    let span = width - block;
+    let pos = if span == 0 {
+        0
+    } else {
+        let phase = tick % (span * 2);
+        if phase <= span { phase } else { span * 2 - phase }
+    };
+    format!(
+        "{}{}{}",
+        "░".repeat(pos),
+        "█".repeat(block),
+        "░".repeat(span - pos)
+    )
+}
+
+fn draw_logs(f: &mut Frame, app: &App, theme: &Theme, area: Rect) {
     // 只显示最近的一条日志（截图中显示的是单行日志区域）
     let log_text = if app.logs.is_empty() {
         "[LOG] Ready".to_string()
@@ -348,11 +554,11 @@ fn draw_logs(f: &mut Frame, app: &App, area: Rect) {
     let block = Block::default().borders(Borders::ALL);
     let paragraph = Paragraph::new(log_text)
         .block(block)
-        .style(Style::default().fg(Color::Gray));
+        .style(Style::default().fg(theme.secondary));
     f.render_widget(paragraph, area);
 }
 
-fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
+fn draw_footer(f: &mut Frame, app: &App, theme: &Theme, area: Rect) {
     // 搜索模式：显示搜索框
     if app.search_mode {
         let result_info = if app.search_results.is_empty() {
@@ -366,10 +572,10 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
         };
 
         let search_line = Line::from(vec![
-            Span::styled("/", Style::default().fg(Color::Cyan)),
-            Span::styled(&app.search_input, Style::default().fg(Color::White)),
-            Span::styled("_", Style::default().fg(Color::Cyan).add_modifier(Modifier::SLOW_BLINK)),
-            Span::styled(result_info, Style::default().fg(Color::DarkGray)),
+            Span::styled("/", Style::default().fg(theme.accent)),
+            Span::styled(&app.search_input, Style::default().fg(theme.text)),
+            Span::styled("_", Style::default().fg(theme.accent).add_modifier(Modifier::SLOW_BLINK)),
+            Span::styled(result_info, Style::default().fg(theme.dim)),
         ]);
 
         let block = Block::default().borders(Borders::ALL).title("Search");
@@ -378,22 +584,48 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
+    // 跳转模式：显示待跳转的编号
+    if app.goto_mode {
+        let range_info = format!(" (1-{}, Enter: Go, Esc: Cancel)", app.playlist.len());
+
+        let goto_line = Line::from(vec![
+            Span::styled("#", Style::default().fg(theme.accent)),
+            Span::styled(&app.goto_input, Style::default().fg(theme.text)),
+            Span::styled("_", Style::default().fg(theme.accent).add_modifier(Modifier::SLOW_BLINK)),
+            Span::styled(range_info, Style::default().fg(theme.dim)),
+        ]);
+
+        let block = Block::default().borders(Borders::ALL).title("Go to track");
+        let paragraph = Paragraph::new(goto_line).block(block);
+        f.render_widget(paragraph, area);
+        return;
+    }
+
     let info = if !matches!(app.dialog, DialogState::None) {
-        "↑/↓: Select | Enter: Confirm | Esc: Cancel"
+        "↑/↓: Select | Enter: Confirm | Esc: Cancel".to_string()
     } else if app.input_mode {
-        "Enter: Load | Esc: Cancel | q: Quit"
+        "Enter: Load | Esc: Cancel | q: Quit".to_string()
     } else {
-        "SPACE: Pause | n/p: Next/Prev | s: Shuffle | r: Repeat | o: Open | q: Quit"
+        // 当前方向键 seek 步长
+        let seek = if app.precision_seek {
+            format!("←/→: {}s ⇧{}s", seek_step_secs(true, false), seek_step_secs(true, true))
+        } else {
+            format!("←/→: {}s", seek_step_secs(false, false))
+        };
+        format!(
+            "SPACE: Pause | {} | n/p: Next/Prev | s: Shuffle | r: Repeat | o: Open | q: Quit",
+            seek
+        )
     };
     let block = Block::default().borders(Borders::ALL);
     let paragraph = Paragraph::new(info)
         .block(block)
-        .style(Style::default().fg(Color::DarkGray));
+        .style(Style::default().fg(theme.dim));
     f.render_widget(paragraph, area);
 }
 
 /// 渲染弹窗（居中显示）
-fn draw_dialog(f: &mut Frame, app: &App) {
+fn draw_dialog(f: &mut Frame, app: &App, theme: &Theme) {
     if let DialogState::OutputModeSelect { selected, .. } = &app.dialog {
         let area = f.size();
 
@@ -413,7 +645,7 @@ fn draw_dialog(f: &mut Frame, app: &App) {
         // 弹窗边框（跟随终端主题背景色）
         let block = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Cyan))
+            .border_style(Style::default().fg(theme.accent))
             .style(Style::default().bg(Color::Reset))
             .title(" Select Output Mode ");
 
@@ -432,15 +664,15 @@ fn draw_dialog(f: &mut Frame, app: &App) {
         // 说明文字（使用终端默认前景色）
         lines.push(Line::from(Span::styled(
             "Choose audio output mode:",
-            Style::default().fg(Color::Reset),
+            Style::default().fg(theme.text),
         )));
         lines.push(Line::from(""));
 
         // 选项 1: HAL Exclusive
         let hal_style = if *selected == OutputModeChoice::HalExclusive {
-            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
         } else {
-            Style::default().fg(Color::Reset)
+            Style::default().fg(theme.text)
         };
         let hal_prefix = if *selected == OutputModeChoice::HalExclusive { "> " } else { "  " };
         lines.push(Line::from(Span::styled(
@@ -449,16 +681,16 @@ fn draw_dialog(f: &mut Frame, app: &App) {
         )));
         lines.push(Line::from(Span::styled(
             "      Best quality, bit-perfect",
-            Style::default().fg(Color::Gray),
+            Style::default().fg(theme.secondary),
         )));
 
         lines.push(Line::from(""));
 
         // 选项 2: System Mixer
         let mixer_style = if *selected == OutputModeChoice::SystemMixer {
-            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
         } else {
-            Style::default().fg(Color::Reset)
+            Style::default().fg(theme.text)
         };
         let mixer_prefix = if *selected == OutputModeChoice::SystemMixer { "> " } else { "  " };
         lines.push(Line::from(Span::styled(
@@ -467,7 +699,7 @@ fn draw_dialog(f: &mut Frame, app: &App) {
         )));
         lines.push(Line::from(Span::styled(
             "      Compatible, allows mixing",
-            Style::default().fg(Color::Gray),
+            Style::default().fg(theme.secondary),
         )));
 
         let paragraph = Paragraph::new(lines);
@@ -475,13 +707,53 @@ fn draw_dialog(f: &mut Frame, app: &App) {
     }
 }
 
+/// 帮助页面宽度（内部区域左右各留 2 列）
+const HELP_WIDTH: u16 = 45;
+
+/// 帮助页面的行：按键列随按键映射变化
+fn help_items(keymap: &KeyMap) -> Vec<(String, &'static str)> {
+    let keys = |action| keymap.keys_for(action);
+    let pair = |a, b| format!("{} / {}", keys(a), keys(b));
+    vec![
+        (keys(Action::TogglePause), "Pause / Resume"),
+        (pair(Action::NextTrack, Action::PrevTrack), "Next / Previous track"),
+        (pair(Action::NextChapter, Action::PrevChapter), "Next / Previous chapter"),
+        (pair(Action::SeekBack, Action::SeekForward), "Seek 5s (fine: 1s / 0.1s)"),
+        (keys(Action::PrecisionSeek), "Toggle precision seek"),
+        (pair(Action::SelectUp, Action::SelectDown), "Navigate playlist"),
+        (pair(Action::MoveUp, Action::MoveDown), "Move selected track up / down"),
+        (format!("{} N Enter", keys(Action::Goto)), "Go to track N (empty: first)"),
+        (format!("{0} / N{0}", keys(Action::JumpLast)), "Jump to last / line N"),
+        (keys(Action::PlaySelected), "Play selected track"),
+        ("Click".to_string(), "Seek on bar / play row"),
+        (keys(Action::Search), "Search songs"),
+        (keys(Action::Shuffle), "Toggle shuffle"),
+        (keys(Action::Repeat), "Cycle repeat mode"),
+        (keys(Action::InvertPolarity), "Toggle polarity invert"),
+        (keys(Action::SwapChannels), "Toggle L/R channel swap"),
+        (keys(Action::MonoMonitor), "Toggle mono check"),
+        (keys(Action::OutputMode), "Switch HAL / System Mixer"),
+        (keys(Action::LockRate), "Lock device sample rate"),
+        (keys(Action::Theme), "Cycle color theme"),
+        (keys(Action::Open), "Open file / folder"),
+        (keys(Action::Help), "Show this help"),
+        (keys(Action::Quit), "Quit"),
+        (String::new(), ""),
+        ("In Search Mode:".to_string(), ""),
+        ("↑ / ↓".to_string(), "Navigate results"),
+        ("Enter".to_string(), "Play & close search"),
+        ("Esc".to_string(), "Cancel search"),
+    ]
+}
+
 /// 渲染帮助页面
-fn draw_help(f: &mut Frame) {
+fn draw_help(f: &mut Frame, keymap: &KeyMap, theme: &Theme) {
     let area = f.size();
+    let help_items = help_items(keymap);
 
-    // 弹窗尺寸
-    let dialog_width = 45u16.min(area.width.saturating_sub(4));
-    let dialog_height = 18u16.min(area.height.saturating_sub(4));
+    // 弹窗尺寸（高度按行数加上下边框）
+    let dialog_width = HELP_WIDTH.min(area.width.saturating_sub(4));
+    let dialog_height = (help_items.len() as u16 + 2).min(area.height.saturating_sub(4));
 
     // 居中计算
     let x = (area.width.saturating_sub(dialog_width)) / 2;
@@ -495,7 +767,7 @@ fn draw_help(f: &mut Frame) {
     // 弹窗边框（跟随终端主题背景色）
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan))
+        .border_style(Style::default().fg(theme.accent))
         .style(Style::default().bg(Color::Reset))
         .title(" Help ");
 
@@ -509,24 +781,6 @@ fn draw_help(f: &mut Frame) {
         height: dialog_area.height.saturating_sub(2),
     };
 
-    let help_items = vec![
-        ("SPACE", "Pause / Resume"),
-        ("n / p", "Next / Previous track"),
-        ("↑ / ↓", "Navigate playlist"),
-        ("g / G", "Jump to first / last"),
-        ("Enter", "Play selected track"),
-        ("/", "Search songs"),
-        ("s", "Toggle shuffle"),
-        ("r", "Cycle repeat mode"),
-        ("o", "Open file / folder"),
-        ("h", "Show this help"),
-        ("q / Esc", "Quit"),
-        ("", ""),
-        ("In Search Mode:", ""),
-        ("↑ / ↓", "Navigate results"),
-        ("Enter", "Play & close search"),
-        ("Esc", "Cancel search"),
-    ];
 
     let lines: Vec<Line> = help_items
         .iter()
@@ -534,11 +788,11 @@ fn draw_help(f: &mut Frame) {
             if key.is_empty() {
                 Line::from("")
             } else if desc.is_empty() {
-                Line::from(Span::styled(*key, Style::default().fg(Color::Yellow)))
+                Line::from(Span::styled(key.as_str(), Style::default().fg(theme.warning)))
             } else {
                 Line::from(vec![
-                    Span::styled(format!("{:<12}", key), Style::default().fg(Color::Cyan)),
-                    Span::styled(*desc, Style::default().fg(Color::Reset)),
+                    Span::styled(format!("{:<12}", key), Style::default().fg(theme.accent)),
+                    Span::styled(*desc, Style::default().fg(theme.text)),
                 ])
             }
         })
@@ -547,3 +801,37 @@ fn draw_help(f: &mut Frame) {
     let paragraph = Paragraph::new(lines);
     f.render_widget(paragraph, inner);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(progress_bar(10, 0.0), "░".repeat(10));
+        assert_eq!(progress_bar(10, 0.5), format!("{}{}", "█".repeat(5), "░".repeat(5)));
+        assert_eq!(progress_bar(10, 1.0), "█".repeat(10));
+        // 超出范围的比例被限制
+        assert_eq!(progress_bar(4, 1.5), "█".repeat(4));
+        assert_eq!(progress_bar(4, -0.5), "░".repeat(4));
+        assert_eq!(progress_bar(0, 0.5), "");
+
+        // 不定进度条：滑块到达右端后折返，宽度不变
+        assert_eq!(spinner_bar(5, 0), "███░░");
+        assert_eq!(spinner_bar(5, 2), "░░███");
+        assert_eq!(spinner_bar(5, 3), "░███░");
+        assert_eq!(spinner_bar(5, 4), "███░░");
+        assert_eq!(spinner_bar(2, 7), "██");
+        assert!((0..20).all(|t| spinner_bar(8, t).chars().count() == 8));
+    }
+
+    #[test]
+    fn test_help_fits_dialog() {
+        // 默认按键下每行都放得进内部宽度（按键列补齐到 12 列）
+        let inner = (HELP_WIDTH - 4) as usize;
+        for (key, desc) in help_items(&KeyMap::default()) {
+            let width = key.chars().count().max(12) + desc.chars().count();
+            assert!(width <= inner, "{} {} ({} > {})", key, desc, width, inner);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! 整曲波形概览
+//!
+//! 后台线程用独立的 `AudioDecoder` 解码整首曲目，按时间分段取峰值得到包络，
+//! 绘制在进度条下方，便于定位。结果按路径缓存。计算线程与 TUI 线程同样
+//! 降为最低优先级并使用 TUI 堆，不阻塞界面，也不触碰播放引擎的解码线程与 ring buffer
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+use crate::decode::{AudioDecoder, RawPcmFormat};
+
+/// 包络分段数（绘制时按实际宽度再取峰值）
+pub const ENVELOPE_BINS: usize = 1024;
+
+/// 最多缓存的曲目数（超出时丢弃最早计算的）
+const CACHE_CAPACITY: usize = 32;
+
+/// 由低到高的波形字符
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// 播放位置标记
+const PLAYHEAD: char = '┃';
+
+/// 累计一块交错样本的峰值
+///
+/// `first_frame` 为本块首帧在整曲中的帧号；峰值取各声道绝对值的最大值，归一化到 0.0-1.0
+fn accumulate_peaks(
+    samples: &[i32],
+    channels: usize,
+    first_frame: u64,
+    frames_per_bin: u64,
+    peaks: &mut [f32],
+) {
+    for (i, frame) in samples.chunks_exact(channels).enumerate() {
+        let bin = ((first_frame + i as u64) / frames_per_bin) as usize;
+        let Some(peak) = peaks.get_mut(bin) else {
+            break;
+        };
+        let level = frame.iter().map(|s| s.unsigned_abs()).max().unwrap_or(0) as f32
+            / 2_147_483_648.0;
+        if level > *peak {
+            *peak = level;
+        }
+    }
+}
+
+/// 解码整首曲目计算峰值包络
+///
+/// 时长未知时返回 None；`cancel` 置位时提前放弃。中途解码失败保留已计算的部分
+fn compute_envelope(
+    mut decoder: AudioDecoder,
+    bins: usize,
+    cancel: &AtomicBool,
+) -> Option<Vec<f32>> {
+    let channels = decoder.info().channels.max(1) as usize;
+    let total = decoder.info().total_frames.filter(|&f| f > 0)?;
+    let frames_per_bin = total.div_ceil(bins as u64).max(1);
+    let mut peaks = vec![0.0; bins];
+    let mut frame = 0;
+    while !cancel.load(Ordering::Relaxed) {
+        let samples = match decoder.decode_next_i32() {
+            Ok(samples) if !samples.is_empty() => samples,
+            _ => return Some(peaks),
+        };
+        accumulate_peaks(samples, channels, frame, frames_per_bin, &mut peaks);
+        frame += (samples.len() / channels) as u64;
+    }
+    None
+}
+
+/// 把包络按列数重新分段，每列取段内峰值
+pub fn fit_peaks(peaks: &[f32], width: usize) -> Vec<f32> {
+    if peaks.is_empty() {
+        return vec![0.0; width];
+    }
+    (0..width)
+        .map(|col| {
+            let start = col * peaks.len() / width;
+            let end = ((col + 1) * peaks.len() / width).max(start + 1);
+            peaks[start..end.min(peaks.len())]
+                .iter()
+                .fold(0.0f32, |max, &p| max.max(p))
+        })
+        .collect()
+}
+
+/// 波形条：每列一个字符，`playhead`（0.0-1.0）所在列显示位置标记
+pub fn waveform_line(peaks: &[f32], width: usize, playhead: f64) -> String {
+    let marker = (playhead.clamp(0.0, 1.0) * width as f64) as usize;
+    fit_peaks(peaks, width)
+        .iter()
+        .enumerate()
+        .map(|(col, &peak)| {
+            if col == marker.min(width.saturating_sub(1)) {
+                PLAYHEAD
+            } else {
+                let level = (peak.clamp(0.0, 1.0) * (LEVELS.len() - 1) as f32).round();
+                LEVELS[level as usize]
+            }
+        })
+        .collect()
+}
+
+/// 计算中的曲目
+struct Pending {
+    path: PathBuf,
+    rx: Receiver<Option<Vec<f32>>>,
+    cancel: Arc<AtomicBool>,
+}
+
+/// 按路径缓存的波形包络，后台逐首计算
+#[derive(Default)]
+pub struct Waveforms {
+    cache: HashMap<PathBuf, Vec<f32>>,
+    /// 计算完成顺序（用于淘汰）
+    order: Vec<PathBuf>,
+    pending: Option<Pending>,
+}
+
+impl Waveforms {
+    /// 已计算的包络
+    pub fn get(&self, path: &Path) -> Option<&[f32]> {
+        self.cache.get(path).map(Vec::as_slice)
+    }
+
+    /// 为曲目计算包络（已缓存或正在计算时不重复；取消之前未完成的计算）
+    ///
+    /// 只处理普通文件（stdin、HTTP 流不计算）
+    pub fn request(&mut self, path: &Path, pcm: RawPcmFormat, force_format: Option<&str>) {
+        if self.cache.contains_key(path) || self.pending.as_ref().is_some_and(|p| p.path == path) {
+            return;
+        }
+        self.cancel();
+        if !path.is_file() {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let thread_cancel = Arc::clone(&cancel);
+        let thread_path = path.to_path_buf();
+        let force_format = force_format.map(str::to_string);
+        let spawned = thread::Builder::new()
+            .name("waveform".to_string())
+            .spawn(move || {
+                super::controller::isolate_background_thread();
+                let envelope =
+                    AudioDecoder::open_with_options(&thread_path, pcm, force_format.as_deref())
+                        .ok()
+                        .and_then(|decoder| {
+                            compute_envelope(decoder, ENVELOPE_BINS, &thread_cancel)
+                        });
+                // 接收端已丢弃（切到了别的曲目）时忽略
+                let _ = tx.send(envelope);
+            });
+        match spawned {
+            Ok(_) => {
+                self.pending = Some(Pending {
+                    path: path.to_path_buf(),
+                    rx,
+                    cancel,
+                })
+            }
+            Err(e) => log::warn!("Failed to spawn waveform thread: {}", e),
+        }
+    }
+
+    /// 非阻塞检查计算结果，有新包络时返回 true
+    pub fn poll(&mut self) -> bool {
+        let Some(pending) = self.pending.as_ref() else {
+            return false;
+        };
+        let result = match pending.rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        let path = self.pending.take().map(|p| p.path).unwrap_or_default();
+        let Some(envelope) = result else {
+            return false;
+        };
+        if self.order.len() >= CACHE_CAPACITY {
+            let oldest = self.order.remove(0);
+            self.cache.remove(&oldest);
+        }
+        self.order.push(path.clone());
+        self.cache.insert(path, envelope);
+        true
+    }
+
+    /// 取消未完成的计算
+    fn cancel(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for Waveforms {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peak_envelope() {
+        // 立体声 8 帧，每 2 帧一段：峰值取两声道中绝对值较大者
+        let half = 1 << 30;
+        let samples = [
+            0, 0, half, -half / 2, // 段 0
+            -half, 0, 0, 0, // 段 1
+            0, 0, 0, 0, // 段 2
+            i32::MIN, i32::MAX, 0, 0, // 段 3
+        ];
+        let mut peaks = [0.0f32; 4];
+        // 分两块送入，第二块从第 4 帧开始
+        accumulate_peaks(&samples[..8], 2, 0, 2, &mut peaks);
+        accumulate_peaks(&samples[8..], 2, 4, 2, &mut peaks);
+        assert_eq!(peaks, [0.5, 0.5, 0.0, 1.0]);
+
+        // 超出分段数的帧被忽略
+        accumulate_peaks(&[i32::MAX; 4], 2, 8, 2, &mut peaks);
+        assert_eq!(peaks, [0.5, 0.5, 0.0, 1.0]);
+
+        // 重新分段：每列取段内峰值
+        assert_eq!(fit_peaks(&peaks, 2), vec![0.5, 1.0]);
+        assert_eq!(fit_peaks(&peaks, 8), vec![0.5, 0.5, 0.5, 0.5, 0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(fit_peaks(&[], 3), vec![0.0; 3]);
+
+        // 播放位置标记
+        assert_eq!(waveform_line(&peaks, 4, 0.0), "┃▅▁█");
+        assert_eq!(waveform_line(&peaks, 4, 1.0), "▅▅▁┃");
+    }
+}